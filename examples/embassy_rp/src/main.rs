@@ -0,0 +1,73 @@
+//! Draws a frame on the Pico-ePaper-2.13-B with the async driver
+//!
+//! The busy pin is awaited with `Wait`, so the executor puts the MCU to sleep (WFE) during
+//! the ~15s refresh of the three colors panel instead of polling the pin.
+//!
+//! Wiring of the Pico-ePaper HAT: CLK GP10, DIN GP11, CS GP9, DC GP8, RST GP12, BUSY GP13.
+#![no_std]
+#![no_main]
+
+use embassy_executor::Spawner;
+use embassy_rp::{
+    gpio::{Input, Level, Output, Pull},
+    spi::{self, Spi},
+};
+use embassy_time::{Delay, Timer};
+use embedded_graphics::{
+    mono_font::{ascii::FONT_10X20, MonoTextStyle},
+    prelude::*,
+    primitives::{Circle, PrimitiveStyle},
+    text::Text,
+};
+use embedded_hal_bus::spi::ExclusiveDevice;
+use epd_waveshare::{
+    epd2in13b_v4::{asynch::Epd2in13b, Display2in13b},
+    prelude::*,
+};
+use panic_halt as _;
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_rp::init(Default::default());
+
+    let mut config = spi::Config::default();
+    config.frequency = 4_000_000;
+    let bus = Spi::new_txonly(p.SPI1, p.PIN_10, p.PIN_11, p.DMA_CH0, config);
+    let cs = Output::new(p.PIN_9, Level::High);
+    let mut spi = ExclusiveDevice::new(bus, cs, Delay).unwrap();
+
+    let dc = Output::new(p.PIN_8, Level::Low);
+    let rst = Output::new(p.PIN_12, Level::High);
+    let busy = Input::new(p.PIN_13, Pull::None);
+    let mut delay = Delay;
+
+    let mut epd = Epd2in13b::new(&mut spi, busy, dc, rst, &mut delay)
+        .await
+        .unwrap();
+
+    let mut display = Display2in13b::default();
+    display.set_rotation(DisplayRotation::Rotate90);
+    display.clear(TriColor::White).ok();
+    Text::new(
+        "Hello, Embassy!",
+        Point::new(10, 30),
+        MonoTextStyle::new(&FONT_10X20, TriColor::Black),
+    )
+    .draw(&mut display)
+    .ok();
+    Circle::new(Point::new(180, 50), 50)
+        .into_styled(PrimitiveStyle::with_fill(TriColor::Chromatic))
+        .draw(&mut display)
+        .ok();
+
+    epd.update_color_frame(&mut spi, display.bw_buffer(), display.chromatic_buffer())
+        .await
+        .unwrap();
+    // other tasks keep running, and the MCU sleeps, until the refresh ends
+    epd.display_frame(&mut spi).await.unwrap();
+    epd.sleep(&mut spi).await.unwrap();
+
+    loop {
+        Timer::after_secs(60).await;
+    }
+}
@@ -1,6 +1,7 @@
 #![deny(warnings)]
 
 use embedded_hal::delay::DelayNs;
+use epd_waveshare::error::Error;
 use epd_waveshare::{epd1in54::Epd1in54, prelude::*};
 use linux_embedded_hal::{
     spidev::{self, SpidevOptions},
@@ -12,7 +13,7 @@ use linux_embedded_hal::{
 // needs to be run with sudo because of some sysfs_gpio permission problems and follow-up timing problems
 // see https://github.com/rust-embedded/rust-sysfs-gpio/issues/5 and follow-up issues
 
-fn main() -> Result<(), SPIError> {
+fn main() -> Result<(), Error<SPIError>> {
     // Configure SPI
     // SPI settings are from eink-waveshare-rs documenation
     let mut spi = SpidevDevice::open("/dev/spidev0.0")?;
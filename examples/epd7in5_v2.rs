@@ -9,6 +9,7 @@ use embedded_graphics::{
     text::{Baseline, Text, TextStyleBuilder},
 };
 use embedded_hal::delay::DelayNs;
+use epd_waveshare::error::Error;
 #[cfg(feature = "graphics")]
 use epd_waveshare::{color::Color, epd7in5_v2::*, graphics::DisplayRotation, prelude::*};
 use linux_embedded_hal::{
@@ -17,7 +18,7 @@ use linux_embedded_hal::{
     Delay, SPIError, SpidevDevice, SysfsPin,
 };
 
-fn main() -> Result<(), SPIError> {
+fn main() -> Result<(), Error<SPIError>> {
     // Set up the device
     let mut spi = SpidevDevice::open("/dev/spidev0.0").expect("spidev directory");
     let options = SpidevOptions::new()
@@ -0,0 +1,423 @@
+//! Black and white displays chosen at runtime
+//!
+//! Each driver is its own type, so the display is usually fixed at compile time. [`AnyEpd`]
+//! wraps all the black and white drivers instead, so that a firmware can drive whichever
+//! [`Panel`] it finds in its configuration at boot. Needs the `any_epd` feature.
+//!
+//! ```rust, no_run
+//!# use embedded_hal_mock::eh1::*;
+//!# fn main() -> Result<(), epd_waveshare::error::Error<embedded_hal::spi::ErrorKind>> {
+//!use epd_waveshare::{any_epd::{AnyEpd, Panel}, color::Color};
+//!#
+//!# let mut spi = spi::Mock::new(&[]);
+//!# let busy_in = digital::Mock::new(&[]);
+//!# let dc = digital::Mock::new(&[]);
+//!# let rst = digital::Mock::new(&[]);
+//!# let mut delay = delay::NoopDelay::new();
+//!# let configured_panel = Panel::Epd4in2;
+//!
+//!let panel: Panel = configured_panel;
+//!let mut epd = AnyEpd::new(panel, &mut spi, busy_in, dc, rst, &mut delay, None)?;
+//!// one bit per pixel, e.g. from a `VarDisplay`
+//!let mut buffer = [0u8; Panel::MAX_BUFFER_LEN];
+//!let buffer = &mut buffer[..panel.buffer_len()];
+//!buffer.fill(Color::White.get_byte_value());
+//!epd.update_and_display_frame(&mut spi, buffer, &mut delay)?;
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{InputPin, OutputPin},
+    spi::SpiDevice,
+};
+
+use crate::buffer_len;
+use crate::color::Color;
+use crate::error::Error;
+use crate::traits::{
+    DeepSleepMode, LutControl, PartialUpdate, RefreshLut, Sleepable, WaveshareDisplay,
+};
+
+#[cfg(not(any(feature = "epd2in13_v2", feature = "epd2in13_v3")))]
+compile_error!(
+    "feature \"any_epd\" needs feature \"epd2in13_v2\" or feature \"epd2in13_v3\" for the 2.13\" display"
+);
+
+/// Largest of two sizes, for `Panel::MAX_BUFFER_LEN`
+const fn max(a: usize, b: usize) -> usize {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+macro_rules! any_epd {
+    ($($(#[$doc:meta])* $variant:ident => $module:ident::$driver:ident,)*) => {
+        /// Black and white display driven by an [`AnyEpd`]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Panel {
+            $($(#[$doc])* $variant,)*
+        }
+
+        impl Panel {
+            /// All the panels
+            pub const ALL: &'static [Panel] = &[$(Panel::$variant,)*];
+
+            /// Length of the largest buffer, to allocate one for any panel
+            pub const MAX_BUFFER_LEN: usize = {
+                let mut len = 0;
+                $(len = max(len, buffer_len(
+                    crate::$module::WIDTH as usize,
+                    crate::$module::HEIGHT as usize,
+                ));)*
+                len
+            };
+
+            /// Width of the panel, in pixels
+            pub const fn width(self) -> u32 {
+                match self {
+                    $(Panel::$variant => crate::$module::WIDTH,)*
+                }
+            }
+
+            /// Height of the panel, in pixels
+            pub const fn height(self) -> u32 {
+                match self {
+                    $(Panel::$variant => crate::$module::HEIGHT,)*
+                }
+            }
+
+            /// Length of a full frame buffer of the panel
+            pub const fn buffer_len(self) -> usize {
+                buffer_len(self.width() as usize, self.height() as usize)
+            }
+        }
+
+        /// Driver of any black and white [`Panel`]
+        ///
+        /// It has the methods of [`WaveshareDisplay`] and [`Sleepable`], each of them forwarded to
+        /// the driver of the panel. The methods of [`PartialUpdate`] and [`LutControl`] return
+        /// [`Error::Unsupported`] for the panels without them.
+        pub enum AnyEpd<SPI, BUSY, DC, RST, DELAY> {
+            $($(#[$doc])* $variant(crate::$module::$driver<SPI, BUSY, DC, RST, DELAY>),)*
+        }
+
+        $(
+            impl<SPI, BUSY, DC, RST, DELAY> From<crate::$module::$driver<SPI, BUSY, DC, RST, DELAY>>
+                for AnyEpd<SPI, BUSY, DC, RST, DELAY>
+            {
+                fn from(epd: crate::$module::$driver<SPI, BUSY, DC, RST, DELAY>) -> Self {
+                    AnyEpd::$variant(epd)
+                }
+            }
+        )*
+
+        impl<SPI, BUSY, DC, RST, DELAY> AnyEpd<SPI, BUSY, DC, RST, DELAY>
+        where
+            SPI: SpiDevice,
+            BUSY: InputPin,
+            DC: OutputPin,
+            RST: OutputPin,
+            DELAY: DelayNs,
+        {
+            /// Creates the driver of `panel`, see [`WaveshareDisplay::new`]
+            pub fn new(
+                panel: Panel,
+                spi: &mut SPI,
+                busy: BUSY,
+                dc: DC,
+                rst: RST,
+                delay: &mut DELAY,
+                delay_us: Option<u32>,
+            ) -> Result<Self, Error<SPI::Error>> {
+                Ok(match panel {
+                    $(Panel::$variant => AnyEpd::$variant(crate::$module::$driver::new(
+                        spi, busy, dc, rst, delay, delay_us,
+                    )?),)*
+                })
+            }
+
+            /// The driven panel
+            pub fn panel(&self) -> Panel {
+                match self {
+                    $(AnyEpd::$variant(_) => Panel::$variant,)*
+                }
+            }
+
+            /// See [`Sleepable::sleep`]
+            pub fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+                match self {
+                    $(AnyEpd::$variant(epd) => epd.sleep(spi, delay),)*
+                }
+            }
+
+            /// See [`Sleepable::sleep_with_mode`]
+            pub fn sleep_with_mode(
+                &mut self,
+                spi: &mut SPI,
+                delay: &mut DELAY,
+                mode: DeepSleepMode,
+            ) -> Result<(), Error<SPI::Error>> {
+                match self {
+                    $(AnyEpd::$variant(epd) => epd.sleep_with_mode(spi, delay, mode),)*
+                }
+            }
+
+            /// See [`Sleepable::wake_up`]
+            pub fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+                match self {
+                    $(AnyEpd::$variant(epd) => epd.wake_up(spi, delay),)*
+                }
+            }
+
+            /// See [`WaveshareDisplay::set_background_color`]
+            pub fn set_background_color(&mut self, color: Color) {
+                match self {
+                    $(AnyEpd::$variant(epd) => epd.set_background_color(color),)*
+                }
+            }
+
+            /// See [`WaveshareDisplay::background_color`]
+            pub fn background_color(&self) -> &Color {
+                match self {
+                    $(AnyEpd::$variant(epd) => epd.background_color(),)*
+                }
+            }
+
+            /// See [`WaveshareDisplay::width`]
+            pub fn width(&self) -> u32 {
+                self.panel().width()
+            }
+
+            /// See [`WaveshareDisplay::height`]
+            pub fn height(&self) -> u32 {
+                self.panel().height()
+            }
+
+            /// See [`WaveshareDisplay::update_frame`]
+            pub fn update_frame(
+                &mut self,
+                spi: &mut SPI,
+                buffer: &[u8],
+                delay: &mut DELAY,
+            ) -> Result<(), Error<SPI::Error>> {
+                match self {
+                    $(AnyEpd::$variant(epd) => epd.update_frame(spi, buffer, delay),)*
+                }
+            }
+
+            /// See [`WaveshareDisplay::display_frame`]
+            pub fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+                match self {
+                    $(AnyEpd::$variant(epd) => epd.display_frame(spi, delay),)*
+                }
+            }
+
+            /// See [`WaveshareDisplay::update_and_display_frame`]
+            pub fn update_and_display_frame(
+                &mut self,
+                spi: &mut SPI,
+                buffer: &[u8],
+                delay: &mut DELAY,
+            ) -> Result<(), Error<SPI::Error>> {
+                match self {
+                    $(AnyEpd::$variant(epd) => epd.update_and_display_frame(spi, buffer, delay),)*
+                }
+            }
+
+            /// See [`WaveshareDisplay::clear_frame`]
+            pub fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+                match self {
+                    $(AnyEpd::$variant(epd) => epd.clear_frame(spi, delay),)*
+                }
+            }
+
+            /// See [`WaveshareDisplay::wait_until_idle`]
+            pub fn wait_until_idle(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+                match self {
+                    $(AnyEpd::$variant(epd) => epd.wait_until_idle(spi, delay),)*
+                }
+            }
+        }
+    };
+}
+
+any_epd! {
+    /// 1.02"
+    Epd1in02 => epd1in02::Epd1in02,
+    /// 1.54"
+    Epd1in54 => epd1in54::Epd1in54,
+    /// 1.54" V2
+    Epd1in54V2 => epd1in54_v2::Epd1in54,
+    /// 2.13" V2 or V3, depending on the `epd2in13_v2` and `epd2in13_v3` features
+    Epd2in13 => epd2in13_v2::Epd2in13,
+    /// 2.66"
+    Epd2in66 => epd2in66::Epd2in66,
+    /// 2.7"
+    Epd2in7 => epd2in7::Epd2in7,
+    /// 2.7" V2
+    Epd2in7V2 => epd2in7_v2::Epd2in7,
+    /// 2.9"
+    Epd2in9 => epd2in9::Epd2in9,
+    /// 2.9" V2
+    Epd2in9V2 => epd2in9_v2::Epd2in9,
+    /// 3.7"
+    Epd3in7 => epd3in7::EPD3in7,
+    /// 4.2"
+    Epd4in2 => epd4in2::Epd4in2,
+    /// 4.26"
+    Epd4in26 => epd4in26::Epd4in26,
+    /// 5.79"
+    Epd5in79 => epd5in79::Epd5in79,
+    /// 5.83" V2
+    Epd5in83V2 => epd5in83_v2::Epd5in83,
+    /// 7.5"
+    Epd7in5 => epd7in5::Epd7in5,
+    /// 7.5" HD
+    Epd7in5Hd => epd7in5_hd::Epd7in5,
+    /// 7.5" V2
+    Epd7in5V2 => epd7in5_v2::Epd7in5,
+    /// 13.3" (K)
+    Epd13in3k => epd13in3k::Epd13in3k,
+}
+
+/// Calls `$call` with the driver of the panels in `[$variant]`, the others return
+/// [`Error::Unsupported`]
+macro_rules! with_driver_of {
+    ($any:expr, [$($variant:ident),* $(,)?], $epd:ident => $call:expr) => {
+        match $any {
+            $(AnyEpd::$variant($epd) => $call,)*
+            #[allow(unreachable_patterns)]
+            _ => Err(Error::Unsupported),
+        }
+    };
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> AnyEpd<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// See [`PartialUpdate::update_partial_frame`]
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        with_driver_of!(
+            self,
+            [
+                Epd1in54, Epd1in54V2, Epd2in13, Epd2in66, Epd2in7, Epd2in7V2, Epd2in9, Epd2in9V2,
+                Epd4in2, Epd4in26, Epd13in3k,
+            ],
+            epd => epd.update_partial_frame(spi, delay, buffer, x, y, width, height)
+        )
+    }
+
+    /// See [`LutControl::set_lut`]
+    pub fn set_lut(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        with_driver_of!(
+            self,
+            [
+                Epd1in02, Epd1in54, Epd1in54V2, Epd2in13, Epd2in7, Epd2in7V2, Epd2in9, Epd2in9V2,
+                Epd3in7, Epd4in2, Epd4in26,
+            ],
+            epd => epd.set_lut(spi, delay, refresh_rate)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Recorder;
+    use embedded_hal::digital::PinState;
+
+    #[test]
+    fn panels() {
+        assert_eq!(Panel::ALL.len(), 18);
+        assert_eq!(
+            (Panel::Epd4in2.width(), Panel::Epd4in2.height()),
+            (crate::epd4in2::WIDTH, crate::epd4in2::HEIGHT)
+        );
+        for panel in Panel::ALL {
+            assert!(panel.buffer_len() <= Panel::MAX_BUFFER_LEN);
+        }
+        assert_eq!(Panel::MAX_BUFFER_LEN, Panel::Epd13in3k.buffer_len());
+    }
+
+    #[test]
+    fn same_traffic_as_the_driver() {
+        let direct = Recorder::new();
+        let (mut spi, mut delay) = (direct.spi(), direct.delay());
+        let busy = direct.busy(PinState::Low);
+        let mut epd = crate::epd2in9_v2::Epd2in9::new(
+            &mut spi,
+            busy,
+            direct.dc(),
+            direct.rst(),
+            &mut delay,
+            None,
+        )
+        .unwrap();
+        epd.clear_frame(&mut spi, &mut delay).unwrap();
+
+        let any = Recorder::new();
+        let (mut spi, mut delay) = (any.spi(), any.delay());
+        let busy = any.busy(PinState::Low);
+        let mut epd = AnyEpd::new(
+            Panel::Epd2in9V2,
+            &mut spi,
+            busy,
+            any.dc(),
+            any.rst(),
+            &mut delay,
+            None,
+        )
+        .unwrap();
+        epd.clear_frame(&mut spi, &mut delay).unwrap();
+
+        assert_eq!(epd.panel(), Panel::Epd2in9V2);
+        assert_eq!(any.transcript(), direct.transcript());
+    }
+
+    #[test]
+    fn unsupported() {
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::High);
+        let (dc, rst) = (recorder.dc(), recorder.rst());
+        let mut epd =
+            AnyEpd::new(Panel::Epd7in5V2, &mut spi, busy, dc, rst, &mut delay, None).unwrap();
+        recorder.clear();
+
+        assert_eq!(
+            epd.update_partial_frame(&mut spi, &mut delay, &[0xFF; 8], 0, 0, 8, 8),
+            Err(Error::Unsupported)
+        );
+        assert_eq!(
+            epd.set_lut(&mut spi, &mut delay, Some(RefreshLut::Quick)),
+            Err(Error::Unsupported)
+        );
+        // nothing must have been sent
+        assert!(recorder.transcript().entries().is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+//! Drivers built from an `SpiBus` and a chip select pin, with [`embedded-hal-bus`]
+//!
+//! The drivers talk to an [`SpiDevice`](embedded_hal::spi::SpiDevice), which owns the chip
+//! select pin of the display. HALs usually give an [`SpiBus`] instead: [`new_exclusive`] builds
+//! a driver on an [`ExclusiveDevice`] when the display is alone on the bus, and
+//! [`new_shared`] on a [`RefCellDevice`] when the bus is shared, e.g. with the SD card reader
+//! found on many Waveshare boards. Needs the `bus` feature.
+//!
+//! Both take a delay for the SPI device, used for the delays within a transaction, besides
+//! the delay of the driver. Create the devices with their `new` method rather than
+//! `new_no_delay`, whose delay panics.
+//!
+//! Sharing the bus with an SD card, each device having its own chip select pin:
+//!
+//! ```rust, no_run
+//!# use embedded_hal_mock::eh1::*;
+//!# fn main() -> Result<(), epd_waveshare::bus::BusError<spi::Mock<u8>, digital::Mock>> {
+//!use core::cell::RefCell;
+//!use embedded_hal_bus::spi::RefCellDevice;
+//!use epd_waveshare::{bus, epd2in13b_v4::*, prelude::*};
+//!#
+//!# let spi_bus = spi::Mock::new(&[]);
+//!# let (epd_cs, sd_cs) = (digital::Mock::new(&[]), digital::Mock::new(&[]));
+//!# let (busy_in, dc, rst) = (digital::Mock::new(&[]), digital::Mock::new(&[]), digital::Mock::new(&[]));
+//!# let (mut delay, epd_spi_delay, sd_spi_delay) = (delay::NoopDelay, delay::NoopDelay, delay::NoopDelay);
+//!
+//!let spi_bus = RefCell::new(spi_bus);
+//!let (mut epd, mut epd_spi): (Epd2in13b<_, _, _, _, _>, _) =
+//!    bus::new_shared(&spi_bus, epd_cs, epd_spi_delay, busy_in, dc, rst, &mut delay)?;
+//!// hand this one to the SD card driver
+//!let sd_spi = RefCellDevice::new(&spi_bus, sd_cs, sd_spi_delay);
+//!
+//!epd.clear_frame(&mut epd_spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+//!
+//! Both chip select pins must be high (inactive) before the bus is first used, or the SD card
+//! may answer to the commands of the display: create the SD card device before the bus is
+//! used if its chip select pin may start low.
+//!
+//! [`embedded-hal-bus`]: https://docs.rs/embedded-hal-bus
+
+use core::cell::RefCell;
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{self, InputPin, OutputPin},
+    spi::{self, SpiBus},
+};
+use embedded_hal_bus::spi::{DeviceError, ExclusiveDevice, RefCellDevice};
+
+use crate::error::Error;
+use crate::traits::WaveshareDisplay;
+
+/// Error of the drivers built on the devices of `embedded-hal-bus`, from the SPI bus `BUS` and
+/// the chip select pin `CS`
+pub type BusError<BUS, CS> =
+    Error<DeviceError<<BUS as spi::ErrorType>::Error, <CS as digital::ErrorType>::Error>>;
+
+/// A driver created by [`new_exclusive`], with the SPI device to use with it
+pub type Exclusive<EPD, BUS, CS, SPIDELAY> = (EPD, ExclusiveDevice<BUS, CS, SPIDELAY>);
+
+/// A driver created by [`new_shared`], with the SPI device to use with it
+pub type Shared<'a, EPD, BUS, CS, SPIDELAY> = (EPD, RefCellDevice<'a, BUS, CS, SPIDELAY>);
+
+/// Creates an [`ExclusiveDevice`] owning `bus` and initialises the display through it
+///
+/// `cs` is set high before the display is initialised, and `spi_delay` is used for the delays
+/// within a transaction.
+pub fn new_exclusive<EPD, BUS, CS, SPIDELAY, BUSY, DC, RST, DELAY>(
+    bus: BUS,
+    cs: CS,
+    spi_delay: SPIDELAY,
+    busy: BUSY,
+    dc: DC,
+    rst: RST,
+    delay: &mut DELAY,
+) -> Result<Exclusive<EPD, BUS, CS, SPIDELAY>, BusError<BUS, CS>>
+where
+    BUS: SpiBus,
+    CS: OutputPin,
+    SPIDELAY: DelayNs,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+    EPD: WaveshareDisplay<ExclusiveDevice<BUS, CS, SPIDELAY>, BUSY, DC, RST, DELAY>,
+{
+    let mut spi =
+        ExclusiveDevice::new(bus, cs, spi_delay).map_err(|e| Error::Spi(DeviceError::Cs(e)))?;
+    let epd = EPD::new(&mut spi, busy, dc, rst, delay, None)?;
+    Ok((epd, spi))
+}
+
+/// Creates a [`RefCellDevice`] on the shared `bus` and initialises the display through it
+///
+/// `cs` is set high before the display is initialised, and `spi_delay` is used for the delays
+/// within a transaction. The other devices on the bus are created with
+/// [`RefCellDevice::new`] on the same `RefCell`. They can't be used from interrupts or other
+/// threads: the bus is borrowed for the time of a transaction, and a second borrow at the
+/// same time panics.
+pub fn new_shared<'a, EPD, BUS, CS, SPIDELAY, BUSY, DC, RST, DELAY>(
+    bus: &'a RefCell<BUS>,
+    cs: CS,
+    spi_delay: SPIDELAY,
+    busy: BUSY,
+    dc: DC,
+    rst: RST,
+    delay: &mut DELAY,
+) -> Result<Shared<'a, EPD, BUS, CS, SPIDELAY>, BusError<BUS, CS>>
+where
+    BUS: SpiBus,
+    CS: OutputPin,
+    SPIDELAY: DelayNs,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+    EPD: WaveshareDisplay<RefCellDevice<'a, BUS, CS, SPIDELAY>, BUSY, DC, RST, DELAY>,
+{
+    let mut spi =
+        RefCellDevice::new(bus, cs, spi_delay).map_err(|e| Error::Spi(DeviceError::Cs(e)))?;
+    let epd = EPD::new(&mut spi, busy, dc, rst, delay, None)?;
+    Ok((epd, spi))
+}
+
+#[cfg(all(test, feature = "epd4in2"))]
+mod tests {
+    use super::*;
+    use crate::epd4in2::Epd4in2;
+    use crate::testing::Recorder;
+    use embedded_hal::digital::{ErrorType as PinErrorType, PinState};
+    use embedded_hal::spi::{ErrorType, SpiDevice};
+    use embedded_hal_mock::eh1::delay::NoopDelay;
+
+    extern crate std;
+    use std::{rc::Rc, vec::Vec};
+
+    /// SPI bus keeping the bytes written to it
+    #[derive(Default)]
+    struct Bus(Vec<u8>);
+
+    impl ErrorType for Bus {
+        type Error = core::convert::Infallible;
+    }
+
+    impl SpiBus for Bus {
+        fn read(&mut self, _words: &mut [u8]) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+            self.0.extend_from_slice(words);
+            Ok(())
+        }
+
+        fn transfer(&mut self, _read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+            self.write(write)
+        }
+
+        fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+            self.write(words)
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    /// Chip select pin counting how often it was selected
+    #[derive(Clone, Default)]
+    struct Cs(Rc<core::cell::Cell<(bool, u32)>>);
+
+    impl PinErrorType for Cs {
+        type Error = core::convert::Infallible;
+    }
+
+    impl OutputPin for Cs {
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            let (_, selected) = self.0.get();
+            self.0.set((false, selected + 1));
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            let (_, selected) = self.0.get();
+            self.0.set((true, selected));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn shared_bus() {
+        let recorder = Recorder::new();
+        let busy = recorder.busy(PinState::High);
+        let (dc, rst) = (recorder.dc(), recorder.rst());
+        let (epd_cs, sd_cs) = (Cs::default(), Cs::default());
+
+        let shared = RefCell::new(Bus::default());
+        let mut sd = RefCellDevice::new(&shared, sd_cs.clone(), NoopDelay).unwrap();
+        let (_epd, _spi): Shared<Epd4in2<_, _, _, _, _>, _, _, _> = new_shared(
+            &shared,
+            epd_cs.clone(),
+            NoopDelay,
+            busy,
+            dc,
+            rst,
+            &mut NoopDelay,
+        )
+        .unwrap();
+        let init = shared.borrow().0.len();
+        assert!(init > 0);
+        // the SD card wasn't selected during the initialisation
+        assert!(epd_cs.0.get().0 && epd_cs.0.get().1 > 0);
+        assert_eq!(sd_cs.0.get(), (true, 0));
+
+        sd.write(&[0x40, 0x00]).unwrap();
+        assert_eq!(shared.borrow().0[init..], [0x40, 0x00]);
+        assert_eq!(sd_cs.0.get(), (true, 1));
+    }
+
+    #[test]
+    fn exclusive_bus() {
+        let recorder = Recorder::new();
+        let busy = recorder.busy(PinState::High);
+        let (dc, rst) = (recorder.dc(), recorder.rst());
+        let cs = Cs::default();
+
+        let (_epd, spi): Exclusive<Epd4in2<_, _, _, _, _>, _, _, _> = new_exclusive(
+            Bus::default(),
+            cs.clone(),
+            NoopDelay,
+            busy,
+            dc,
+            rst,
+            &mut NoopDelay,
+        )
+        .unwrap();
+        assert!(cs.0.get().0);
+        assert!(!spi.bus().0.is_empty());
+    }
+}
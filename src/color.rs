@@ -68,6 +68,56 @@ pub enum OctColor {
     HiZ = 0x07,
 }
 
+/// For the displays with 4 gray levels, 2 bits per pixel
+///
+/// The high bit of a pixel goes to the B/W RAM of the controller, the low one to its red RAM.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Gray4Epd {
+    /// Black color
+    Black = 0b00,
+    /// Dark gray color
+    DarkGray = 0b01,
+    /// Light gray color
+    LightGray = 0b10,
+    /// White color
+    #[default]
+    White = 0b11,
+}
+
+/// For the 6 color displays (E, Spectra 6), 4 bits per pixel
+///
+/// The nibbles differ from the ones of [`OctColor`], 4 being unused.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum HexColor {
+    /// Black Color
+    Black = 0x00,
+    /// White Color
+    #[default]
+    White = 0x01,
+    /// Yellow Color
+    Yellow = 0x02,
+    /// Red Color
+    Red = 0x03,
+    /// Blue Color
+    Blue = 0x05,
+    /// Green Color
+    Green = 0x06,
+}
+
+/// For the 4 color displays (G), 2 bits per pixel
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum QuadColor {
+    /// Black color
+    Black = 0b00,
+    /// White color
+    #[default]
+    White = 0b01,
+    /// Yellow color
+    Yellow = 0b10,
+    /// Red color
+    Red = 0b11,
+}
+
 /// Color trait for use in `Display`s
 pub trait ColorType {
     /// Number of bit used to represent this color type in a single buffer.
@@ -88,7 +138,33 @@ pub trait ColorType {
     /// * .0 is the mask used to exclude this pixel from the byte (eg: 0x7F in BiColor)
     /// * .1 are the bits used to set the color in the byte (eg: 0x80 in BiColor)
     ///      this is u16 because we set 2 bytes in case of split buffer
+    #[allow(clippy::doc_overindented_list_items)]
     fn bitmask(&self, bwrbit: bool, pos: u32) -> (u8, u16);
+
+    /// Color of a pixel stored as `bits`, the opposite of [`bitmask`](Self::bitmask)
+    ///
+    /// The bits of the pixel are the lowest ones of each byte: the low byte from the first
+    /// buffer, the high byte from the second one for split buffers. Unknown values give the
+    /// closest color, usually white.
+    ///
+    /// `None` by default, for the color types which can't be read back.
+    fn from_pixel_bits(_bits: u16) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// Color drawn instead of this one on an inverted display, see
+    /// [`Display::set_inverted`](crate::graphics::Display::set_inverted)
+    ///
+    /// Black and white are swapped, the other colors are kept.
+    fn inverse(self) -> Self
+    where
+        Self: Sized,
+    {
+        self
+    }
 }
 
 impl ColorType for Color {
@@ -101,6 +177,18 @@ impl ColorType for Color {
             Color::White => (!bit, bit as u16),
         }
     }
+
+    fn from_pixel_bits(bits: u16) -> Option<Self> {
+        Some(if bits & 1 == 0 {
+            Color::Black
+        } else {
+            Color::White
+        })
+    }
+
+    fn inverse(self) -> Self {
+        Color::inverse(self)
+    }
 }
 
 impl ColorType for TriColor {
@@ -121,6 +209,25 @@ impl ColorType for TriColor {
             ),
         }
     }
+
+    /// The chromatic bit wins, whatever the `bwrbit` mode
+    fn from_pixel_bits(bits: u16) -> Option<Self> {
+        Some(if bits & 0x100 != 0 {
+            TriColor::Chromatic
+        } else if bits & 1 != 0 {
+            TriColor::White
+        } else {
+            TriColor::Black
+        })
+    }
+
+    fn inverse(self) -> Self {
+        match self {
+            TriColor::Black => TriColor::White,
+            TriColor::White => TriColor::Black,
+            TriColor::Chromatic => TriColor::Chromatic,
+        }
+    }
 }
 
 impl ColorType for OctColor {
@@ -131,6 +238,336 @@ impl ColorType for OctColor {
         let bits = self.get_nibble() as u16;
         (mask, if pos % 2 == 1 { bits } else { bits << 4 })
     }
+
+    fn from_pixel_bits(bits: u16) -> Option<Self> {
+        Some(OctColor::from_nibble(bits as u8).unwrap_or(OctColor::White))
+    }
+
+    fn inverse(self) -> Self {
+        match self {
+            OctColor::Black => OctColor::White,
+            OctColor::White => OctColor::Black,
+            color => color,
+        }
+    }
+}
+
+impl ColorType for Gray4Epd {
+    const BITS_PER_PIXEL_PER_BUFFER: usize = 2;
+    const BUFFER_COUNT: usize = 1;
+    fn bitmask(&self, _bwrbit: bool, pos: u32) -> (u8, u16) {
+        let shift = 6 - (pos % 4) * 2;
+        (!(0b11 << shift), (*self as u16) << shift)
+    }
+
+    /// The gray levels are swapped too
+    fn from_pixel_bits(bits: u16) -> Option<Self> {
+        Some(Gray4Epd::from_bits(bits as u8))
+    }
+
+    fn inverse(self) -> Self {
+        Gray4Epd::from_bits(!self.get_bits())
+    }
+}
+
+impl Gray4Epd {
+    /// Gets the 2 bits representation of the color
+    pub fn get_bits(self) -> u8 {
+        self as u8
+    }
+
+    /// Gets a full byte of 4 pixels of this color
+    pub fn get_byte_value(self) -> u8 {
+        self.get_bits() * 0x55
+    }
+
+    /// Takes the 2 lower bits and converts them to a color
+    pub fn from_bits(bits: u8) -> Gray4Epd {
+        match bits & 0b11 {
+            0b00 => Gray4Epd::Black,
+            0b01 => Gray4Epd::DarkGray,
+            0b10 => Gray4Epd::LightGray,
+            _ => Gray4Epd::White,
+        }
+    }
+
+    /// Extracts one bit of each pixel of a buffer of 2 bits pixels, `width` pixels per line,
+    /// packing 8 pixels per byte: the high bits (`bit` 1) for the B/W RAM, the low ones
+    /// (`bit` 0) for the red RAM
+    #[cfg(any(feature = "epd2in13b_v4", feature = "epd3in7", feature = "epd4in26"))]
+    pub(crate) fn plane(buffer: &[u8], width: u32, bit: u8) -> impl Iterator<Item = u8> + '_ {
+        let line_len = crate::buffer_len(width as usize * 2, 1);
+        buffer.chunks(line_len).flat_map(move |line| {
+            line.chunks(2).map(move |pixels| {
+                let byte = pixels.iter().fold(0u8, |byte, &pixels| {
+                    (0..4).fold(byte, |byte, p| {
+                        byte << 1 | (pixels >> (6 - 2 * p + bit)) & 1
+                    })
+                });
+                // the last byte of a line may only hold 4 pixels
+                byte << (4 * (2 - pixels.len()))
+            })
+        })
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl From<embedded_graphics_core::pixelcolor::raw::RawU2> for Gray4Epd {
+    fn from(b: embedded_graphics_core::pixelcolor::raw::RawU2) -> Self {
+        use embedded_graphics_core::prelude::RawData;
+        Gray4Epd::from_bits(b.into_inner())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl PixelColor for Gray4Epd {
+    type Raw = embedded_graphics_core::pixelcolor::raw::RawU2;
+}
+
+#[cfg(feature = "graphics")]
+impl From<BinaryColor> for Gray4Epd {
+    fn from(b: BinaryColor) -> Gray4Epd {
+        match b {
+            BinaryColor::On => Gray4Epd::Black,
+            BinaryColor::Off => Gray4Epd::White,
+        }
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl From<embedded_graphics_core::pixelcolor::Gray2> for Gray4Epd {
+    fn from(gray: embedded_graphics_core::pixelcolor::Gray2) -> Gray4Epd {
+        use embedded_graphics_core::pixelcolor::GrayColor;
+        Gray4Epd::from_bits(gray.luma())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl From<Gray4Epd> for embedded_graphics_core::pixelcolor::Gray2 {
+    fn from(gray: Gray4Epd) -> Self {
+        Self::new(gray.get_bits())
+    }
+}
+
+impl ColorType for HexColor {
+    const BITS_PER_PIXEL_PER_BUFFER: usize = 4;
+    const BUFFER_COUNT: usize = 1;
+    fn bitmask(&self, _bwrbit: bool, pos: u32) -> (u8, u16) {
+        let mask = !(0xF0 >> ((pos % 2) * 4));
+        let bits = self.get_nibble() as u16;
+        (mask, if pos % 2 == 1 { bits } else { bits << 4 })
+    }
+
+    fn from_pixel_bits(bits: u16) -> Option<Self> {
+        Some(HexColor::from_nibble(bits as u8).unwrap_or(HexColor::White))
+    }
+
+    fn inverse(self) -> Self {
+        match self {
+            HexColor::Black => HexColor::White,
+            HexColor::White => HexColor::Black,
+            color => color,
+        }
+    }
+}
+
+impl HexColor {
+    const ALL: [HexColor; 6] = [
+        HexColor::Black,
+        HexColor::White,
+        HexColor::Yellow,
+        HexColor::Red,
+        HexColor::Blue,
+        HexColor::Green,
+    ];
+
+    /// Gets the Nibble representation of the Color as needed by the display
+    pub fn get_nibble(self) -> u8 {
+        self as u8
+    }
+
+    /// Converts two colors into a single byte for the Display
+    pub fn colors_byte(a: HexColor, b: HexColor) -> u8 {
+        a.get_nibble() << 4 | b.get_nibble()
+    }
+
+    ///Take the nibble (lower 4 bits) and convert to a HexColor if possible
+    pub fn from_nibble(nibble: u8) -> Result<HexColor, OutOfColorRangeParseError> {
+        let nibble = nibble & 0xf;
+        HexColor::ALL
+            .iter()
+            .find(|c| c.get_nibble() == nibble)
+            .copied()
+            .ok_or(OutOfColorRangeParseError(nibble))
+    }
+
+    /// Converts to limited range of RGB values.
+    pub fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            HexColor::Black => (0x00, 0x00, 0x00),
+            HexColor::White => (0xff, 0xff, 0xff),
+            HexColor::Yellow => (0xff, 0xff, 0x00),
+            HexColor::Red => (0xff, 0x00, 0x00),
+            HexColor::Blue => (0x00, 0x00, 0xff),
+            HexColor::Green => (0x00, 0xff, 0x00),
+        }
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl From<embedded_graphics_core::pixelcolor::raw::RawU4> for HexColor {
+    /// The unused nibbles are white
+    fn from(b: embedded_graphics_core::pixelcolor::raw::RawU4) -> Self {
+        use embedded_graphics_core::prelude::RawData;
+        HexColor::from_nibble(b.into_inner()).unwrap_or(HexColor::White)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl PixelColor for HexColor {
+    type Raw = embedded_graphics_core::pixelcolor::raw::RawU4;
+}
+
+#[cfg(feature = "graphics")]
+impl From<BinaryColor> for HexColor {
+    fn from(b: BinaryColor) -> HexColor {
+        match b {
+            BinaryColor::On => HexColor::Black,
+            BinaryColor::Off => HexColor::White,
+        }
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl From<HexColor> for embedded_graphics_core::pixelcolor::Rgb888 {
+    fn from(color: HexColor) -> Self {
+        let (r, g, b) = color.rgb();
+        Self::new(r, g, b)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl From<embedded_graphics_core::pixelcolor::Rgb888> for HexColor {
+    /// Picks the nearest of the 6 colors
+    fn from(p: embedded_graphics_core::pixelcolor::Rgb888) -> HexColor {
+        use embedded_graphics_core::prelude::RgbColor;
+        *HexColor::ALL
+            .iter()
+            .min_by_key(|c| {
+                let (r, g, b) = c.rgb();
+                (i32::from(r) - i32::from(p.r())).pow(2)
+                    + (i32::from(g) - i32::from(p.g())).pow(2)
+                    + (i32::from(b) - i32::from(p.b())).pow(2)
+            })
+            .unwrap_or(&HexColor::White)
+    }
+}
+
+impl ColorType for QuadColor {
+    const BITS_PER_PIXEL_PER_BUFFER: usize = 2;
+    const BUFFER_COUNT: usize = 1;
+    fn bitmask(&self, _bwrbit: bool, pos: u32) -> (u8, u16) {
+        let shift = 6 - (pos % 4) * 2;
+        (!(0b11 << shift), (*self as u16) << shift)
+    }
+
+    fn from_pixel_bits(bits: u16) -> Option<Self> {
+        Some(QuadColor::from_bits(bits as u8))
+    }
+
+    fn inverse(self) -> Self {
+        match self {
+            QuadColor::Black => QuadColor::White,
+            QuadColor::White => QuadColor::Black,
+            color => color,
+        }
+    }
+}
+
+impl QuadColor {
+    /// Gets the 2 bits representation of the color as needed by the display
+    pub fn get_bits(self) -> u8 {
+        self as u8
+    }
+
+    /// Gets a full byte of 4 pixels of this color
+    pub fn get_byte_value(self) -> u8 {
+        self.get_bits() * 0x55
+    }
+
+    /// Takes the 2 lower bits and converts them to a color
+    pub fn from_bits(bits: u8) -> QuadColor {
+        match bits & 0b11 {
+            0b00 => QuadColor::Black,
+            0b01 => QuadColor::White,
+            0b10 => QuadColor::Yellow,
+            _ => QuadColor::Red,
+        }
+    }
+
+    /// Converts to limited range of RGB values.
+    pub fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            QuadColor::Black => (0x00, 0x00, 0x00),
+            QuadColor::White => (0xff, 0xff, 0xff),
+            QuadColor::Yellow => (0xff, 0xff, 0x00),
+            QuadColor::Red => (0xff, 0x00, 0x00),
+        }
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl From<embedded_graphics_core::pixelcolor::raw::RawU2> for QuadColor {
+    fn from(b: embedded_graphics_core::pixelcolor::raw::RawU2) -> Self {
+        use embedded_graphics_core::prelude::RawData;
+        QuadColor::from_bits(b.into_inner())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl PixelColor for QuadColor {
+    type Raw = embedded_graphics_core::pixelcolor::raw::RawU2;
+}
+
+#[cfg(feature = "graphics")]
+impl From<BinaryColor> for QuadColor {
+    fn from(b: BinaryColor) -> QuadColor {
+        match b {
+            BinaryColor::On => QuadColor::Black,
+            BinaryColor::Off => QuadColor::White,
+        }
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl From<QuadColor> for embedded_graphics_core::pixelcolor::Rgb888 {
+    fn from(color: QuadColor) -> Self {
+        let (r, g, b) = color.rgb();
+        Self::new(r, g, b)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl From<embedded_graphics_core::pixelcolor::Rgb888> for QuadColor {
+    /// Picks the nearest of the 4 colors
+    fn from(p: embedded_graphics_core::pixelcolor::Rgb888) -> QuadColor {
+        use embedded_graphics_core::prelude::RgbColor;
+        let colors = [
+            QuadColor::Black,
+            QuadColor::White,
+            QuadColor::Yellow,
+            QuadColor::Red,
+        ];
+        *colors
+            .iter()
+            .min_by_key(|c| {
+                let (r, g, b) = c.rgb();
+                (i32::from(r) - i32::from(p.r())).pow(2)
+                    + (i32::from(g) - i32::from(p.g())).pow(2)
+                    + (i32::from(b) - i32::from(p.b())).pow(2)
+            })
+            .unwrap_or(&QuadColor::White)
+    }
 }
 
 #[cfg(feature = "graphics")]
@@ -488,6 +925,107 @@ impl From<TriColor> for embedded_graphics_core::pixelcolor::Rgb888 {
     }
 }
 
+/// Configurable mapping of RGB colors to [`Color`] and [`TriColor`]
+///
+/// A color is `Chromatic` when its chroma, the difference between its largest and smallest
+/// components, reaches the chroma threshold. Otherwise it is `White` or `Black` depending on
+/// its perceived brightness. With [`ColorMapper::red_only`] only red-ish colors, which are
+/// shown by most three color displays, are chromatic.
+///
+/// ```rust
+/// use embedded_graphics::pixelcolor::Rgb888;
+/// use epd_waveshare::{color::ColorMapper, prelude::*};
+///
+/// let mapper = ColorMapper::new().chroma_threshold(64).red_only(32);
+/// assert_eq!(mapper.map_tricolor(Rgb888::new(200, 90, 90)), TriColor::Chromatic);
+/// assert_eq!(mapper.map_tricolor(Rgb888::new(40, 40, 200)), TriColor::Black);
+/// ```
+#[cfg(feature = "graphics")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ColorMapper {
+    luma_threshold: u8,
+    chroma_threshold: u8,
+    red_margin: Option<u8>,
+}
+
+#[cfg(feature = "graphics")]
+impl Default for ColorMapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl ColorMapper {
+    /// Mapper with a brightness and a chroma threshold of 128, any saturated color being
+    /// chromatic
+    pub const fn new() -> Self {
+        ColorMapper {
+            luma_threshold: 128,
+            chroma_threshold: 128,
+            red_margin: None,
+        }
+    }
+
+    /// Brightness from which colors are `White` rather than `Black`
+    pub const fn luma_threshold(mut self, threshold: u8) -> Self {
+        self.luma_threshold = threshold;
+        self
+    }
+
+    /// Chroma from which colors are `Chromatic`
+    pub const fn chroma_threshold(mut self, threshold: u8) -> Self {
+        self.chroma_threshold = threshold;
+        self
+    }
+
+    /// Only colors whose red component exceeds both the green and the blue ones by `margin`
+    /// are `Chromatic`
+    pub const fn red_only(mut self, margin: u8) -> Self {
+        self.red_margin = Some(margin);
+        self
+    }
+
+    /// Black or white color for `rgb`
+    pub fn map_color(&self, rgb: embedded_graphics_core::pixelcolor::Rgb888) -> Color {
+        use embedded_graphics_core::pixelcolor::RgbColor;
+        if self.is_light([rgb.r() as i16, rgb.g() as i16, rgb.b() as i16]) {
+            Color::White
+        } else {
+            Color::Black
+        }
+    }
+
+    /// Black, white or chromatic color for `rgb`
+    pub fn map_tricolor(&self, rgb: embedded_graphics_core::pixelcolor::Rgb888) -> TriColor {
+        use embedded_graphics_core::pixelcolor::RgbColor;
+        self.classify([rgb.r() as i16, rgb.g() as i16, rgb.b() as i16])
+    }
+
+    /// Same as `map_tricolor`, with components which may be out of the 0..=255 range
+    pub(crate) fn classify(&self, rgb: [i16; 3]) -> TriColor {
+        let [r, g, b] = rgb.map(i32::from);
+        let chroma = r.max(g).max(b) - r.min(g).min(b);
+        let red = match self.red_margin {
+            Some(margin) => r >= g.max(b) + margin as i32,
+            None => true,
+        };
+        if chroma >= self.chroma_threshold as i32 && red {
+            TriColor::Chromatic
+        } else if self.is_light(rgb) {
+            TriColor::White
+        } else {
+            TriColor::Black
+        }
+    }
+
+    /// Whether the perceived brightness of `rgb` reaches the threshold
+    pub(crate) fn is_light(&self, rgb: [i16; 3]) -> bool {
+        let [r, g, b] = rgb.map(i32::from);
+        (r * 299 + g * 587 + b * 114) / 1000 >= self.luma_threshold as i32
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -530,6 +1068,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_gray4_bitmask() {
+        assert_eq!(Gray4Epd::White.bitmask(false, 0), (0b00111111, 0b11000000));
+        assert_eq!(
+            Gray4Epd::LightGray.bitmask(false, 1),
+            (0b11001111, 0b00100000)
+        );
+        assert_eq!(
+            Gray4Epd::DarkGray.bitmask(false, 7),
+            (0b11111100, 0b00000001)
+        );
+        assert_eq!(Gray4Epd::LightGray.get_byte_value(), 0b10101010);
+        assert_eq!(Gray4Epd::from_bits(0b01), Gray4Epd::DarkGray);
+    }
+
+    #[test]
+    fn test_gray4_planes() {
+        // black, dark gray, light gray, white and back
+        let buffer = [0b00_01_10_11, 0b11_10_01_00];
+        assert_eq!(Gray4Epd::plane(&buffer, 8, 1).next(), Some(0b0011_1100));
+        assert_eq!(Gray4Epd::plane(&buffer, 8, 0).next(), Some(0b0101_1010));
+
+        // lines of 12 pixels take 3 bytes, and 2 bytes in each plane
+        let buffer = [0xFF, 0x00, 0b11_11_00_00, 0x00, 0xFF, 0b00_00_11_11];
+        let mut plane = Gray4Epd::plane(&buffer, 12, 1);
+        assert!(plane.by_ref().take(2).eq([0xF0, 0xC0]));
+        assert!(plane.eq([0x0F, 0x30]));
+    }
+
+    #[test]
+    fn test_hex_color() {
+        assert_eq!(
+            HexColor::from_nibble(HexColor::colors_byte(HexColor::Blue, HexColor::Red) >> 4),
+            Ok(HexColor::Blue)
+        );
+        assert_eq!(
+            HexColor::from_nibble(0x04),
+            Err(OutOfColorRangeParseError(0x04))
+        );
+        assert_eq!(HexColor::Green.bitmask(false, 1), (0xF0, 0x06));
+    }
+
+    #[test]
+    fn test_quad_color() {
+        assert_eq!(QuadColor::Red.bitmask(false, 2), (0b11110011, 0b00001100));
+        assert_eq!(QuadColor::White.get_byte_value(), 0b01010101);
+        assert_eq!(QuadColor::from_bits(0b10), QuadColor::Yellow);
+    }
+
     #[test]
     fn test_tricolor_bitmask() {
         assert_eq!(
@@ -558,4 +1145,35 @@ mod tests {
             (0b01111111, u16::from_le_bytes([0b00000000, 0b10000000]))
         );
     }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn test_color_mapper() {
+        use embedded_graphics_core::pixelcolor::{Rgb888, RgbColor};
+
+        let mapper = ColorMapper::default();
+        assert_eq!(mapper.map_tricolor(Rgb888::RED), TriColor::Chromatic);
+        assert_eq!(mapper.map_tricolor(Rgb888::YELLOW), TriColor::Chromatic);
+        assert_eq!(
+            mapper.map_tricolor(Rgb888::new(200, 200, 190)),
+            TriColor::White
+        );
+        assert_eq!(
+            mapper.map_tricolor(Rgb888::new(60, 50, 50)),
+            TriColor::Black
+        );
+        assert_eq!(mapper.map_color(Rgb888::new(120, 140, 130)), Color::White);
+
+        // a dull red with a red-only mapper, but no more yellow or blue
+        let mapper = ColorMapper::new().chroma_threshold(60).red_only(40);
+        assert_eq!(
+            mapper.map_tricolor(Rgb888::new(180, 80, 70)),
+            TriColor::Chromatic
+        );
+        assert_eq!(mapper.map_tricolor(Rgb888::YELLOW), TriColor::White);
+        assert_eq!(mapper.map_tricolor(Rgb888::BLUE), TriColor::Black);
+
+        let mapper = ColorMapper::new().luma_threshold(100);
+        assert_eq!(mapper.map_color(Rgb888::new(110, 110, 110)), Color::White);
+    }
 }
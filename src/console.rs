@@ -0,0 +1,236 @@
+//! Text printed on a region of a display, scrolling like a terminal
+//!
+//! A [`TextConsole`] keeps the last `ROWS` lines of text written to it, `COLUMNS` characters
+//! wide: long lines wrap, and the oldest line scrolls out when the console is full. Writing
+//! is done with [`core::fmt::Write`], and [`draw`](TextConsole::draw) renders the lines with
+//! a monospaced font of `embedded-graphics`. Needs the `console` feature.
+//!
+//! ```rust, no_run
+//!# use embedded_hal_mock::eh1::*;
+//!# fn main() -> Result<(), epd_waveshare::error::Error<embedded_hal::spi::ErrorKind>> {
+//!use core::fmt::Write;
+//!use embedded_graphics::{mono_font::ascii::FONT_6X10, prelude::*};
+//!use epd_waveshare::{console::TextConsole, epd2in13b_v4::*, prelude::*};
+//!#
+//!# let mut spi = spi::Mock::new(&[]);
+//!# let (busy_in, dc, rst) = (digital::Mock::new(&[]), digital::Mock::new(&[]), digital::Mock::new(&[]));
+//!# let mut delay = delay::NoopDelay::new();
+//!
+//!let mut epd = Epd2in13b::new(&mut spi, busy_in, dc, rst, &mut delay, None)?;
+//!let mut display = Display2in13b::default();
+//!display.set_rotation(DisplayRotation::Rotate90);
+//!// 41 columns and 12 rows of 6x10 characters fill the rotated 250x122 display
+//!let mut console = TextConsole::<_, 41, 12>::new(
+//!    Point::zero(),
+//!    &FONT_6X10,
+//!    TriColor::Black,
+//!    TriColor::White,
+//!);
+//!
+//!let _ = writeln!(console, "connecting to {}...", "wifi");
+//!let _ = console.draw(&mut display);
+//!epd.update_and_display_frame(&mut spi, display.buffer(), &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use core::fmt;
+
+use embedded_graphics::{
+    mono_font::{MonoFont, MonoTextStyle},
+    prelude::*,
+    primitives::Rectangle,
+    text::{Baseline, Text},
+};
+
+/// Character drawn for the characters which are not ASCII
+const REPLACEMENT: u8 = b'?';
+
+/// The last `ROWS` lines of text written to it, `COLUMNS` characters wide, drawn with a
+/// monospaced font
+///
+/// Only ASCII characters are kept, the others are replaced by `?`. `\n` starts a new line,
+/// the other control characters are ignored.
+pub struct TextConsole<C: PixelColor, const COLUMNS: usize, const ROWS: usize> {
+    lines: [[u8; COLUMNS]; ROWS],
+    lengths: [usize; ROWS],
+    /// line being written
+    row: usize,
+    /// the next character goes to a new line
+    newline: bool,
+    origin: Point,
+    style: MonoTextStyle<'static, C>,
+    font: &'static MonoFont<'static>,
+    background: C,
+}
+
+impl<C: PixelColor, const COLUMNS: usize, const ROWS: usize> TextConsole<C, COLUMNS, ROWS> {
+    /// Creates an empty console, its top left corner at `origin`
+    ///
+    /// The text is drawn with `font` in `text_color`, over `background`.
+    pub fn new(
+        origin: Point,
+        font: &'static MonoFont<'static>,
+        text_color: C,
+        background: C,
+    ) -> Self {
+        TextConsole {
+            lines: [[b' '; COLUMNS]; ROWS],
+            lengths: [0; ROWS],
+            row: 0,
+            newline: false,
+            origin,
+            style: MonoTextStyle::new(font, text_color),
+            font,
+            background,
+        }
+    }
+
+    /// Region of the display covered by the console
+    pub fn area(&self) -> Rectangle {
+        let character = self.font.character_size;
+        let width = COLUMNS as u32 * (character.width + self.font.character_spacing);
+        Rectangle::new(
+            self.origin,
+            Size::new(width, ROWS as u32 * character.height),
+        )
+    }
+
+    /// Removes all the lines
+    pub fn clear(&mut self) {
+        self.lengths = [0; ROWS];
+        self.row = 0;
+        self.newline = false;
+    }
+
+    /// The lines of text, from the oldest one
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.lines
+            .iter()
+            .zip(self.lengths)
+            .take(self.row + 1)
+            // only ASCII is stored
+            .map(|(line, length)| core::str::from_utf8(&line[..length]).unwrap_or_default())
+    }
+
+    /// Draws the console over its [`area`](Self::area), and returns it, e.g. for a partial
+    /// refresh
+    pub fn draw<D>(&self, target: &mut D) -> Result<Rectangle, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let area = self.area();
+        target.fill_solid(&area, self.background)?;
+        let mut position = self.origin;
+        for line in self.lines() {
+            Text::with_baseline(line, position, self.style, Baseline::Top).draw(target)?;
+            position.y += self.font.character_size.height as i32;
+        }
+        Ok(area)
+    }
+
+    /// Starts a new line, scrolling the oldest one out if the console is full
+    fn new_line(&mut self) {
+        if self.row + 1 < ROWS {
+            self.row += 1;
+        } else {
+            self.lines.rotate_left(1);
+            self.lengths.rotate_left(1);
+        }
+        self.lengths[self.row] = 0;
+        self.newline = false;
+    }
+
+    fn push(&mut self, c: char) {
+        if COLUMNS == 0 || ROWS == 0 {
+            return;
+        }
+        match c {
+            '\n' => {
+                // the line is only started with its first character, so that a final `\n`
+                // doesn't leave an empty line at the bottom
+                if self.newline {
+                    self.new_line();
+                }
+                self.newline = true;
+            }
+            c if c.is_ascii_control() => {}
+            c => {
+                if self.newline || self.lengths[self.row] == COLUMNS {
+                    self.new_line();
+                }
+                let byte = if c.is_ascii() { c as u8 } else { REPLACEMENT };
+                self.lines[self.row][self.lengths[self.row]] = byte;
+                self.lengths[self.row] += 1;
+            }
+        }
+    }
+}
+
+impl<C: PixelColor, const COLUMNS: usize, const ROWS: usize> fmt::Write
+    for TextConsole<C, COLUMNS, ROWS>
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        s.chars().for_each(|c| self.push(c));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::graphics::Display;
+    use core::fmt::Write;
+    use embedded_graphics::mono_font::ascii::FONT_6X10;
+
+    extern crate std;
+    use std::vec::Vec;
+
+    type Console = TextConsole<Color, 4, 3>;
+
+    fn console() -> Console {
+        TextConsole::new(Point::new(8, 2), &FONT_6X10, Color::Black, Color::White)
+    }
+
+    #[test]
+    fn wraps_and_scrolls() {
+        let mut console = console();
+        assert_eq!(
+            console.area(),
+            Rectangle::new(Point::new(8, 2), Size::new(24, 30))
+        );
+        assert_eq!(console.lines().collect::<Vec<_>>(), [""]);
+
+        writeln!(console, "abcdef").unwrap();
+        assert_eq!(console.lines().collect::<Vec<_>>(), ["abcd", "ef"]);
+        // a new line only starts with its first character
+        write!(console, "\nx\u{e9}\r").unwrap();
+        assert_eq!(console.lines().collect::<Vec<_>>(), ["ef", "", "x?"]);
+        writeln!(console, "x\u{e9}").unwrap();
+        assert_eq!(console.lines().collect::<Vec<_>>(), ["ef", "", "x?x?"]);
+        write!(console, "z").unwrap();
+        assert_eq!(console.lines().collect::<Vec<_>>(), ["", "x?x?", "z"]);
+
+        console.clear();
+        assert_eq!(console.lines().collect::<Vec<_>>(), [""]);
+    }
+
+    #[test]
+    fn draw() {
+        let mut console = console();
+        writeln!(console, "one\ntwo").unwrap();
+        let mut display = Display::<40, 40, false, 200, Color>::default();
+        assert_eq!(console.draw(&mut display), Ok(console.area()));
+
+        let mut expected = Display::<40, 40, false, 200, Color>::default();
+        let style = MonoTextStyle::new(&FONT_6X10, Color::Black);
+        expected.fill_solid(&console.area(), Color::White).unwrap();
+        for (text, y) in [("one", 2), ("two", 12)] {
+            Text::with_baseline(text, Point::new(8, y), style, Baseline::Top)
+                .draw(&mut expected)
+                .unwrap();
+        }
+        assert_eq!(display.buffer(), expected.buffer());
+    }
+}
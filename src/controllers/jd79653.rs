@@ -0,0 +1,183 @@
+//! Commands of the JD79653 family of controllers, driving the 4 color (G) panels
+//!
+//! The panels store 2 bits per pixel ([`QuadColor`](crate::color::QuadColor)) in a single RAM,
+//! written with [`Command::DataStartTransmission`]. Most of their init is made of vendor
+//! registers, so each driver keeps its own list of them and [`Jd79653`] sends it, along with
+//! the resolution and the refresh sequence.
+
+use embedded_hal::{delay::DelayNs, digital::InputPin, digital::OutputPin, spi::SpiDevice};
+
+use crate::error::Error;
+use crate::interface::DisplayInterface;
+use crate::traits;
+
+/// The busy pin is low while the controller is busy
+pub(crate) const IS_BUSY_LOW: bool = true;
+
+/// JD79653 commands
+///
+/// The `OxNN` ones are undocumented, and set as in the Waveshare code.
+#[allow(dead_code, clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Command {
+    PanelSetting = 0x00,
+    PowerSetting = 0x01,
+    /// Powers off after the refresh, one data byte (0x00)
+    PowerOff = 0x02,
+    PowerOffSequenceSetting = 0x03,
+    PowerOn = 0x04,
+    BoosterSoftStart = 0x06,
+    /// Deep sleep, with the check code 0xA5
+    DeepSleep = 0x07,
+    /// The pixels of the frame, 2 bits each
+    DataStartTransmission = 0x10,
+    /// Refreshes the display, one data byte (0x00)
+    DisplayRefresh = 0x12,
+    PllControl = 0x30,
+    VcomAndDataIntervalSetting = 0x50,
+    TconSetting = 0x60,
+    ResolutionSetting = 0x61,
+
+    Ox4D = 0x4D,
+    Ox66 = 0x66,
+    Ox84 = 0x84,
+    OxB0 = 0xB0,
+    OxB4 = 0xB4,
+    OxB5 = 0xB5,
+    OxE3 = 0xE3,
+    OxE7 = 0xE7,
+    OxE9 = 0xE9,
+}
+
+impl traits::Command for Command {
+    /// Returns the address of the command
+    fn address(self) -> u8 {
+        self as u8
+    }
+}
+
+/// Init, refresh and sleep of a `WIDTH` x `HEIGHT` panel driven by a JD79653
+pub(crate) struct Jd79653<const WIDTH: u32, const HEIGHT: u32>;
+
+impl<const WIDTH: u32, const HEIGHT: u32> Jd79653<WIDTH, HEIGHT> {
+    /// Number of bytes of a frame, the lines being padded to whole bytes
+    pub(crate) const FRAME_LEN: usize = crate::buffer_len(WIDTH as usize * 2, HEIGHT as usize);
+
+    /// Resets the controller and sends the `registers` of the panel, then its resolution
+    pub(crate) fn init<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>(
+        interface: &mut DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        registers: &[(Command, &[u8])],
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        interface.reset(delay, 20_000, 2_000)?;
+        interface.wait_until_idle(delay, IS_BUSY_LOW)?;
+
+        for &(command, data) in registers {
+            interface.cmd_with_data(spi, command, data)?;
+        }
+        interface.cmd_with_data(
+            spi,
+            Command::ResolutionSetting,
+            &[
+                (WIDTH >> 8) as u8,
+                WIDTH as u8,
+                (HEIGHT >> 8) as u8,
+                HEIGHT as u8,
+            ],
+        )
+    }
+
+    /// Powers on, refreshes the display with the frame in RAM and powers off again
+    pub(crate) fn refresh<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>(
+        interface: &mut DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        interface.cmd(spi, Command::PowerOn)?;
+        interface.wait_until_idle(delay, IS_BUSY_LOW)?;
+        interface.cmd_with_data(spi, Command::DisplayRefresh, &[0x00])?;
+        interface.wait_until_idle(delay, IS_BUSY_LOW)?;
+        interface.cmd_with_data(spi, Command::PowerOff, &[0x00])?;
+        interface.wait_until_idle(delay, IS_BUSY_LOW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::Command as CommandTrait;
+    use embedded_hal_mock::eh1::{delay::NoopDelay, digital, spi};
+
+    extern crate std;
+
+    #[test]
+    fn command_addr() {
+        assert_eq!(Command::DataStartTransmission.address(), 0x10);
+        assert_eq!(Command::ResolutionSetting.address(), 0x61);
+        assert_eq!(Command::OxE9.address(), 0xE9);
+    }
+
+    #[test]
+    fn frame_len() {
+        // lines of 122 pixels take 31 bytes
+        assert_eq!(Jd79653::<122, 250>::FRAME_LEN, 31 * 250);
+        assert_eq!(Jd79653::<168, 400>::FRAME_LEN, 42 * 400);
+    }
+
+    #[test]
+    fn resolution() {
+        let mut spi_expectations = std::vec::Vec::new();
+        for byte in [0x4D, 0x78, 0x61, 0x00, 0xA8, 0x01, 0x90] {
+            spi_expectations.push(spi::Transaction::transaction_start());
+            spi_expectations.push(spi::Transaction::write_vec(std::vec![byte]));
+            spi_expectations.push(spi::Transaction::transaction_end());
+        }
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut busy = digital::Mock::new(&[digital::Transaction::get(digital::State::High)]);
+        let mut dc = digital::Mock::new(&[
+            digital::Transaction::set(digital::State::Low),
+            digital::Transaction::set(digital::State::High),
+            digital::Transaction::set(digital::State::Low),
+            digital::Transaction::set(digital::State::High),
+        ]);
+        let mut rst = digital::Mock::new(&[
+            digital::Transaction::set(digital::State::High),
+            digital::Transaction::set(digital::State::Low),
+            digital::Transaction::set(digital::State::High),
+        ]);
+        let mut interface = DisplayInterface::<_, _, _, _, NoopDelay, true>::new(
+            busy.clone(),
+            dc.clone(),
+            rst.clone(),
+            None,
+        );
+
+        Jd79653::<168, 400>::init(
+            &mut interface,
+            &mut spi,
+            &mut NoopDelay,
+            &[(Command::Ox4D, &[0x78])],
+        )
+        .unwrap();
+
+        spi.done();
+        busy.done();
+        dc.done();
+        rst.done();
+    }
+}
@@ -0,0 +1,26 @@
+//! Controllers shared by several displays
+//!
+//! Panels of different sizes often use the same controller. Their commands and the logic
+//! which only depends on the resolution live here, so each driver only adds what is specific
+//! to its panel.
+
+#[cfg(any(
+    feature = "epd1in64g",
+    feature = "epd2in13g",
+    feature = "epd2in36g",
+    feature = "epd3in0g"
+))]
+pub(crate) mod jd79653;
+#[cfg(any(feature = "epd4in26", feature = "epd13in3k"))]
+pub(crate) mod ssd1677;
+// also for the commands of the SSD1677
+#[cfg(any(
+    feature = "epd2in13b_v4",
+    feature = "epd2in66",
+    feature = "epd2in66b",
+    feature = "epd4in26",
+    feature = "epd13in3k"
+))]
+pub(crate) mod ssd1680;
+#[cfg(feature = "epd2in9d")]
+pub(crate) mod uc8151;
@@ -0,0 +1,105 @@
+//! RAM addressing of the SSD1677 controller, driving the large panels like the 13.3" K
+//!
+//! The SSD1677 shares the commands of the [SSD1680](super::ssd1680), but has up to 960
+//! sources: its X addresses are counted in pixels on 16 bits instead of bytes on 8 bits.
+
+use embedded_hal::{delay::DelayNs, digital::InputPin, digital::OutputPin, spi::SpiDevice};
+
+pub(crate) use super::ssd1680::{Command, IS_BUSY_LOW};
+use crate::error::Error;
+use crate::interface::DisplayInterface;
+
+/// RAM window and address counters of a `WIDTH` x `HEIGHT` panel driven by a SSD1677
+pub(crate) struct Ssd1677<const WIDTH: u32, const HEIGHT: u32>;
+
+impl<const WIDTH: u32, const HEIGHT: u32> Ssd1677<WIDTH, HEIGHT> {
+    /// Sets both X and Y pixels ranges
+    pub(crate) fn set_ram_area<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>(
+        interface: &mut DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+        spi: &mut SPI,
+        start_x: u32,
+        start_y: u32,
+        end_x: u32,
+        end_y: u32,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        interface.cmd_with_data(
+            spi,
+            Command::SetRamXAddressStartEndPosition,
+            &[
+                start_x as u8,
+                (start_x >> 8) as u8,
+                end_x as u8,
+                (end_x >> 8) as u8,
+            ],
+        )?;
+        interface.cmd_with_data(
+            spi,
+            Command::SetRamYAddressStartEndPosition,
+            &[
+                start_y as u8,
+                (start_y >> 8) as u8,
+                end_y as u8,
+                (end_y >> 8) as u8,
+            ],
+        )
+    }
+
+    /// Sets the position of the next pixel written to the RAM
+    pub(crate) fn set_ram_address_counters<
+        SPI,
+        BUSY,
+        DC,
+        RST,
+        DELAY,
+        const SINGLE_BYTE_WRITE: bool,
+    >(
+        interface: &mut DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        x: u32,
+        y: u32,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        interface.wait_until_idle(delay, IS_BUSY_LOW)?;
+        interface.cmd_with_data(
+            spi,
+            Command::SetRamXAddressCounter,
+            &[x as u8, (x >> 8) as u8],
+        )?;
+        interface.cmd_with_data(
+            spi,
+            Command::SetRamYAddressCounter,
+            &[y as u8, (y >> 8) as u8],
+        )
+    }
+
+    /// Restores the RAM window to the whole panel, with the counters at its start
+    pub(crate) fn set_full_window<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>(
+        interface: &mut DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        Self::set_ram_area(interface, spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
+        Self::set_ram_address_counters(interface, spi, delay, 0, 0)
+    }
+}
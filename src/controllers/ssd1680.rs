@@ -0,0 +1,533 @@
+//! Commands and RAM addressing of the SSD1680 controller, also found as SSD1681
+//!
+//! Drivers of panels using it keep their own [`DisplayInterface`] and use [`Ssd1680`] for the
+//! parts which only depend on the resolution: the RAM window, the address counters and the
+//! usual init sequence. Only the waveforms, voltages and refresh logic are left to them.
+
+#[cfg(any(feature = "epd2in13b_v4", feature = "epd2in66", feature = "epd2in66b"))]
+use embedded_hal::{delay::DelayNs, digital::InputPin, digital::OutputPin, spi::SpiDevice};
+
+#[cfg(feature = "epd2in13b_v4")]
+use crate::color::Gray4Epd;
+use crate::traits;
+#[cfg(any(feature = "epd2in13b_v4", feature = "epd2in66"))]
+pub(crate) use crate::traits::DeepSleepMode;
+#[cfg(any(feature = "epd2in13b_v4", feature = "epd2in66", feature = "epd2in66b"))]
+use crate::{error::Error, interface::DisplayInterface};
+
+extern crate bit_field;
+#[cfg(any(feature = "epd2in13b_v4", feature = "epd2in66"))]
+use bit_field::BitField;
+
+/// The busy pin of the SSD1680 is high while busy
+pub(crate) const IS_BUSY_LOW: bool = false;
+
+/// SSD1680 commands
+///
+/// For more infos about the addresses and what they are doing look into the datasheet
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub(crate) enum Command {
+    DriverOutputControl = 0x01,
+    GateDrivingVoltageCtrl = 0x03,
+    SourceDrivingVoltageCtrl = 0x04,
+    BoosterSoftStartControl = 0x0C,
+    DeepSleepMode = 0x10,
+    DataEntryModeSetting = 0x11,
+    SwReset = 0x12,
+    TemperatureSensorSelection = 0x18,
+    TemperatureSensorWrite = 0x1A,
+    TemperatureSensorRead = 0x1B,
+    MasterActivation = 0x20,
+    DisplayUpdateControl1 = 0x21,
+    DisplayUpdateControl2 = 0x22,
+    WriteRam = 0x24,
+    WriteRamRed = 0x26,
+    ReadRam = 0x27,
+    WriteVcomRegister = 0x2C,
+    StatusBitRead = 0x2F,
+    WriteLutRegister = 0x32,
+    WriteLutRegisterEnd = 0x3F,
+    BorderWaveformControl = 0x3C,
+    ReadRamOption = 0x41,
+    SetRamXAddressStartEndPosition = 0x44,
+    SetRamYAddressStartEndPosition = 0x45,
+    SetRamXAddressCounter = 0x4E,
+    SetRamYAddressCounter = 0x4F,
+}
+
+#[cfg(any(feature = "epd2in13b_v4", feature = "epd2in66"))]
+pub(crate) struct DriverOutput {
+    pub scan_is_linear: bool,
+    pub scan_g0_is_first: bool,
+    pub scan_dir_incr: bool,
+    pub width: u16,
+}
+
+#[cfg(any(feature = "epd2in13b_v4", feature = "epd2in66"))]
+impl DriverOutput {
+    pub fn to_bytes(&self) -> [u8; 3] {
+        [
+            self.width as u8,
+            (self.width >> 8) as u8,
+            *0u8.set_bit(0, !self.scan_dir_incr)
+                .set_bit(1, !self.scan_g0_is_first)
+                .set_bit(2, !self.scan_is_linear),
+        ]
+    }
+}
+
+/// How the content of a RAM is used by a refresh
+#[cfg(any(feature = "epd2in13b_v4", feature = "epd2in66"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum RamOption {
+    /// The content is shown as written
+    #[default]
+    Normal = 0x0,
+    /// The content is ignored and read as 0, e.g. to ignore the red RAM for black and white
+    /// refreshes
+    BypassAs0 = 0x4,
+    /// The content is shown inverted
+    // only set by the users of the 2.13" (B) V4, which exports it
+    #[cfg_attr(not(feature = "epd2in13b_v4"), allow(dead_code))]
+    Inverse = 0x8,
+}
+
+#[cfg(any(feature = "epd2in13b_v4", feature = "epd2in66"))]
+pub(crate) struct DisplayUpdateControl {
+    pub red_ram_option: RamOption,
+    pub bw_ram_option: RamOption,
+    pub source_output_mode: bool,
+}
+
+#[cfg(any(feature = "epd2in13b_v4", feature = "epd2in66"))]
+impl DisplayUpdateControl {
+    pub fn to_bytes(&self) -> [u8; 2] {
+        [
+            ((self.red_ram_option as u8) << 4) | (self.bw_ram_option as u8),
+            if self.source_output_mode { 128 } else { 0 },
+        ]
+    }
+}
+
+#[allow(dead_code, clippy::enum_variant_names)]
+pub(crate) enum DataEntryModeIncr {
+    XDecrYDecr = 0x0,
+    XIncrYDecr = 0x1,
+    XDecrYIncr = 0x2,
+    XIncrYIncr = 0x3,
+}
+
+#[allow(dead_code)]
+pub(crate) enum DataEntryModeDir {
+    XDir = 0x0,
+    YDir = 0x4,
+}
+
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub(crate) enum BorderWaveFormVbd {
+    Gs = 0x0,
+    FixLevel = 0x1,
+    Vcom = 0x2,
+}
+
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub(crate) enum BorderWaveFormFixLevel {
+    Vss = 0x0,
+    Vsh1 = 0x1,
+    Vsl = 0x2,
+    Vsh2 = 0x3,
+}
+
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub(crate) enum BorderWaveFormGs {
+    Lut0 = 0x0,
+    Lut1 = 0x1,
+    Lut2 = 0x2,
+    Lut3 = 0x3,
+}
+
+#[cfg(feature = "epd2in13b_v4")]
+pub(crate) struct BorderWaveForm {
+    pub vbd: BorderWaveFormVbd,
+    pub fix_level: BorderWaveFormFixLevel,
+    pub gs_trans: BorderWaveFormGs,
+}
+
+#[cfg(feature = "epd2in13b_v4")]
+impl BorderWaveForm {
+    pub const fn to_u8(&self) -> u8 {
+        (self.vbd as u8) << 6 | (self.fix_level as u8) << 4 | self.gs_trans as u8
+    }
+}
+
+/// Gate driving voltage (VGH), from 10V to 21V
+///
+/// Higher voltages give more contrast, at the cost of the lifetime of the panel.
+#[cfg(feature = "epd2in13b_v4")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GateVoltage(u8);
+
+#[cfg(feature = "epd2in13b_v4")]
+impl GateVoltage {
+    /// 20V, the usual value
+    pub const DEFAULT: Self = GateVoltage(0x17);
+
+    /// Voltage from the raw register value, see the SSD1680 datasheet
+    pub const fn from_raw(value: u8) -> Self {
+        GateVoltage(value)
+    }
+
+    /// Voltage in tenths of volts, from 100 to 210 by steps of 5
+    pub const fn from_decivolts(decivolts: u16) -> Option<Self> {
+        if decivolts < 100 || decivolts > 210 || decivolts % 5 != 0 {
+            return None;
+        }
+        Some(GateVoltage(((decivolts - 100) / 5 + 0x03) as u8))
+    }
+
+    /// The raw register value
+    pub const fn to_raw(self) -> u8 {
+        self.0
+    }
+}
+
+#[cfg(feature = "epd2in13b_v4")]
+impl Default for GateVoltage {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Source driving voltages: VSH1 and VSH2 for white and red, VSL for black
+#[cfg(feature = "epd2in13b_v4")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceVoltage {
+    vsh1: u8,
+    vsh2: u8,
+    vsl: u8,
+}
+
+#[cfg(feature = "epd2in13b_v4")]
+impl SourceVoltage {
+    /// VSH1 at 15V, VSH2 unset and VSL at -15V, the usual values
+    pub const DEFAULT: Self = SourceVoltage::from_raw(0x41, 0x00, 0x32);
+
+    /// Voltages from the raw register values, see the SSD1680 datasheet
+    pub const fn from_raw(vsh1: u8, vsh2: u8, vsl: u8) -> Self {
+        SourceVoltage { vsh1, vsh2, vsl }
+    }
+
+    /// Voltages in tenths of volts, VSH1 and VSH2 from 24 to 170 and VSL from -170 to -90
+    ///
+    /// VSH steps are 0.1V up to 8.8V, then 0.2V from 9V. VSL steps are 0.5V.
+    pub const fn from_decivolts(vsh1: i16, vsh2: i16, vsl: i16) -> Option<Self> {
+        let (vsh1, vsh2, vsl) = match (vsh(vsh1), vsh(vsh2), vsl_raw(vsl)) {
+            (Some(vsh1), Some(vsh2), Some(vsl)) => (vsh1, vsh2, vsl),
+            _ => return None,
+        };
+        Some(SourceVoltage { vsh1, vsh2, vsl })
+    }
+
+    /// The raw register values, in the order they are written
+    pub const fn to_raw(self) -> [u8; 3] {
+        [self.vsh1, self.vsh2, self.vsl]
+    }
+}
+
+#[cfg(feature = "epd2in13b_v4")]
+impl Default for SourceVoltage {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Register value of a VSH voltage
+#[cfg(feature = "epd2in13b_v4")]
+const fn vsh(decivolts: i16) -> Option<u8> {
+    match decivolts {
+        24..=88 => Some((decivolts - 24) as u8 + 0x8E),
+        90..=170 if decivolts % 2 == 0 => Some(((decivolts - 90) / 2) as u8 + 0x23),
+        _ => None,
+    }
+}
+
+/// Register value of a VSL voltage
+#[cfg(feature = "epd2in13b_v4")]
+const fn vsl_raw(decivolts: i16) -> Option<u8> {
+    match decivolts {
+        -170..=-90 if decivolts % 5 == 0 => Some(((-decivolts - 90) / 5 * 2) as u8 + 0x1A),
+        _ => None,
+    }
+}
+
+/// Strength and duration of the phases of the booster which generates the driving voltages
+///
+/// Panels with a weak booster, e.g. driven from a low battery, refresh more reliably with a
+/// longer, softer start.
+#[cfg(feature = "epd2in13b_v4")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoosterSoftStart {
+    phase1: u8,
+    phase2: u8,
+    phase3: u8,
+    duration: u8,
+}
+
+#[cfg(feature = "epd2in13b_v4")]
+impl BoosterSoftStart {
+    /// Values of the controller after a reset
+    pub const DEFAULT: Self = BoosterSoftStart::from_raw(0x8B, 0x9C, 0x96, 0x0F);
+
+    /// Booster from the raw register values of the three phases and their durations, see the
+    /// SSD1680 datasheet
+    pub const fn from_raw(phase1: u8, phase2: u8, phase3: u8, duration: u8) -> Self {
+        BoosterSoftStart {
+            phase1,
+            phase2,
+            phase3,
+            duration,
+        }
+    }
+
+    /// The raw register values, in the order they are written
+    pub const fn to_raw(self) -> [u8; 4] {
+        [self.phase1, self.phase2, self.phase3, self.duration]
+    }
+}
+
+#[cfg(feature = "epd2in13b_v4")]
+impl Default for BoosterSoftStart {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl traits::Command for Command {
+    /// Returns the address of the command
+    fn address(self) -> u8 {
+        self as u8
+    }
+}
+
+/// Commands and RAM addressing of a `WIDTH` x `HEIGHT` panel driven by a SSD1680
+///
+/// The RAM is addressed in whole bytes on the X axis: the X coordinates passed to it are
+/// rounded down to a multiple of 8.
+#[cfg(any(feature = "epd2in13b_v4", feature = "epd2in66", feature = "epd2in66b"))]
+pub(crate) struct Ssd1680<const WIDTH: u32, const HEIGHT: u32>;
+
+#[cfg(any(feature = "epd2in13b_v4", feature = "epd2in66", feature = "epd2in66b"))]
+impl<const WIDTH: u32, const HEIGHT: u32> Ssd1680<WIDTH, HEIGHT> {
+    /// Number of bytes of a frame of 4 gray levels, see [`write_gray_frame`](Self::write_gray_frame)
+    #[cfg(feature = "epd2in13b_v4")]
+    pub(crate) const GRAY_FRAME_LEN: usize = crate::buffer_len(WIDTH as usize * 2, HEIGHT as usize);
+
+    /// Software reset, waiting for the controller before and after it
+    #[cfg(any(feature = "epd2in13b_v4", feature = "epd2in66"))]
+    pub(crate) fn sw_reset<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>(
+        interface: &mut DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        interface.wait_until_idle(delay, IS_BUSY_LOW)?;
+        interface.cmd(spi, Command::SwReset)?;
+        interface.wait_until_idle(delay, IS_BUSY_LOW)
+    }
+
+    /// Scans all the gates of the panel and writes the RAM row by row, from the top left
+    /// corner, in a window covering the whole panel
+    #[cfg(any(feature = "epd2in13b_v4", feature = "epd2in66"))]
+    pub(crate) fn init_addressing<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>(
+        interface: &mut DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        let output = DriverOutput {
+            scan_is_linear: true,
+            scan_g0_is_first: true,
+            scan_dir_incr: true,
+            width: (HEIGHT - 1) as u16,
+        };
+        interface.cmd_with_data(spi, Command::DriverOutputControl, &output.to_bytes())?;
+
+        let mode = DataEntryModeIncr::XIncrYIncr as u8 | DataEntryModeDir::XDir as u8;
+        interface.cmd_with_data(spi, Command::DataEntryModeSetting, &[mode])?;
+
+        Self::set_full_window(interface, spi, delay)
+    }
+
+    /// Sets both X and Y pixels ranges
+    pub(crate) fn set_ram_area<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>(
+        interface: &mut DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+        spi: &mut SPI,
+        start_x: u32,
+        start_y: u32,
+        end_x: u32,
+        end_y: u32,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        interface.cmd_with_data(
+            spi,
+            Command::SetRamXAddressStartEndPosition,
+            &[(start_x >> 3) as u8, (end_x >> 3) as u8],
+        )?;
+
+        interface.cmd_with_data(
+            spi,
+            Command::SetRamYAddressStartEndPosition,
+            &[
+                start_y as u8,
+                (start_y >> 8) as u8,
+                end_y as u8,
+                (end_y >> 8) as u8,
+            ],
+        )
+    }
+
+    /// Sets both X and Y pixels counters when writing data to RAM
+    pub(crate) fn set_ram_address_counters<
+        SPI,
+        BUSY,
+        DC,
+        RST,
+        DELAY,
+        const SINGLE_BYTE_WRITE: bool,
+    >(
+        interface: &mut DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        x: u32,
+        y: u32,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        interface.wait_until_idle(delay, IS_BUSY_LOW)?;
+        interface.cmd_with_data(spi, Command::SetRamXAddressCounter, &[(x >> 3) as u8])?;
+        interface.cmd_with_data(
+            spi,
+            Command::SetRamYAddressCounter,
+            &[y as u8, (y >> 8) as u8],
+        )
+    }
+
+    /// Restores the RAM window to the whole panel, with the counters at its start
+    pub(crate) fn set_full_window<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>(
+        interface: &mut DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        Self::set_ram_area(interface, spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
+        Self::set_ram_address_counters(interface, spi, delay, 0, 0)
+    }
+
+    /// Writes a full frame of 4 gray levels, 2 bits per pixel, to the two RAMs
+    ///
+    /// The high bits of the pixels go to the B/W RAM and the low bits to the red RAM, the
+    /// LUT selected by both bits of a pixel driving it to its gray level.
+    #[cfg(feature = "epd2in13b_v4")]
+    pub(crate) fn write_gray_frame<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>(
+        interface: &mut DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        Error::check_len(buffer, Self::GRAY_FRAME_LEN)?;
+        for (command, bit) in [(Command::WriteRam, 1), (Command::WriteRamRed, 0)] {
+            Self::set_ram_address_counters(interface, spi, delay, 0, 0)?;
+            interface.cmd(spi, command)?;
+            interface.data_iter(spi, Gray4Epd::plane(buffer, WIDTH, bit))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::Command as CommandTrait;
+
+    #[test]
+    fn command_addr() {
+        assert_eq!(Command::DriverOutputControl.address(), 0x01);
+        assert_eq!(Command::ReadRam.address(), 0x27);
+        assert_eq!(Command::SetRamYAddressCounter.address(), 0x4F);
+    }
+
+    #[test]
+    fn voltages() {
+        assert_eq!(GateVoltage::from_decivolts(200), Some(GateVoltage::DEFAULT));
+        assert_eq!(GateVoltage::from_decivolts(100).unwrap().to_raw(), 0x03);
+        assert_eq!(GateVoltage::from_decivolts(99), None);
+        assert_eq!(GateVoltage::from_decivolts(212), None);
+
+        assert_eq!(
+            SourceVoltage::from_decivolts(150, 24, -150)
+                .unwrap()
+                .to_raw(),
+            [0x41, 0x8E, 0x32]
+        );
+        assert_eq!(SourceVoltage::from_decivolts(89, 150, -150), None);
+        assert_eq!(SourceVoltage::from_decivolts(150, 150, -152), None);
+    }
+
+    #[test]
+    fn command_data() {
+        let output = DriverOutput {
+            scan_is_linear: true,
+            scan_g0_is_first: true,
+            scan_dir_incr: false,
+            width: 295,
+        };
+        assert_eq!(output.to_bytes(), [0x27, 0x01, 0x01]);
+
+        let border = BorderWaveForm {
+            vbd: BorderWaveFormVbd::FixLevel,
+            fix_level: BorderWaveFormFixLevel::Vsl,
+            gs_trans: BorderWaveFormGs::Lut1,
+        };
+        assert_eq!(border.to_u8(), 0x61);
+    }
+}
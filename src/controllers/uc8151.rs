@@ -0,0 +1,431 @@
+//! Commands of the UC8151 controller, also sold as IL0373
+//!
+//! Used by many Waveshare and GoodDisplay panels, e.g. the flexible 2.9" D. Drivers keep their
+//! own [`DisplayInterface`] and use [`Uc8151`] for the resolution, the partial window and the
+//! LUTs, and the typed settings below for the panel, power and VCOM registers.
+
+use embedded_hal::{delay::DelayNs, digital::InputPin, digital::OutputPin, spi::SpiDevice};
+
+use crate::error::Error;
+use crate::interface::DisplayInterface;
+use crate::traits;
+
+extern crate bit_field;
+use bit_field::BitField;
+
+/// UC8151 commands
+///
+/// For more infos about the addresses and what they are doing look into the datasheet
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub(crate) enum Command {
+    PanelSetting = 0x00,
+    /// selecting internal and external power
+    ///    self.send_data(0x03)?; //VDS_EN, VDG_EN
+    ///    self.send_data(0x00)?; //VCOM_HV, VGHL_LV[1], VGHL_LV[0]
+    ///    self.send_data(0x2b)?; //VDH
+    ///    self.send_data(0x2b)?; //VDL
+    ///    self.send_data(0xff)?; //VDHR
+    PowerSetting = 0x01,
+    /// After the Power Off command, the driver will power off following the Power Off Sequence. This command will turn off charge
+    /// pump, T-con, source driver, gate driver, VCOM, and temperature sensor, but register data will be kept until VDD becomes OFF.
+    /// Source Driver output and Vcom will remain as previous condition, which may have 2 conditions: floating.
+    PowerOff = 0x02,
+    /// Setting Power OFF sequence
+    PowerOffSequenceSetting = 0x03,
+    /// Turning On the Power
+    PowerOn = 0x04,
+    /// This command enables the internal bandgap, which will be cleared by the next POF.
+    PowerOnMeasure = 0x05,
+    /// Starting data transmission
+    ///     3-times: self.send_data(0x17)?; //07 0f 17 1f 27 2F 37 2f
+    BoosterSoftStart = 0x06,
+    /// After this command is transmitted, the chip would enter the deep-sleep mode to save power.
+    ///
+    /// The deep sleep mode would return to standby by hardware reset.
+    ///
+    /// The only one parameter is a check code, the command would be excuted if check code = 0xA5.
+    DeepSleep = 0x07,
+    /// This command starts transmitting data and write them into SRAM. To complete data transmission, command DSP (Data
+    /// transmission Stop) must be issued. Then the chip will start to send data/VCOM for panel.
+    ///
+    /// - In B/W mode, this command writes “OLD” data to SRAM.
+    /// - In B/W/Red mode, this command writes “B/W” data to SRAM.
+    /// - In Program mode, this command writes “OTP” data to SRAM for programming.
+    DataStartTransmission1 = 0x10,
+    /// Stopping data transmission
+    DataStop = 0x11,
+    /// While user sent this command, driver will refresh display (data/VCOM) according to SRAM data and LUT.
+    ///
+    /// After Display Refresh command, BUSY_N signal will become “0” and the refreshing of panel starts.
+    DisplayRefresh = 0x12,
+    /// This command starts transmitting data and write them into SRAM. To complete data transmission, command DSP (Data
+    /// transmission Stop) must be issued. Then the chip will start to send data/VCOM for panel.
+    /// - In B/W mode, this command writes “NEW” data to SRAM.
+    /// - In B/W/Red mode, this command writes “RED” data to SRAM.
+    DataStartTransmission2 = 0x13,
+
+    /// This command stores VCOM Look-Up Table with 7 groups of data. Each group contains information for one state and is stored
+    /// with 6 bytes, while the sixth byte indicates how many times that phase will repeat.
+    ///
+    /// from IL0373
+    LutForVcom = 0x20,
+    /// This command stores White-to-White Look-Up Table with 7 groups of data. Each group contains information for one state and is
+    /// stored with 6 bytes, while the sixth byte indicates how many times that phase will repeat.
+    ///
+    /// from IL0373
+    LutWhiteToWhite = 0x21,
+    /// This command stores Black-to-White Look-Up Table with 7 groups of data. Each group contains information for one state and is
+    /// stored with 6 bytes, while the sixth byte indicates how many times that phase will repeat.
+    ///
+    /// from IL0373
+    LutBlackToWhite = 0x22,
+    /// This command stores White-to-Black Look-Up Table with 7 groups of data. Each group contains information for one state and is
+    /// stored with 6 bytes, while the sixth byte indicates how many times that phase will repeat.
+    ///
+    /// from IL0373
+    LutWhiteToBlack = 0x23,
+    /// This command stores Black-to-Black Look-Up Table with 7 groups of data. Each group contains information for one state and is
+    /// stored with 6 bytes, while the sixth byte indicates how many times that phase will repeat.
+    ///
+    /// from IL0373
+    LutBlackToBlack = 0x24,
+    /// The command controls the PLL clock frequency.
+    PllControl = 0x30,
+    /// This command reads the temperature sensed by the temperature sensor.
+    ///
+    /// Doesn't work! Waveshare doesn't connect the read pin
+    TemperatureSensor = 0x40,
+    /// Selects the Internal or External temperature sensor and offset
+    TemperatureSensorSelection = 0x41,
+    /// Write External Temperature Sensor
+    TemperatureSensorWrite = 0x42,
+    /// Read External Temperature Sensor
+    ///
+    /// Doesn't work! Waveshare doesn't connect the read pin
+    TemperatureSensorRead = 0x43,
+    /// This command indicates the interval of Vcom and data output. When setting the vertical back porch, the total blanking will be kept (20 Hsync)
+    VcomAndDataIntervalSetting = 0x50,
+    /// This command indicates the input power condition. Host can read this flag to learn the battery condition.
+    LowPowerDetection = 0x51,
+    /// This command defines non-overlap period of Gate and Source.
+    TconSetting = 0x60,
+    /// This command defines alternative resolution and this setting is of higher priority than the RES\[1:0\] in R00H (PSR).
+    ResolutionSetting = 0x61,
+    /// This command defines the Fist Active Gate and First Active Source of active channels.
+    // GsstSetting = 0x65,
+    /// The LUT_REV / Chip Revision is read from OTP address = 0x001.
+    ///
+    /// Doesn't work! Waveshare doesn't connect the read pin
+    // Revision = 0x70,
+    /// Read Flags. This command reads the IC status
+    /// PTL, I2C_ERR, I2C_BUSY, DATA, PON, POF, BUSY
+    ///
+    /// Doesn't work! Waveshare doesn't connect the read pin
+    GetStatus = 0x71,
+    /// Automatically measure VCOM. This command reads the IC status
+    AutoMeasurementVcom = 0x80,
+    /// This command gets the VCOM value
+    ///
+    /// Doesn't work! Waveshare doesn't connect the read pin
+    ReadVcomValue = 0x81,
+    /// Set VCM_DC
+    VcmDcSetting = 0x82,
+    /// This command sets partial window
+    PartialWindow = 0x90,
+    /// This command makes the display enter partial mode
+    PartialIn = 0x91,
+    /// This command makes the display exit partial mode and enter normal mode
+    PartialOut = 0x92,
+    /// After this command is issued, the chip would enter the program mode.
+    ///
+    /// After the programming procedure completed, a hardware reset is necessary for leaving program mode.
+    ///
+    /// The only one parameter is a check code, the command would be excuted if check code = 0xA5.
+    ProgramMode = 0xA0,
+    /// After this command is transmitted, the programming state machine would be activated.
+    ///
+    /// The BUSY flag would fall to 0 until the programming is completed.
+    ActiveProgramming = 0xA1,
+    /// The command is used for reading the content of OTP for checking the data of programming.
+    ///
+    /// The value of (n) is depending on the amount of programmed data, tha max address = 0xFFF.
+    ReadOtp = 0xA2,
+    /// This command is set for saving power during fresh period. If the output voltage of VCOM / Source is from negative to positive or
+    /// from positive to negative, the power saving mechanism will be activated. The active period width is defined by the following two
+    /// parameters.
+    PowerSaving = 0xE3,
+}
+
+impl traits::Command for Command {
+    /// Returns the address of the command
+    fn address(self) -> u8 {
+        self as u8
+    }
+}
+
+/// Resolution selected by the panel setting, overridden by the resolution setting
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub(crate) enum PanelResolution {
+    R96x230 = 0x0,
+    R96x252 = 0x1,
+    R128x296 = 0x2,
+    R160x296 = 0x3,
+}
+
+/// First byte of [`Command::PanelSetting`], the reset and booster bits being always set
+pub(crate) struct PanelSetting {
+    pub resolution: PanelResolution,
+    /// Use the LUTs written with the `Lut*` commands instead of those of the OTP
+    pub lut_from_register: bool,
+    /// Black and white only, instead of black, white and red
+    pub black_white: bool,
+    pub scan_up: bool,
+    pub shift_right: bool,
+}
+
+impl PanelSetting {
+    pub fn to_u8(&self) -> u8 {
+        *0b11u8
+            .set_bits(6..8, self.resolution as u8)
+            .set_bit(5, self.lut_from_register)
+            .set_bit(4, self.black_white)
+            .set_bit(3, self.scan_up)
+            .set_bit(2, self.shift_right)
+    }
+}
+
+/// Data of [`Command::PowerSetting`]
+pub(crate) struct PowerSetting {
+    /// Internal source power (VDH/VDL)
+    pub internal_source: bool,
+    /// Internal gate power (VGH/VGL)
+    pub internal_gate: bool,
+    /// VCOM voltage is VDH/VDL instead of VGH/VGL
+    pub vcom_high_voltage: bool,
+    /// VGH/VGL level, 2 bits
+    pub gate_level: u8,
+    /// VDH level, 6 bits
+    pub vdh: u8,
+    /// VDL level, 6 bits
+    pub vdl: u8,
+    /// VDHR level (red), 6 bits
+    pub vdhr: u8,
+}
+
+impl PowerSetting {
+    pub fn to_bytes(&self) -> [u8; 5] {
+        [
+            *0u8.set_bit(1, self.internal_source)
+                .set_bit(0, self.internal_gate),
+            *0u8.set_bit(2, self.vcom_high_voltage)
+                .set_bits(0..2, self.gate_level & 0b11),
+            self.vdh & 0x3F,
+            self.vdl & 0x3F,
+            self.vdhr & 0x3F,
+        ]
+    }
+}
+
+/// Data of [`Command::VcomAndDataIntervalSetting`]
+pub(crate) struct VcomDataInterval {
+    /// Border output (VBD), 2 bits
+    pub border: u8,
+    /// Data polarity (DDX), 2 bits
+    pub data_polarity: u8,
+    /// Interval between VCOM and data (CDI), 4 bits
+    pub interval: u8,
+}
+
+impl VcomDataInterval {
+    pub fn to_u8(&self) -> u8 {
+        *0u8.set_bits(6..8, self.border & 0b11)
+            .set_bits(4..6, self.data_polarity & 0b11)
+            .set_bits(0..4, self.interval & 0x0F)
+    }
+}
+
+/// Resolution, partial window and LUTs of a `WIDTH` x `HEIGHT` panel driven by a UC8151
+pub(crate) struct Uc8151<const WIDTH: u32, const HEIGHT: u32>;
+
+impl<const WIDTH: u32, const HEIGHT: u32> Uc8151<WIDTH, HEIGHT> {
+    /// Sets the resolution of the panel, taking precedence over the one of the panel setting
+    pub(crate) fn set_resolution<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>(
+        interface: &mut DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+        spi: &mut SPI,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        interface.cmd_with_data(
+            spi,
+            Command::ResolutionSetting,
+            &[WIDTH as u8, (HEIGHT >> 8) as u8, HEIGHT as u8],
+        )
+    }
+
+    /// Restricts the following data transmissions and refreshes to a window
+    ///
+    /// The window is widened to whole bytes on the X axis. With `scan_outside`, the gates
+    /// outside of the window are scanned too.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn set_partial_window<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>(
+        interface: &mut DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+        spi: &mut SPI,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        scan_outside: bool,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        let end_x = x + width - 1;
+        let end_y = y + height - 1;
+        interface.cmd_with_data(
+            spi,
+            Command::PartialWindow,
+            &[
+                (x & 0xF8) as u8,
+                (end_x | 0x07) as u8,
+                (y >> 8) as u8,
+                y as u8,
+                (end_y >> 8) as u8,
+                end_y as u8,
+                scan_outside as u8,
+            ],
+        )
+    }
+
+    /// Writes the LUTs of VCOM and of the white to white, black to white, white to black
+    /// and black to black transitions, used with [`PanelSetting::lut_from_register`]
+    pub(crate) fn set_luts<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>(
+        interface: &mut DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+        spi: &mut SPI,
+        [vcom, ww, bw, wb, bb]: [&[u8]; 5],
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        interface.cmd_with_data(spi, Command::LutForVcom, vcom)?;
+        interface.cmd_with_data(spi, Command::LutWhiteToWhite, ww)?;
+        interface.cmd_with_data(spi, Command::LutBlackToWhite, bw)?;
+        interface.cmd_with_data(spi, Command::LutWhiteToBlack, wb)?;
+        interface.cmd_with_data(spi, Command::LutBlackToBlack, bb)
+    }
+
+    /// Enters deep sleep, left with a hardware reset
+    pub(crate) fn deep_sleep<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>(
+        interface: &mut DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+        spi: &mut SPI,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        // check code
+        interface.cmd_with_data(spi, Command::DeepSleep, &[0xA5])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::Command as CommandTrait;
+    use embedded_hal_mock::eh1::{delay::NoopDelay, digital, spi};
+
+    extern crate std;
+
+    #[test]
+    fn command_addr() {
+        assert_eq!(Command::PanelSetting.address(), 0x00);
+        assert_eq!(Command::VcomAndDataIntervalSetting.address(), 0x50);
+        assert_eq!(Command::PartialWindow.address(), 0x90);
+    }
+
+    #[test]
+    fn command_data() {
+        let otp = PanelSetting {
+            resolution: PanelResolution::R96x230,
+            lut_from_register: false,
+            black_white: true,
+            scan_up: true,
+            shift_right: true,
+        };
+        assert_eq!(otp.to_u8(), 0x1F);
+        let register = PanelSetting {
+            resolution: PanelResolution::R128x296,
+            lut_from_register: true,
+            ..otp
+        };
+        assert_eq!(register.to_u8(), 0xBF);
+
+        let power = PowerSetting {
+            internal_source: true,
+            internal_gate: true,
+            vcom_high_voltage: false,
+            gate_level: 0,
+            vdh: 0x2B,
+            vdl: 0x2B,
+            vdhr: 0x03,
+        };
+        assert_eq!(power.to_bytes(), [0x03, 0x00, 0x2B, 0x2B, 0x03]);
+
+        let interval = VcomDataInterval {
+            border: 0b10,
+            data_polarity: 0b01,
+            interval: 0x7,
+        };
+        assert_eq!(interval.to_u8(), 0x97);
+    }
+
+    #[test]
+    fn partial_window() {
+        let data = [0x08, 0x1F, 0x01, 0x00, 0x01, 0x27, 0x00];
+        let mut spi_expectations = std::vec![
+            spi::Transaction::transaction_start(),
+            spi::Transaction::write_vec(std::vec![Command::PartialWindow as u8]),
+            spi::Transaction::transaction_end(),
+        ];
+        for byte in data {
+            spi_expectations.extend([
+                spi::Transaction::transaction_start(),
+                spi::Transaction::write_vec(std::vec![byte]),
+                spi::Transaction::transaction_end(),
+            ]);
+        }
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&[
+            digital::Transaction::set(digital::State::Low),
+            digital::Transaction::set(digital::State::High),
+        ]);
+        let mut pin = digital::Mock::new(&[]);
+        let mut interface: DisplayInterface<_, _, _, _, NoopDelay, true> =
+            DisplayInterface::new(pin.clone(), dc.clone(), pin.clone(), None);
+
+        // x is widened to bytes, the window ends on the last row of the panel
+        Uc8151::<128, 296>::set_partial_window(&mut interface, &mut spi, 10, 256, 20, 40, false)
+            .unwrap();
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+}
@@ -0,0 +1,106 @@
+//! CRC-32 of the frames sent to the displays, to detect corrupted transfers
+//!
+//! With the `crc` feature, drivers compute the CRC of the frames they write to the RAM of the
+//! display, given by their `last_frame_crc`, e.g.
+//! [`Epd2in13b::last_frame_crc`](crate::epd2in13b_v4::Epd2in13b::last_frame_crc). All the
+//! drivers do but the IT8951 and the 12.48" (B) V2.
+//! Comparing it with the [`crc32`] of the expected image, computed by the host or stored with
+//! the image, tells whether the frame got through as expected, e.g. in a device reporting its
+//! state in the field.
+//!
+//! The CRC is the usual CRC-32 of zip and Ethernet (`CRC-32/ISO-HDLC`).
+//!
+//! ```rust
+//! use epd_waveshare::crc::{crc32, Crc32};
+//!
+//! assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+//!
+//! let mut crc = Crc32::new();
+//! crc.update(b"12345");
+//! crc.update(b"6789");
+//! assert_eq!(crc.finish(), 0xCBF4_3926);
+//! ```
+
+/// Reversed polynomial of the CRC
+const POLYNOMIAL: u32 = 0xEDB8_8320;
+
+/// CRC of the 16 values of a nibble, a small table being enough for frames of a few KB
+const TABLE: [u32; 16] = {
+    let mut table = [0; 16];
+    let mut i = 0;
+    while i < 16 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 4 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 computed over data given in several parts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crc32 {
+    state: u32,
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Crc32 {
+    /// Starts a CRC, of no data yet
+    pub const fn new() -> Self {
+        Crc32 { state: !0 }
+    }
+
+    /// Adds `data` to the CRC
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            let mut crc = self.state ^ byte as u32;
+            crc = (crc >> 4) ^ TABLE[(crc & 0xF) as usize];
+            crc = (crc >> 4) ^ TABLE[(crc & 0xF) as usize];
+            self.state = crc;
+        }
+    }
+
+    /// CRC of the data added so far
+    pub const fn finish(&self) -> u32 {
+        !self.state
+    }
+}
+
+/// CRC-32 of `data`
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(data);
+    crc.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_values() {
+        assert_eq!(crc32(&[]), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(&[0xFF; 4000]), crc32(&[0xFF; 4000]));
+        assert_ne!(crc32(&[0xFF; 4000]), crc32(&[0x00; 4000]));
+
+        let mut crc = Crc32::default();
+        for part in [&b"1234"[..], b"", b"56789"] {
+            crc.update(part);
+        }
+        assert_eq!(crc.finish(), 0xCBF4_3926);
+    }
+}
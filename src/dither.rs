@@ -0,0 +1,301 @@
+//! Dithering of images to the few colors of the displays
+//!
+//! The pixels of an image, as [`Gray8`] or [`Rgb888`] in rows of the width of the display, are
+//! converted to a [`Color`] or [`TriColor`] pixel stream which can be drawn on a
+//! [`Display`](crate::graphics::Display):
+//!
+//! - [`FloydSteinberg`] spreads the error of each pixel to its neighbours, which gives the
+//!   best looking photos. It keeps the errors of two rows, so the width is a const parameter.
+//! - [`Ordered`] compares the pixels to a 4x4 Bayer matrix, without memory. Its regular
+//!   patterns suit drawings and gradients better than photos.
+//!
+//! ```rust
+//! use embedded_graphics::{pixelcolor::Gray8, prelude::*};
+//! use epd_waveshare::{dither::FloydSteinberg, epd2in9::*, prelude::*};
+//!
+//! // a horizontal gradient
+//! let image = (0..HEIGHT).flat_map(|_| (0..WIDTH).map(|x| Gray8::new((x * 255 / WIDTH) as u8)));
+//!
+//! let mut display = Display2in9::default();
+//! let pixels = FloydSteinberg::<_, Color, { WIDTH as usize }>::new(image);
+//! display.draw_iter(pixels).ok();
+//! ```
+//!
+//! Chromatic pixels are dithered as red, the color of most three colors displays. Which colors
+//! are chromatic, and the brightness threshold, are set by a [`ColorMapper`], given to
+//! `with_mapper`.
+
+use embedded_graphics_core::pixelcolor::{Gray8, GrayColor, PixelColor, Rgb888, RgbColor};
+use embedded_graphics_core::prelude::{Pixel, Point};
+
+use crate::color::{Color, ColorMapper, TriColor};
+
+/// A color of the source image
+pub trait SourceColor: Copy {
+    /// Red, green and blue components of the color, from 0 to 255
+    fn to_rgb(self) -> [i16; 3];
+}
+
+impl SourceColor for Gray8 {
+    fn to_rgb(self) -> [i16; 3] {
+        [self.luma() as i16; 3]
+    }
+}
+
+impl SourceColor for Rgb888 {
+    fn to_rgb(self) -> [i16; 3] {
+        [self.r() as i16, self.g() as i16, self.b() as i16]
+    }
+}
+
+/// A color of a display, which pixels are dithered to
+pub trait DitherColor: PixelColor {
+    /// Returns the color `mapper` maps `rgb` to, with its own red, green and blue components
+    fn nearest(rgb: [i16; 3], mapper: &ColorMapper) -> (Self, [i16; 3]);
+}
+
+impl DitherColor for Color {
+    fn nearest(rgb: [i16; 3], mapper: &ColorMapper) -> (Self, [i16; 3]) {
+        if mapper.is_light(rgb) {
+            (Color::White, [255; 3])
+        } else {
+            (Color::Black, [0; 3])
+        }
+    }
+}
+
+impl DitherColor for TriColor {
+    fn nearest(rgb: [i16; 3], mapper: &ColorMapper) -> (Self, [i16; 3]) {
+        match mapper.classify(rgb) {
+            TriColor::Black => (TriColor::Black, [0; 3]),
+            TriColor::White => (TriColor::White, [255; 3]),
+            TriColor::Chromatic => (TriColor::Chromatic, [255, 0, 0]),
+        }
+    }
+}
+
+/// Position of the `index`th pixel of rows of `width` pixels
+fn position(index: usize, width: usize) -> Point {
+    Point::new((index % width) as i32, (index / width) as i32)
+}
+
+/// Floyd–Steinberg error diffusion of an image `WIDTH` pixels wide
+///
+/// The error of each pixel is spread to the pixels on its right and below it, keeping the
+/// errors of the current and the next rows: 12 bytes per pixel of a row.
+pub struct FloydSteinberg<I, C, const WIDTH: usize> {
+    pixels: I,
+    index: usize,
+    errors: [[i16; 3]; WIDTH],
+    next_errors: [[i16; 3]; WIDTH],
+    mapper: ColorMapper,
+    color: core::marker::PhantomData<C>,
+}
+
+impl<I, C, const WIDTH: usize> FloydSteinberg<I, C, WIDTH>
+where
+    I: Iterator,
+    I::Item: SourceColor,
+    C: DitherColor,
+{
+    /// Dithers the pixels of an image, row by row from its top left corner
+    pub fn new(pixels: impl IntoIterator<IntoIter = I>) -> Self {
+        Self::with_mapper(pixels, ColorMapper::default())
+    }
+
+    /// Same as `new`, mapping the colors with `mapper`
+    pub fn with_mapper(pixels: impl IntoIterator<IntoIter = I>, mapper: ColorMapper) -> Self {
+        FloydSteinberg {
+            pixels: pixels.into_iter(),
+            index: 0,
+            errors: [[0; 3]; WIDTH],
+            next_errors: [[0; 3]; WIDTH],
+            mapper,
+            color: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<I, C, const WIDTH: usize> Iterator for FloydSteinberg<I, C, WIDTH>
+where
+    I: Iterator,
+    I::Item: SourceColor,
+    C: DitherColor,
+{
+    type Item = Pixel<C>;
+
+    fn next(&mut self) -> Option<Pixel<C>> {
+        let rgb = self.pixels.next()?.to_rgb();
+        let point = position(self.index, WIDTH);
+        let x = self.index % WIDTH;
+        self.index += 1;
+
+        let mut value = [0; 3];
+        for c in 0..3 {
+            // bounded, as large errors can't build up
+            value[c] = (rgb[c] + self.errors[x][c] / 16).clamp(-255, 510);
+        }
+        let (color, chosen) = C::nearest(value, &self.mapper);
+
+        for c in 0..3 {
+            let error = value[c] - chosen[c];
+            if x + 1 < WIDTH {
+                self.errors[x + 1][c] += error * 7;
+                self.next_errors[x + 1][c] += error;
+            }
+            if x > 0 {
+                self.next_errors[x - 1][c] += error * 3;
+            }
+            self.next_errors[x][c] += error * 5;
+        }
+
+        if x + 1 == WIDTH {
+            self.errors = self.next_errors;
+            self.next_errors = [[0; 3]; WIDTH];
+        }
+        Some(Pixel(point, color))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.pixels.size_hint()
+    }
+}
+
+/// 4x4 Bayer matrix, the order in which the pixels of a block turn on
+const BAYER: [[i16; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Ordered dithering of an image, comparing its pixels to a 4x4 Bayer matrix
+pub struct Ordered<I, C> {
+    pixels: I,
+    width: usize,
+    index: usize,
+    mapper: ColorMapper,
+    color: core::marker::PhantomData<C>,
+}
+
+impl<I, C> Ordered<I, C>
+where
+    I: Iterator,
+    I::Item: SourceColor,
+    C: DitherColor,
+{
+    /// Dithers the pixels of an image `width` pixels wide, row by row from its top left
+    /// corner
+    pub fn new(pixels: impl IntoIterator<IntoIter = I>, width: u32) -> Self {
+        Self::with_mapper(pixels, width, ColorMapper::default())
+    }
+
+    /// Same as `new`, mapping the colors with `mapper`
+    pub fn with_mapper(
+        pixels: impl IntoIterator<IntoIter = I>,
+        width: u32,
+        mapper: ColorMapper,
+    ) -> Self {
+        Ordered {
+            pixels: pixels.into_iter(),
+            width: width as usize,
+            index: 0,
+            mapper,
+            color: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<I, C> Iterator for Ordered<I, C>
+where
+    I: Iterator,
+    I::Item: SourceColor,
+    C: DitherColor,
+{
+    type Item = Pixel<C>;
+
+    fn next(&mut self) -> Option<Pixel<C>> {
+        let rgb = self.pixels.next()?.to_rgb();
+        let point = position(self.index, self.width);
+        self.index += 1;
+
+        // threshold between -120 and 120, centered on the middle of the range
+        let threshold = BAYER[point.y as usize % 4][point.x as usize % 4] * 16 + 8 - 128;
+        let (color, _) = C::nearest(rgb.map(|c| c + threshold), &self.mapper);
+        Some(Pixel(point, color))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.pixels.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count<C: PixelColor>(pixels: impl Iterator<Item = Pixel<C>>, color: C) -> usize {
+        pixels.filter(|Pixel(_, c)| *c == color).count()
+    }
+
+    #[test]
+    fn floyd_steinberg_gray() {
+        let gray = [Gray8::new(128); 64];
+        let pixels = FloydSteinberg::<_, Color, 8>::new(gray);
+        assert_eq!(count(pixels, Color::White), 32);
+
+        let dark = [Gray8::new(64); 64];
+        let pixels = FloydSteinberg::<_, Color, 8>::new(dark);
+        // about a quarter, the error of the last row being lost
+        assert!((14..=16).contains(&count(pixels, Color::White)));
+    }
+
+    #[test]
+    fn floyd_steinberg_positions() {
+        let mut pixels = FloydSteinberg::<_, Color, 3>::new([Gray8::WHITE; 4]);
+        assert_eq!(pixels.nth(2), Some(Pixel(Point::new(2, 0), Color::White)));
+        assert_eq!(pixels.next(), Some(Pixel(Point::new(0, 1), Color::White)));
+        assert_eq!(pixels.next(), None);
+    }
+
+    #[test]
+    fn ordered_gray() {
+        // each 4x4 block has as many white pixels as the level of gray
+        let gray = [Gray8::new(128); 16];
+        assert_eq!(count(Ordered::<_, Color>::new(gray, 4), Color::White), 8);
+        let light = [Gray8::new(192); 16];
+        assert_eq!(count(Ordered::<_, Color>::new(light, 4), Color::White), 12);
+    }
+
+    #[test]
+    fn tricolor() {
+        let image = [
+            Rgb888::RED,
+            Rgb888::new(250, 30, 20),
+            Rgb888::BLACK,
+            Rgb888::WHITE,
+        ];
+        let colors = [
+            TriColor::Chromatic,
+            TriColor::Chromatic,
+            TriColor::Black,
+            TriColor::White,
+        ];
+        let pixels = Ordered::<_, TriColor>::new(image, 4).map(|Pixel(_, c)| c);
+        assert!(pixels.eq(colors));
+
+        // the error of the dark red pixel is spread to the black one
+        let image = [Rgb888::new(100, 0, 0), Rgb888::new(100, 0, 0)];
+        let pixels = FloydSteinberg::<_, TriColor, 2>::new(image).map(|Pixel(_, c)| c);
+        assert!(pixels.eq([TriColor::Black, TriColor::Chromatic]));
+    }
+
+    #[test]
+    fn mapper() {
+        // a dull red, not saturated enough for the default mapper
+        let image = [Rgb888::new(190, 90, 80); 16];
+        let pixels = Ordered::<_, TriColor>::new(image, 4);
+        assert_eq!(count(pixels, TriColor::Chromatic), 0);
+
+        let mapper = ColorMapper::new().chroma_threshold(80).red_only(40);
+        let pixels = Ordered::<_, TriColor>::with_mapper(image, 4, mapper);
+        assert_eq!(count(pixels, TriColor::Chromatic), 16);
+        let pixels = FloydSteinberg::<_, TriColor, 4>::with_mapper(image, mapper);
+        assert!(count(pixels, TriColor::Chromatic) > 0);
+    }
+}
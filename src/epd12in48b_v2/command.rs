@@ -1,7 +1,5 @@
 //! SPI Commands for the Waveshare 12.48"(B) V2 Ink Display
 
-use crate::traits;
-
 /// Epd12in48 commands
 ///
 #[allow(unused, non_camel_case_types)]
@@ -34,10 +32,3 @@ pub enum Command {
     PowerSaving = 0xE3,
     ForceTemperature = 0xE5,
 }
-
-impl traits::Command for Command {
-    /// Returns the address of the command
-    fn address(self) -> u8 {
-        self as u8
-    }
-}
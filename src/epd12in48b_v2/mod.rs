@@ -17,6 +17,7 @@ use embedded_hal::{
     spi::SpiBus,
 };
 
+use crate::error::Error;
 pub use crate::rect::Rect;
 use command::Command;
 pub use config::*;
@@ -35,6 +36,9 @@ pub use config::*;
 /// Resolution of `S2` and `M1` is 648 x 492,
 /// resolution of `S1` and `M2` is 656 x 492.
 ///
+/// [`EpdDriver`] selects the sub-displays with their chip select lines and splits the
+/// written windows between them, so the display is drawn as a single 1304 x 984 frame.
+///
 pub struct Peripherals<INPUT, OUTPUT, SPI>
 where
     INPUT: InputPin,
@@ -258,31 +262,40 @@ where
     /// - data2 contains the red/not red channel.
     ///
     /// `pixels` may contain a lesser number of rows than the window being written,
-    /// in which case it will be treated as circular.
-    pub fn write_data1(&mut self, pixels: &[u8]) -> Result<(), SPI::Error> {
+    /// in which case it will be treated as circular. It must hold at least one row and a
+    /// whole number of them, or [`Error::InvalidBuffer`] is returned.
+    pub fn write_data1(&mut self, pixels: &[u8]) -> Result<(), Error<SPI::Error>> {
         self.write_window_data(Command::DataStartTransmission1, FULL_RECT, pixels)?;
-        self.flush()
+        Ok(self.flush()?)
     }
 
     /// Fill data2 buffer with pixels.
     /// See also [`write_data1`](EpdDriver::write_data1).
-    pub fn write_data2(&mut self, pixels: &[u8]) -> Result<(), SPI::Error> {
+    pub fn write_data2(&mut self, pixels: &[u8]) -> Result<(), Error<SPI::Error>> {
         self.write_window_data(Command::DataStartTransmission2, FULL_RECT, pixels)?;
-        self.flush()
+        Ok(self.flush()?)
     }
 
     /// Fill a window in the data1 buffer with pixels.
     /// See also [`write_data1`](EpdDriver::write_data1).
-    pub fn write_data1_partial(&mut self, window: Rect, pixels: &[u8]) -> Result<(), SPI::Error> {
+    pub fn write_data1_partial(
+        &mut self,
+        window: Rect,
+        pixels: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
         self.write_partial(Command::DataStartTransmission1, window, pixels)?;
-        self.flush()
+        Ok(self.flush()?)
     }
 
     /// Fill a window in the data2 buffer with pixels.
     /// See also [`write_data1`](EpdDriver::write_data1).
-    pub fn write_data2_partial(&mut self, window: Rect, pixels: &[u8]) -> Result<(), SPI::Error> {
+    pub fn write_data2_partial(
+        &mut self,
+        window: Rect,
+        pixels: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
         self.write_partial(Command::DataStartTransmission2, window, pixels)?;
-        self.flush()
+        Ok(self.flush()?)
     }
 
     /// Store VCOM Look-Up Table.
@@ -459,17 +472,15 @@ where
         transmission_cmd: Command,
         window: Rect,
         pixels: &[u8],
-    ) -> Result<(), SPI::Error> {
-        if window.x % 8 != 0 || window.w % 8 != 0 {
-            panic!("Window is not 8-aligned horizontally");
-        }
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_aligned(window.x, window.w)?;
 
         self.cmd(CS_ALL, Command::PartialIn)?;
 
         self.setup_partial_windows(window)?;
         self.write_window_data(transmission_cmd, window, pixels)?;
 
-        self.cmd(CS_ALL, Command::PartialOut)
+        Ok(self.cmd(CS_ALL, Command::PartialOut)?)
     }
 
     // Send data to each sub-display for the window area that overlaps with it.
@@ -478,9 +489,7 @@ where
         transmission_cmd: Command,
         window: Rect,
         pixels: &[u8],
-    ) -> Result<(), SPI::Error> {
-        assert!(!pixels.is_empty());
-
+    ) -> Result<(), Error<SPI::Error>> {
         let s2_part = window.intersect(S2_RECT);
         let s1_part = window.intersect(S1_RECT);
 
@@ -489,6 +498,12 @@ where
         let left_bytes = (s2_part.w / 8) as usize;
         let right_bytes = (s1_part.w / 8) as usize;
 
+        // the rows are repeated, so there must be a whole number of them
+        let row_bytes = left_bytes + right_bytes;
+        if pixels.is_empty() || (row_bytes > 0 && pixels.len() % row_bytes != 0) {
+            return Err(Error::InvalidBuffer);
+        }
+
         let row_offset = |row| {
             let offset = row * (left_bytes + right_bytes);
             if offset < pixels.len() {
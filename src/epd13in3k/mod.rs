@@ -0,0 +1,465 @@
+//! A simple Driver for the Waveshare 13.3" E-Ink Display (K) via SPI
+//!
+//! # References
+//!
+//! - [Wiki](https://www.waveshare.com/wiki/13.3inch_e-Paper_HAT_(K))
+//! - [Waveshare C driver](https://github.com/waveshareteam/e-Paper/blob/master/RaspberryPi_JetsonNano/c/lib/e-Paper/EPD_13in3k.c)
+//!
+//! The display is driven by a SSD1677, addressing its 960 columns on 16 bits.
+//!
+//! A full frame takes 81.6KB, more than the RAM of most MCUs. The frame can also be sent
+//! in bands with [`Epd13in3k::update_frame_in_bands`], or streamed with
+//! [`Epd13in3k::update_frame_iter`].
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{InputPin, OutputPin},
+    spi::SpiDevice,
+};
+
+use crate::buffer_len;
+use crate::color::Color;
+use crate::controllers::ssd1677::{Command, Ssd1677, IS_BUSY_LOW};
+use crate::error::Error;
+#[cfg(feature = "graphics")]
+use crate::graphics::{band_height, render_in_bands, Band, BandError, VarDisplay};
+use crate::interface::{BusyYield, DisplayInterface};
+use crate::traits::{
+    DeepSleepMode, InternalWiAdditions, PartialUpdate, Sleepable, WaveshareDisplay,
+};
+
+/// Full size buffer for use with the 13in3k EPD
+#[cfg(feature = "graphics")]
+pub type Display13in3k = crate::graphics::Display<
+    WIDTH,
+    HEIGHT,
+    false,
+    { buffer_len(WIDTH as usize, HEIGHT as usize) },
+    Color,
+>;
+
+/// Width of the display
+pub const WIDTH: u32 = 960;
+/// Height of the display
+pub const HEIGHT: u32 = 680;
+/// Default Background Color
+pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+const SINGLE_BYTE_WRITE: bool = false;
+
+type Controller = Ssd1677<WIDTH, HEIGHT>;
+
+/// Epd13in3k driver
+pub struct Epd13in3k<SPI, BUSY, DC, RST, DELAY> {
+    /// Connection Interface
+    interface: DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+    /// Background Color
+    color: Color,
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Epd13in3k<SPI, BUSY, DC, RST, DELAY> {
+    /// Gives back the busy, data/command and reset pins, e.g. to reuse them once the display
+    /// is asleep
+    ///
+    /// The display keeps its state, so a driver can be created again later with the pins.
+    pub fn release(self) -> (BUSY, DC, RST) {
+        self.interface.release()
+    }
+
+    /// Gives up waiting for the display with [`Error::BusyTimeout`] once it has been busy for
+    /// `timeout_us`, e.g. because of a loose cable, instead of waiting forever. `None` removes
+    /// the timeout.
+    ///
+    /// The time is counted by adding up the delays of the idle loop, each poll of the busy pin
+    /// counting as at least 1us.
+    pub fn set_busy_timeout(&mut self, timeout_us: Option<u32>) {
+        self.interface.set_busy_timeout(timeout_us);
+    }
+
+    /// Polls the busy pin every `interval_us` while the display is busy (0 busy waits), or,
+    /// with a `max_interval_us`, backs off: the interval doubles after every poll until
+    /// `max_interval_us`
+    ///
+    /// The interval is the `delay_us` given to `new` (10ms by default) until this is called.
+    pub fn set_poll_interval(&mut self, interval_us: u32, max_interval_us: Option<u32>) {
+        self.interface.set_poll_interval(interval_us);
+        self.interface.set_poll_backoff(max_interval_us);
+    }
+
+    /// Sets a [`BusyYield`] called on every poll of the busy pin while the display is busy, e.g.
+    /// to feed a watchdog during a refresh. `None` removes it.
+    pub fn set_busy_yield(&mut self, busy_yield: Option<BusyYield>) {
+        self.interface.set_busy_yield(busy_yield);
+    }
+
+    /// Time the display was busy during the last wait for it, counted like the busy timeout,
+    /// e.g. to choose the timeout
+    pub fn last_wait_us(&self) -> u32 {
+        self.interface.last_wait_us()
+    }
+
+    /// CRC-32 of the data of the last frame written to the display, e.g. by
+    /// [`update_frame`](WaveshareDisplay::update_frame), to compare with the
+    /// [`crc32`](crate::crc::crc32) of the expected image and detect a corrupted transfer. `None`
+    /// until a frame is written.
+    #[cfg(any(feature = "crc", test))]
+    pub fn last_frame_crc(&self) -> Option<u32> {
+        self.interface.last_crc()
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, BUSY, DC, RST, DELAY>
+    for Epd13in3k<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 20_000, 2_000)?;
+        self.wait_until_idle(spi, delay)?;
+        self.interface.cmd(spi, Command::SwReset)?;
+        self.wait_until_idle(spi, delay)?;
+
+        self.interface.cmd_with_data(
+            spi,
+            Command::BoosterSoftStartControl,
+            &[0xAE, 0xC7, 0xC3, 0xC0, 0x80],
+        )?;
+        self.interface.cmd_with_data(
+            spi,
+            Command::DriverOutputControl,
+            &[(HEIGHT - 1) as u8, ((HEIGHT - 1) >> 8) as u8, 0x02],
+        )?;
+        self.interface
+            .cmd_with_data(spi, Command::BorderWaveformControl, &[0x01])?;
+        // x increment, y increment, address counter is updated in x direction
+        self.interface
+            .cmd_with_data(spi, Command::DataEntryModeSetting, &[0x03])?;
+        Controller::set_full_window(&mut self.interface, spi, delay)?;
+        self.wait_until_idle(spi, delay)?;
+
+        // internal temperature sensor
+        self.interface
+            .cmd_with_data(spi, Command::TemperatureSensorSelection, &[0x80])
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Sleepable<SPI, BUSY, DC, RST, DELAY>
+    for Epd13in3k<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.sleep_with_mode(spi, delay, DeepSleepMode::Mode2)
+    }
+
+    fn sleep_with_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mode: DeepSleepMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        // the SSD1677 uses 0x03 for its second deep sleep mode
+        let mode = match mode {
+            DeepSleepMode::Normal => 0x00,
+            DeepSleepMode::Mode1 => 0x01,
+            DeepSleepMode::Mode2 => 0x03,
+        };
+        self.wait_until_idle(spi, delay)?;
+        self.interface
+            .cmd_with_data(spi, Command::DeepSleepMode, &[mode])
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
+    for Epd13in3k<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    type DisplayColor = Color;
+
+    fn new(
+        spi: &mut SPI,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+        delay_us: Option<u32>,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let mut epd = Epd13in3k {
+            interface: DisplayInterface::new(busy, dc, rst, delay_us),
+            color: DEFAULT_BACKGROUND_COLOR,
+        };
+
+        #[cfg(any(feature = "crc", test))]
+        epd.interface.track_crc(Command::WriteRam);
+        epd.init(spi, delay)?;
+        Ok(epd)
+    }
+
+    fn set_background_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    fn background_color(&self) -> &Color {
+        &self.color
+    }
+
+    fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.start_frame(spi, delay)?;
+        self.interface.data(spi, buffer)
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface
+            .cmd_with_data(spi, Command::DisplayUpdateControl2, &[0xF7])?;
+        self.interface.cmd(spi, Command::MasterActivation)?;
+        self.wait_until_idle(spi, delay)
+    }
+
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.display_frame(spi, delay)
+    }
+
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.start_frame(spi, delay)?;
+        self.interface.data_x_times(
+            spi,
+            self.color.get_byte_value(),
+            buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
+        )
+    }
+
+    fn wait_until_idle(
+        &mut self,
+        _spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_until_idle(delay, IS_BUSY_LOW)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> PartialUpdate<SPI, BUSY, DC, RST, DELAY>
+    for Epd13in3k<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// `x` and `width` must be multiples of 8
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(width as usize, height as usize))?;
+        self.wait_until_idle(spi, delay)?;
+        Controller::set_ram_area(
+            &mut self.interface,
+            spi,
+            x,
+            y,
+            x + width - 1,
+            y + height - 1,
+        )?;
+        Controller::set_ram_address_counters(&mut self.interface, spi, delay, x, y)?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam, buffer)?;
+        Controller::set_full_window(&mut self.interface, spi, delay)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Epd13in3k<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Sets the largest number of bytes written in a single SPI transfer, longer writes being
+    /// split, e.g. for DMA engines with a limited transfer size. `None` writes a whole frame
+    /// at once.
+    ///
+    /// It is 4096 bytes on Linux, the default limit of spidev, and unlimited elsewhere.
+    pub fn set_max_transfer_size(&mut self, size: Option<usize>) {
+        self.interface.set_max_transfer_size(size);
+    }
+
+    /// Same as [`update_frame`](WaveshareDisplay::update_frame), with the data streamed from
+    /// an iterator, e.g. reading an image from a file or decompressing it
+    ///
+    /// At most a full frame is taken from the iterator, so endless iterators can be used.
+    /// If it is shorter, the rest of the RAM is left unchanged.
+    pub fn update_frame_iter(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        data: impl IntoIterator<Item = u8>,
+    ) -> Result<(), Error<SPI::Error>> {
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize);
+        self.start_frame(spi, delay)?;
+        self.interface.data_iter(spi, data.into_iter().take(len))
+    }
+
+    /// Same as [`update_frame`](WaveshareDisplay::update_frame), drawing the frame in
+    /// `BANDS` horizontal bands with [`render_in_bands`]
+    ///
+    /// `buffer` only needs to hold one band, e.g. 10.2KB with 8 bands instead of the 81.6KB
+    /// of a full frame. `draw` is called for each band, in the coordinates of the band.
+    #[cfg(feature = "graphics")]
+    pub fn update_frame_in_bands<const BANDS: u32>(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &mut [u8],
+        draw: impl FnMut(&mut VarDisplay<'_, Color>, Band),
+    ) -> Result<(), Error<SPI::Error>> {
+        let expected = buffer_len(WIDTH as usize, band_height(HEIGHT, BANDS) as usize);
+        let too_small = Error::WrongBufferSize {
+            expected,
+            got: buffer.len(),
+        };
+        if buffer.len() < expected {
+            return Err(too_small);
+        }
+
+        self.start_frame(spi, delay)?;
+        // the bands follow each other in the same RAM write
+        render_in_bands::<BANDS, Color, _>(WIDTH, HEIGHT, false, buffer, draw, |_, data| {
+            self.interface.data(spi, data)
+        })
+        .map_err(|e| match e {
+            BandError::BufferTooSmall => too_small,
+            BandError::Send(e) => e,
+        })
+    }
+
+    /// Starts writing the RAM from its first pixel
+    fn start_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        Controller::set_ram_address_counters(&mut self.interface, spi, delay, 0, 0)?;
+        self.interface.cmd(spi, Command::WriteRam)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::eh1::{delay::NoopDelay, digital, spi};
+
+    extern crate std;
+    use std::vec::Vec;
+
+    // a command and its data, each written in a single transaction
+    fn write(
+        spi: &mut Vec<spi::Transaction<u8>>,
+        dc: &mut Vec<digital::Transaction>,
+        command: Command,
+        data: &[u8],
+    ) {
+        dc.push(digital::Transaction::set(digital::State::Low));
+        spi.push(spi::Transaction::transaction_start());
+        spi.push(spi::Transaction::write_vec(std::vec![command as u8]));
+        spi.push(spi::Transaction::transaction_end());
+        if data.is_empty() {
+            return;
+        }
+        dc.push(digital::Transaction::set(digital::State::High));
+        spi.push(spi::Transaction::transaction_start());
+        spi.push(spi::Transaction::write_vec(data.to_vec()));
+        spi.push(spi::Transaction::transaction_end());
+    }
+
+    #[test]
+    fn epd_size() {
+        assert_eq!(WIDTH, 960);
+        assert_eq!(HEIGHT, 680);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+    }
+
+    #[test]
+    fn partial_window_addresses() {
+        let (mut spi_expectations, mut dc_expectations) = (Vec::new(), Vec::new());
+        let mut push = |command, data: &[u8]| {
+            write(&mut spi_expectations, &mut dc_expectations, command, data)
+        };
+        // 16 bit X addresses, in pixels
+        push(
+            Command::SetRamXAddressStartEndPosition,
+            &[0x20, 0x03, 0x2F, 0x03],
+        );
+        push(
+            Command::SetRamYAddressStartEndPosition,
+            &[0xA6, 0x02, 0xA7, 0x02],
+        );
+        push(Command::SetRamXAddressCounter, &[0x20, 0x03]);
+        push(Command::SetRamYAddressCounter, &[0xA6, 0x02]);
+        push(Command::WriteRam, &[0x00; 4]);
+        push(Command::SetRamXAddressStartEndPosition, &[0, 0, 0xBF, 0x03]);
+        push(Command::SetRamYAddressStartEndPosition, &[0, 0, 0xA7, 0x02]);
+        push(Command::SetRamXAddressCounter, &[0, 0]);
+        push(Command::SetRamYAddressCounter, &[0, 0]);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&std::vec![
+            digital::Transaction::get(digital::State::Low);
+            3
+        ]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd = Epd13in3k {
+            interface: DisplayInterface::new(busy.clone(), dc.clone(), rst.clone(), None),
+            color: DEFAULT_BACKGROUND_COLOR,
+        };
+
+        // the bottom right corner
+        epd.update_partial_frame(&mut spi, &mut NoopDelay, &[0x00; 4], 800, 678, 16, 2)
+            .unwrap();
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
+}
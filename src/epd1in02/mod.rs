@@ -11,9 +11,10 @@ use embedded_hal::{
 };
 
 use crate::color::Color;
-use crate::interface::DisplayInterface;
+use crate::error::Error;
+use crate::interface::{BusyYield, DisplayInterface};
 use crate::prelude::WaveshareDisplay;
-use crate::traits::{InternalWiAdditions, QuickRefresh, RefreshLut};
+use crate::traits::{InternalWiAdditions, LutControl, QuickRefresh, RefreshLut, Sleepable};
 
 pub(crate) mod command;
 use self::command::Command;
@@ -58,6 +59,82 @@ pub struct Epd1in02<SPI, BUSY, DC, RST, DELAY> {
     refresh_mode: RefreshLut,
 }
 
+impl<SPI, BUSY, DC, RST, DELAY> Epd1in02<SPI, BUSY, DC, RST, DELAY> {
+    /// Gives back the busy, data/command and reset pins, e.g. to reuse them once the display
+    /// is asleep
+    ///
+    /// The display keeps its state, so a driver can be created again later with the pins.
+    pub fn release(self) -> (BUSY, DC, RST) {
+        self.interface.release()
+    }
+
+    /// Gives up waiting for the display with [`Error::BusyTimeout`] once it has been busy for
+    /// `timeout_us`, e.g. because of a loose cable, instead of waiting forever. `None` removes
+    /// the timeout.
+    ///
+    /// The time is counted by adding up the delays of the idle loop, each poll of the busy pin
+    /// counting as at least 1us.
+    pub fn set_busy_timeout(&mut self, timeout_us: Option<u32>) {
+        self.interface.set_busy_timeout(timeout_us);
+    }
+
+    /// Polls the busy pin every `interval_us` while the display is busy (0 busy waits), or,
+    /// with a `max_interval_us`, backs off: the interval doubles after every poll until
+    /// `max_interval_us`
+    ///
+    /// The interval is the `delay_us` given to `new` (10ms by default) until this is called.
+    pub fn set_poll_interval(&mut self, interval_us: u32, max_interval_us: Option<u32>) {
+        self.interface.set_poll_interval(interval_us);
+        self.interface.set_poll_backoff(max_interval_us);
+    }
+
+    /// Sets a [`BusyYield`] called on every poll of the busy pin while the display is busy, e.g.
+    /// to feed a watchdog during a refresh. `None` removes it.
+    pub fn set_busy_yield(&mut self, busy_yield: Option<BusyYield>) {
+        self.interface.set_busy_yield(busy_yield);
+    }
+
+    /// Time the display was busy during the last wait for it, counted like the busy timeout,
+    /// e.g. to choose the timeout
+    pub fn last_wait_us(&self) -> u32 {
+        self.interface.last_wait_us()
+    }
+
+    /// CRC-32 of the data of the last frame written to the display, e.g. by
+    /// [`update_frame`](WaveshareDisplay::update_frame), to compare with the
+    /// [`crc32`](crate::crc::crc32) of the expected image and detect a corrupted transfer. `None`
+    /// until a frame is written.
+    #[cfg(any(feature = "crc", test))]
+    pub fn last_frame_crc(&self) -> Option<u32> {
+        self.interface.last_crc()
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Sleepable<SPI, BUSY, DC, RST, DELAY>
+    for Epd1in02<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.turn_off(spi, delay)?;
+        self.cmd_with_data(spi, Command::DeepSleep, &[0xA5])?;
+
+        // display registers are set to default value
+        self.refresh_mode = RefreshLut::Full;
+
+        Ok(())
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+}
+
 impl<SPI, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
     for Epd1in02<SPI, BUSY, DC, RST, DELAY>
 where
@@ -76,7 +153,7 @@ where
         rst: RST,
         delay: &mut DELAY,
         delay_us: Option<u32>,
-    ) -> Result<Self, SPI::Error> {
+    ) -> Result<Self, Error<SPI::Error>> {
         let interface = DisplayInterface::new(busy, dc, rst, delay_us);
         let color = DEFAULT_BACKGROUND_COLOR;
 
@@ -87,26 +164,13 @@ where
             refresh_mode: RefreshLut::Full,
         };
 
+        #[cfg(any(feature = "crc", test))]
+        epd.interface.track_crc(Command::DataStartTransmission2);
         epd.init(spi, delay)?;
 
         Ok(epd)
     }
 
-    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.wait_until_idle(spi, delay)?;
-        self.turn_off(spi, delay)?;
-        self.cmd_with_data(spi, Command::DeepSleep, &[0xA5])?;
-
-        // display registers are set to default value
-        self.refresh_mode = RefreshLut::Full;
-
-        Ok(())
-    }
-
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.init(spi, delay)
-    }
-
     fn set_background_color(&mut self, color: Color) {
         self.color = color;
     }
@@ -128,7 +192,8 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, NUMBER_OF_BYTES as usize)?;
         self.wait_until_idle(spi, delay)?;
 
         self.set_full_mode(spi, delay)?;
@@ -145,20 +210,8 @@ where
 
     // Implemented as quick partial update
     // as it requires old frame update
-    fn update_partial_frame(
-        &mut self,
-        _spi: &mut SPI,
-        _delay: &mut DELAY,
-        _buffer: &[u8],
-        _x: u32,
-        _y: u32,
-        _width: u32,
-        _height: u32,
-    ) -> Result<(), SPI::Error> {
-        unimplemented!()
-    }
 
-    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
         self.turn_on_if_turned_off(spi, delay)?;
 
@@ -172,13 +225,13 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, buffer, delay)?;
         self.display_frame(spi, delay)?;
         Ok(())
     }
 
-    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
         self.set_full_mode(spi, delay)?;
 
@@ -195,14 +248,35 @@ where
         Ok(())
     }
 
+    fn wait_until_idle(
+        &mut self,
+        _spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_until_idle(delay, IS_BUSY_LOW)?;
+        Ok(())
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> LutControl<SPI, BUSY, DC, RST, DELAY>
+    for Epd1in02<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
     fn set_lut(
         &mut self,
         spi: &mut SPI,
         _delay: &mut DELAY,
         refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         let (white_lut, black_lut) = match refresh_rate {
-            Some(RefreshLut::Full) => (&LUT_FULL_UPDATE_WHITE, &LUT_FULL_UPDATE_BLACK),
+            Some(RefreshLut::Full | RefreshLut::Fast) => {
+                (&LUT_FULL_UPDATE_WHITE, &LUT_FULL_UPDATE_BLACK)
+            }
             Some(RefreshLut::Quick) => (&LUT_PARTIAL_UPDATE_WHITE, &LUT_PARTIAL_UPDATE_BLACK),
             None => return Ok(()),
         };
@@ -211,11 +285,6 @@ where
         self.cmd_with_data(spi, Command::SetBlackLut, black_lut)?;
         Ok(())
     }
-
-    fn wait_until_idle(&mut self, _spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.interface.wait_until_idle(delay, IS_BUSY_LOW);
-        Ok(())
-    }
 }
 
 impl<SPI, BUSY, DC, RST, DELAY> QuickRefresh<SPI, BUSY, DC, RST, DELAY>
@@ -233,7 +302,8 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, NUMBER_OF_BYTES as usize)?;
         self.set_partial_mode(spi, delay)?;
         self.set_partial_window(spi, delay, 0, 0, WIDTH, HEIGHT)?;
 
@@ -247,13 +317,18 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         _delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, NUMBER_OF_BYTES as usize)?;
         self.cmd_with_data(spi, Command::DataStartTransmission2, buffer)?;
         Ok(())
     }
 
-    fn display_new_frame(&mut self, _spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
-        unimplemented!()
+    fn display_new_frame(
+        &mut self,
+        _spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        Err(Error::Unsupported)
     }
 
     fn update_and_display_new_frame(
@@ -261,8 +336,8 @@ where
         _spi: &mut SPI,
         _buffer: &[u8],
         _delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
-        unimplemented!()
+    ) -> Result<(), Error<SPI::Error>> {
+        Err(Error::Unsupported)
     }
 
     /// To be followed immediately by update_partial_new_frame
@@ -276,9 +351,12 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         if !is_buffer_size_ok(buffer, width, height) {
-            panic!("Image buffer size is not correct")
+            return Err(Error::WrongBufferSize {
+                expected: buffer_len(width as usize, height as usize),
+                got: buffer.len(),
+            });
         }
 
         self.set_partial_mode(spi, delay)?;
@@ -298,9 +376,12 @@ where
         _y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         if !is_buffer_size_ok(buffer, width, height) {
-            panic!("Image buffer size is not correct")
+            return Err(Error::WrongBufferSize {
+                expected: buffer_len(width as usize, height as usize),
+                got: buffer.len(),
+            });
         }
 
         self.cmd_with_data(spi, Command::DataStartTransmission2, buffer)?;
@@ -316,7 +397,7 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
         // set full LUT as quick LUT requires old image
         self.set_full_mode(spi, delay)?;
@@ -349,9 +430,9 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         // Reset the device
-        self.interface.reset(delay, 20_000, 2000);
+        self.interface.reset(delay, 20_000, 2000)?;
 
         // Set the panel settings: LUT from register
         self.cmd_with_data(spi, Command::PanelSetting, &[0x6F])?;
@@ -403,11 +484,11 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), SPI::Error> {
+    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd(spi, command)
     }
 
-    fn send_data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
+    fn send_data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), Error<SPI::Error>> {
         self.interface.data(spi, data)
     }
 
@@ -416,11 +497,11 @@ where
         spi: &mut SPI,
         command: Command,
         data: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd_with_data(spi, command, data)
     }
 
-    fn send_resolution(&mut self, spi: &mut SPI) -> Result<(), SPI::Error> {
+    fn send_resolution(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
         let w = self.width();
         let h = self.height();
 
@@ -434,7 +515,7 @@ where
         &mut self,
         spi: &mut SPI,
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         if !self.is_turned_on {
             self.command(spi, Command::PowerOn)?;
             self.wait_until_idle(spi, delay)?;
@@ -443,14 +524,14 @@ where
         Ok(())
     }
 
-    fn turn_off(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn turn_off(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.command(spi, Command::PowerOff)?;
         self.wait_until_idle(spi, delay)?;
         self.is_turned_on = false;
         Ok(())
     }
 
-    fn set_full_mode(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn set_full_mode(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         if self.refresh_mode != RefreshLut::Full {
             self.command(spi, Command::PartialOut)?;
             self.set_lut(spi, delay, Some(RefreshLut::Full))?;
@@ -459,7 +540,11 @@ where
         Ok(())
     }
 
-    fn set_partial_mode(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn set_partial_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
         if self.refresh_mode != RefreshLut::Quick {
             self.command(spi, Command::PartialIn)?;
             self.set_lut(spi, delay, Some(RefreshLut::Quick))?;
@@ -476,9 +561,9 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         if !is_window_size_ok(x, y, width, height) {
-            panic!("Partial update window size is not correct")
+            return Err(Error::InvalidBuffer);
         }
 
         self.cmd_with_data(
@@ -4,7 +4,7 @@
 //!
 //!```rust, no_run
 //!# use embedded_hal_mock::eh1::*;
-//!# fn main() -> Result<(), embedded_hal::spi::ErrorKind> {
+//!# fn main() -> Result<(), epd_waveshare::error::Error<embedded_hal::spi::ErrorKind>> {
 //!use embedded_graphics::{
 //!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyleBuilder},
 //!};
@@ -56,6 +56,8 @@ const SINGLE_BYTE_WRITE: bool = true;
 
 use embedded_hal::{delay::*, digital::*, spi::SpiDevice};
 
+use crate::error::Error;
+use crate::rect::WindowError;
 use crate::type_a::{
     command::Command,
     constants::{LUT_FULL_UPDATE, LUT_PARTIAL_UPDATE},
@@ -63,10 +65,13 @@ use crate::type_a::{
 
 use crate::color::Color;
 
-use crate::traits::{RefreshLut, WaveshareDisplay};
+use crate::traits::{
+    DeepSleepMode, InternalWiAdditions, LutControl, PartialRefresh, PartialUpdate, RefreshLut,
+    Sleepable, WaveshareDisplay,
+};
 
 use crate::buffer_len;
-use crate::interface::DisplayInterface;
+use crate::interface::{BusyYield, DisplayInterface};
 
 /// Full size buffer for use with the 1in54b EPD
 #[cfg(feature = "graphics")]
@@ -88,7 +93,59 @@ pub struct Epd1in54<SPI, BUSY, DC, RST, DELAY> {
     refresh: RefreshLut,
 }
 
-impl<SPI, BUSY, DC, RST, DELAY> Epd1in54<SPI, BUSY, DC, RST, DELAY>
+impl<SPI, BUSY, DC, RST, DELAY> Epd1in54<SPI, BUSY, DC, RST, DELAY> {
+    /// Gives back the busy, data/command and reset pins, e.g. to reuse them once the display
+    /// is asleep
+    ///
+    /// The display keeps its state, so a driver can be created again later with the pins.
+    pub fn release(self) -> (BUSY, DC, RST) {
+        self.interface.release()
+    }
+
+    /// Gives up waiting for the display with [`Error::BusyTimeout`] once it has been busy for
+    /// `timeout_us`, e.g. because of a loose cable, instead of waiting forever. `None` removes
+    /// the timeout.
+    ///
+    /// The time is counted by adding up the delays of the idle loop, each poll of the busy pin
+    /// counting as at least 1us.
+    pub fn set_busy_timeout(&mut self, timeout_us: Option<u32>) {
+        self.interface.set_busy_timeout(timeout_us);
+    }
+
+    /// Polls the busy pin every `interval_us` while the display is busy (0 busy waits), or,
+    /// with a `max_interval_us`, backs off: the interval doubles after every poll until
+    /// `max_interval_us`
+    ///
+    /// The interval is the `delay_us` given to `new` (10ms by default) until this is called.
+    pub fn set_poll_interval(&mut self, interval_us: u32, max_interval_us: Option<u32>) {
+        self.interface.set_poll_interval(interval_us);
+        self.interface.set_poll_backoff(max_interval_us);
+    }
+
+    /// Sets a [`BusyYield`] called on every poll of the busy pin while the display is busy, e.g.
+    /// to feed a watchdog during a refresh. `None` removes it.
+    pub fn set_busy_yield(&mut self, busy_yield: Option<BusyYield>) {
+        self.interface.set_busy_yield(busy_yield);
+    }
+
+    /// Time the display was busy during the last wait for it, counted like the busy timeout,
+    /// e.g. to choose the timeout
+    pub fn last_wait_us(&self) -> u32 {
+        self.interface.last_wait_us()
+    }
+
+    /// CRC-32 of the data of the last frame written to the display, e.g. by
+    /// [`update_frame`](WaveshareDisplay::update_frame), to compare with the
+    /// [`crc32`](crate::crc::crc32) of the expected image and detect a corrupted transfer. `None`
+    /// until a frame is written.
+    #[cfg(any(feature = "crc", test))]
+    pub fn last_frame_crc(&self) -> Option<u32> {
+        self.interface.last_crc()
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, BUSY, DC, RST, DELAY>
 where
     SPI: SpiDevice,
     BUSY: InputPin,
@@ -96,8 +153,8 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.interface.reset(delay, 10_000, 10_000);
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 10_000, 10_000)?;
 
         // 3 Databytes:
         // A[7:0]
@@ -142,6 +199,45 @@ where
     }
 }
 
+impl<SPI, BUSY, DC, RST, DELAY> Sleepable<SPI, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        // 0x00 for Normal mode (Power on Reset), 0x01 for Deep Sleep Mode
+        //TODO: is 0x00 needed here or would 0x01 be even more efficient?
+        self.interface
+            .cmd_with_data(spi, Command::DeepSleepMode, &[0x00])?;
+        Ok(())
+    }
+
+    fn sleep_with_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mode: DeepSleepMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        // the controller has a single deep sleep mode, keeping the RAM content
+        let mode = match mode {
+            DeepSleepMode::Normal => 0x00,
+            DeepSleepMode::Mode1 | DeepSleepMode::Mode2 => 0x01,
+        };
+        self.wait_until_idle(spi, delay)?;
+        self.interface
+            .cmd_with_data(spi, Command::DeepSleepMode, &[mode])
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+}
+
 impl<SPI, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
     for Epd1in54<SPI, BUSY, DC, RST, DELAY>
 where
@@ -167,7 +263,7 @@ where
         rst: RST,
         delay: &mut DELAY,
         delay_us: Option<u32>,
-    ) -> Result<Self, SPI::Error> {
+    ) -> Result<Self, Error<SPI::Error>> {
         let interface = DisplayInterface::new(busy, dc, rst, delay_us);
 
         let mut epd = Epd1in54 {
@@ -176,30 +272,20 @@ where
             refresh: RefreshLut::Full,
         };
 
+        #[cfg(any(feature = "crc", test))]
+        epd.interface.track_crc(Command::WriteRam);
         epd.init(spi, delay)?;
 
         Ok(epd)
     }
 
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.init(spi, delay)
-    }
-
-    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.wait_until_idle(spi, delay)?;
-        // 0x00 for Normal mode (Power on Reset), 0x01 for Deep Sleep Mode
-        //TODO: is 0x00 needed here or would 0x01 be even more efficient?
-        self.interface
-            .cmd_with_data(spi, Command::DeepSleepMode, &[0x00])?;
-        Ok(())
-    }
-
     fn update_frame(
         &mut self,
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         self.wait_until_idle(spi, delay)?;
         self.use_full_frame(spi, delay)?;
         self.interface
@@ -208,26 +294,8 @@ where
     }
 
     //TODO: update description: last 3 bits will be ignored for width and x_pos
-    fn update_partial_frame(
-        &mut self,
-        spi: &mut SPI,
-        delay: &mut DELAY,
-        buffer: &[u8],
-        x: u32,
-        y: u32,
-        width: u32,
-        height: u32,
-    ) -> Result<(), SPI::Error> {
-        self.wait_until_idle(spi, delay)?;
-        self.set_ram_area(spi, delay, x, y, x + width, y + height)?;
-        self.set_ram_counter(spi, delay, x, y)?;
 
-        self.interface
-            .cmd_with_data(spi, Command::WriteRam, buffer)?;
-        Ok(())
-    }
-
-    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
         // enable clock signal, enable cp, display pattern -> 0xC4 (tested with the arduino version)
         //TODO: test control_1 or control_2 with default value 0xFF (from the datasheet)
@@ -246,13 +314,13 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, buffer, delay)?;
         self.display_frame(spi, delay)?;
         Ok(())
     }
 
-    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
         self.use_full_frame(spi, delay)?;
 
@@ -273,27 +341,106 @@ where
         &self.background_color
     }
 
+    fn wait_until_idle(
+        &mut self,
+        _spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_until_idle(delay, IS_BUSY_LOW)?;
+        Ok(())
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> LutControl<SPI, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
     fn set_lut(
         &mut self,
         spi: &mut SPI,
         delay: &mut DELAY,
         refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         if let Some(refresh_lut) = refresh_rate {
             self.refresh = refresh_lut;
         }
         match self.refresh {
-            RefreshLut::Full => self.set_lut_helper(spi, delay, &LUT_FULL_UPDATE),
+            RefreshLut::Full | RefreshLut::Fast => {
+                self.set_lut_helper(spi, delay, &LUT_FULL_UPDATE)
+            }
             RefreshLut::Quick => self.set_lut_helper(spi, delay, &LUT_PARTIAL_UPDATE),
         }
     }
+}
 
-    fn wait_until_idle(&mut self, _spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.interface.wait_until_idle(delay, IS_BUSY_LOW);
+impl<SPI, BUSY, DC, RST, DELAY> PartialUpdate<SPI, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.set_ram_area(spi, delay, x, y, x + width, y + height)?;
+        self.set_ram_counter(spi, delay, x, y)?;
+
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam, buffer)?;
         Ok(())
     }
 }
 
+impl<SPI, BUSY, DC, RST, DELAY> PartialRefresh<SPI, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Refreshes the display with `buffer` once
+    ///
+    /// The controller swaps its two RAMs at every refresh, the partial waveform comparing the
+    /// new frame with the previous one: the frame is written, shown, and written again so
+    /// both RAMs hold it.
+    fn set_base_image(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_and_display_frame(spi, buffer, delay)?;
+        self.update_frame(spi, buffer, delay)
+    }
+
+    fn set_update_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mode: RefreshLut,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.set_lut(spi, delay, Some(mode))
+    }
+}
+
 impl<SPI, BUSY, DC, RST, DELAY> Epd1in54<SPI, BUSY, DC, RST, DELAY>
 where
     SPI: SpiDevice,
@@ -306,7 +453,7 @@ where
         &mut self,
         spi: &mut SPI,
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         // choose full frame/ram
         self.set_ram_area(spi, delay, 0, 0, WIDTH - 1, HEIGHT - 1)?;
 
@@ -322,10 +469,11 @@ where
         start_y: u32,
         end_x: u32,
         end_y: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
-        assert!(start_x < end_x);
-        assert!(start_y < end_y);
+        if start_x >= end_x || start_y >= end_y {
+            return Err(Error::Window(WindowError::Empty));
+        }
 
         // x is positioned in bytes, so the last 3 bits which show the position inside a byte in the ram
         // aren't relevant
@@ -355,7 +503,7 @@ where
         delay: &mut DELAY,
         x: u32,
         y: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
         // x is positioned in bytes, so the last 3 bits which show the position inside a byte in the ram
         // aren't relevant
@@ -376,9 +524,9 @@ where
         spi: &mut SPI,
         delay: &mut DELAY,
         buffer: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
-        assert!(buffer.len() == 30);
+        Error::check_len(buffer, 30)?;
 
         self.interface
             .cmd_with_data(spi, Command::WriteLutRegister, buffer)?;
@@ -396,4 +544,47 @@ mod tests {
         assert_eq!(HEIGHT, 200);
         assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
     }
+
+    #[test]
+    fn set_base_image() {
+        extern crate std;
+        use crate::testing::{Entry, Recorder};
+        use std::vec::Vec;
+
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let (dc, rst) = (recorder.dc(), recorder.rst());
+        let mut epd = Epd1in54::new(&mut spi, busy, dc, rst, &mut delay, None).unwrap();
+        recorder.clear();
+
+        let frame = [0xA5; buffer_len(WIDTH as usize, HEIGHT as usize)];
+        assert_eq!(
+            epd.set_base_image(&mut spi, &mut delay, &frame[1..]),
+            Err(Error::WrongBufferSize {
+                expected: frame.len(),
+                got: frame.len() - 1
+            })
+        );
+        assert!(recorder.transcript().entries().is_empty());
+        epd.set_base_image(&mut spi, &mut delay, &frame).unwrap();
+
+        // the frame is written to the RAM shown by the refresh, then to the other one
+        let mut writes: Vec<(u8, Vec<u8>)> = Vec::new();
+        for entry in recorder.transcript().entries() {
+            match entry {
+                Entry::Command(command) => writes.push((*command, Vec::new())),
+                Entry::Data(data) => writes.last_mut().unwrap().1.extend_from_slice(data),
+                _ => {}
+            }
+        }
+        let written: Vec<_> = writes
+            .iter()
+            .filter(|(c, _)| *c == Command::WriteRam as u8 || *c == Command::MasterActivation as u8)
+            .collect();
+        assert_eq!(written.len(), 3);
+        assert_eq!(written[0], &(Command::WriteRam as u8, frame.to_vec()));
+        assert_eq!(written[1].0, Command::MasterActivation as u8);
+        assert_eq!(written[2], &(Command::WriteRam as u8, frame.to_vec()));
+    }
 }
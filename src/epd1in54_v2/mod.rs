@@ -13,6 +13,8 @@ const SINGLE_BYTE_WRITE: bool = true;
 
 use embedded_hal::{delay::*, digital::*, spi::SpiDevice};
 
+use crate::error::Error;
+use crate::rect::WindowError;
 use crate::type_a::command::Command;
 
 mod constants;
@@ -20,9 +22,13 @@ use crate::epd1in54_v2::constants::{LUT_FULL_UPDATE, LUT_PARTIAL_UPDATE};
 
 use crate::color::Color;
 
-use crate::traits::{RefreshLut, WaveshareDisplay};
+use crate::traits::{
+    DeepSleepMode, InternalWiAdditions, LutControl, PartialRefresh, PartialUpdate, RefreshLut,
+    Sleepable, WaveshareDisplay,
+};
 
-use crate::interface::DisplayInterface;
+use crate::buffer_len;
+use crate::interface::{BusyYield, DisplayInterface};
 
 #[cfg(feature = "graphics")]
 pub use crate::epd1in54::Display1in54;
@@ -38,7 +44,59 @@ pub struct Epd1in54<SPI, BUSY, DC, RST, DELAY> {
     refresh: RefreshLut,
 }
 
-impl<SPI, BUSY, DC, RST, DELAY> Epd1in54<SPI, BUSY, DC, RST, DELAY>
+impl<SPI, BUSY, DC, RST, DELAY> Epd1in54<SPI, BUSY, DC, RST, DELAY> {
+    /// Gives back the busy, data/command and reset pins, e.g. to reuse them once the display
+    /// is asleep
+    ///
+    /// The display keeps its state, so a driver can be created again later with the pins.
+    pub fn release(self) -> (BUSY, DC, RST) {
+        self.interface.release()
+    }
+
+    /// Gives up waiting for the display with [`Error::BusyTimeout`] once it has been busy for
+    /// `timeout_us`, e.g. because of a loose cable, instead of waiting forever. `None` removes
+    /// the timeout.
+    ///
+    /// The time is counted by adding up the delays of the idle loop, each poll of the busy pin
+    /// counting as at least 1us.
+    pub fn set_busy_timeout(&mut self, timeout_us: Option<u32>) {
+        self.interface.set_busy_timeout(timeout_us);
+    }
+
+    /// Polls the busy pin every `interval_us` while the display is busy (0 busy waits), or,
+    /// with a `max_interval_us`, backs off: the interval doubles after every poll until
+    /// `max_interval_us`
+    ///
+    /// The interval is the `delay_us` given to `new` (10ms by default) until this is called.
+    pub fn set_poll_interval(&mut self, interval_us: u32, max_interval_us: Option<u32>) {
+        self.interface.set_poll_interval(interval_us);
+        self.interface.set_poll_backoff(max_interval_us);
+    }
+
+    /// Sets a [`BusyYield`] called on every poll of the busy pin while the display is busy, e.g.
+    /// to feed a watchdog during a refresh. `None` removes it.
+    pub fn set_busy_yield(&mut self, busy_yield: Option<BusyYield>) {
+        self.interface.set_busy_yield(busy_yield);
+    }
+
+    /// Time the display was busy during the last wait for it, counted like the busy timeout,
+    /// e.g. to choose the timeout
+    pub fn last_wait_us(&self) -> u32 {
+        self.interface.last_wait_us()
+    }
+
+    /// CRC-32 of the data of the last frame written to the display, e.g. by
+    /// [`update_frame`](WaveshareDisplay::update_frame), to compare with the
+    /// [`crc32`](crate::crc::crc32) of the expected image and detect a corrupted transfer. `None`
+    /// until a frame is written.
+    #[cfg(any(feature = "crc", test))]
+    pub fn last_frame_crc(&self) -> Option<u32> {
+        self.interface.last_crc()
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, BUSY, DC, RST, DELAY>
 where
     SPI: SpiDevice,
     BUSY: InputPin,
@@ -46,8 +104,8 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.interface.reset(delay, 10_000, 10_000);
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 10_000, 10_000)?;
         self.wait_until_idle(spi, delay)?;
         self.interface.cmd(spi, Command::SwReset)?;
         self.wait_until_idle(spi, delay)?;
@@ -87,6 +145,35 @@ where
     }
 }
 
+impl<SPI, BUSY, DC, RST, DELAY> Sleepable<SPI, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.sleep_with_mode(spi, delay, DeepSleepMode::Mode1)
+    }
+
+    fn sleep_with_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mode: DeepSleepMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.interface
+            .cmd_with_data(spi, Command::DeepSleepMode, &[mode as u8])
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+}
+
 impl<SPI, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
     for Epd1in54<SPI, BUSY, DC, RST, DELAY>
 where
@@ -112,7 +199,7 @@ where
         rst: RST,
         delay: &mut DELAY,
         delay_us: Option<u32>,
-    ) -> Result<Self, SPI::Error> {
+    ) -> Result<Self, Error<SPI::Error>> {
         let interface = DisplayInterface::new(busy, dc, rst, delay_us);
 
         let mut epd = Epd1in54 {
@@ -121,28 +208,20 @@ where
             refresh: RefreshLut::Full,
         };
 
+        #[cfg(any(feature = "crc", test))]
+        epd.interface.track_crc(Command::WriteRam);
         epd.init(spi, delay)?;
 
         Ok(epd)
     }
 
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.init(spi, delay)
-    }
-
-    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.wait_until_idle(spi, delay)?;
-        self.interface
-            .cmd_with_data(spi, Command::DeepSleepMode, &[0x01])?;
-        Ok(())
-    }
-
     fn update_frame(
         &mut self,
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         self.wait_until_idle(spi, delay)?;
         self.use_full_frame(spi, delay)?;
         self.interface
@@ -151,33 +230,15 @@ where
     }
 
     //TODO: update description: last 3 bits will be ignored for width and x_pos
-    fn update_partial_frame(
-        &mut self,
-        spi: &mut SPI,
-        delay: &mut DELAY,
-        buffer: &[u8],
-        x: u32,
-        y: u32,
-        width: u32,
-        height: u32,
-    ) -> Result<(), SPI::Error> {
-        self.wait_until_idle(spi, delay)?;
-        self.set_ram_area(spi, delay, x, y, x + width, y + height)?;
-        self.set_ram_counter(spi, delay, x, y)?;
 
-        self.interface
-            .cmd_with_data(spi, Command::WriteRam, buffer)?;
-        Ok(())
-    }
-
-    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
-        if self.refresh == RefreshLut::Full {
-            self.interface
-                .cmd_with_data(spi, Command::DisplayUpdateControl2, &[0xC7])?;
-        } else if self.refresh == RefreshLut::Quick {
+        if self.refresh == RefreshLut::Quick {
             self.interface
                 .cmd_with_data(spi, Command::DisplayUpdateControl2, &[0xCF])?;
+        } else {
+            self.interface
+                .cmd_with_data(spi, Command::DisplayUpdateControl2, &[0xC7])?;
         }
 
         self.interface.cmd(spi, Command::MasterActivation)?;
@@ -192,13 +253,13 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, buffer, delay)?;
         self.display_frame(spi, delay)?;
         Ok(())
     }
 
-    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
         self.use_full_frame(spi, delay)?;
 
@@ -222,17 +283,38 @@ where
         &self.background_color
     }
 
+    fn wait_until_idle(
+        &mut self,
+        _spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_until_idle(delay, IS_BUSY_LOW)?;
+        Ok(())
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> LutControl<SPI, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
     fn set_lut(
         &mut self,
         spi: &mut SPI,
         delay: &mut DELAY,
         refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         if let Some(refresh_lut) = refresh_rate {
             self.refresh = refresh_lut;
         }
         match self.refresh {
-            RefreshLut::Full => self.set_lut_helper(spi, delay, &LUT_FULL_UPDATE),
+            RefreshLut::Full | RefreshLut::Fast => {
+                self.set_lut_helper(spi, delay, &LUT_FULL_UPDATE)
+            }
             RefreshLut::Quick => self.set_lut_helper(spi, delay, &LUT_PARTIAL_UPDATE),
         }?;
 
@@ -254,13 +336,72 @@ where
         }
         Ok(())
     }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> PartialUpdate<SPI, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.set_ram_area(spi, delay, x, y, x + width, y + height)?;
+        self.set_ram_counter(spi, delay, x, y)?;
 
-    fn wait_until_idle(&mut self, _spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.interface.wait_until_idle(delay, IS_BUSY_LOW);
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam, buffer)?;
         Ok(())
     }
 }
 
+impl<SPI, BUSY, DC, RST, DELAY> PartialRefresh<SPI, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn set_base_image(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.wait_until_idle(spi, delay)?;
+        self.use_full_frame(spi, delay)?;
+        // the partial waveform compares the new frame with the previous one
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam, buffer)?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam2, buffer)
+    }
+
+    fn set_update_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mode: RefreshLut,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.set_lut(spi, delay, Some(mode))
+    }
+}
+
 impl<SPI, BUSY, DC, RST, DELAY> Epd1in54<SPI, BUSY, DC, RST, DELAY>
 where
     SPI: SpiDevice,
@@ -273,7 +414,7 @@ where
         &mut self,
         spi: &mut SPI,
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         // choose full frame/ram
         self.set_ram_area(spi, delay, 0, 0, WIDTH - 1, HEIGHT - 1)?;
 
@@ -289,10 +430,11 @@ where
         start_y: u32,
         end_x: u32,
         end_y: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
-        assert!(start_x < end_x);
-        assert!(start_y < end_y);
+        if start_x >= end_x || start_y >= end_y {
+            return Err(Error::Window(WindowError::Empty));
+        }
 
         // x is positioned in bytes, so the last 3 bits which show the position inside a byte in the ram
         // aren't relevant
@@ -322,7 +464,7 @@ where
         delay: &mut DELAY,
         x: u32,
         y: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
         // x is positioned in bytes, so the last 3 bits which show the position inside a byte in the ram
         // aren't relevant
@@ -343,9 +485,9 @@ where
         spi: &mut SPI,
         delay: &mut DELAY,
         buffer: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
-        assert!(buffer.len() == 159);
+        Error::check_len(buffer, 159)?;
 
         self.interface
             .cmd_with_data(spi, Command::WriteLutRegister, &buffer[0..153])?;
@@ -380,4 +522,46 @@ mod tests {
         assert_eq!(HEIGHT, 200);
         assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
     }
+
+    #[test]
+    fn set_base_image() {
+        extern crate std;
+        use crate::testing::{Entry, Recorder};
+        use std::vec::Vec;
+
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let (dc, rst) = (recorder.dc(), recorder.rst());
+        let mut epd = Epd1in54::new(&mut spi, busy, dc, rst, &mut delay, None).unwrap();
+        recorder.clear();
+
+        let frame = [0xA5; buffer_len(WIDTH as usize, HEIGHT as usize)];
+        assert_eq!(
+            epd.set_base_image(&mut spi, &mut delay, &frame[1..]),
+            Err(Error::WrongBufferSize {
+                expected: frame.len(),
+                got: frame.len() - 1
+            })
+        );
+        epd.set_base_image(&mut spi, &mut delay, &frame).unwrap();
+
+        // both RAMs hold the frame, the partial waveform comparing them
+        let mut writes: Vec<(u8, Vec<u8>)> = Vec::new();
+        for entry in recorder.transcript().entries() {
+            match entry {
+                Entry::Command(command) => writes.push((*command, Vec::new())),
+                Entry::Data(data) => writes.last_mut().unwrap().1.extend_from_slice(data),
+                _ => {}
+            }
+        }
+        let ram = |command: Command| {
+            writes
+                .iter()
+                .find(|(c, _)| *c == command as u8)
+                .map(|(_, data)| data.as_slice())
+        };
+        assert_eq!(ram(Command::WriteRam), Some(&frame[..]));
+        assert_eq!(ram(Command::WriteRam2), Some(&frame[..]));
+    }
 }
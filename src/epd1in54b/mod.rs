@@ -2,9 +2,11 @@
 
 use embedded_hal::{delay::*, digital::*, spi::SpiDevice};
 
-use crate::interface::DisplayInterface;
+use crate::error::Error;
+use crate::interface::{BusyYield, DisplayInterface};
 use crate::traits::{
-    InternalWiAdditions, RefreshLut, WaveshareDisplay, WaveshareThreeColorDisplay,
+    InternalWiAdditions, LutControl, RefreshLut, Sleepable, WaveshareDisplay,
+    WaveshareThreeColorDisplay,
 };
 
 //The Lookup Tables for the Display
@@ -43,6 +45,58 @@ pub struct Epd1in54b<SPI, BUSY, DC, RST, DELAY> {
     color: Color,
 }
 
+impl<SPI, BUSY, DC, RST, DELAY> Epd1in54b<SPI, BUSY, DC, RST, DELAY> {
+    /// Gives back the busy, data/command and reset pins, e.g. to reuse them once the display
+    /// is asleep
+    ///
+    /// The display keeps its state, so a driver can be created again later with the pins.
+    pub fn release(self) -> (BUSY, DC, RST) {
+        self.interface.release()
+    }
+
+    /// Gives up waiting for the display with [`Error::BusyTimeout`] once it has been busy for
+    /// `timeout_us`, e.g. because of a loose cable, instead of waiting forever. `None` removes
+    /// the timeout.
+    ///
+    /// The time is counted by adding up the delays of the idle loop, each poll of the busy pin
+    /// counting as at least 1us.
+    pub fn set_busy_timeout(&mut self, timeout_us: Option<u32>) {
+        self.interface.set_busy_timeout(timeout_us);
+    }
+
+    /// Polls the busy pin every `interval_us` while the display is busy (0 busy waits), or,
+    /// with a `max_interval_us`, backs off: the interval doubles after every poll until
+    /// `max_interval_us`
+    ///
+    /// The interval is the `delay_us` given to `new` (10ms by default) until this is called.
+    pub fn set_poll_interval(&mut self, interval_us: u32, max_interval_us: Option<u32>) {
+        self.interface.set_poll_interval(interval_us);
+        self.interface.set_poll_backoff(max_interval_us);
+    }
+
+    /// Sets a [`BusyYield`] called on every poll of the busy pin while the display is busy, e.g.
+    /// to feed a watchdog during a refresh. `None` removes it.
+    pub fn set_busy_yield(&mut self, busy_yield: Option<BusyYield>) {
+        self.interface.set_busy_yield(busy_yield);
+    }
+
+    /// Time the display was busy during the last wait for it, counted like the busy timeout,
+    /// e.g. to choose the timeout
+    pub fn last_wait_us(&self) -> u32 {
+        self.interface.last_wait_us()
+    }
+
+    /// CRC-32 of the data of the last frame written to the display, e.g. by
+    /// [`update_frame`](WaveshareDisplay::update_frame), to compare with the
+    /// [`crc32`](crate::crc::crc32) of the expected image and detect a corrupted transfer. `None`
+    /// until a frame is written. The frame is sent with 2 bits per pixel, the CRC being the one of
+    /// the expanded data. The chromatic frame isn't covered.
+    #[cfg(any(feature = "crc", test))]
+    pub fn last_frame_crc(&self) -> Option<u32> {
+        self.interface.last_crc()
+    }
+}
+
 impl<SPI, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, BUSY, DC, RST, DELAY>
     for Epd1in54b<SPI, BUSY, DC, RST, DELAY>
 where
@@ -52,8 +106,8 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.interface.reset(delay, 10_000, 10_000);
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 10_000, 10_000)?;
 
         // set the power settings
         self.interface
@@ -104,7 +158,9 @@ where
         delay: &mut DELAY,
         black: &[u8],
         chromatic: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        // check both buffers before writing anything
+        Error::check_len(chromatic, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         self.update_achromatic_frame(spi, delay, black)?;
         self.update_chromatic_frame(spi, delay, chromatic)
     }
@@ -114,7 +170,8 @@ where
         spi: &mut SPI,
         delay: &mut DELAY,
         black: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(black, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         self.wait_until_idle(spi, delay)?;
         self.send_resolution(spi)?;
 
@@ -132,14 +189,15 @@ where
         spi: &mut SPI,
         _delay: &mut DELAY,
         chromatic: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(chromatic, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         self.interface.cmd(spi, Command::DataStartTransmission2)?;
         self.interface.data(spi, chromatic)?;
         Ok(())
     }
 }
 
-impl<SPI, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
+impl<SPI, BUSY, DC, RST, DELAY> Sleepable<SPI, BUSY, DC, RST, DELAY>
     for Epd1in54b<SPI, BUSY, DC, RST, DELAY>
 where
     SPI: SpiDevice,
@@ -148,26 +206,7 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    type DisplayColor = Color;
-    fn new(
-        spi: &mut SPI,
-        busy: BUSY,
-        dc: DC,
-        rst: RST,
-        delay: &mut DELAY,
-        delay_us: Option<u32>,
-    ) -> Result<Self, SPI::Error> {
-        let interface = DisplayInterface::new(busy, dc, rst, delay_us);
-        let color = DEFAULT_BACKGROUND_COLOR;
-
-        let mut epd = Epd1in54b { interface, color };
-
-        epd.init(spi, delay)?;
-
-        Ok(epd)
-    }
-
-    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
         self.interface
             .cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x17])?; //border floating
@@ -187,9 +226,40 @@ where
         Ok(())
     }
 
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.init(spi, delay)
     }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
+    for Epd1in54b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    type DisplayColor = Color;
+    fn new(
+        spi: &mut SPI,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+        delay_us: Option<u32>,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let interface = DisplayInterface::new(busy, dc, rst, delay_us);
+        let color = DEFAULT_BACKGROUND_COLOR;
+
+        let mut epd = Epd1in54b { interface, color };
+
+        #[cfg(any(feature = "crc", test))]
+        epd.interface.track_crc(Command::DataStartTransmission1);
+        epd.init(spi, delay)?;
+
+        Ok(epd)
+    }
 
     fn set_background_color(&mut self, color: Color) {
         self.color = color;
@@ -212,7 +282,8 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         self.wait_until_idle(spi, delay)?;
         self.send_resolution(spi)?;
 
@@ -237,21 +308,7 @@ where
         Ok(())
     }
 
-    #[allow(unused)]
-    fn update_partial_frame(
-        &mut self,
-        spi: &mut SPI,
-        delay: &mut DELAY,
-        buffer: &[u8],
-        x: u32,
-        y: u32,
-        width: u32,
-        height: u32,
-    ) -> Result<(), SPI::Error> {
-        unimplemented!()
-    }
-
-    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
         self.command(spi, Command::DisplayRefresh)?;
         Ok(())
@@ -262,13 +319,13 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, buffer, delay)?;
         self.display_frame(spi, delay)?;
         Ok(())
     }
 
-    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
         self.send_resolution(spi)?;
 
@@ -288,12 +345,31 @@ where
         Ok(())
     }
 
+    fn wait_until_idle(
+        &mut self,
+        _spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_until_idle(delay, IS_BUSY_LOW)?;
+        Ok(())
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> LutControl<SPI, BUSY, DC, RST, DELAY>
+    for Epd1in54b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
     fn set_lut(
         &mut self,
         spi: &mut SPI,
         _delay: &mut DELAY,
         _refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.interface
             .cmd_with_data(spi, Command::LutForVcom, LUT_VCOM0)?;
         self.interface
@@ -311,11 +387,6 @@ where
 
         Ok(())
     }
-
-    fn wait_until_idle(&mut self, _spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.interface.wait_until_idle(delay, IS_BUSY_LOW);
-        Ok(())
-    }
 }
 
 impl<SPI, BUSY, DC, RST, DELAY> Epd1in54b<SPI, BUSY, DC, RST, DELAY>
@@ -326,11 +397,11 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), SPI::Error> {
+    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd(spi, command)
     }
 
-    fn send_data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
+    fn send_data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), Error<SPI::Error>> {
         self.interface.data(spi, data)
     }
 
@@ -339,11 +410,11 @@ where
         spi: &mut SPI,
         command: Command,
         data: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd_with_data(spi, command, data)
     }
 
-    fn send_resolution(&mut self, spi: &mut SPI) -> Result<(), SPI::Error> {
+    fn send_resolution(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
         let w = self.width();
         let h = self.height();
 
@@ -0,0 +1,352 @@
+//! Async driver for the 2.13" B V4, built on the `embedded-hal-async` traits
+//!
+//! It sends the same commands as the blocking [`Epd2in13b`](super::Epd2in13b), but awaits
+//! the busy pin instead of polling it, so the long waits during a refresh don't block the
+//! executor.
+//!
+//! The `async` feature needs Rust 1.75, newer than the 1.62 needed by the rest of the crate.
+//!
+//!```rust, ignore
+//!use epd_waveshare::{epd2in13b_v4::{asynch::Epd2in13b, Display2in13b}, prelude::*};
+//!
+//!let mut epd = Epd2in13b::new(&mut spi, busy_in, dc, rst, &mut delay).await?;
+//!let display = Display2in13b::default();
+//!epd.update_color_frame(&mut spi, display.bw_buffer(), display.chromatic_buffer())
+//!    .await?;
+//!epd.display_frame(&mut spi).await?;
+//!epd.sleep(&mut spi).await?;
+//!```
+//!
+//! A complete Embassy application for the Raspberry Pi Pico is in `examples/embassy_rp`.
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::{delay::DelayNs, digital::Wait, spi::SpiDevice};
+
+use super::command::{Command, DeepSleepMode, DisplayUpdateControl};
+use super::{
+    shared, BoosterSoftStart, GateVoltage, RamOption, SourceVoltage, DEFAULT_BACKGROUND_COLOR,
+    DEFAULT_BORDER_WAVEFORM, DEFAULT_VCOM, HEIGHT, IS_BUSY_LOW, RESET_DURATION_US,
+    SINGLE_BYTE_WRITE, WIDTH,
+};
+use crate::buffer_len;
+use crate::color::TriColor;
+use crate::controllers::ssd1680::{DataEntryModeDir, DataEntryModeIncr, DriverOutput};
+use crate::error::Error;
+use crate::interface_async::AsyncDisplayInterface;
+
+/// Async Epd2in13b (V4) driver
+pub struct Epd2in13b<SPI, BUSY, DC, RST, DELAY> {
+    /// Connection Interface
+    interface: AsyncDisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+    /// Background Color
+    background_color: TriColor,
+    /// Value of the border waveform control register
+    border_waveform: u8,
+    /// Value of the VCOM register
+    vcom: u8,
+    /// Gate driving voltage
+    gate_voltage: GateVoltage,
+    /// Source driving voltages
+    source_voltage: SourceVoltage,
+    /// Booster soft start, left to the controller when `None`
+    booster_soft_start: Option<BoosterSoftStart>,
+    /// How refreshes use the black/white RAM
+    bw_ram_option: RamOption,
+    /// How refreshes use the chromatic RAM
+    red_ram_option: RamOption,
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Epd2in13b<SPI, BUSY, DC, RST, DELAY> {
+    /// Gives back the busy, data/command and reset pins, e.g. to reuse them once the display
+    /// is asleep
+    ///
+    /// The display keeps its state, so a driver can be created again later with the pins.
+    pub fn release(self) -> (BUSY, DC, RST) {
+        self.interface.release()
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Epd2in13b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: Wait,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Creates a new driver from a SPI peripheral, CS Pin, Busy InputPin, DC
+    ///
+    /// This already initialises the device.
+    pub async fn new(
+        spi: &mut SPI,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let mut epd = Epd2in13b {
+            interface: AsyncDisplayInterface::new(busy, dc, rst),
+            background_color: DEFAULT_BACKGROUND_COLOR,
+            border_waveform: DEFAULT_BORDER_WAVEFORM,
+            vcom: DEFAULT_VCOM,
+            gate_voltage: GateVoltage::DEFAULT,
+            source_voltage: SourceVoltage::DEFAULT,
+            booster_soft_start: None,
+            bw_ram_option: RamOption::Normal,
+            red_ram_option: RamOption::Normal,
+        };
+
+        epd.init(spi, delay).await?;
+        Ok(epd)
+    }
+
+    /// Wakes the device up and initialises it again
+    pub async fn wake_up(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay).await
+    }
+
+    /// Lets the device enter deep-sleep mode to save power.
+    ///
+    /// The deep sleep mode returns to standby with a hardware reset.
+    pub async fn sleep(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        shared::sleep!(self, spi, DeepSleepMode::Mode1, [.await])
+    }
+
+    /// Same as [`sleep`](Self::sleep), entering the deep sleep `mode`
+    pub async fn sleep_with_mode(
+        &mut self,
+        spi: &mut SPI,
+        mode: DeepSleepMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        shared::sleep!(self, spi, mode, [.await])
+    }
+
+    async fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        // HW reset
+        self.interface
+            .reset(delay, 10_000, RESET_DURATION_US)
+            .await?;
+
+        self.wait_until_idle().await?;
+        self.interface.cmd(spi, Command::SwReset).await?;
+        self.wait_until_idle().await?;
+
+        let output = DriverOutput {
+            scan_is_linear: true,
+            scan_g0_is_first: true,
+            scan_dir_incr: true,
+            width: (HEIGHT - 1) as u16,
+        };
+        self.cmd_with_data(spi, Command::DriverOutputControl, &output.to_bytes())
+            .await?;
+
+        let mode = DataEntryModeIncr::XIncrYIncr as u8 | DataEntryModeDir::XDir as u8;
+        self.cmd_with_data(spi, Command::DataEntryModeSetting, &[mode])
+            .await?;
+
+        // Use simple X/Y auto increase
+        self.set_full_window(spi).await?;
+
+        shared::init_registers!(self, spi, [.await]);
+
+        self.wait_until_idle().await?;
+        Ok(())
+    }
+
+    /// Transmit a full frame to the SRAM of the EPD, the chromatic layer is cleared
+    pub async fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.cmd_with_data(spi, Command::WriteRam, buffer).await?;
+
+        self.interface.cmd(spi, Command::WriteRamRed).await?;
+        self.interface
+            .data_x_times(
+                spi,
+                TriColor::Black.get_byte_value(),
+                buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
+            )
+            .await
+    }
+
+    /// Updates both the black and the secondary color layers
+    pub async fn update_color_frame(
+        &mut self,
+        spi: &mut SPI,
+        black: &[u8],
+        chromatic: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_achromatic_frame(spi, black).await?;
+        self.update_chromatic_frame(spi, chromatic).await
+    }
+
+    /// Update only the black/white data of the display.
+    ///
+    /// This must be finished by calling `update_chromatic_frame`.
+    pub async fn update_achromatic_frame(
+        &mut self,
+        spi: &mut SPI,
+        black: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(spi, Command::WriteRam, black).await
+    }
+
+    /// Update only the chromatic data of the display.
+    ///
+    /// This should be preceded by a call to `update_achromatic_frame`.
+    /// This data takes precedence over the black/white data.
+    pub async fn update_chromatic_frame(
+        &mut self,
+        spi: &mut SPI,
+        chromatic: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(spi, Command::WriteRamRed, chromatic)
+            .await
+    }
+
+    /// Displays the frame data from SRAM, awaiting the end of the refresh
+    pub async fn display_frame(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.interface.cmd(spi, Command::MasterActivation).await?;
+        self.wait_until_idle().await?;
+        Ok(())
+    }
+
+    /// Clears the frame buffer on the EPD with the declared background color
+    pub async fn clear_frame(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        shared::clear_frame!(self, spi, [.await])
+    }
+
+    /// Sets the backgroundcolor for various commands like [clear_frame](Self::clear_frame)
+    pub fn set_background_color(&mut self, background_color: TriColor) {
+        self.background_color = background_color;
+    }
+
+    /// Get current background color
+    pub fn background_color(&self) -> &TriColor {
+        &self.background_color
+    }
+
+    /// Get the width of the display
+    pub fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    /// Get the height of the display
+    pub fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    /// Waits until the device isn't busy anymore
+    pub async fn wait_until_idle(&mut self) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_until_idle(IS_BUSY_LOW).await
+    }
+
+    /// Restores the RAM window and the address counters to the whole display
+    async fn set_full_window(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(
+            spi,
+            Command::SetRamXAddressStartEndPosition,
+            &[0, ((WIDTH - 1) >> 3) as u8],
+        )
+        .await?;
+        self.cmd_with_data(
+            spi,
+            Command::SetRamYAddressStartEndPosition,
+            &[0, 0, (HEIGHT - 1) as u8, ((HEIGHT - 1) >> 8) as u8],
+        )
+        .await?;
+
+        self.wait_until_idle().await?;
+        self.cmd_with_data(spi, Command::SetRamXAddressCounter, &[0])
+            .await?;
+        self.cmd_with_data(spi, Command::SetRamYAddressCounter, &[0, 0])
+            .await
+    }
+
+    async fn cmd_with_data(
+        &mut self,
+        spi: &mut SPI,
+        command: Command,
+        data: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.cmd_with_data(spi, command, data).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{cmd, expectations, init_traffic};
+    use super::*;
+    use embedded_hal_mock::eh1::{delay::NoopDelay, digital, spi};
+
+    extern crate std;
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll};
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    // the mocks never return `Pending`, polling until ready is enough
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct NoopWaker;
+        impl Wake for NoopWaker {
+            fn wake(self: Arc<Self>) {}
+        }
+        let waker = Arc::new(NoopWaker).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn same_traffic_as_blocking_driver() {
+        let traffic = [
+            init_traffic(),
+            cmd(Command::MasterActivation, &[]),
+            cmd(Command::DeepSleepMode, &[0x01]),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&std::vec![
+            digital::Transaction::wait_for_state(digital::State::Low);
+            5
+        ]);
+        let mut rst = digital::Mock::new(&[
+            digital::Transaction::set(digital::State::High),
+            digital::Transaction::set(digital::State::Low),
+            digital::Transaction::set(digital::State::High),
+        ]);
+
+        block_on(async {
+            let mut epd = Epd2in13b::new(
+                &mut spi,
+                busy.clone(),
+                dc.clone(),
+                rst.clone(),
+                &mut NoopDelay,
+            )
+            .await
+            .unwrap();
+            epd.display_frame(&mut spi).await.unwrap();
+            epd.sleep(&mut spi).await.unwrap();
+        });
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
+}
@@ -0,0 +1,266 @@
+//! Builder for a configured [`Epd2in13b`]
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{InputPin, OutputPin},
+    spi::SpiDevice,
+};
+
+#[cfg(any(feature = "crc", test))]
+use super::command::Command;
+use super::{
+    BoosterSoftStart, Epd2in13b, GateVoltage, RamOption, SourceVoltage, DEFAULT_BACKGROUND_COLOR,
+    DEFAULT_BORDER_WAVEFORM, DEFAULT_VCOM, RESET_DURATION_US, RESET_HIGH_US,
+};
+use crate::color::TriColor;
+use crate::error::Error;
+use crate::interface::{BusyYield, DisplayInterface};
+use crate::traits::{InternalWiAdditions, RefreshLut};
+
+/// Builder for [`Epd2in13b`], for when the defaults used by
+/// [`new`](crate::traits::WaveshareDisplay::new) don't fit your panel
+///
+/// ```rust, no_run
+///# use embedded_hal_mock::eh1::*;
+///# fn main() -> Result<(), epd_waveshare::error::Error<embedded_hal::spi::ErrorKind>> {
+///use epd_waveshare::epd2in13b_v4::Epd2in13bBuilder;
+///#
+///# let mut spi = spi::Mock::new(&[]);
+///# let busy_in = digital::Mock::new(&[]);
+///# let dc = digital::Mock::new(&[]);
+///# let rst = digital::Mock::new(&[]);
+///# let mut delay = delay::NoopDelay::new();
+///
+///let mut epd = Epd2in13bBuilder::new()
+///    // this panel needs a longer reset pulse to wake up from deep sleep
+///    .resume_reset_us(50_000)
+///    // the VCOM printed on its flex cable
+///    .vcom(0x28)
+///    .build(&mut spi, busy_in, dc, rst, &mut delay)?;
+///# Ok(())
+///# }
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct Epd2in13bBuilder {
+    pub(super) delay_us: Option<u32>,
+    max_delay_us: Option<u32>,
+    busy_timeout_us: Option<u32>,
+    busy_yield: Option<BusyYield>,
+    reset_high_us: u32,
+    cold_reset_us: u32,
+    resume_reset_us: u32,
+    background_color: TriColor,
+    border_waveform: u8,
+    vcom: u8,
+    gate_voltage: GateVoltage,
+    source_voltage: SourceVoltage,
+    booster_soft_start: Option<BoosterSoftStart>,
+    bw_ram_option: RamOption,
+    red_ram_option: RamOption,
+    auto_init: bool,
+    auto_sleep: bool,
+}
+
+impl Default for Epd2in13bBuilder {
+    fn default() -> Self {
+        Self {
+            delay_us: None,
+            max_delay_us: None,
+            busy_timeout_us: None,
+            busy_yield: None,
+            reset_high_us: RESET_HIGH_US,
+            cold_reset_us: RESET_DURATION_US,
+            resume_reset_us: RESET_DURATION_US,
+            background_color: DEFAULT_BACKGROUND_COLOR,
+            border_waveform: DEFAULT_BORDER_WAVEFORM,
+            vcom: DEFAULT_VCOM,
+            gate_voltage: GateVoltage::DEFAULT,
+            source_voltage: SourceVoltage::DEFAULT,
+            booster_soft_start: None,
+            bw_ram_option: RamOption::Normal,
+            red_ram_option: RamOption::Normal,
+            auto_init: true,
+            auto_sleep: false,
+        }
+    }
+}
+
+impl Epd2in13bBuilder {
+    /// Creates a builder with the default configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of us the idle loop should sleep on, 0 implies busy waiting
+    pub fn delay_us(mut self, delay_us: u32) -> Self {
+        self.delay_us = Some(delay_us);
+        self
+    }
+
+    /// Doubles the sleep of the idle loop after every poll of the busy pin, until
+    /// `max_delay_us`, see [`Epd2in13b::set_poll_interval`]
+    pub fn poll_backoff_us(mut self, max_delay_us: u32) -> Self {
+        self.max_delay_us = Some(max_delay_us);
+        self
+    }
+
+    /// Gives up waiting for the busy display after `timeout_us`, see
+    /// [`Epd2in13b::set_busy_timeout`]
+    pub fn busy_timeout_us(mut self, timeout_us: u32) -> Self {
+        self.busy_timeout_us = Some(timeout_us);
+        self
+    }
+
+    /// Calls `busy_yield` while waiting for the busy display, including during the
+    /// initialisation, see [`Epd2in13b::set_busy_yield`]
+    pub fn busy_yield(mut self, busy_yield: BusyYield) -> Self {
+        self.busy_yield = Some(busy_yield);
+        self
+    }
+
+    /// How long the reset pin is held high before it is pulled low to reset the display
+    /// (10ms by default)
+    pub fn reset_high_us(mut self, duration: u32) -> Self {
+        self.reset_high_us = duration;
+        self
+    }
+
+    /// How long the reset pin is held low on the first initialisation (10ms by default)
+    pub fn cold_reset_us(mut self, duration: u32) -> Self {
+        self.cold_reset_us = duration;
+        self
+    }
+
+    /// How long the reset pin is held low when waking up from deep sleep (10ms by default)
+    pub fn resume_reset_us(mut self, duration: u32) -> Self {
+        self.resume_reset_us = duration;
+        self
+    }
+
+    /// Background color of the display (white by default), see
+    /// [`set_background_color`](crate::traits::WaveshareDisplay::set_background_color)
+    pub fn background_color(mut self, color: TriColor) -> Self {
+        self.background_color = color;
+        self
+    }
+
+    /// Value of the border waveform control register, written on initialisation (`0x03` by
+    /// default: the border follows the waveform of its gray level transition)
+    pub fn border_waveform(mut self, value: u8) -> Self {
+        self.border_waveform = value;
+        self
+    }
+
+    /// Value of the VCOM register, written on initialisation (`0x36`, -1.35V, by default)
+    ///
+    /// Panels often have their calibrated VCOM printed on the flex cable.
+    pub fn vcom(mut self, value: u8) -> Self {
+        self.vcom = value;
+        self
+    }
+
+    /// Gate driving voltage, written on initialisation (20V by default)
+    pub fn gate_voltage(mut self, voltage: GateVoltage) -> Self {
+        self.gate_voltage = voltage;
+        self
+    }
+
+    /// Source driving voltages, written on initialisation (+15V and -15V by default)
+    pub fn source_voltage(mut self, voltage: SourceVoltage) -> Self {
+        self.source_voltage = voltage;
+        self
+    }
+
+    /// Booster soft start, written on initialisation (the controller's own by default)
+    pub fn booster_soft_start(mut self, booster: BoosterSoftStart) -> Self {
+        self.booster_soft_start = Some(booster);
+        self
+    }
+
+    /// How refreshes use the black/white and the chromatic RAMs, written on initialisation
+    /// ([`RamOption::Normal`] for both by default)
+    pub fn ram_options(mut self, bw: RamOption, red: RamOption) -> Self {
+        self.bw_ram_option = bw;
+        self.red_ram_option = red;
+        self
+    }
+
+    /// Whether [`build`](Self::build) resets and initialises the display (the default)
+    ///
+    /// Without it, nothing is sent to the display before
+    /// [`wake_up`](crate::traits::Sleepable::wake_up), e.g. to power it later, or
+    /// because it is still initialised after a reboot of the microcontroller.
+    pub fn auto_init(mut self, auto_init: bool) -> Self {
+        self.auto_init = auto_init;
+        self
+    }
+
+    /// Sends the display to deep sleep at the end of each refresh, and wakes it up before it
+    /// is written to again (disabled by default)
+    ///
+    /// Panels shouldn't be left powered for long, so this suits battery powered devices
+    /// refreshing now and then. The methods which don't take a `delay` can't wake the display
+    /// up: call [`wake_up`](crate::traits::Sleepable::wake_up) before them.
+    pub fn auto_sleep(mut self, auto_sleep: bool) -> Self {
+        self.auto_sleep = auto_sleep;
+        self
+    }
+
+    /// Creates the driver, and initialises the display unless disabled with
+    /// [`auto_init`](Self::auto_init)
+    #[allow(clippy::type_complexity)]
+    pub fn build<SPI, BUSY, DC, RST, DELAY>(
+        self,
+        spi: &mut SPI,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+    ) -> Result<Epd2in13b<SPI, BUSY, DC, RST, DELAY>, Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        let mut epd = Epd2in13b {
+            interface: DisplayInterface::new(busy, dc, rst, self.delay_us),
+            background_color: self.background_color,
+            border_waveform: self.border_waveform,
+            vcom: self.vcom,
+            gate_voltage: self.gate_voltage,
+            source_voltage: self.source_voltage,
+            booster_soft_start: self.booster_soft_start,
+            bw_ram_option: self.bw_ram_option,
+            red_ram_option: self.red_ram_option,
+            reset_high_us: self.reset_high_us,
+            cold_reset_us: self.cold_reset_us,
+            resume_reset_us: self.resume_reset_us,
+            refresh: RefreshLut::Full,
+            keep_red: false,
+            red_written: false,
+            red_clobbered: false,
+            red_cleared: false,
+            bw_ram_generation: 0,
+            verify_writes: false,
+            temperature_override: None,
+            temperature_luts: None,
+            gray: false,
+            auto_sleep: self.auto_sleep,
+            asleep: false,
+            powered: false,
+            stats: None,
+        };
+        epd.interface.set_poll_backoff(self.max_delay_us);
+        epd.interface.set_busy_timeout(self.busy_timeout_us);
+        epd.interface.set_busy_yield(self.busy_yield);
+        #[cfg(any(feature = "crc", test))]
+        epd.interface.track_crc(Command::WriteRam);
+
+        if self.auto_init {
+            epd.init(spi, delay)?;
+        }
+        Ok(epd)
+    }
+}
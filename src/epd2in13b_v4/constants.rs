@@ -0,0 +1,92 @@
+// Fast full refresh waveform (~1.5s). Only black and white are driven, the red particles are
+// not moved so chromatic pixels are shown white.
+#[rustfmt::skip]
+pub(crate) const LUT_FAST: [u8; super::LUT_SIZE] = [
+    0x80,0x66,0x00,0x00,0x00,0x00,0x00,0x00,0x40,0x00,0x00,0x00,   // LUT0: black:   VS L0~L11
+    0x10,0x66,0x00,0x00,0x00,0x00,0x00,0x00,0x20,0x00,0x00,0x00,   // LUT1: white:   VS L0~L11
+    0x10,0x66,0x00,0x00,0x00,0x00,0x00,0x00,0x20,0x00,0x00,0x00,   // LUT2: red:     VS L0~L11
+    0x10,0x66,0x00,0x00,0x00,0x00,0x00,0x00,0x20,0x00,0x00,0x00,   // LUT3: red:     VS L0~L11
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,   // LUT4: VCOM:    VS L0~L11
+
+    0x0A,0x08,0x00,0x00,0x00,0x00,0x01,                            // TP0 A~D SR RP0
+    0x05,0x05,0x00,0x05,0x05,0x00,0x01,                            // TP1 A~D SR RP1
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP2 A~D SR RP2
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP3 A~D SR RP3
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP4 A~D SR RP4
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP5 A~D SR RP5
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP6 A~D SR RP6
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP7 A~D SR RP7
+    0x0A,0x08,0x00,0x01,0x00,0x00,0x01,                            // TP8 A~D SR RP8
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP9 A~D SR RP9
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP10 A~D SR RP10
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP11 A~D SR RP11
+
+    0x44,0x44,0x44,0x44,0x44,0x44,                                 // FR
+    0x00,0x00,0x00,                                                // XON
+    0x22,                                                          // EOPT
+    0x17,                                                          // VGH
+    0x41,0x00,0x32,                                                // VSH1, VSH2, VSL
+    0x36,                                                          // VCOM
+];
+
+// Partial refresh waveform (<1s), from the old (chromatic RAM) to the new (black/white RAM)
+// frame. Only pixels which changed are driven.
+#[rustfmt::skip]
+pub(crate) const LUT_PARTIAL: [u8; super::LUT_SIZE] = [
+    0x00,0x40,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,   // LUT0: B->B:    VS L0~L11
+    0x80,0x80,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,   // LUT1: B->W:    VS L0~L11
+    0x40,0x40,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,   // LUT2: W->B:    VS L0~L11
+    0x00,0x80,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,   // LUT3: W->W:    VS L0~L11
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,   // LUT4: VCOM:    VS L0~L11
+
+    0x0A,0x00,0x00,0x00,0x00,0x00,0x01,                            // TP0 A~D SR RP0
+    0x01,0x00,0x00,0x00,0x00,0x00,0x01,                            // TP1 A~D SR RP1
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP2 A~D SR RP2
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP3 A~D SR RP3
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP4 A~D SR RP4
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP5 A~D SR RP5
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP6 A~D SR RP6
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP7 A~D SR RP7
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP8 A~D SR RP8
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP9 A~D SR RP9
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP10 A~D SR RP10
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP11 A~D SR RP11
+
+    0x22,0x22,0x22,0x22,0x22,0x22,                                 // FR
+    0x00,0x00,0x00,                                                // XON
+    0x22,                                                          // EOPT
+    0x17,                                                          // VGH
+    0x41,0x00,0x32,                                                // VSH1, VSH2, VSL
+    0x36,                                                          // VCOM
+];
+
+// 4 gray levels waveform (~2.3s), from the Waveshare SSD1680 panels. The LUT of a pixel is
+// selected by its high bit in the black/white RAM and its low bit in the chromatic RAM.
+#[rustfmt::skip]
+pub(crate) const LUT_GRAY4: [u8; super::LUT_SIZE] = [
+    0x00,0x60,0x10,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,   // LUT0: black:      VS L0~L11
+    0x20,0x60,0x10,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,   // LUT1: light gray: VS L0~L11
+    0x28,0x60,0x10,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,   // LUT2: dark gray:  VS L0~L11
+    0x2A,0x60,0x10,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,   // LUT3: white:      VS L0~L11
+    0x00,0x90,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,   // LUT4: VCOM:       VS L0~L11
+
+    0x00,0x02,0x00,0x05,0x14,0x00,0x00,                            // TP0 A~D SR RP0
+    0x1E,0x1E,0x00,0x00,0x00,0x00,0x01,                            // TP1 A~D SR RP1
+    0x00,0x02,0x00,0x05,0x14,0x00,0x00,                            // TP2 A~D SR RP2
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP3 A~D SR RP3
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP4 A~D SR RP4
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP5 A~D SR RP5
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP6 A~D SR RP6
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP7 A~D SR RP7
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP8 A~D SR RP8
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP9 A~D SR RP9
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP10 A~D SR RP10
+    0x00,0x00,0x00,0x00,0x00,0x00,0x00,                            // TP11 A~D SR RP11
+
+    0x24,0x22,0x22,0x22,0x23,0x32,                                 // FR
+    0x00,0x00,0x00,                                                // XON
+    0x22,                                                          // EOPT
+    0x17,                                                          // VGH
+    0x41,0xAE,0x32,                                                // VSH1, VSH2, VSL
+    0x28,                                                          // VCOM
+];
@@ -0,0 +1,202 @@
+//! Double buffering on top of [`Display2in13b`]
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{InputPin, OutputPin},
+    spi::SpiDevice,
+};
+
+use super::{Display2in13b, Epd2in13b, HEIGHT, WIDTH};
+use crate::buffer_len;
+use crate::error::Error;
+use crate::rect::Rect;
+use crate::traits::{WaveshareDisplay, WaveshareThreeColorDisplay};
+
+/// Two [`Display2in13b`]: the front one holds what is shown on the panel, the back one is
+/// drawn on. [`present`](DoubleBuffer::present) only sends what differs between them.
+#[derive(Default)]
+pub struct DoubleBuffer {
+    displays: [Display2in13b; 2],
+    front: usize,
+    /// whether the panel RAM holds the content of the front buffer
+    synced: bool,
+}
+
+impl DoubleBuffer {
+    /// Creates both buffers. The first [`present`](DoubleBuffer::present) sends the whole frame.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Buffer holding what is currently shown
+    pub fn front(&self) -> &Display2in13b {
+        &self.displays[self.front]
+    }
+
+    /// Buffer of the next frame
+    pub fn back(&self) -> &Display2in13b {
+        &self.displays[1 - self.front]
+    }
+
+    /// Buffer of the next frame, to draw on
+    pub fn back_mut(&mut self) -> &mut Display2in13b {
+        &mut self.displays[1 - self.front]
+    }
+
+    /// Smallest window containing every pixel that differs between the back and the front
+    /// buffers, `x` and `w` are expanded to byte boundaries.
+    ///
+    /// Returns `None` if both buffers are identical.
+    pub fn diff(&self) -> Option<Rect> {
+        let line = buffer_len(WIDTH as usize, 1);
+        let (front, back) = (self.front(), self.back());
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+
+        let changes = front
+            .bw_buffer()
+            .iter()
+            .zip(back.bw_buffer())
+            .zip(front.chromatic_buffer().iter().zip(back.chromatic_buffer()))
+            .enumerate()
+            .filter(|(_, ((f_bw, b_bw), (f_chromatic, b_chromatic)))| {
+                f_bw != b_bw || f_chromatic != b_chromatic
+            });
+        for (i, _) in changes {
+            let (row, column) = (i / line, i % line);
+            bounds = Some(match bounds {
+                None => (column, row, column, row),
+                Some((x0, y0, x1, y1)) => (x0.min(column), y0, x1.max(column), y1.max(row)),
+            });
+        }
+
+        bounds.map(|(x0, y0, x1, y1)| {
+            Rect::new(
+                x0 as u32 * 8,
+                y0 as u32,
+                (x1 - x0 + 1) as u32 * 8,
+                (y1 - y0 + 1) as u32,
+            )
+        })
+    }
+
+    /// Sends the window which changed since the last call, refreshes the display and swaps the
+    /// buffers. The new back buffer starts as a copy of what is now shown.
+    ///
+    /// Returns the window that was sent, or `None` if nothing changed (and the display was not
+    /// refreshed).
+    pub fn present<SPI, BUSY, DC, RST, DELAY>(
+        &mut self,
+        epd: &mut Epd2in13b<SPI, BUSY, DC, RST, DELAY>,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<Option<Rect>, Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        let back = self.back();
+        let window = if self.synced {
+            let window = match self.diff() {
+                Some(window) => window,
+                None => return Ok(None),
+            };
+            let line = buffer_len(WIDTH as usize, 1);
+            let row = (window.w / 8) as usize;
+            let index =
+                |i: usize| (window.y as usize + i / row) * line + window.x as usize / 8 + i % row;
+            epd.update_partial_color_frame_with(
+                spi,
+                delay,
+                |i| back.bw_buffer()[index(i)],
+                |i| back.chromatic_buffer()[index(i)],
+                window.x,
+                window.y,
+                window.w,
+                window.h,
+            )?;
+            window
+        } else {
+            epd.update_color_frame(spi, delay, back.bw_buffer(), back.chromatic_buffer())?;
+            Rect::new(0, 0, WIDTH, HEIGHT)
+        };
+        epd.display_frame(spi, delay)?;
+
+        self.synced = true;
+        self.front = 1 - self.front;
+        self.displays[1 - self.front] = self.displays[self.front].clone();
+        Ok(Some(window))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::command::Command;
+    use super::super::tests::{cmd, expectations, uninitialised, Traffic};
+    use super::*;
+    use crate::color::TriColor;
+    use embedded_graphics_core::prelude::*;
+    use embedded_hal_mock::eh1::{delay::NoopDelay, digital, spi};
+
+    extern crate std;
+
+    #[test]
+    fn diff_of_single_pixel() {
+        let mut buffers = DoubleBuffer::new();
+        assert_eq!(buffers.diff(), None);
+
+        buffers
+            .back_mut()
+            .set_pixel(Pixel(Point::new(13, 100), TriColor::Chromatic));
+        assert_eq!(buffers.diff(), Some(Rect::new(8, 100, 8, 1)));
+    }
+
+    #[test]
+    fn present_sends_only_changed_window() {
+        let mut buffers = DoubleBuffer::new();
+        // pretend the panel already shows the front buffer
+        buffers.synced = true;
+        buffers
+            .back_mut()
+            .set_pixel(Pixel(Point::new(13, 100), TriColor::White));
+
+        let traffic: Traffic = [
+            cmd(Command::SetRamXAddressStartEndPosition, &[1, 1]),
+            cmd(Command::SetRamYAddressStartEndPosition, &[100, 0, 100, 0]),
+            cmd(Command::SetRamXAddressCounter, &[1]),
+            cmd(Command::SetRamYAddressCounter, &[100, 0]),
+            cmd(Command::WriteRam, &[0b0000_0100]),
+            cmd(Command::SetRamXAddressCounter, &[1]),
+            cmd(Command::SetRamYAddressCounter, &[100, 0]),
+            cmd(Command::WriteRamRed, &[0x00]),
+            cmd(Command::SetRamXAddressStartEndPosition, &[0, 15]),
+            cmd(Command::SetRamYAddressStartEndPosition, &[0, 0, 249, 0]),
+            cmd(Command::SetRamXAddressCounter, &[0]),
+            cmd(Command::SetRamYAddressCounter, &[0, 0]),
+            cmd(Command::MasterActivation, &[]),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&std::vec![
+            digital::Transaction::get(digital::State::Low);
+            4
+        ]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&busy, &dc, &rst);
+
+        let window = buffers.present(&mut epd, &mut spi, &mut NoopDelay).unwrap();
+        assert_eq!(window, Some(Rect::new(8, 100, 8, 1)));
+        // both buffers now hold the new frame
+        assert_eq!(buffers.diff(), None);
+        assert_eq!(buffers.front().bw_buffer()[100 * 16 + 1], 0b0000_0100);
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
+}
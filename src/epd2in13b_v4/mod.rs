@@ -6,7 +6,7 @@
 //!
 //!```rust, no_run
 //!# use embedded_hal_mock::eh1::*;
-//!# fn main() -> Result<(), MockError> {
+//!# fn main() -> Result<(), epd_waveshare::error::Error<MockError>> {
 //!use embedded_graphics::{prelude::*, primitives::{Line, PrimitiveStyle, PrimitiveStyleBuilder}};
 //!use epd_waveshare::{epd2in13b_v4::*, prelude::*};
 //!#
@@ -50,8 +50,10 @@
 //!# Ok(())
 //!# }
 //!```
+#[cfg(feature = "graphics")]
 use core::convert::Infallible;
 
+#[cfg(feature = "graphics")]
 use embedded_graphics_core::prelude::DrawTarget;
 // Original Waveforms from Waveshare
 use embedded_hal::{
@@ -60,22 +62,51 @@ use embedded_hal::{
     spi::SpiDevice,
 };
 
+use crate::buffer_len;
 use crate::color::TriColor;
-use crate::interface::DisplayInterface;
+#[cfg(feature = "graphics")]
+use crate::color::{Color, Gray4Epd};
+use crate::controllers::ssd1680::{Ssd1680, IS_BUSY_LOW};
+use crate::error::Error;
+use crate::frame::{FrameSource, FrameStream, StreamError};
+use crate::interface::{BusyYield, DisplayInterface, Tap};
+use crate::lut::{Lut, TemperatureLut};
+use crate::refresh::{RefreshKind, RefreshStats};
+use crate::rle;
 use crate::traits::{
-    InternalWiAdditions, RefreshLut, WaveshareDisplay, WaveshareThreeColorDisplay,
+    BorderColor, InternalWiAdditions, LutControl, PartialColorUpdate, PartialRefresh,
+    PartialUpdate, PowerControl, QuickRefresh, RefreshLut, Sleepable, WaveshareDisplay,
+    WaveshareThreeColorDisplay,
 };
-use crate::{buffer_len, color::Color};
+
+mod builder;
+pub use self::builder::Epd2in13bBuilder;
+pub use crate::controllers::ssd1680::{BoosterSoftStart, GateVoltage, RamOption, SourceVoltage};
+
+#[cfg(feature = "async")]
+pub mod asynch;
+
+#[cfg(feature = "graphics")]
+mod double_buffer;
+#[cfg(feature = "graphics")]
+pub use self::double_buffer::DoubleBuffer;
 
 pub(crate) mod command;
+pub(crate) mod constants;
+mod shadow;
+pub use self::shadow::RamShadow;
+mod shared;
 use self::command::{
     BorderWaveForm, BorderWaveFormFixLevel, BorderWaveFormGs, BorderWaveFormVbd, Command,
-    DataEntryModeDir, DataEntryModeIncr, DeepSleepMode, DisplayUpdateControl, DriverOutput,
-    RamOption,
+    DeepSleepMode, DisplayUpdateControl,
 };
+use self::constants::{LUT_FAST, LUT_GRAY4, LUT_PARTIAL};
 
 const SINGLE_BYTE_WRITE: bool = true;
 
+/// Commands and RAM addressing shared with the other SSD1680 panels
+type Controller = Ssd1680<WIDTH, HEIGHT>;
+
 /// Full size buffer for use with the 2.13" v4 EPD
 #[cfg(feature = "graphics")]
 pub type Display2in13b = crate::graphics::Display<
@@ -86,13 +117,19 @@ pub type Display2in13b = crate::graphics::Display<
     TriColor,
 >;
 
+/// Full size buffer of 4 gray levels for use with the 2.13" v4 EPD, see
+/// [`Epd2in13b::set_gray_mode`]
+#[cfg(feature = "graphics")]
+pub type Display2in13Gray =
+    crate::graphics::Display<WIDTH, HEIGHT, false, { Controller::GRAY_FRAME_LEN }, Gray4Epd>;
+
 #[cfg(feature = "graphics")]
 /// buffered buffer
 pub type BufferMonoDisplay2in13b = crate::graphics::Display<
     WIDTH,
-    { HEIGHT / BUFFER },
+    CHUNK_HEIGHT,
     false,
-    { buffer_len(WIDTH as usize, (HEIGHT / BUFFER) as usize) },
+    { buffer_len(WIDTH as usize, CHUNK_HEIGHT as usize) },
     Color,
 >;
 
@@ -100,15 +137,18 @@ pub type BufferMonoDisplay2in13b = crate::graphics::Display<
 /// buffered buffer
 pub type BufferChromaticDisplay2in13b = crate::graphics::Display<
     WIDTH,
-    { HEIGHT / BUFFER },
+    CHUNK_HEIGHT,
     false,
-    { buffer_len(WIDTH as usize, (HEIGHT / BUFFER) as usize) },
+    { buffer_len(WIDTH as usize, CHUNK_HEIGHT as usize) * 2 },
     TriColor,
 >;
 
 /// buffers
 pub const BUFFER: u32 = 4;
 
+/// Height of the chunk buffers, the last chunk only uses the rows that are left
+pub const CHUNK_HEIGHT: u32 = (HEIGHT + BUFFER - 1) / BUFFER;
+
 /// Width of the display.
 pub const WIDTH: u32 = 122;
 
@@ -117,7 +157,47 @@ pub const HEIGHT: u32 = 250;
 
 /// Default Background Color
 pub const DEFAULT_BACKGROUND_COLOR: TriColor = TriColor::White;
-const IS_BUSY_LOW: bool = false;
+/// Display update sequence of quick refreshes: display mode 2, with the LUT in the register
+const QUICK_UPDATE: u8 = 0xCF;
+/// Display update sequence of full refreshes with a temperature override: same as the
+/// default one, but the temperature isn't loaded from the sensor
+const FULL_UPDATE_FIXED_TEMPERATURE: u8 = 0xDF;
+/// Update sequence only loading the temperature from the sensor
+const LOAD_TEMPERATURE: u8 = 0xA1;
+/// Update sequence only enabling the clock and the analog power
+const POWER_ON: u8 = 0xC0;
+/// Update sequence only disabling the analog power and the clock, also the bits of the other
+/// sequences doing it at their end
+const POWER_OFF: u8 = 0x03;
+/// Size of a waveform passed to [`Epd2in13b::set_custom_lut`]
+pub const LUT_SIZE: usize = crate::lut::LUT_SIZE;
+/// Default time the reset pin is held high before a reset
+const RESET_HIGH_US: u32 = 10_000;
+/// Default time the reset pin is held low
+const RESET_DURATION_US: u32 = 10_000;
+/// Default border waveform: the border follows the LUT of its gray level transition
+const DEFAULT_BORDER_WAVEFORM: u8 = BorderWaveForm {
+    vbd: BorderWaveFormVbd::Gs,
+    fix_level: BorderWaveFormFixLevel::Vss,
+    gs_trans: BorderWaveFormGs::Lut3,
+}
+.to_u8();
+/// Border waveform following the waveform of the pixels of `color`
+const fn border_waveform(color: TriColor) -> u8 {
+    let gs_trans = match color {
+        TriColor::Black => BorderWaveFormGs::Lut0,
+        TriColor::White => BorderWaveFormGs::Lut1,
+        TriColor::Chromatic => BorderWaveFormGs::Lut2,
+    };
+    BorderWaveForm {
+        vbd: BorderWaveFormVbd::Gs,
+        fix_level: BorderWaveFormFixLevel::Vss,
+        gs_trans,
+    }
+    .to_u8()
+}
+/// Default VCOM register value, -1.35V
+const DEFAULT_VCOM: u8 = 0x36;
 
 /// Epd2in13b (V4) driver
 pub struct Epd2in13b<SPI, BUSY, DC, RST, DELAY> {
@@ -126,6 +206,89 @@ pub struct Epd2in13b<SPI, BUSY, DC, RST, DELAY> {
 
     /// Background Color
     background_color: TriColor,
+    /// Value of the border waveform control register
+    border_waveform: u8,
+    /// Value of the VCOM register
+    vcom: u8,
+    /// Gate driving voltage
+    gate_voltage: GateVoltage,
+    /// Source driving voltages
+    source_voltage: SourceVoltage,
+    /// Booster soft start, left to the controller when `None`
+    booster_soft_start: Option<BoosterSoftStart>,
+    /// How refreshes use the black/white RAM
+    bw_ram_option: RamOption,
+    /// How refreshes use the chromatic RAM
+    red_ram_option: RamOption,
+
+    /// How long the reset pin is held high before a reset
+    reset_high_us: u32,
+    /// How long the reset pin is held low on the first initialisation
+    cold_reset_us: u32,
+    /// How long the reset pin is held low when waking up from deep sleep
+    resume_reset_us: u32,
+    /// Refresh LUT
+    refresh: RefreshLut,
+    /// Whether `update_frame` leaves the chromatic RAM untouched
+    keep_red: bool,
+    /// Whether chromatic data was written since the chromatic RAM was last filled by
+    /// `update_frame`
+    red_written: bool,
+    /// Whether `update_frame` overwrote chromatic data, until checked
+    red_clobbered: bool,
+    /// Whether the chromatic RAM is known to hold no chromatic pixel, so that `update_frame`
+    /// doesn't need to fill it again
+    red_cleared: bool,
+    /// Changes every time the black/white RAM is written or may have been lost, so that a
+    /// [`RamShadow`] knows whether it still holds a copy of the RAM
+    bw_ram_generation: u32,
+    /// Whether full frames are read back after being written
+    verify_writes: bool,
+    /// Temperature selecting the waveform of full refreshes instead of the sensor
+    temperature_override: Option<i8>,
+    /// Waveforms of the full refreshes, by temperature, instead of the OTP
+    temperature_luts: Option<&'static [TemperatureLut]>,
+    /// Refreshes with the 4 gray levels waveform
+    gray: bool,
+    /// Whether the display is sent to deep sleep after each refresh
+    auto_sleep: bool,
+    /// Whether the display is in deep sleep, until the next initialisation
+    asleep: bool,
+    /// Whether the analog power is kept on after the refreshes, see [`PowerControl`]
+    powered: bool,
+    /// Refreshes counted since the statistics were enabled
+    stats: Option<RefreshStats>,
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Epd2in13b<SPI, BUSY, DC, RST, DELAY> {
+    /// Gives back the busy, data/command and reset pins, e.g. to reuse them once the display
+    /// is asleep
+    ///
+    /// The display keeps its state, so a driver can be created again later with the pins.
+    pub fn release(self) -> (BUSY, DC, RST) {
+        self.interface.release()
+    }
+}
+
+#[cfg(all(feature = "linux", unix))]
+impl
+    Epd2in13b<
+        linux_embedded_hal::SpidevDevice,
+        linux_embedded_hal::SysfsPin,
+        linux_embedded_hal::SysfsPin,
+        linux_embedded_hal::SysfsPin,
+        linux_embedded_hal::Delay,
+    >
+{
+    /// Sets up the display on a Raspberry Pi, see [`linux::new_rpi`](crate::linux::new_rpi)
+    pub fn new_rpi(
+        spidev_path: impl AsRef<std::path::Path>,
+        busy_gpio: u64,
+        dc_gpio: u64,
+        rst_gpio: u64,
+    ) -> Result<crate::linux::Rpi<Self>, crate::linux::LinuxError> {
+        crate::linux::new_rpi(spidev_path, busy_gpio, dc_gpio, rst_gpio)
+    }
 }
 
 impl<SPI, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, BUSY, DC, RST, DELAY>
@@ -137,51 +300,43 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        // HW reset
-        self.interface.reset(delay, 10_000, 10_000);
-
-        self.wait_until_idle(spi, delay)?;
-        self.interface.cmd(spi, Command::SwReset)?;
-        self.wait_until_idle(spi, delay)?;
-
-        self.set_driver_output(
-            spi,
-            DriverOutput {
-                scan_is_linear: true,
-                scan_g0_is_first: true,
-                scan_dir_incr: true,
-                width: (HEIGHT - 1) as u16,
-            },
-        )?;
-
-        self.set_data_entry_mode(spi, DataEntryModeIncr::XIncrYIncr, DataEntryModeDir::XDir)?;
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.reset_and_init(spi, delay, self.cold_reset_us)
+    }
+}
 
+impl<SPI, BUSY, DC, RST, DELAY> Epd2in13b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Resets the display, holding the reset pin low for `reset_us`, and initialises it
+    fn reset_and_init(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        reset_us: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        // HW reset
+        self.interface.reset(delay, self.reset_high_us, reset_us)?;
+        self.asleep = false;
+        self.powered = false;
+        // the RAMs may have been lost in deep sleep
+        self.red_cleared = false;
+        self.bw_ram_generation = self.bw_ram_generation.wrapping_add(1);
+
+        Controller::sw_reset(&mut self.interface, spi, delay)?;
         // Use simple X/Y auto increase
-        self.set_ram_area(spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
-        self.set_ram_address_counters(spi, delay, 0, 0)?;
-
-        self.set_border_waveform(
-            spi,
-            command::BorderWaveForm {
-                vbd: BorderWaveFormVbd::Gs,
-                fix_level: BorderWaveFormFixLevel::Vss,
-                gs_trans: BorderWaveFormGs::Lut3,
-            },
-        )?;
+        Controller::init_addressing(&mut self.interface, spi, delay)?;
 
-        self.cmd_with_data(spi, Command::WriteVcomRegister, &[0x36])?;
-        self.cmd_with_data(spi, Command::GateDrivingVoltageCtrl, &[0x17])?;
-        self.cmd_with_data(spi, Command::SourceDrivingVoltageCtrl, &[0x41, 0x00, 0x32])?;
+        shared::init_registers!(self, spi, []);
 
-        self.set_display_update_control(
-            spi,
-            command::DisplayUpdateControl {
-                red_ram_option: RamOption::Normal,
-                bw_ram_option: RamOption::Normal,
-                source_output_mode: true,
-            },
-        )?;
+        if self.refresh != RefreshLut::Full || self.temperature_override.is_some() || self.gray {
+            self.set_lut(spi, delay, None)?;
+        }
 
         self.wait_until_idle(spi, delay)?;
 
@@ -202,8 +357,27 @@ where
     /// Updates both the black and the secondary color layers
     /// Useful for rendering directly from progmem buffers.
     ///
+    /// Nothing is sent if `black_len` or `chromatic_len` isn't the size of a full frame.
+    ///
     /// Example:
     /// ```rust no_run
+    /// # use embedded_hal_mock::eh1::{delay::NoopDelay, digital, spi};
+    /// # use epd_waveshare::{epd2in13b_v4::Epd2in13b, error::Error};
+    /// # // stands for avr_progmem::progmem, which only builds for AVR
+    /// # macro_rules! progmem {
+    /// #     ($($item:tt)*) => {
+    /// #         struct ProgMem;
+    /// #         impl ProgMem {
+    /// #             fn load_at(&self, _: usize) -> u8 { 0 }
+    /// #             fn len(&self) -> usize { 4000 }
+    /// #         }
+    /// #         static BLACK: ProgMem = ProgMem;
+    /// #         static RED: ProgMem = ProgMem;
+    /// #     };
+    /// # }
+    /// # type Epd = Epd2in13b<spi::Mock<u8>, digital::Mock, digital::Mock, digital::Mock, NoopDelay>;
+    /// # fn run(epd: &mut Epd, mut spi: spi::Mock<u8>) -> Result<(), Error<embedded_hal::spi::ErrorKind>> {
+    /// # let mut delay = NoopDelay;
     /// progmem! {
     ///     static progmem BLACK: [u8; 4000] = *include_bytes!("black.gray");
     ///     static progmem RED: [u8; 4000] = *include_bytes!("red.gray");
@@ -216,6 +390,8 @@ where
     ///     BLACK.len(),
     ///     RED.len(),
     /// )?;
+    /// # Ok(())
+    /// # }
     /// ```
     pub fn update_color_frame_with(
         &mut self,
@@ -225,22 +401,27 @@ where
         chromatic: impl Fn(usize) -> u8,
         black_len: usize,
         chromatic_len: usize,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        // check both lengths before writing anything
+        Error::check_size(chromatic_len, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         self.update_achromatic_frame_with(spi, delay, black, black_len)?;
         self.update_chromatic_frame_with(spi, delay, chromatic, chromatic_len)
     }
 
     /// Update only the black/white data of the display using a generator
     ///
-    /// This must be finished by calling `update_chromatic_frame`.
+    /// This must be finished by calling `update_chromatic_frame`. Nothing is sent if `len`
+    /// isn't the size of a full frame.
     pub fn update_achromatic_frame_with(
         &mut self,
         spi: &mut SPI,
-        _delay: &mut DELAY,
+        delay: &mut DELAY,
         black: impl Fn(usize) -> u8,
         len: usize,
-    ) -> Result<(), SPI::Error> {
-        self.interface.cmd(spi, Command::WriteRam)?;
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_size(len, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.wake_if_asleep(spi, delay)?;
+        self.command(spi, Command::WriteRam)?;
         self.interface.data_with(spi, black, len)?;
         Ok(())
     }
@@ -248,353 +429,1600 @@ where
     /// Update only the chromatic data of the display.
     ///
     /// This should be preceded by a call to `update_achromatic_frame`.
-    /// This data takes precedence over the black/white data.
+    /// This data takes precedence over the black/white data. Nothing is sent if `len` isn't
+    /// the size of a full frame.
     pub fn update_chromatic_frame_with(
         &mut self,
         spi: &mut SPI,
-        _delay: &mut DELAY,
+        delay: &mut DELAY,
         chromatic: impl Fn(usize) -> u8,
         len: usize,
-    ) -> Result<(), SPI::Error> {
-        self.interface.cmd(spi, Command::WriteRamRed)?;
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_size(len, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.wake_if_asleep(spi, delay)?;
+        self.command(spi, Command::WriteRamRed)?;
         self.interface.data_with(spi, chromatic, len)?;
+        self.red_written = true;
         Ok(())
     }
-}
 
-impl<SPI, BUSY, DC, RST, DELAY> WaveshareThreeColorDisplay<SPI, BUSY, DC, RST, DELAY>
-    for Epd2in13b<SPI, BUSY, DC, RST, DELAY>
-where
-    SPI: SpiDevice,
-    BUSY: InputPin,
-    DC: OutputPin,
-    RST: OutputPin,
-    DELAY: DelayNs,
-{
-    fn update_color_frame(
+    /// Same as [`update_color_frame`](WaveshareThreeColorDisplay::update_color_frame), reading
+    /// the frames from [`FrameSource`]s, e.g. [`FnFrame`](crate::frame::FnFrame)s
+    /// stored in flash
+    ///
+    /// Nothing is sent if one of the frames doesn't have the size of a full frame.
+    pub fn update_color_frame_from<B, C>(
         &mut self,
         spi: &mut SPI,
         delay: &mut DELAY,
-        black: &[u8],
-        chromatic: &[u8],
-    ) -> Result<(), SPI::Error> {
-        self.update_achromatic_frame(spi, delay, black)?;
-        self.update_chromatic_frame(spi, delay, chromatic)
+        black: &B,
+        chromatic: &C,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        B: FrameSource + ?Sized,
+        C: FrameSource + ?Sized,
+    {
+        let expected = buffer_len(WIDTH as usize, HEIGHT as usize);
+        for got in [black.len(), chromatic.len()] {
+            if got != expected {
+                return Err(Error::WrongBufferSize { expected, got });
+            }
+        }
+        self.wake_if_asleep(spi, delay)?;
+        self.update_color_frame_iter(
+            spi,
+            delay,
+            (0..expected).map(|i| black.byte_at(i)),
+            (0..expected).map(|i| chromatic.byte_at(i)),
+        )
     }
 
-    fn update_achromatic_frame(
+    /// Same as [`update_frame`](WaveshareDisplay::update_frame), with the frame run-length
+    /// encoded by [`rle::encode`] and decoded while it is sent
+    ///
+    /// Nothing is sent if `encoded` can't be decoded, or doesn't decode to a full frame.
+    pub fn update_frame_rle(
         &mut self,
         spi: &mut SPI,
-        _delay: &mut DELAY,
-        black: &[u8],
-    ) -> Result<(), SPI::Error> {
-        self.interface.cmd(spi, Command::WriteRam)?;
-        self.interface.data(spi, black)?;
-        Ok(())
+        delay: &mut DELAY,
+        encoded: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        let expected = buffer_len(WIDTH as usize, HEIGHT as usize);
+        let got = rle::decoded_len(encoded).map_err(Error::Rle)?;
+        if got != expected {
+            return Err(Error::WrongBufferSize { expected, got });
+        }
+        self.wake_if_asleep(spi, delay)?;
+        self.command(spi, Command::WriteRam)?;
+        self.interface.data_iter(spi, rle::Decoder::new(encoded))?;
+        self.blank_red_ram(spi)
     }
 
-    fn update_chromatic_frame(
+    /// Same as [`update_frame`](WaveshareDisplay::update_frame), with the frame read from a
+    /// [`FrameStream`] while it is sent, e.g. a file on an SD card
+    ///
+    /// Only a few bytes are buffered at a time. At most a full frame is read: if the stream
+    /// ends before, the rest of the RAM is left unchanged.
+    pub fn update_frame_from_source<S: FrameStream + ?Sized>(
         &mut self,
         spi: &mut SPI,
-        _delay: &mut DELAY,
-        chromatic: &[u8],
-    ) -> Result<(), SPI::Error> {
-        self.interface.cmd(spi, Command::WriteRamRed)?;
-        self.interface.data(spi, chromatic)?;
-        Ok(())
+        delay: &mut DELAY,
+        source: &mut S,
+    ) -> Result<(), StreamError<SPI::Error, S::Error>> {
+        self.wake_if_asleep(spi, delay)?;
+        self.command(spi, Command::WriteRam)?;
+        let mut chunk = [0; 64];
+        let mut left = buffer_len(WIDTH as usize, HEIGHT as usize);
+        while left > 0 {
+            let read = chunk.len().min(left);
+            let read = source
+                .next_chunk(&mut chunk[..read])
+                .map_err(StreamError::Source)?;
+            if read == 0 {
+                break;
+            }
+            self.interface.data(spi, &chunk[..read])?;
+            left -= read;
+        }
+        Ok(self.blank_red_ram(spi)?)
     }
-}
 
-impl<SPI, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
-    for Epd2in13b<SPI, BUSY, DC, RST, DELAY>
-where
-    SPI: SpiDevice,
-    BUSY: InputPin,
-    DC: OutputPin,
-    RST: OutputPin,
-    DELAY: DelayNs,
-{
-    type DisplayColor = TriColor;
-    fn new(
+    /// Same as [`update_color_frame`](WaveshareThreeColorDisplay::update_color_frame), with the
+    /// data streamed from iterators, e.g. reading an image from a file or decompressing it
+    ///
+    /// At most a full frame is taken from each iterator, so endless iterators can be used.
+    /// If one is shorter, the rest of the RAM is left unchanged.
+    pub fn update_color_frame_iter(
+        &mut self,
         spi: &mut SPI,
-        busy: BUSY,
-        dc: DC,
-        rst: RST,
         delay: &mut DELAY,
-        delay_us: Option<u32>,
-    ) -> Result<Self, SPI::Error> {
-        let mut epd = Epd2in13b {
-            interface: DisplayInterface::new(busy, dc, rst, delay_us),
-            background_color: DEFAULT_BACKGROUND_COLOR,
-        };
-
-        epd.init(spi, delay)?;
-        Ok(epd)
-    }
-
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.init(spi, delay)
+        black: impl IntoIterator<Item = u8>,
+        chromatic: impl IntoIterator<Item = u8>,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wake_if_asleep(spi, delay)?;
+        self.update_achromatic_frame_iter(spi, delay, black)?;
+        self.update_chromatic_frame_iter(spi, delay, chromatic)
     }
 
-    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.set_sleep_mode(spi, DeepSleepMode::Normal)?;
-        Ok(())
+    /// Update only the black/white data of the display from an iterator, see
+    /// [`update_color_frame_iter`](Self::update_color_frame_iter)
+    ///
+    /// This must be finished by calling `update_chromatic_frame`.
+    pub fn update_achromatic_frame_iter(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        black: impl IntoIterator<Item = u8>,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wake_if_asleep(spi, delay)?;
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize);
+        self.command(spi, Command::WriteRam)?;
+        self.interface.data_iter(spi, black.into_iter().take(len))
     }
 
-    fn update_frame(
+    /// Update only the chromatic data of the display from an iterator, see
+    /// [`update_color_frame_iter`](Self::update_color_frame_iter)
+    ///
+    /// This should be preceded by a call to `update_achromatic_frame`.
+    pub fn update_chromatic_frame_iter(
         &mut self,
         spi: &mut SPI,
-        buffer: &[u8],
-        _delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
-        assert!(buffer.len() == buffer_len(WIDTH as usize, HEIGHT as usize));
-        self.cmd_with_data(spi, Command::WriteRam, buffer)?;
-
+        delay: &mut DELAY,
+        chromatic: impl IntoIterator<Item = u8>,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wake_if_asleep(spi, delay)?;
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize);
         self.command(spi, Command::WriteRamRed)?;
-        self.interface.data_x_times(
-            spi,
-            TriColor::Black.get_byte_value(),
-            buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
-        )?;
+        self.interface
+            .data_iter(spi, chromatic.into_iter().take(len))?;
+        self.red_written = true;
         Ok(())
     }
 
-    fn update_partial_frame(
-        &mut self,
-        _spi: &mut SPI,
-        _delay: &mut DELAY,
-        _buffer: &[u8],
-        _x: u32,
-        _y: u32,
-        _width: u32,
-        _height: u32,
-    ) -> Result<(), SPI::Error> {
-        unimplemented!();
+    /// Sends the command writing the black/white RAM and leaves the bus ready for its data, so
+    /// a full frame, e.g. [`Display2in13b::bw_buffer`], can be written with the SPI device
+    /// directly, typically by a DMA transfer
+    ///
+    /// Unlike [`update_frame`](WaveshareDisplay::update_frame), the chromatic RAM is left
+    /// unchanged. The data isn't seen by the [`Tap`].
+    pub fn prepare_ram_write(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.command(spi, Command::WriteRam)?;
+        self.interface.prepare_data()
     }
 
-    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.command(spi, Command::MasterActivation)?;
-        self.wait_until_idle(spi, delay)?;
-
-        Ok(())
+    /// Same as [`prepare_ram_write`](Self::prepare_ram_write) for the chromatic RAM, e.g. for
+    /// [`Display2in13b::chromatic_buffer`]
+    pub fn prepare_chromatic_ram_write(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.command(spi, Command::WriteRamRed)?;
+        self.red_written = true;
+        self.interface.prepare_data()
     }
 
-    fn update_and_display_frame(
+    /// Same as
+    /// [`update_partial_color_frame`](PartialColorUpdate::update_partial_color_frame),
+    /// but the data is provided by generators.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_partial_color_frame_with(
         &mut self,
         spi: &mut SPI,
-        buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
-        self.update_frame(spi, buffer, delay)?;
-        self.display_frame(spi, delay)?;
-        Ok(())
-    }
-
-    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.clear_achromatic_frame(spi)?;
-        self.clear_chromatic_frame(spi)
-    }
+        black: impl Fn(usize) -> u8,
+        chromatic: impl Fn(usize) -> u8,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_aligned(x, width)?;
+        self.wake_if_asleep(spi, delay)?;
+        let len = buffer_len(width as usize, height as usize);
+
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.set_ram_address_counters(spi, delay, x, y)?;
+        self.command(spi, Command::WriteRam)?;
+        self.interface.data_with(spi, black, len)?;
 
-    fn set_background_color(&mut self, background_color: TriColor) {
-        self.background_color = background_color;
-    }
+        self.set_ram_address_counters(spi, delay, x, y)?;
+        self.command(spi, Command::WriteRamRed)?;
+        self.interface.data_with(spi, chromatic, len)?;
+        self.red_written = true;
 
-    fn background_color(&self) -> &TriColor {
-        &self.background_color
+        self.set_full_window(spi, delay)
     }
 
-    fn width(&self) -> u32 {
-        WIDTH
+    /// By default [`update_frame`](WaveshareDisplay::update_frame) also fills the chromatic RAM,
+    /// so that only black and white are shown. With `keep_red` it only writes the black/white
+    /// RAM, keeping whatever chromatic data was written before.
+    ///
+    /// The chromatic RAM isn't filled again while nothing else was written to it, so
+    /// successive black and white frames only cost the black/white RAM anyway.
+    pub fn set_keep_red(&mut self, keep_red: bool) {
+        self.keep_red = keep_red;
     }
 
-    fn height(&self) -> u32 {
-        HEIGHT
+    /// Returns `true` once if [`update_frame`](WaveshareDisplay::update_frame) has overwritten
+    /// chromatic data written before, which is likely not intended (see
+    /// [`set_keep_red`](Self::set_keep_red)).
+    ///
+    /// Checking it resets it, so it's only reported again after new chromatic data got
+    /// overwritten.
+    pub fn take_red_clobbered(&mut self) -> bool {
+        core::mem::replace(&mut self.red_clobbered, false)
     }
 
-    fn set_lut(
+    /// Loads a custom waveform, used by the following refreshes instead of the one stored in
+    /// the display OTP
+    ///
+    /// `lut` has the layout of the SSD1680 waveform setting: the 153 bytes of the LUT
+    /// register (voltages of the 5 LUTs, timings of the 12 groups, frame rates and XON),
+    /// followed by the EOPT, VGH, VSH1, VSH2, VSL and VCOM bytes. The [`lut`](crate::lut)
+    /// module builds and decodes them.
+    ///
+    /// The waveform is lost on [`wake_up`](Sleepable::wake_up), and replaced by
+    /// [`set_lut`](LutControl::set_lut).
+    pub fn set_custom_lut(
         &mut self,
-        _spi: &mut SPI,
-        _delay: &mut DELAY,
-        _refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
-        unimplemented!()
-    }
-
-    fn wait_until_idle(&mut self, _spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.interface.wait_until_idle(delay, IS_BUSY_LOW);
-        Ok(())
-    }
-}
-
-#[derive(Copy, Clone)]
-/// a type safe chunk reperesentation for BufferMonoDisplay
-pub enum Chunk {
-    /// the first chunk of the display
-    Buf1,
-    /// the second chunk of the display
-    Buf2,
-    /// the third chunk of the display
-    Buf3,
-    /// the fourth chunk of the display
-    Buf4,
-}
-
-impl Chunk {
-    /// converts chunk to a zero-indexed `u32`
-    pub fn to_zero_indexed(&self) -> u32 {
-        match self {
-            Chunk::Buf1 => 0,
-            Chunk::Buf2 => 1,
-            Chunk::Buf3 => 2,
-            Chunk::Buf4 => 3,
-        }
-    }
-    /// converts from zero-indexed `u32` to a `Chunk`
-    pub const fn from_zero_indexed(i: u32) -> Self {
-        match i {
-            0 => Chunk::Buf1,
-            1 => Chunk::Buf2,
-            2 => Chunk::Buf3,
-            3 => Chunk::Buf4,
-            _ => panic!("please check buffer"),
-        }
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        lut: &[u8; LUT_SIZE],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.load_lut(spi, delay, lut)?;
+        // display with the LUT in the register instead of loading it from OTP
+        self.update_sequence(spi, 0xC7)
     }
-}
 
-impl<SPI, BUSY, DC, RST, DELAY> Epd2in13b<SPI, BUSY, DC, RST, DELAY>
-where
-    SPI: SpiDevice,
-    BUSY: InputPin,
-    DC: OutputPin,
-    RST: OutputPin,
-    DELAY: DelayNs,
-{
-    /// Due to memory limitations on the arduino boards, this function allows the user to separate the 122x250 board into four 122x62.5(rounding to 63) subgrids.
-    ///
-    /// for usage on `mono_buffers` and colored_buffers`, please refer to the documentation of `update_achromatic_buffered` and `update_chromatic_buffered`
-    pub fn update_frame_buffered(
+    /// Same as [`set_custom_lut`](Self::set_custom_lut), with a waveform built with
+    /// [`LutBuilder`](crate::lut::LutBuilder) or decoded with
+    /// [`Lut::from_bytes`](crate::lut::Lut::from_bytes)
+    pub fn write_lut(
         &mut self,
         spi: &mut SPI,
         delay: &mut DELAY,
-        mono_buffers: impl FnMut(&mut BufferMonoDisplay2in13b, Chunk) -> Result<Option<()>, Infallible>,
-        colored_buffers: impl FnMut(
-            &mut BufferMonoDisplay2in13b,
-            Chunk,
-        ) -> Result<Option<()>, Infallible>,
-    ) -> Result<(), SPI::Error> {
-        self.update_achromatic_buffered(spi, delay, mono_buffers)?;
-        self.update_chromatic_buffered(spi, delay, colored_buffers)
+        lut: &Lut,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.set_custom_lut(spi, delay, &lut.to_bytes())
     }
 
-    /// Due to memory limitations on the arduino boards, this function allows the user to separate the 122x250 board into four 122x62.5(rounding to 63) subgrids.
-    ///
-    /// IMPORTANT: this must be followed by `update_chromatic_buffered`, even if you're trying to only display purely mono content, otherwise the display won't be updated.
+    /// Switches between the 4 gray levels refreshes and the refresh mode of
+    /// [`set_lut`](LutControl::set_lut)
     ///
-    /// `buffers`: A function that that should populate the content of each section of the display.
-    ///     - Takes a mutable reference to `BuffermonoDisplay2in13b` and a buffer index(0-3)
-    ///     - Returns `Result<Option<(), Infalliable>>`
-    ///         * `Ok(Some(()))` indicates successful execution.
-    ///         * `Ok(None)` indicates the buffer should be left unmodified, leaving it uncolored.
-    ///         * `Err(Infalliable)` is here purely for allowing `?` with `embedded-graphics` draw operations.
-    pub fn update_achromatic_buffered(
+    /// The gray levels are made of the bits of the pixels in both RAMs: in gray mode, frames
+    /// are sent with [`update_gray_frame`](Self::update_gray_frame), and chromatic pixels
+    /// can't be shown. The chromatic RAM is cleared when leaving it.
+    pub fn set_gray_mode(
         &mut self,
         spi: &mut SPI,
-        _delay: &mut DELAY,
-        mut buffers: impl FnMut(&mut BufferMonoDisplay2in13b, Chunk) -> Result<Option<()>, Infallible>,
-    ) -> Result<(), SPI::Error> {
-        self.interface.cmd(spi, Command::WriteRam)?;
-        for i in 0..BUFFER {
-            let mut buffer = BufferMonoDisplay2in13b::default();
-            if buffers(&mut buffer, Chunk::from_zero_indexed(i))
-                .unwrap()
-                .is_none()
-            {
-                buffer.clear(Color::White).unwrap();
-            }
-            let data = buffer.buffer();
-            self.interface.data(spi, data)?;
+        delay: &mut DELAY,
+        gray: bool,
+    ) -> Result<(), Error<SPI::Error>> {
+        if self.gray && !gray {
+            Controller::set_ram_address_counters(&mut self.interface, spi, delay, 0, 0)?;
+            self.command(spi, Command::WriteRamRed)?;
+            self.interface.data_x_times(
+                spi,
+                TriColor::Black.get_byte_value(),
+                buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
+            )?;
+            self.red_written = false;
         }
-        Ok(())
+        self.gray = gray;
+        self.set_lut(spi, delay, None)
     }
 
-    /// Due to memory limitations on the arduino boards, this function allows the user to separate the 122x250 board into four 122x62.5(rounding to 63) subgrids.
-    ///
-    /// IMPORTANT: this function must be called after `update_achromatic_buffered`, even if you're trying to only display purely mono content, otherwise the display won't be updated.
-    ///
-    /// The usage of color within `BufferMonoDisplay2in13b` is a misnomer. `Color::White` stands for colored(red), while `Color::Black` stands for uncolored(white).
+    /// Returns `true` if refreshes show 4 gray levels
+    pub fn is_gray_mode(&self) -> bool {
+        self.gray
+    }
+
+    /// Transmits a full frame of 4 gray levels, 2 bits per pixel, e.g. a [`Display2in13Gray`]
     ///
-    /// `buffers`: A function that that should populate the content of each section of the display.
-    ///     - Takes a mutable reference to `BufferMonoDisplay2in13b` and a buffer index(0-3)
-    ///     - Returns `Result<Option<(), Infalliable>>`
-    ///         * `Ok(Some(()))` indicates successful execution
-    ///         * `Ok(None)` indicates the buffer should be left unmodified, leaving it uncolored.
-    ///         * `Err(Infalliable)` is here purely for allowing `?` with `embedded-graphics` draw operations.
-    pub fn update_chromatic_buffered(
+    /// The high bits of the pixels go to the black/white RAM and the low bits to the
+    /// chromatic RAM.
+    pub fn update_gray_frame(
         &mut self,
         spi: &mut SPI,
         delay: &mut DELAY,
-        mut buffers: impl FnMut(&mut BufferMonoDisplay2in13b, Chunk) -> Result<Option<()>, Infallible>,
-    ) -> Result<(), SPI::Error> {
-        self.command(spi, Command::WriteRamRed)?;
-        let mut buffer = BufferMonoDisplay2in13b::default();
-        for i in 0..BUFFER {
-            if buffers(&mut buffer, Chunk::from_zero_indexed(i))
-                .unwrap()
-                .is_none()
-            {
-                buffer.clear(Color::Black).unwrap();
-            }
-            let data = buffer.buffer();
-            self.interface.data(spi, data)?;
-        }
-        self.interface.cmd(spi, Command::MasterActivation)?;
-        self.wait_until_idle(spi, delay)?;
-        Ok(())
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, Controller::GRAY_FRAME_LEN)?;
+        self.wake_if_asleep(spi, delay)?;
+        self.red_cleared = false;
+        Controller::write_gray_frame(&mut self.interface, spi, delay, buffer)
     }
 
-    fn set_display_update_control(
+    /// Writes a waveform and the voltages following it, see [`set_custom_lut`](Self::set_custom_lut)
+    fn load_lut(
         &mut self,
         spi: &mut SPI,
-        display_update_control: DisplayUpdateControl,
-    ) -> Result<(), SPI::Error> {
-        self.cmd_with_data(
-            spi,
-            Command::DisplayUpdateControl1,
-            &display_update_control.to_bytes(),
-        )
+        delay: &mut DELAY,
+        lut: &[u8; LUT_SIZE],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.cmd_with_data(spi, Command::WriteLutRegister, &lut[0..153])?;
+        self.cmd_with_data(spi, Command::WriteLutRegisterEnd, &lut[153..154])?;
+        self.cmd_with_data(spi, Command::GateDrivingVoltageCtrl, &lut[154..155])?;
+        self.cmd_with_data(spi, Command::SourceDrivingVoltageCtrl, &lut[155..158])?;
+        self.cmd_with_data(spi, Command::WriteVcomRegister, &lut[158..159])
     }
 
-    fn set_border_waveform(
+    /// Writes `buffer` to a window of one of the RAMs, leaving the window set.
+    ///
+    /// The window and the length of `buffer` are checked by the caller, with
+    /// [`check_window`](Self::check_window), before waking the display up.
+    #[allow(clippy::too_many_arguments)]
+    fn write_window(
         &mut self,
         spi: &mut SPI,
-        borderwaveform: BorderWaveForm,
-    ) -> Result<(), SPI::Error> {
-        self.cmd_with_data(
-            spi,
-            Command::BorderWaveformControl,
-            &[borderwaveform.to_u8()],
-        )
+        delay: &mut DELAY,
+        ram: Command,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.set_ram_address_counters(spi, delay, x, y)?;
+        self.cmd_with_data(spi, ram, buffer)
+    }
+
+    /// Checks that `x` and `width` are multiples of 8 and that `buffer` fills the window
+    fn check_window(
+        buffer: &[u8],
+        x: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_aligned(x, width)?;
+        Error::check_len(buffer, buffer_len(width as usize, height as usize))
+    }
+
+    /// Sets a [`Tap`] observing every command and data byte before it is sent to the display,
+    /// e.g. to compare it with a logic analyzer capture. `None` removes it.
+    pub fn set_traffic_tap(&mut self, tap: Option<Tap>) {
+        self.interface.set_tap(tap);
+    }
+
+    /// CRC-32 of the data of the last write of the black/white RAM, e.g. a full frame sent by
+    /// [`update_frame`](WaveshareDisplay::update_frame), to compare with the
+    /// [`crc32`](crate::crc::crc32) of the expected image and detect a corrupted transfer.
+    /// `None` until the RAM is written.
+    ///
+    /// A partial update gives the CRC of its window only. The data written directly to the
+    /// SPI device after [`prepare_ram_write`](Self::prepare_ram_write) is not seen.
+    #[cfg(any(feature = "crc", test))]
+    pub fn last_frame_crc(&self) -> Option<u32> {
+        self.interface.last_crc()
+    }
+
+    /// Gives up waiting for the display with [`Error::BusyTimeout`] once it has been busy for
+    /// `timeout_us`, e.g. because of a loose cable, instead of waiting forever. `None` removes
+    /// the timeout.
+    ///
+    /// The time is counted by adding up the delays of the idle loop (see
+    /// [`Epd2in13bBuilder::delay_us`]), each poll of the busy pin counting as at least 1us.
+    pub fn set_busy_timeout(&mut self, timeout_us: Option<u32>) {
+        self.interface.set_busy_timeout(timeout_us);
+    }
+
+    /// Polls the busy pin every `interval_us` while the display is busy (0 busy waits), or,
+    /// with a `max_interval_us`, backs off: the interval doubles after every poll until
+    /// `max_interval_us`
+    ///
+    /// The interval is [`Epd2in13bBuilder::delay_us`] (10ms by default) until this is called. A
+    /// backoff, e.g. from 10ms to 200ms, keeps the short waits short while polling a full
+    /// refresh only a few times, for low power designs.
+    pub fn set_poll_interval(&mut self, interval_us: u32, max_interval_us: Option<u32>) {
+        self.interface.set_poll_interval(interval_us);
+        self.interface.set_poll_backoff(max_interval_us);
+    }
+
+    /// Sets a [`BusyYield`] called on every poll of the busy pin while the display is busy, e.g.
+    /// to let an RTOS run other tasks or to feed a watchdog during a refresh. `None` removes it.
+    ///
+    /// It is called right before the delay of the idle loop, which can be set to 0 with
+    /// [`Epd2in13bBuilder::delay_us`] to only yield.
+    pub fn set_busy_yield(&mut self, busy_yield: Option<BusyYield>) {
+        self.interface.set_busy_yield(busy_yield);
+    }
+
+    /// Time the display was busy during the last wait for it, counted like the busy timeout,
+    /// e.g. to choose the timeout
+    pub fn last_wait_us(&self) -> u32 {
+        self.interface.last_wait_us()
+    }
+
+    /// Sets the reset pulse of the next initialisations: the reset pin is held high for
+    /// `high_us`, then low for `low_us`, both 10ms by default
+    ///
+    /// It replaces the durations of [`Epd2in13bBuilder::cold_reset_us`] and
+    /// [`Epd2in13bBuilder::resume_reset_us`], e.g. for clone panels which need a longer pulse.
+    pub fn set_reset_timing(&mut self, high_us: u32, low_us: u32) {
+        self.reset_high_us = high_us;
+        self.cold_reset_us = low_us;
+        self.resume_reset_us = low_us;
+    }
+
+    /// Sets the border waveform control register, which drives the border around the pixels
+    ///
+    /// The value is kept across initialisations, like [`Epd2in13bBuilder::border_waveform`].
+    /// It is only written when the display wakes up if it is asleep.
+    pub fn set_border_waveform(
+        &mut self,
+        spi: &mut SPI,
+        value: u8,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.border_waveform = value;
+        if self.asleep {
+            return Ok(());
+        }
+        self.cmd_with_data(spi, Command::BorderWaveformControl, &[value])
+    }
+
+    /// Sets the border to follow the waveform of `color`, so it turns to that color with the
+    /// next refresh, see [`set_border_waveform`](Self::set_border_waveform)
+    pub fn set_border_color(
+        &mut self,
+        spi: &mut SPI,
+        color: TriColor,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.set_border_waveform(spi, border_waveform(color))
+    }
+
+    /// Sets the VCOM register, used from the next refresh and kept across initialisations,
+    /// see [`Epd2in13bBuilder::vcom`]
+    pub fn set_vcom(&mut self, spi: &mut SPI, value: u8) -> Result<(), Error<SPI::Error>> {
+        self.vcom = value;
+        if self.asleep {
+            return Ok(());
+        }
+        self.cmd_with_data(spi, Command::WriteVcomRegister, &[value])
+    }
+
+    /// Value of the VCOM register, e.g. to store a value tuned at runtime and give it to
+    /// [`Epd2in13bBuilder::vcom`] after a reboot
+    pub fn vcom(&self) -> u8 {
+        self.vcom
+    }
+
+    /// Sets how the next refreshes use the black/white and the chromatic RAMs, kept across
+    /// initialisations, see [`Epd2in13bBuilder::ram_options`]
+    ///
+    /// E.g. [`RamOption::Inverse`] on both shows the inverse of the frame without rewriting it.
+    pub fn set_ram_options(
+        &mut self,
+        spi: &mut SPI,
+        bw: RamOption,
+        red: RamOption,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.bw_ram_option = bw;
+        self.red_ram_option = red;
+        if self.asleep {
+            return Ok(());
+        }
+        let update_control = DisplayUpdateControl {
+            red_ram_option: red,
+            bw_ram_option: bw,
+            source_output_mode: true,
+        };
+        self.cmd_with_data(
+            spi,
+            Command::DisplayUpdateControl1,
+            &update_control.to_bytes(),
+        )
+    }
+
+    /// Sets the gate driving voltage, used from the next refresh and kept across
+    /// initialisations, see [`Epd2in13bBuilder::gate_voltage`]
+    pub fn set_gate_voltage(
+        &mut self,
+        spi: &mut SPI,
+        voltage: GateVoltage,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.gate_voltage = voltage;
+        if self.asleep {
+            return Ok(());
+        }
+        self.cmd_with_data(spi, Command::GateDrivingVoltageCtrl, &[voltage.to_raw()])
+    }
+
+    /// Sets the source driving voltages, used from the next refresh and kept across
+    /// initialisations, see [`Epd2in13bBuilder::source_voltage`]
+    pub fn set_source_voltage(
+        &mut self,
+        spi: &mut SPI,
+        voltage: SourceVoltage,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.source_voltage = voltage;
+        if self.asleep {
+            return Ok(());
+        }
+        self.cmd_with_data(spi, Command::SourceDrivingVoltageCtrl, &voltage.to_raw())
+    }
+
+    /// Sets the booster soft start, used from the next refresh and kept across
+    /// initialisations, see [`Epd2in13bBuilder::booster_soft_start`]
+    pub fn set_booster_soft_start(
+        &mut self,
+        spi: &mut SPI,
+        booster: BoosterSoftStart,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.booster_soft_start = Some(booster);
+        if self.asleep {
+            return Ok(());
+        }
+        self.cmd_with_data(spi, Command::BoosterSoftStartControl, &booster.to_raw())
+    }
+
+    /// Whether the display is sent to deep sleep at the end of each refresh, see
+    /// [`Epd2in13bBuilder::auto_sleep`]
+    pub fn set_auto_sleep(&mut self, auto_sleep: bool) {
+        self.auto_sleep = auto_sleep;
+    }
+
+    /// Starts counting the refreshes from zero, or stops counting them, see
+    /// [`stats`](Self::stats)
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        self.stats = if enabled {
+            Some(RefreshStats::default())
+        } else {
+            None
+        };
+    }
+
+    /// Refreshes done since [`set_stats_enabled`](Self::set_stats_enabled), `None` if they
+    /// aren't counted
+    pub fn stats(&self) -> Option<RefreshStats> {
+        self.stats
+    }
+
+    /// Kind of the refreshes of [`display_frame`](WaveshareDisplay::display_frame) with the
+    /// current LUT
+    fn refresh_kind(&self) -> RefreshKind {
+        match self.refresh {
+            RefreshLut::Quick => RefreshKind::Partial,
+            RefreshLut::Full | RefreshLut::Fast => RefreshKind::Full,
+        }
+    }
+
+    /// Counts a refresh which just ended, if the statistics are enabled
+    fn count_refresh(&mut self, kind: RefreshKind) {
+        let busy_us = self.interface.last_wait_us();
+        if let Some(stats) = &mut self.stats {
+            stats.count(kind, busy_us);
+        }
+    }
+
+    /// Wakes the display up before it is written to, if it was sent to deep sleep
+    /// automatically
+    fn wake_if_asleep(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        if self.auto_sleep && self.asleep {
+            self.wake_up(spi, delay)?;
+        }
+        Ok(())
+    }
+
+    /// Empties the chromatic RAM after a black/white frame was written by `update_frame`,
+    /// unless it must be kept
+    fn blank_red_ram(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        // the chromatic RAM holds the previous frame for quick refreshes
+        if self.keep_red || self.refresh == RefreshLut::Quick {
+            return Ok(());
+        }
+
+        if self.red_written {
+            self.red_clobbered = true;
+            self.red_written = false;
+        }
+        // the chromatic RAM is often still empty from the previous frame
+        if self.red_cleared {
+            return Ok(());
+        }
+        self.command(spi, Command::WriteRamRed)?;
+        self.interface.data_x_times(
+            spi,
+            TriColor::Black.get_byte_value(),
+            buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
+        )?;
+        self.red_cleared = true;
+        Ok(())
+    }
+
+    /// Refreshes the display with the current refresh mode and waits for the end of the refresh
+    fn activate(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.load_temperature_lut(spi, delay)?;
+        self.command(spi, Command::MasterActivation)?;
+        self.wait_until_idle(spi, delay)?;
+        self.count_refresh(self.refresh_kind());
+        Ok(())
+    }
+
+    /// Quick refresh with the waveform loaded by `update_new_frame`, then restores the refresh
+    /// mode
+    fn activate_quick(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.update_sequence(spi, QUICK_UPDATE)?;
+        self.command(spi, Command::MasterActivation)?;
+        self.wait_until_idle(spi, delay)?;
+        self.count_refresh(RefreshKind::Partial);
+        self.set_lut(spi, delay, None)
+    }
+
+    /// Sends the display to deep sleep at the end of a refresh, if enabled
+    fn sleep_if_auto(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        if self.auto_sleep {
+            self.sleep(spi, delay)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the update `sequence` turning the power on or off, the update sequence of the
+    /// refreshes being sent again afterwards
+    fn switch_power(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        sequence: u8,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.cmd_with_data(spi, Command::DisplayUpdateControl2, &[sequence])?;
+        self.command(spi, Command::MasterActivation)?;
+        self.wait_until_idle(spi, delay)
+    }
+
+    /// Reads the status bit register, e.g. to check the wiring of the display
+    ///
+    /// Reading needs the SPI bus to receive on the data line of the display: MISO must be
+    /// connected to DIN.
+    pub fn read_status(&mut self, spi: &mut SPI) -> Result<u8, Error<SPI::Error>> {
+        let mut status = [0];
+        self.interface
+            .cmd_with_read(spi, Command::StatusBitRead, &mut status)?;
+        Ok(status[0])
+    }
+
+    /// Measures the temperature of the panel with the built-in sensor, in °C
+    ///
+    /// Like [`read_status`](Self::read_status) this needs MISO to be connected to DIN.
+    pub fn read_temperature(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<i8, Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.update_sequence(spi, LOAD_TEMPERATURE)?;
+        self.command(spi, Command::MasterActivation)?;
+        self.wait_until_idle(spi, delay)?;
+
+        // 12 bits in 1/16 °C, the first byte holds the whole degrees
+        let mut temperature = [0; 2];
+        self.interface
+            .cmd_with_read(spi, Command::TemperatureSensorRead, &mut temperature)?;
+
+        // restore the update sequence, and the overridden temperature
+        self.set_lut(spi, delay, None)?;
+        Ok(temperature[0] as i8)
+    }
+
+    /// Selects the waveform of full refreshes for `temperature` (in °C) instead of the
+    /// temperature measured by the sensor, e.g. when the panel is colder than its sensor
+    ///
+    /// `None` goes back to the sensor. The fast and quick refreshes of
+    /// [`set_lut`](LutControl::set_lut) don't depend on the temperature. The override
+    /// is kept on [`wake_up`](Sleepable::wake_up).
+    pub fn set_temperature_override(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        temperature: Option<i8>,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.temperature_override = temperature;
+        if self.refresh == RefreshLut::Full {
+            self.set_lut(spi, delay, None)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the waveform of the full refreshes by the one of `luts` for the temperature of
+    /// the panel, e.g. with longer phases for the cold, picked by
+    /// [`for_temperature`](crate::lut::for_temperature). `None` goes back to the waveforms of
+    /// the OTP.
+    ///
+    /// Before each full refresh of [`display_frame`](WaveshareDisplay::display_frame) and
+    /// [`update_and_display_frame`](WaveshareDisplay::update_and_display_frame), the
+    /// temperature is taken from the [override](Self::set_temperature_override), or else
+    /// measured like [`read_temperature`](Self::read_temperature), which needs MISO to be
+    /// connected to DIN. The waveform is then loaded, and also used by
+    /// [`display_frame_nonblocking`](Self::display_frame_nonblocking) until the next one.
+    /// The fast, quick and gray refreshes keep their own waveform.
+    pub fn set_temperature_luts(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        luts: Option<&'static [TemperatureLut]>,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.temperature_luts = luts;
+        if self.refresh == RefreshLut::Full {
+            self.set_lut(spi, delay, None)?;
+        }
+        Ok(())
+    }
+
+    /// Loads the waveform of the temperature LUTs for the temperature of the panel, before a
+    /// full refresh
+    fn load_temperature_lut(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        let luts = match self.temperature_luts {
+            Some(luts) if self.refresh == RefreshLut::Full && !self.gray => luts,
+            _ => return Ok(()),
+        };
+        let temperature = match self.temperature_override {
+            Some(temperature) => temperature,
+            None => self.read_temperature(spi, delay)?,
+        };
+        match crate::lut::for_temperature(luts, temperature) {
+            Some(temperature_lut) => self.set_custom_lut(spi, delay, &temperature_lut.lut),
+            None => Ok(()),
+        }
+    }
+
+    /// Reads the black/white RAM of the display into `buffer`, which needs to be as large
+    /// as a full frame
+    ///
+    /// Like [`read_status`](Self::read_status) this needs MISO to be connected to DIN.
+    pub fn read_ram(&mut self, spi: &mut SPI, buffer: &mut [u8]) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.read_ram_rows(spi, false, 0, buffer)
+    }
+
+    /// Reads the chromatic RAM of the display into `buffer`, see [`read_ram`](Self::read_ram)
+    pub fn read_chromatic_ram(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &mut [u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.read_ram_rows(spi, true, 0, buffer)
+    }
+
+    /// Enables reading back full frames after they were written, returning
+    /// [`Error::VerifyMismatch`] if the RAM differs from the written buffer
+    ///
+    /// This catches bit errors on long or marginal SPI wires, at the cost of reading every
+    /// frame a second time. The frames written by [`update_frame`](WaveshareDisplay::update_frame),
+    /// [`update_achromatic_frame`](WaveshareThreeColorDisplay::update_achromatic_frame) and
+    /// [`update_chromatic_frame`](WaveshareThreeColorDisplay::update_chromatic_frame) are
+    /// verified. Like [`read_ram`](Self::read_ram) this needs MISO to be connected to DIN.
+    pub fn set_verify_writes(&mut self, verify: bool) {
+        self.verify_writes = verify;
+    }
+
+    /// Reads the RAM starting at the beginning of `row` until `buffer` is full
+    ///
+    /// The address counters are reset to the start of the RAM afterwards, as the frame
+    /// updates expect them there.
+    fn read_ram_rows(
+        &mut self,
+        spi: &mut SPI,
+        chromatic: bool,
+        row: u32,
+        buffer: &mut [u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(
+            spi,
+            Command::SetRamYAddressCounter,
+            &[row as u8, (row >> 8) as u8],
+        )?;
+        self.cmd_with_data(spi, Command::ReadRamOption, &[chromatic as u8])?;
+        self.command(spi, Command::ReadRam)?;
+        self.interface.read_data_after_dummy(spi, buffer)?;
+        self.cmd_with_data(spi, Command::SetRamXAddressCounter, &[0])?;
+        self.cmd_with_data(spi, Command::SetRamYAddressCounter, &[0, 0])
+    }
+
+    /// Compares the RAM with `expected` if [`set_verify_writes`](Self::set_verify_writes)
+    /// is enabled, a few rows at a time to keep the stack small
+    fn verify_ram(
+        &mut self,
+        spi: &mut SPI,
+        chromatic: bool,
+        expected: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        if !self.verify_writes {
+            return Ok(());
+        }
+        const ROWS: usize = 4;
+        const LEN: usize = ROWS * buffer_len(WIDTH as usize, 1);
+        let mut read = [0; LEN];
+        for (i, expected) in expected.chunks(LEN).enumerate() {
+            let read = &mut read[..expected.len()];
+            self.read_ram_rows(spi, chromatic, (i * ROWS) as u32, read)?;
+            if read != expected {
+                return Err(Error::VerifyMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts displaying the frame data from SRAM and returns immediately, unlike
+    /// [`display_frame`](WaveshareDisplay::display_frame) which waits for the end of the
+    /// refresh (~15s with chromatic pixels).
+    ///
+    /// Poll [`is_busy`](Self::is_busy) or wait with [`finish_refresh`](Self::finish_refresh)
+    /// before sending anything else to the display.
+    pub fn display_frame_nonblocking(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.command(spi, Command::MasterActivation)
+    }
+
+    /// Checks if the display is still busy, e.g. refreshing after
+    /// [`display_frame_nonblocking`](Self::display_frame_nonblocking)
+    pub fn is_busy(&mut self) -> Result<bool, Error<SPI::Error>> {
+        self.interface.is_busy(IS_BUSY_LOW)
+    }
+
+    /// Waits for the end of a refresh started with
+    /// [`display_frame_nonblocking`](Self::display_frame_nonblocking)
+    pub fn finish_refresh(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.count_refresh(self.refresh_kind());
+        self.sleep_if_auto(spi, delay)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> WaveshareThreeColorDisplay<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in13b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn update_color_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        black: &[u8],
+        chromatic: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        // check both buffers before writing anything
+        Error::check_len(chromatic, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.update_achromatic_frame(spi, delay, black)?;
+        self.update_chromatic_frame(spi, delay, chromatic)
+    }
+
+    fn update_achromatic_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        black: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(black, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.wake_if_asleep(spi, delay)?;
+        self.command(spi, Command::WriteRam)?;
+        self.interface.data(spi, black)?;
+        self.verify_ram(spi, false, black)
+    }
+
+    fn update_chromatic_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        chromatic: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(chromatic, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.wake_if_asleep(spi, delay)?;
+        self.command(spi, Command::WriteRamRed)?;
+        self.interface.data(spi, chromatic)?;
+        self.red_written = true;
+        self.verify_ram(spi, true, chromatic)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Sleepable<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in13b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.sleep_with_mode(spi, delay, DeepSleepMode::Mode1)
+    }
+
+    fn sleep_with_mode(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+        mode: DeepSleepMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.set_sleep_mode(spi, mode)?;
+        // the normal mode leaves the display awake
+        if mode != DeepSleepMode::Normal {
+            self.asleep = true;
+            self.powered = false;
+        }
+        Ok(())
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.reset_and_init(spi, delay, self.resume_reset_us)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in13b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    type DisplayColor = TriColor;
+    fn new(
+        spi: &mut SPI,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+        delay_us: Option<u32>,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let mut builder = Epd2in13bBuilder::new();
+        builder.delay_us = delay_us;
+        builder.build(spi, busy, dc, rst, delay)
+    }
+
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.wake_if_asleep(spi, delay)?;
+        self.cmd_with_data(spi, Command::WriteRam, buffer)?;
+        self.verify_ram(spi, false, buffer)?;
+        self.blank_red_ram(spi)
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wake_if_asleep(spi, delay)?;
+        self.activate(spi, delay)?;
+        self.sleep_if_auto(spi, delay)
+    }
+
+    /// In the quick refresh mode, `buffer` is then written to the chromatic RAM as the old frame
+    /// of the next quick refresh.
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.activate(spi, delay)?;
+        if self.refresh == RefreshLut::Quick {
+            self.cmd_with_data(spi, Command::WriteRamRed, buffer)?;
+        }
+        self.sleep_if_auto(spi, delay)
+    }
+
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wake_if_asleep(spi, delay)?;
+        self.red_cleared = false;
+        self.bw_ram_generation = self.bw_ram_generation.wrapping_add(1);
+        shared::clear_frame!(self, spi, [])?;
+        self.red_cleared = self.background_color != TriColor::Chromatic;
+        Ok(())
+    }
+
+    fn set_background_color(&mut self, background_color: TriColor) {
+        self.background_color = background_color;
+    }
+
+    fn background_color(&self) -> &TriColor {
+        &self.background_color
+    }
+
+    fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    fn wait_until_idle(
+        &mut self,
+        _spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_until_idle(delay, IS_BUSY_LOW)?;
+        Ok(())
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> LutControl<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in13b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// [`RefreshLut::Fast`] loads a ~1.5s waveform which only drives black and white,
+    /// chromatic pixels are shown white. [`RefreshLut::Quick`] loads the partial waveform used
+    /// by [`QuickRefresh`]: frames are then written to the black/white RAM only, and
+    /// compared to the previous one. [`RefreshLut::Full`] uses the waveform stored in the
+    /// display OTP.
+    ///
+    /// In the [gray mode](Epd2in13b::set_gray_mode), the 4 gray levels waveform is loaded
+    /// instead, and the refresh mode is only kept for when the gray mode is left.
+    fn set_lut(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(refresh_lut) = refresh_rate {
+            self.refresh = refresh_lut;
+        }
+        if self.gray {
+            return self.set_custom_lut(spi, delay, &LUT_GRAY4);
+        }
+        match self.refresh {
+            RefreshLut::Fast => self.set_custom_lut(spi, delay, &LUT_FAST),
+            RefreshLut::Quick => {
+                self.load_lut(spi, delay, &LUT_PARTIAL)?;
+                self.update_sequence(spi, QUICK_UPDATE)
+            }
+            // default sequence: load the LUT from OTP before displaying
+            RefreshLut::Full => {
+                self.wait_until_idle(spi, delay)?;
+                match self.temperature_override {
+                    Some(temperature) => {
+                        self.cmd_with_data(
+                            spi,
+                            Command::TemperatureSensorWrite,
+                            &[temperature as u8, 0],
+                        )?;
+                        self.update_sequence(spi, FULL_UPDATE_FIXED_TEMPERATURE)
+                    }
+                    None => self.update_sequence(spi, 0xFF),
+                }
+            }
+        }
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> PowerControl<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in13b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// The refreshes then leave the analog power and the clock on: their update sequence
+    /// (Display Update Control 2) is sent without the bits turning them off.
+    fn power_on(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wake_if_asleep(spi, delay)?;
+        self.switch_power(spi, delay, POWER_ON)?;
+        self.powered = true;
+        // the update sequence of the refreshes, without powering off
+        self.set_lut(spi, delay, None)
+    }
+
+    /// Does nothing if the power isn't kept on, e.g. in deep sleep.
+    fn power_off(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        if !self.powered {
+            return Ok(());
+        }
+        self.powered = false;
+        self.switch_power(spi, delay, POWER_OFF)?;
+        self.set_lut(spi, delay, None)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> PartialUpdate<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in13b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Only writes the black/white RAM, see [`QuickRefresh`] and [`PartialRefresh`].
+    ///
+    /// `x` and `width` need to be multiples of 8.
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        Self::check_window(buffer, x, width, height)?;
+        self.wake_if_asleep(spi, delay)?;
+        self.write_window(spi, delay, Command::WriteRam, buffer, x, y, width, height)?;
+        self.set_full_window(spi, delay)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> PartialColorUpdate<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in13b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    #[allow(clippy::too_many_arguments)]
+    fn update_partial_color_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        black: &[u8],
+        chromatic: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        let expected = buffer_len(width as usize, height as usize);
+        Error::check_len(black, expected)?;
+        Error::check_len(chromatic, expected)?;
+        // checks the window before waking the display up
+        self.update_partial_color_frame_with(
+            spi,
+            delay,
+            |i| black[i],
+            |i| chromatic[i],
+            x,
+            y,
+            width,
+            height,
+        )
+    }
+}
+
+/// Quick refresh of the black and white content, in less than a second
+///
+/// The old frame is written to the chromatic RAM, so chromatic pixels can't be shown. Only the
+/// pixels which differ between the old and the new frame are refreshed, which leaves some
+/// ghosting: do a full refresh from time to time.
+impl<SPI, BUSY, DC, RST, DELAY> QuickRefresh<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in13b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Writes the frame currently shown, to be followed by `update_new_frame`.
+    fn update_old_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.wake_if_asleep(spi, delay)?;
+        self.wait_until_idle(spi, delay)?;
+        self.cmd_with_data(spi, Command::WriteRam, buffer)?;
+        self.cmd_with_data(spi, Command::WriteRamRed, buffer)
+    }
+
+    /// Writes the frame to show and loads the quick refresh waveform.
+    fn update_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.wake_if_asleep(spi, delay)?;
+        self.load_lut(spi, delay, &LUT_PARTIAL)?;
+        self.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    /// Quick refresh of the new frame, to be used after `update_new_frame`.
+    ///
+    /// The refresh mode of [`set_lut`](LutControl::set_lut) is then restored. The frame
+    /// shown must be written with `update_old_frame` before the next quick refresh, which
+    /// `update_and_display_new_frame` does.
+    fn display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.activate_quick(spi, delay)?;
+        self.sleep_if_auto(spi, delay)
+    }
+
+    /// The new frame is then written to the chromatic RAM, as the old frame of the next quick
+    /// refresh.
+    fn update_and_display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_new_frame(spi, buffer, delay)?;
+        self.activate_quick(spi, delay)?;
+        self.cmd_with_data(spi, Command::WriteRamRed, buffer)?;
+        self.sleep_if_auto(spi, delay)
+    }
+
+    /// `x` and `width` need to be multiples of 8.
+    fn update_partial_old_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        Self::check_window(buffer, x, width, height)?;
+        self.wake_if_asleep(spi, delay)?;
+        self.write_window(spi, delay, Command::WriteRam, buffer, x, y, width, height)?;
+        self.set_ram_address_counters(spi, delay, x, y)?;
+        self.cmd_with_data(spi, Command::WriteRamRed, buffer)?;
+        self.set_full_window(spi, delay)
+    }
+
+    /// `x` and `width` need to be multiples of 8.
+    fn update_partial_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        Self::check_window(buffer, x, width, height)?;
+        self.wake_if_asleep(spi, delay)?;
+        self.load_lut(spi, delay, &LUT_PARTIAL)?;
+        self.write_window(spi, delay, Command::WriteRam, buffer, x, y, width, height)?;
+        self.set_full_window(spi, delay)
+    }
+
+    /// `x` and `width` need to be multiples of 8.
+    fn clear_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_aligned(x, width)?;
+        self.wake_if_asleep(spi, delay)?;
+        let color = match self.background_color {
+            TriColor::Black => 0x00,
+            TriColor::White | TriColor::Chromatic => 0xFF,
+        };
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.set_ram_address_counters(spi, delay, x, y)?;
+        self.command(spi, Command::WriteRam)?;
+        self.interface.data_x_times(
+            spi,
+            color,
+            buffer_len(width as usize, height as usize) as u32,
+        )?;
+        self.set_full_window(spi, delay)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> BorderColor<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in13b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn set_border_color(
+        &mut self,
+        spi: &mut SPI,
+        color: TriColor,
+    ) -> Result<(), Error<SPI::Error>> {
+        Epd2in13b::set_border_color(self, spi, color)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> PartialRefresh<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in13b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn set_base_image(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_old_frame(spi, buffer, delay)
+    }
+
+    fn set_update_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mode: RefreshLut,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.set_lut(spi, delay, Some(mode))
+    }
+}
+
+/// A type safe chunk representation for BufferMonoDisplay
+///
+/// The display is split into `N` chunks of [`rows`](Self::rows) rows each, the last chunk only
+/// covers the remaining rows. `N` defaults to [`BUFFER`], the number of chunks of the
+/// `update_*_buffered` methods. `N` must not be 0, which doesn't build.
+///
+/// ```rust,compile_fail
+/// # use epd_waveshare::epd2in13b_v4::Chunk;
+/// let rows = Chunk::<0>::rows();
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Chunk<const N: u32 = BUFFER>(u32);
+
+/// Error converting an index to a [`Chunk`] which is not on the display
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ChunkOutOfRange(pub u32);
+
+impl<const N: u32> Chunk<N> {
+    /// Makes the chunks fail to build with `N` = 0, instead of dividing by zero
+    const NOT_ZERO: () = assert!(N > 0, "the display needs at least one chunk");
+
+    /// converts chunk to a zero-indexed `u32`
+    pub fn to_zero_indexed(&self) -> u32 {
+        self.0
+    }
+
+    /// All the chunks of the display, from the top
+    pub fn iter() -> impl Iterator<Item = Self> {
+        (0..N).map_while(|i| Self::try_from(i).ok())
+    }
+
+    /// number of rows of all chunks but the last one
+    pub const fn rows() -> u32 {
+        let () = Self::NOT_ZERO;
+        HEIGHT / N + (HEIGHT % N != 0) as u32
+    }
+
+    /// first row of the display covered by this chunk
+    pub fn y_offset(&self) -> u32 {
+        self.0 * Self::rows()
+    }
+
+    /// number of rows of the display covered by this chunk
+    ///
+    /// This is [`rows`](Self::rows) for all chunks but the last one, which takes the remaining
+    /// rows.
+    pub fn height(&self) -> u32 {
+        Self::rows().min(HEIGHT - self.y_offset())
+    }
+}
+
+impl<const N: u32> TryFrom<u32> for Chunk<N> {
+    type Error = ChunkOutOfRange;
+
+    /// converts from a zero-indexed `u32`, which fails if the chunk would be past the last row
+    fn try_from(i: u32) -> Result<Self, Self::Error> {
+        if i < N && i * Self::rows() < HEIGHT {
+            Ok(Chunk(i))
+        } else {
+            Err(ChunkOutOfRange(i))
+        }
+    }
+}
+
+/// The part of a chunk buffer which is actually shown on the display
+#[cfg(feature = "graphics")]
+fn chunk_data(buffer: &[u8], chunk: Chunk) -> &[u8] {
+    &buffer[..buffer_len(WIDTH as usize, chunk.height() as usize)]
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Epd2in13b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Due to memory limitations on the arduino boards, this function allows the user to separate the 122x250 board into four subgrids.
+    /// The first three are 122x63, the last one only uses the 61 remaining rows of its buffer (see [`Chunk::height`]).
+    ///
+    /// for usage on `mono_buffers` and colored_buffers`, please refer to the documentation of `update_achromatic_buffered` and `update_chromatic_buffered`
+    #[cfg(feature = "graphics")]
+    pub fn update_frame_buffered(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mono_buffers: impl FnMut(&mut BufferMonoDisplay2in13b, Chunk) -> Result<Option<()>, Infallible>,
+        colored_buffers: impl FnMut(
+            &mut BufferChromaticDisplay2in13b,
+            Chunk,
+        ) -> Result<Option<()>, Infallible>,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wake_if_asleep(spi, delay)?;
+        self.update_achromatic_buffered(spi, delay, mono_buffers)?;
+        self.update_chromatic_buffered(spi, delay, colored_buffers)
+    }
+
+    /// Due to memory limitations on the arduino boards, this function allows the user to separate the 122x250 board into four subgrids.
+    /// The first three are 122x63, the last one only uses the 61 remaining rows of its buffer (see [`Chunk::height`]).
+    ///
+    /// IMPORTANT: this must be followed by `update_chromatic_buffered`, even if you're trying to only display purely mono content, otherwise the display won't be updated.
+    ///
+    /// `buffers`: A function that that should populate the content of each section of the display.
+    ///     - Takes a mutable reference to `BuffermonoDisplay2in13b` and a buffer index(0-3)
+    ///     - Returns `Result<Option<(), Infalliable>>`
+    ///         * `Ok(Some(()))` indicates successful execution.
+    ///         * `Ok(None)` indicates the buffer should be left unmodified, leaving it uncolored.
+    ///         * `Err(Infalliable)` is here purely for allowing `?` with `embedded-graphics` draw operations.
+    #[cfg(feature = "graphics")]
+    pub fn update_achromatic_buffered(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mut buffers: impl FnMut(&mut BufferMonoDisplay2in13b, Chunk) -> Result<Option<()>, Infallible>,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wake_if_asleep(spi, delay)?;
+        for chunk in Chunk::iter() {
+            self.write_achromatic_chunk(spi, delay, chunk, &mut buffers)?;
+        }
+        self.set_full_window(spi, delay)
+    }
+
+    /// Same as [`update_achromatic_buffered`](Self::update_achromatic_buffered) for a single
+    /// chunk: only the rows of `chunk` are written, the rest of the black/white RAM is kept.
+    ///
+    /// It's followed by a [`display_frame`](WaveshareDisplay::display_frame) to show it.
+    #[cfg(feature = "graphics")]
+    pub fn update_achromatic_chunk(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        chunk: Chunk,
+        buffer: impl FnOnce(&mut BufferMonoDisplay2in13b, Chunk) -> Result<Option<()>, Infallible>,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wake_if_asleep(spi, delay)?;
+        self.write_achromatic_chunk(spi, delay, chunk, buffer)?;
+        self.set_full_window(spi, delay)
+    }
+
+    #[cfg(feature = "graphics")]
+    // renders a chunk and writes it to its window of the black/white RAM
+    fn write_achromatic_chunk(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        chunk: Chunk,
+        buffer: impl FnOnce(&mut BufferMonoDisplay2in13b, Chunk) -> Result<Option<()>, Infallible>,
+    ) -> Result<(), Error<SPI::Error>> {
+        let mut display = BufferMonoDisplay2in13b::default();
+        if buffer(&mut display, chunk).unwrap().is_none() {
+            display.clear(Color::White).unwrap();
+        }
+        self.set_chunk_window(spi, delay, chunk)?;
+        self.command(spi, Command::WriteRam)?;
+        self.interface
+            .data(spi, chunk_data(display.buffer(), chunk))
+    }
+
+    /// Due to memory limitations on the arduino boards, this function allows the user to separate the 122x250 board into four subgrids.
+    /// The first three are 122x63, the last one only uses the 61 remaining rows of its buffer (see [`Chunk::height`]).
+    ///
+    /// IMPORTANT: this function must be called after `update_achromatic_buffered`, even if you're trying to only display purely mono content, otherwise the display won't be updated.
+    ///
+    /// Pixels drawn with `TriColor::Chromatic` are shown in red, the black and white pixels are
+    /// the ones of `update_achromatic_buffered`.
+    ///
+    /// `buffers`: A function that that should populate the content of each section of the display.
+    ///     - Takes a mutable reference to `BufferChromaticDisplay2in13b` and a buffer index(0-3)
+    ///     - Returns `Result<Option<(), Infalliable>>`
+    ///         * `Ok(Some(()))` indicates successful execution
+    ///         * `Ok(None)` indicates the buffer should be left unmodified, leaving it uncolored.
+    ///         * `Err(Infalliable)` is here purely for allowing `?` with `embedded-graphics` draw operations.
+    #[cfg(feature = "graphics")]
+    pub fn update_chromatic_buffered(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mut buffers: impl FnMut(
+            &mut BufferChromaticDisplay2in13b,
+            Chunk,
+        ) -> Result<Option<()>, Infallible>,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wake_if_asleep(spi, delay)?;
+        let mut buffer = BufferChromaticDisplay2in13b::default();
+        for chunk in Chunk::iter() {
+            if buffers(&mut buffer, chunk).unwrap().is_none() {
+                buffer.clear(TriColor::White).unwrap();
+            }
+            self.set_chunk_window(spi, delay, chunk)?;
+            self.command(spi, Command::WriteRamRed)?;
+            // only the chromatic bits matter, the black/white ones come from the other RAM
+            self.interface
+                .data(spi, chunk_data(buffer.chromatic_buffer(), chunk))?;
+        }
+        self.red_written = true;
+        self.set_full_window(spi, delay)?;
+        self.interface.cmd(spi, Command::MasterActivation)?;
+        self.wait_until_idle(spi, delay)?;
+        self.count_refresh(RefreshKind::Full);
+        self.sleep_if_auto(spi, delay)
     }
 
-    /// Triggers the deep sleep mode
-    fn set_sleep_mode(&mut self, spi: &mut SPI, mode: DeepSleepMode) -> Result<(), SPI::Error> {
-        self.cmd_with_data(spi, Command::DeepSleepMode, &[mode as u8])
+    /// Restricts the RAM window to the rows of a chunk
+    #[cfg(feature = "graphics")]
+    fn set_chunk_window(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        chunk: Chunk,
+    ) -> Result<(), Error<SPI::Error>> {
+        let end_y = chunk.y_offset() + chunk.height() - 1;
+        self.set_ram_area(spi, 0, chunk.y_offset(), WIDTH - 1, end_y)?;
+        self.set_ram_address_counters(spi, delay, 0, chunk.y_offset())
     }
 
-    fn set_driver_output(&mut self, spi: &mut SPI, output: DriverOutput) -> Result<(), SPI::Error> {
-        self.cmd_with_data(spi, Command::DriverOutputControl, &output.to_bytes())
+    /// Restores the RAM window to the whole display
+    fn set_full_window(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        Controller::set_full_window(&mut self.interface, spi, delay)
     }
 
-    /// Sets the data entry mode (ie. how X and Y positions changes when writing
-    /// data to RAM)
-    fn set_data_entry_mode(
+    /// Triggers the deep sleep mode
+    fn set_sleep_mode(
         &mut self,
         spi: &mut SPI,
-        counter_incr_mode: DataEntryModeIncr,
-        counter_direction: DataEntryModeDir,
-    ) -> Result<(), SPI::Error> {
-        let mode = counter_incr_mode as u8 | counter_direction as u8;
-        self.cmd_with_data(spi, Command::DataEntryModeSetting, &[mode])
+        mode: DeepSleepMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        shared::sleep!(self, spi, mode, [])
     }
 
     /// Sets both X and Y pixels ranges
@@ -605,23 +2033,8 @@ where
         start_y: u32,
         end_x: u32,
         end_y: u32,
-    ) -> Result<(), SPI::Error> {
-        self.cmd_with_data(
-            spi,
-            Command::SetRamXAddressStartEndPosition,
-            &[(start_x >> 3) as u8, (end_x >> 3) as u8],
-        )?;
-
-        self.cmd_with_data(
-            spi,
-            Command::SetRamYAddressStartEndPosition,
-            &[
-                start_y as u8,
-                (start_y >> 8) as u8,
-                end_y as u8,
-                (end_y >> 8) as u8,
-            ],
-        )
+    ) -> Result<(), Error<SPI::Error>> {
+        Controller::set_ram_area(&mut self.interface, spi, start_x, start_y, end_x, end_y)
     }
 
     /// Sets both X and Y pixels counters when writing data to RAM
@@ -631,19 +2044,12 @@ where
         delay: &mut DELAY,
         x: u32,
         y: u32,
-    ) -> Result<(), SPI::Error> {
-        self.wait_until_idle(spi, delay)?;
-        self.cmd_with_data(spi, Command::SetRamXAddressCounter, &[(x >> 3) as u8])?;
-
-        self.cmd_with_data(
-            spi,
-            Command::SetRamYAddressCounter,
-            &[y as u8, (y >> 8) as u8],
-        )?;
-        Ok(())
+    ) -> Result<(), Error<SPI::Error>> {
+        Controller::set_ram_address_counters(&mut self.interface, spi, delay, x, y)
     }
 
-    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), SPI::Error> {
+    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), Error<SPI::Error>> {
+        self.note_ram_write(command);
         self.interface.cmd(spi, command)
     }
 
@@ -652,76 +2058,127 @@ where
         spi: &mut SPI,
         command: Command,
         data: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        self.note_ram_write(command);
         self.interface.cmd_with_data(spi, command, data)
     }
 
-    fn clear_achromatic_frame(&mut self, spi: &mut SPI) -> Result<(), SPI::Error> {
-        match self.background_color {
-            TriColor::White => {
-                self.command(spi, Command::WriteRam)?;
-                self.interface.data_x_times(
-                    spi,
-                    0xFF,
-                    buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
-                )?;
-            }
-            TriColor::Chromatic => {
-                self.command(spi, Command::WriteRam)?;
-                self.interface.data_x_times(
-                    spi,
-                    0xFF,
-                    buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
-                )?;
-            }
-            TriColor::Black => {
-                self.command(spi, Command::WriteRam)?;
-                self.interface.data_x_times(
-                    spi,
-                    0x00,
-                    buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
-                )?;
-            }
-        }
-
-        Ok(())
+    /// Sets the display update `sequence` of the next activations, without disabling the
+    /// analog power at its end while it is kept on by [`PowerControl`]
+    fn update_sequence(&mut self, spi: &mut SPI, sequence: u8) -> Result<(), Error<SPI::Error>> {
+        let sequence = if self.powered {
+            sequence & !POWER_OFF
+        } else {
+            sequence
+        };
+        self.cmd_with_data(spi, Command::DisplayUpdateControl2, &[sequence])
     }
 
-    fn clear_chromatic_frame(&mut self, spi: &mut SPI) -> Result<(), SPI::Error> {
-        match self.background_color {
-            TriColor::White => {
-                self.command(spi, Command::WriteRam)?;
-                self.interface.data_x_times(
-                    spi,
-                    0x00,
-                    buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
-                )?;
-            }
-            TriColor::Chromatic => {
-                self.command(spi, Command::WriteRam)?;
-                self.interface.data_x_times(
-                    spi,
-                    0xFF,
-                    buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
-                )?;
-            }
-            TriColor::Black => {
-                self.command(spi, Command::WriteRam)?;
-                self.interface.data_x_times(
-                    spi,
-                    0x00,
-                    buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
-                )?;
-            }
+    /// Keeps track of the RAM written by `command`: the black/white RAM changes generation, and
+    /// the chromatic RAM may no longer be empty
+    fn note_ram_write(&mut self, command: Command) {
+        match command {
+            Command::WriteRam => self.bw_ram_generation = self.bw_ram_generation.wrapping_add(1),
+            Command::WriteRamRed => self.red_cleared = false,
+            _ => {}
         }
-
-        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::TrafficKind;
+    use embedded_hal_mock::eh1::{delay::NoopDelay, digital, spi};
+
+    extern crate std;
+    use std::vec::Vec;
+
+    pub(super) type Traffic = Vec<(TrafficKind, Vec<u8>)>;
+
+    // traffic of a command followed by its data
+    pub(super) fn cmd(command: Command, data: &[u8]) -> Traffic {
+        let mut traffic = std::vec![(TrafficKind::Command, std::vec![command as u8])];
+        if !data.is_empty() {
+            traffic.push((TrafficKind::Data, data.to_vec()));
+        }
+        traffic
+    }
+
+    // traffic of `init`, after the hardware reset
+    pub(super) fn init_traffic() -> Traffic {
+        [
+            cmd(Command::SwReset, &[]),
+            cmd(Command::DriverOutputControl, &[249, 0, 0]),
+            cmd(Command::DataEntryModeSetting, &[0x03]),
+            cmd(Command::SetRamXAddressStartEndPosition, &[0, 15]),
+            cmd(Command::SetRamYAddressStartEndPosition, &[0, 0, 249, 0]),
+            cmd(Command::SetRamXAddressCounter, &[0]),
+            cmd(Command::SetRamYAddressCounter, &[0, 0]),
+            cmd(Command::BorderWaveformControl, &[0x03]),
+            cmd(Command::WriteVcomRegister, &[0x36]),
+            cmd(Command::GateDrivingVoltageCtrl, &[0x17]),
+            cmd(Command::SourceDrivingVoltageCtrl, &[0x41, 0x00, 0x32]),
+            cmd(Command::DisplayUpdateControl1, &[0x00, 0x80]),
+        ]
+        .concat()
+    }
+
+    // SPI and DC pin expectations for the given traffic, written one byte at a time
+    pub(super) fn expectations(
+        traffic: &Traffic,
+    ) -> (Vec<spi::Transaction<u8>>, Vec<digital::Transaction>) {
+        let mut spi = Vec::new();
+        let mut dc = Vec::new();
+        for (kind, bytes) in traffic {
+            dc.push(digital::Transaction::set(match kind {
+                TrafficKind::Command => digital::State::Low,
+                TrafficKind::Data => digital::State::High,
+            }));
+            for byte in bytes.iter() {
+                spi.push(spi::Transaction::transaction_start());
+                spi.push(spi::Transaction::write_vec(std::vec![*byte]));
+                spi.push(spi::Transaction::transaction_end());
+            }
+        }
+        (spi, dc)
+    }
+
+    // driver which has not been initialised, to test single operations
+    pub(super) fn uninitialised<DELAY: DelayNs>(
+        busy: &digital::Mock,
+        dc: &digital::Mock,
+        rst: &digital::Mock,
+    ) -> Epd2in13b<spi::Mock<u8>, digital::Mock, digital::Mock, digital::Mock, DELAY> {
+        Epd2in13b {
+            interface: DisplayInterface::new(busy.clone(), dc.clone(), rst.clone(), None),
+            background_color: DEFAULT_BACKGROUND_COLOR,
+            border_waveform: DEFAULT_BORDER_WAVEFORM,
+            vcom: DEFAULT_VCOM,
+            gate_voltage: GateVoltage::DEFAULT,
+            source_voltage: SourceVoltage::DEFAULT,
+            booster_soft_start: None,
+            bw_ram_option: RamOption::Normal,
+            red_ram_option: RamOption::Normal,
+            reset_high_us: RESET_HIGH_US,
+            cold_reset_us: RESET_DURATION_US,
+            resume_reset_us: RESET_DURATION_US,
+            refresh: RefreshLut::Full,
+            keep_red: false,
+            red_written: false,
+            red_clobbered: false,
+            red_cleared: false,
+            bw_ram_generation: 0,
+            verify_writes: false,
+            temperature_override: None,
+            temperature_luts: None,
+            gray: false,
+            auto_sleep: false,
+            asleep: false,
+            powered: false,
+            stats: None,
+        }
+    }
 
     #[test]
     fn epd_size() {
@@ -729,4 +2186,1640 @@ mod tests {
         assert_eq!(HEIGHT, 250);
         assert_eq!(DEFAULT_BACKGROUND_COLOR, TriColor::White);
     }
+
+    #[test]
+    fn chunks_cover_whole_display() {
+        let mut next_row = 0;
+        for chunk in Chunk::<BUFFER>::iter() {
+            assert_eq!(chunk.y_offset(), next_row);
+            assert!(chunk.height() <= CHUNK_HEIGHT);
+            next_row += chunk.height();
+        }
+        assert_eq!(next_row, HEIGHT);
+        let last = Chunk::<BUFFER>::try_from(3).unwrap();
+        assert_eq!(last.y_offset(), 189);
+        assert_eq!(last.height(), 61);
+        assert_eq!(Chunk::<BUFFER>::try_from(4), Err(ChunkOutOfRange(4)));
+    }
+
+    #[test]
+    fn chunks_of_other_sizes() {
+        assert_eq!(Chunk::<10>::iter().count(), 10);
+        assert_eq!(Chunk::<10>::rows(), 25);
+        assert_eq!(Chunk::<4>::rows(), 63);
+        assert_eq!(Chunk::<3>::iter().map(|c| c.height()).sum::<u32>(), HEIGHT);
+        // 2 rows per chunk, the last ones would be past the display
+        assert_eq!(Chunk::<200>::iter().count(), 125);
+        assert_eq!(Chunk::<200>::try_from(125), Err(ChunkOutOfRange(125)));
+        assert_eq!(Chunk::<{ u32::MAX }>::rows(), 1);
+        assert_eq!(Chunk::<{ u32::MAX }>::iter().count(), HEIGHT as usize);
+    }
+
+    #[test]
+    fn update_color_frame_rejects_short_buffer() {
+        let mut spi = spi::Mock::new(&[]);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&pin, &pin, &pin);
+
+        let black = [0xFF; buffer_len(WIDTH as usize, HEIGHT as usize)];
+        let chromatic = [0x00; 100];
+        assert_eq!(
+            epd.update_color_frame(&mut spi, &mut NoopDelay, &black, &chromatic),
+            Err(Error::WrongBufferSize {
+                expected: black.len(),
+                got: 100
+            })
+        );
+
+        // nothing must have been sent
+        spi.done();
+        pin.done();
+    }
+
+    #[test]
+    fn wrong_buffers_are_rejected() {
+        let mut spi = spi::Mock::new(&[]);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&pin, &pin, &pin);
+
+        assert_eq!(
+            epd.update_frame(&mut spi, &[0xFF; 10], &mut NoopDelay),
+            Err(Error::WrongBufferSize {
+                expected: buffer_len(WIDTH as usize, HEIGHT as usize),
+                got: 10
+            })
+        );
+        assert_eq!(
+            epd.update_partial_frame(&mut spi, &mut NoopDelay, &[0xFF; 4], 4, 0, 8, 4),
+            Err(Error::Window(crate::rect::WindowError::Misaligned))
+        );
+
+        // nothing must have been sent
+        spi.done();
+        pin.done();
+    }
+
+    #[test]
+    fn generators_of_the_wrong_length_are_rejected() {
+        let mut spi = spi::Mock::new(&[]);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&pin, &pin, &pin);
+
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize);
+        let wrong = Err(Error::WrongBufferSize {
+            expected: len,
+            got: len - 1,
+        });
+        let mut delay = NoopDelay;
+        assert_eq!(
+            epd.update_color_frame_with(&mut spi, &mut delay, |_| 0xFF, |_| 0, len, len - 1),
+            wrong
+        );
+        assert_eq!(
+            epd.update_color_frame_with(&mut spi, &mut delay, |_| 0xFF, |_| 0, len - 1, len),
+            wrong
+        );
+        assert_eq!(
+            epd.update_achromatic_frame_with(&mut spi, &mut delay, |_| 0xFF, len - 1),
+            wrong
+        );
+        assert_eq!(
+            epd.update_chromatic_frame_with(&mut spi, &mut delay, |_| 0, len - 1),
+            wrong
+        );
+
+        // nothing must have been sent
+        spi.done();
+        pin.done();
+    }
+
+    #[test]
+    fn wrong_buffers_dont_wake_the_display() {
+        let mut spi = spi::Mock::new(&[]);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&pin, &pin, &pin);
+        epd.auto_sleep = true;
+        epd.asleep = true;
+
+        let frame = [0xFF; buffer_len(WIDTH as usize, HEIGHT as usize)];
+        let short = [0xFF; 10];
+        let wrong = Err(Error::WrongBufferSize {
+            expected: frame.len(),
+            got: 10,
+        });
+        let mut delay = NoopDelay;
+        assert_eq!(
+            epd.update_color_frame(&mut spi, &mut delay, &frame, &short),
+            wrong
+        );
+        assert_eq!(
+            epd.update_achromatic_frame(&mut spi, &mut delay, &short),
+            wrong
+        );
+        assert_eq!(epd.update_old_frame(&mut spi, &short, &mut delay), wrong);
+        assert_eq!(
+            epd.update_partial_frame(&mut spi, &mut delay, &short, 0, 0, 8, 4),
+            Err(Error::WrongBufferSize {
+                expected: 4,
+                got: 10
+            })
+        );
+        assert_eq!(
+            epd.clear_partial_frame(&mut spi, &mut delay, 4, 0, 8, 4),
+            Err(Error::Window(crate::rect::WindowError::Misaligned))
+        );
+
+        // the display was neither reset nor written to
+        assert!(epd.asleep);
+        spi.done();
+        pin.done();
+    }
+
+    #[test]
+    fn traffic_tap_sees_frame_update() {
+        use std::sync::Mutex;
+
+        static TRAFFIC: Mutex<Traffic> = Mutex::new(Vec::new());
+        fn record(kind: TrafficKind, data: &[u8]) {
+            TRAFFIC.lock().unwrap().push((kind, data.to_vec()));
+        }
+
+        let black = [0xAA; buffer_len(WIDTH as usize, HEIGHT as usize)];
+        let chromatic = [0x55; buffer_len(WIDTH as usize, HEIGHT as usize)];
+        let expected = [
+            cmd(Command::WriteRam, &black),
+            cmd(Command::WriteRamRed, &chromatic),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&expected);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&pin, &dc, &pin);
+        epd.set_traffic_tap(Some(record));
+
+        epd.update_color_frame(&mut spi, &mut NoopDelay, &black, &chromatic)
+            .unwrap();
+        assert_eq!(*TRAFFIC.lock().unwrap(), expected);
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[test]
+    fn traffic_tap_only_sees_bytes() {
+        use std::sync::Mutex;
+
+        static TRAFFIC: Mutex<Traffic> = Mutex::new(Vec::new());
+        fn record(kind: TrafficKind, data: &[u8]) {
+            TRAFFIC.lock().unwrap().push((kind, data.to_vec()));
+        }
+
+        let (spi_expectations, dc_expectations) =
+            expectations(&cmd(Command::MasterActivation, &[]));
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&[
+            digital::Transaction::get(digital::State::High),
+            digital::Transaction::get(digital::State::High),
+            digital::Transaction::get(digital::State::Low),
+        ]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&busy, &dc, &rst);
+        epd.set_traffic_tap(Some(record));
+
+        epd.display_frame(&mut spi, &mut NoopDelay).unwrap();
+        // the busy wait isn't traffic, it is only traced with `log` or `defmt`
+        assert_eq!(
+            *TRAFFIC.lock().unwrap(),
+            cmd(Command::MasterActivation, &[])
+        );
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
+
+    #[test]
+    fn last_frame_crc() {
+        use crate::crc::crc32;
+        use crate::testing::Recorder;
+        use embedded_hal::digital::PinState;
+
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let (dc, rst) = (recorder.dc(), recorder.rst());
+        let mut epd = Epd2in13b::new(&mut spi, busy, dc, rst, &mut delay, None).unwrap();
+        assert_eq!(epd.last_frame_crc(), None);
+
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize);
+        let frame: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        epd.update_frame(&mut spi, &frame, &mut delay).unwrap();
+        // the chromatic RAM written afterwards doesn't count
+        assert_eq!(epd.last_frame_crc(), Some(crc32(&frame)));
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+        assert_eq!(epd.last_frame_crc(), Some(crc32(&frame)));
+
+        // same for the data sent in several parts
+        let black = [0x0F; 8];
+        epd.update_achromatic_frame_iter(&mut spi, &mut delay, black.iter().copied().cycle())
+            .unwrap();
+        assert_eq!(epd.last_frame_crc(), Some(crc32(&std::vec![0x0F; len])));
+    }
+
+    #[test]
+    fn fast_refresh_loads_fast_lut() {
+        let traffic = [
+            cmd(Command::WriteLutRegister, &LUT_FAST[0..153]),
+            cmd(Command::WriteLutRegisterEnd, &[0x22]),
+            cmd(Command::GateDrivingVoltageCtrl, &[0x17]),
+            cmd(Command::SourceDrivingVoltageCtrl, &[0x41, 0x00, 0x32]),
+            cmd(Command::WriteVcomRegister, &[0x36]),
+            cmd(Command::DisplayUpdateControl2, &[0xC7]),
+            cmd(Command::MasterActivation, &[]),
+            cmd(Command::DisplayUpdateControl2, &[0xFF]),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&std::vec![
+            digital::Transaction::get(digital::State::Low);
+            3
+        ]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&busy, &dc, &rst);
+
+        epd.set_lut(&mut spi, &mut NoopDelay, Some(RefreshLut::Fast))
+            .unwrap();
+        epd.display_frame(&mut spi, &mut NoopDelay).unwrap();
+        epd.set_lut(&mut spi, &mut NoopDelay, Some(RefreshLut::Full))
+            .unwrap();
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
+
+    #[test]
+    fn custom_lut_is_written() {
+        let mut lut = [0; LUT_SIZE];
+        for (i, byte) in lut.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        let traffic = [
+            cmd(Command::WriteLutRegister, &lut[..153]),
+            cmd(Command::WriteLutRegisterEnd, &[153]),
+            cmd(Command::GateDrivingVoltageCtrl, &[154]),
+            cmd(Command::SourceDrivingVoltageCtrl, &[155, 156, 157]),
+            cmd(Command::WriteVcomRegister, &[158]),
+            cmd(Command::DisplayUpdateControl2, &[0xC7]),
+        ]
+        .concat();
+        // once as bytes, once decoded
+        let (spi_expectations, dc_expectations) =
+            expectations(&[traffic.clone(), traffic].concat());
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&[
+            digital::Transaction::get(digital::State::Low),
+            digital::Transaction::get(digital::State::Low),
+        ]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&busy, &dc, &rst);
+
+        epd.set_custom_lut(&mut spi, &mut NoopDelay, &lut).unwrap();
+        epd.write_lut(&mut spi, &mut NoopDelay, &Lut::from_bytes(&lut))
+            .unwrap();
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
+
+    #[test]
+    fn waveforms_decode() {
+        for lut in [LUT_FAST, LUT_PARTIAL] {
+            assert_eq!(Lut::from_bytes(&lut).to_bytes(), lut);
+        }
+        let fast = Lut::from_bytes(&LUT_FAST);
+        assert_eq!(fast.groups[1].frames, [5, 5, 5, 5]);
+        assert_eq!(fast.groups[8].repeat, 1);
+    }
+
+    #[test]
+    fn quick_refresh_compares_with_old_frame() {
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize);
+        let old = std::vec![0xFF; len];
+        let new = std::vec![0xF0; len];
+        let traffic = [
+            cmd(Command::WriteRam, &old),
+            cmd(Command::WriteRamRed, &old),
+            cmd(Command::WriteLutRegister, &LUT_PARTIAL[..153]),
+            cmd(Command::WriteLutRegisterEnd, &[0x22]),
+            cmd(Command::GateDrivingVoltageCtrl, &[0x17]),
+            cmd(Command::SourceDrivingVoltageCtrl, &[0x41, 0x00, 0x32]),
+            cmd(Command::WriteVcomRegister, &[0x36]),
+            cmd(Command::WriteRam, &new),
+            cmd(Command::DisplayUpdateControl2, &[0xCF]),
+            cmd(Command::MasterActivation, &[]),
+            // back to full refreshes
+            cmd(Command::DisplayUpdateControl2, &[0xFF]),
+            // the old frame of the next quick refresh
+            cmd(Command::WriteRamRed, &new),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&std::vec![
+            digital::Transaction::get(digital::State::Low);
+            5
+        ]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&busy, &dc, &rst);
+
+        epd.update_old_frame(&mut spi, &old, &mut NoopDelay)
+            .unwrap();
+        epd.update_and_display_new_frame(&mut spi, &new, &mut NoopDelay)
+            .unwrap();
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
+
+    #[test]
+    fn quick_update_writes_old_frame() {
+        let new = std::vec![0xF0; buffer_len(WIDTH as usize, HEIGHT as usize)];
+        let traffic = [
+            cmd(Command::WriteRam, &new),
+            cmd(Command::MasterActivation, &[]),
+            cmd(Command::WriteRamRed, &new),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&[digital::Transaction::get(digital::State::Low)]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&busy, &dc, &rst);
+        epd.refresh = RefreshLut::Quick;
+
+        epd.update_and_display_frame(&mut spi, &new, &mut NoopDelay)
+            .unwrap();
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn update_single_achromatic_chunk() {
+        let chunk = Chunk::try_from(1).unwrap();
+        let mut white = BufferMonoDisplay2in13b::default();
+        white.clear(Color::White).unwrap();
+        let traffic = [
+            cmd(Command::SetRamXAddressStartEndPosition, &[0, 15]),
+            cmd(Command::SetRamYAddressStartEndPosition, &[63, 0, 125, 0]),
+            cmd(Command::SetRamXAddressCounter, &[0]),
+            cmd(Command::SetRamYAddressCounter, &[63, 0]),
+            cmd(Command::WriteRam, chunk_data(white.buffer(), chunk)),
+            // back to the whole display
+            cmd(Command::SetRamXAddressStartEndPosition, &[0, 15]),
+            cmd(Command::SetRamYAddressStartEndPosition, &[0, 0, 249, 0]),
+            cmd(Command::SetRamXAddressCounter, &[0]),
+            cmd(Command::SetRamYAddressCounter, &[0, 0]),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&[
+            digital::Transaction::get(digital::State::Low),
+            digital::Transaction::get(digital::State::Low),
+        ]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&busy, &dc, &rst);
+
+        epd.update_achromatic_chunk(&mut spi, &mut NoopDelay, chunk, |_, drawn| {
+            assert_eq!(drawn, chunk);
+            Ok(None)
+        })
+        .unwrap();
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn chromatic_buffered_uses_tricolor() {
+        use embedded_graphics::prelude::{Pixel, Point};
+
+        let mut traffic = Vec::new();
+        for chunk in Chunk::<BUFFER>::iter() {
+            let (start, end) = (chunk.y_offset(), chunk.y_offset() + chunk.height() - 1);
+            let mut data = std::vec![0x00; buffer_len(WIDTH as usize, chunk.height() as usize)];
+            if chunk.to_zero_indexed() == 1 {
+                // the first pixel of the chunk is red
+                data[0] = 0x80;
+            }
+            traffic.extend([
+                cmd(Command::SetRamXAddressStartEndPosition, &[0, 15]),
+                cmd(
+                    Command::SetRamYAddressStartEndPosition,
+                    &[start as u8, 0, end as u8, 0],
+                ),
+                cmd(Command::SetRamXAddressCounter, &[0]),
+                cmd(Command::SetRamYAddressCounter, &[start as u8, 0]),
+                cmd(Command::WriteRamRed, &data),
+            ]);
+        }
+        traffic.extend([
+            cmd(Command::SetRamXAddressStartEndPosition, &[0, 15]),
+            cmd(Command::SetRamYAddressStartEndPosition, &[0, 0, 249, 0]),
+            cmd(Command::SetRamXAddressCounter, &[0]),
+            cmd(Command::SetRamYAddressCounter, &[0, 0]),
+            cmd(Command::MasterActivation, &[]),
+        ]);
+        let (spi_expectations, dc_expectations) = expectations(&traffic.concat());
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&std::vec![
+            digital::Transaction::get(digital::State::Low);
+            6
+        ]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&busy, &dc, &rst);
+
+        epd.update_chromatic_buffered(&mut spi, &mut NoopDelay, |display, chunk| {
+            if chunk.to_zero_indexed() != 1 {
+                return Ok(None);
+            }
+            display.clear(TriColor::White)?;
+            display.set_pixel(Pixel(Point::new(0, 0), TriColor::Chromatic));
+            display.set_pixel(Pixel(Point::new(1, 0), TriColor::Black));
+            Ok(Some(()))
+        })
+        .unwrap();
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
+
+    #[test]
+    fn update_color_frame_from_iterators() {
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize);
+        let traffic = [
+            cmd(Command::WriteRam, &std::vec![0xAA; len]),
+            cmd(Command::WriteRamRed, &[1, 2, 3]),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&pin, &dc, &pin);
+
+        // endless and short iterators
+        epd.update_color_frame_iter(
+            &mut spi,
+            &mut NoopDelay,
+            core::iter::repeat(0xAA),
+            [1, 2, 3],
+        )
+        .unwrap();
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[test]
+    fn update_color_frame_from_frame_sources() {
+        use crate::frame::FnFrame;
+
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize);
+        let black = std::vec![0xF0; len];
+        let traffic = [
+            cmd(Command::WriteRam, &black),
+            cmd(Command::WriteRamRed, &std::vec![0x01; len]),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&pin, &dc, &pin);
+
+        let chromatic = FnFrame::new(len, |_| 0x01);
+        assert_eq!(
+            epd.update_color_frame_from(&mut spi, &mut NoopDelay, &black[1..], &chromatic),
+            Err(Error::WrongBufferSize {
+                expected: len,
+                got: len - 1
+            })
+        );
+        epd.update_color_frame_from(&mut spi, &mut NoopDelay, black.as_slice(), &chromatic)
+            .unwrap();
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[test]
+    fn update_frame_rle() {
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize);
+        let mut frame = std::vec![0xFF; len];
+        frame[100..140].fill(0x00);
+        let mut encoded = std::vec![0; rle::encoded_len(&frame)];
+        rle::encode(&frame, &mut encoded).unwrap();
+        let traffic = [
+            cmd(Command::WriteRam, &frame),
+            cmd(Command::WriteRamRed, &std::vec![0x00; len]),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&pin, &dc, &pin);
+
+        let truncated = &encoded[..encoded.len() - 1];
+        assert_eq!(
+            epd.update_frame_rle(&mut spi, &mut NoopDelay, truncated),
+            Err(Error::Rle(rle::RleError::Truncated))
+        );
+        // without the last run, of 20 bytes
+        assert_eq!(
+            epd.update_frame_rle(&mut spi, &mut NoopDelay, &encoded[..encoded.len() - 2]),
+            Err(Error::WrongBufferSize {
+                expected: len,
+                got: len - 20
+            })
+        );
+        epd.update_frame_rle(&mut spi, &mut NoopDelay, &encoded)
+            .unwrap();
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[test]
+    fn update_frame_from_source() {
+        use crate::frame::ReadStream;
+
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize);
+        let frame: Vec<u8> = (0..100).collect();
+        let traffic = [
+            // sent a chunk at a time, until the end of the stream
+            cmd(Command::WriteRam, &frame[..64]),
+            std::vec![(TrafficKind::Data, frame[64..].to_vec())],
+            cmd(Command::WriteRamRed, &std::vec![0x00; len]),
+            cmd(Command::WriteRam, &[]),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&pin, &dc, &pin);
+
+        epd.update_frame_from_source(&mut spi, &mut NoopDelay, &mut frame.as_slice())
+            .unwrap();
+        let mut failing = ReadStream::new(|_: &mut [u8]| Err("disconnected"));
+        assert_eq!(
+            epd.update_frame_from_source(&mut spi, &mut NoopDelay, &mut failing),
+            Err(StreamError::Source("disconnected"))
+        );
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[test]
+    fn update_frame_skips_cleared_red() {
+        use crate::testing::{Entry, Recorder};
+        use embedded_hal::digital::PinState;
+
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let mut epd = Epd2in13b::new(
+            &mut spi,
+            busy,
+            recorder.dc(),
+            recorder.rst(),
+            &mut delay,
+            None,
+        )
+        .unwrap();
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize);
+        let frame = std::vec![0xFF; len];
+        let red_writes = || {
+            recorder
+                .transcript()
+                .entries()
+                .iter()
+                .filter(|entry| **entry == Entry::Command(Command::WriteRamRed as u8))
+                .count()
+        };
+
+        recorder.clear();
+        epd.update_frame(&mut spi, &frame, &mut delay).unwrap();
+        epd.update_frame(&mut spi, &frame, &mut delay).unwrap();
+        assert_eq!(red_writes(), 1);
+
+        // chromatic pixels must be cleared again
+        recorder.clear();
+        epd.update_chromatic_frame(&mut spi, &mut delay, &frame)
+            .unwrap();
+        epd.update_frame(&mut spi, &frame, &mut delay).unwrap();
+        assert_eq!(red_writes(), 2);
+
+        // a white background leaves the chromatic RAM empty
+        recorder.clear();
+        epd.clear_frame(&mut spi, &mut delay).unwrap();
+        epd.update_frame(&mut spi, &frame, &mut delay).unwrap();
+        assert_eq!(red_writes(), 1);
+
+        // the RAMs may be lost in deep sleep
+        recorder.clear();
+        epd.sleep(&mut spi, &mut delay).unwrap();
+        epd.wake_up(&mut spi, &mut delay).unwrap();
+        epd.update_frame(&mut spi, &frame, &mut delay).unwrap();
+        assert_eq!(red_writes(), 1);
+    }
+
+    #[test]
+    fn update_frame_reports_clobbered_red() {
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize);
+        let black = std::vec![0xFF; len];
+        let chromatic = std::vec![0x0F; len];
+        let traffic = [
+            cmd(Command::WriteRamRed, &chromatic),
+            // keep_red
+            cmd(Command::WriteRam, &black),
+            cmd(Command::WriteRam, &black),
+            cmd(Command::WriteRamRed, &std::vec![0x00; len]),
+            // the chromatic RAM is still empty
+            cmd(Command::WriteRam, &black),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&pin, &dc, &pin);
+
+        epd.update_chromatic_frame(&mut spi, &mut NoopDelay, &chromatic)
+            .unwrap();
+        epd.set_keep_red(true);
+        epd.update_frame(&mut spi, &black, &mut NoopDelay).unwrap();
+        assert!(!epd.take_red_clobbered());
+
+        epd.set_keep_red(false);
+        epd.update_frame(&mut spi, &black, &mut NoopDelay).unwrap();
+        assert!(epd.take_red_clobbered());
+        assert!(!epd.take_red_clobbered());
+
+        // nothing chromatic left to overwrite
+        epd.update_frame(&mut spi, &black, &mut NoopDelay).unwrap();
+        assert!(!epd.take_red_clobbered());
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[test]
+    fn reset_durations_for_init_and_wake_up() {
+        use embedded_hal_mock::eh1::delay::{CheckedDelay, Transaction as DelayTransaction};
+
+        let (spi_expectations, dc_expectations) =
+            expectations(&[init_traffic(), init_traffic()].concat());
+        let reset = [
+            digital::Transaction::set(digital::State::High),
+            digital::Transaction::set(digital::State::Low),
+            digital::Transaction::set(digital::State::High),
+        ];
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&std::vec![
+            digital::Transaction::get(digital::State::Low);
+            8
+        ]);
+        let mut rst = digital::Mock::new(&[reset.clone(), reset].concat());
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_us(10_000),
+            DelayTransaction::delay_us(20_000),
+            DelayTransaction::delay_us(200_000),
+            DelayTransaction::delay_us(10_000),
+            DelayTransaction::delay_us(50_000),
+            DelayTransaction::delay_us(200_000),
+        ]);
+
+        let mut epd = Epd2in13bBuilder::new()
+            .cold_reset_us(20_000)
+            .resume_reset_us(50_000)
+            .build(&mut spi, busy.clone(), dc.clone(), rst.clone(), &mut delay)
+            .unwrap();
+        epd.wake_up(&mut spi, &mut delay).unwrap();
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+        delay.done();
+    }
+
+    #[test]
+    fn power_control() {
+        use crate::testing::{Entry, Recorder};
+        use embedded_hal::digital::PinState;
+
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let (dc, rst) = (recorder.dc(), recorder.rst());
+        let mut epd = Epd2in13b::new(&mut spi, busy, dc, rst, &mut delay, None).unwrap();
+        // update sequences sent, and whether they were activated right away
+        let sequences = || {
+            let transcript = recorder.transcript();
+            let entries = transcript.entries();
+            let update = Entry::Command(Command::DisplayUpdateControl2 as u8);
+            let activation = Entry::Command(Command::MasterActivation as u8);
+            let mut sequences = Vec::new();
+            for (i, entry) in entries.iter().enumerate() {
+                if let (true, Some(Entry::Data(data))) = (*entry == update, entries.get(i + 1)) {
+                    sequences.push((data[0], entries.get(i + 2) == Some(&activation)));
+                }
+            }
+            recorder.clear();
+            sequences
+        };
+
+        recorder.clear();
+        epd.power_off(&mut spi, &mut delay).unwrap();
+        assert_eq!(sequences(), []);
+
+        epd.power_on(&mut spi, &mut delay).unwrap();
+        assert_eq!(sequences(), [(POWER_ON, true), (0xFC, false)]);
+        epd.set_lut(&mut spi, &mut delay, Some(RefreshLut::Quick))
+            .unwrap();
+        assert_eq!(sequences(), [(0xCC, false)]);
+
+        epd.power_off(&mut spi, &mut delay).unwrap();
+        assert_eq!(sequences(), [(POWER_OFF, true), (QUICK_UPDATE, false)]);
+
+        // deep sleep turns the power off
+        epd.power_on(&mut spi, &mut delay).unwrap();
+        epd.sleep(&mut spi, &mut delay).unwrap();
+        recorder.clear();
+        epd.wake_up(&mut spi, &mut delay).unwrap();
+        assert_eq!(sequences(), [(QUICK_UPDATE, false)]);
+        epd.power_off(&mut spi, &mut delay).unwrap();
+        assert_eq!(sequences(), []);
+    }
+
+    #[test]
+    fn sleep_with_mode() {
+        let traffic = [
+            cmd(Command::DeepSleepMode, &[0x11]),
+            cmd(Command::DeepSleepMode, &[0x01]),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&pin, &dc, &pin);
+
+        epd.sleep_with_mode(&mut spi, &mut NoopDelay, DeepSleepMode::Mode2)
+            .unwrap();
+        epd.sleep(&mut spi, &mut NoopDelay).unwrap();
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[test]
+    fn border_color() {
+        let traffic = [
+            cmd(Command::BorderWaveformControl, &[0x01]),
+            cmd(Command::BorderWaveformControl, &[0x02]),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised::<NoopDelay>(&pin, &dc, &pin);
+
+        epd.set_border_color(&mut spi, TriColor::White).unwrap();
+        BorderColor::set_border_color(&mut epd, &mut spi, TriColor::Chromatic).unwrap();
+        // kept for the next initialisation while asleep
+        epd.asleep = true;
+        epd.set_border_color(&mut spi, TriColor::Black).unwrap();
+        assert_eq!(epd.border_waveform, 0x00);
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[test]
+    fn set_vcom() {
+        let traffic = cmd(Command::WriteVcomRegister, &[0x28]);
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised::<NoopDelay>(&pin, &dc, &pin);
+        assert_eq!(epd.vcom(), DEFAULT_VCOM);
+
+        epd.set_vcom(&mut spi, 0x28).unwrap();
+        assert_eq!(epd.vcom(), 0x28);
+        // only kept while asleep
+        epd.asleep = true;
+        epd.set_vcom(&mut spi, 0x30).unwrap();
+        assert_eq!(epd.vcom(), 0x30);
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[test]
+    fn ram_options() {
+        let traffic = [
+            cmd(Command::DisplayUpdateControl1, &[0x88, 0x80]),
+            cmd(Command::DisplayUpdateControl1, &[0x40, 0x80]),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised::<NoopDelay>(&pin, &dc, &pin);
+
+        epd.set_ram_options(&mut spi, RamOption::Inverse, RamOption::Inverse)
+            .unwrap();
+        epd.set_ram_options(&mut spi, RamOption::Normal, RamOption::BypassAs0)
+            .unwrap();
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[test]
+    fn release() {
+        use crate::testing::{Entry, Recorder};
+        use embedded_hal::digital::PinState;
+
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let mut epd = Epd2in13b::new(
+            &mut spi,
+            busy,
+            recorder.dc(),
+            recorder.rst(),
+            &mut delay,
+            None,
+        )
+        .unwrap();
+        epd.sleep(&mut spi, &mut delay).unwrap();
+        let (busy, dc, rst) = epd.release();
+
+        // the pins drive a new driver, which resets the display
+        recorder.clear();
+        Epd2in13b::new(&mut spi, busy, dc, rst, &mut delay, None).unwrap();
+        assert_eq!(
+            recorder.transcript().entries()[0],
+            Entry::Reset(PinState::High)
+        );
+    }
+
+    #[test]
+    fn refresh_stats() {
+        let traffic = [
+            cmd(Command::MasterActivation, &[]),
+            cmd(Command::MasterActivation, &[]),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let busy = |polls| {
+            let mut busy = std::vec![digital::Transaction::get(digital::State::High); polls];
+            busy.push(digital::Transaction::get(digital::State::Low));
+            busy
+        };
+        let mut busy = digital::Mock::new(&[busy(2), busy(1)].concat());
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&busy, &dc, &rst);
+
+        assert_eq!(epd.stats(), None);
+        epd.set_stats_enabled(true);
+        epd.display_frame(&mut spi, &mut NoopDelay).unwrap();
+        epd.refresh = RefreshLut::Quick;
+        epd.display_frame(&mut spi, &mut NoopDelay).unwrap();
+        assert_eq!(
+            epd.stats(),
+            Some(RefreshStats {
+                full_refreshes: 1,
+                partial_refreshes: 1,
+                last_busy_us: 10_000,
+                total_busy_us: 30_000,
+            })
+        );
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
+
+    #[test]
+    fn auto_sleep() {
+        use crate::testing::{Entry, Recorder};
+        use embedded_hal::digital::PinState;
+
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let mut epd = Epd2in13bBuilder::new()
+            .auto_sleep(true)
+            .build(&mut spi, busy, recorder.dc(), recorder.rst(), &mut delay)
+            .unwrap();
+        let frame = std::vec![0xFF; buffer_len(WIDTH as usize, HEIGHT as usize)];
+        let sleep = Entry::Command(Command::DeepSleepMode as u8);
+        let resets = |entries: &[Entry]| {
+            entries
+                .iter()
+                .filter(|entry| **entry == Entry::Reset(PinState::Low))
+                .count()
+        };
+
+        recorder.clear();
+        epd.update_and_display_frame(&mut spi, &frame, &mut delay)
+            .unwrap();
+        let transcript = recorder.transcript();
+        assert_eq!(resets(transcript.entries()), 0);
+        let entries = transcript.entries();
+        assert_eq!(entries[entries.len() - 2], sleep);
+        // deep sleep mode 1, 0x00 would keep the display awake
+        assert_eq!(entries[entries.len() - 1], Entry::Data(std::vec![0x01]));
+
+        // woken up before the next frame is written
+        recorder.clear();
+        epd.update_frame(&mut spi, &frame, &mut delay).unwrap();
+        let transcript = recorder.transcript();
+        assert_eq!(resets(transcript.entries()), 1);
+        assert!(!transcript.entries().contains(&sleep));
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+
+        epd.set_auto_sleep(false);
+        recorder.clear();
+        epd.update_frame(&mut spi, &frame, &mut delay).unwrap();
+        assert_eq!(resets(recorder.transcript().entries()), 0);
+    }
+
+    #[test]
+    fn normal_sleep_mode_stays_awake() {
+        use crate::testing::{Entry, Recorder};
+        use embedded_hal::digital::PinState;
+
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let mut epd = Epd2in13bBuilder::new()
+            .auto_sleep(true)
+            .build(&mut spi, busy, recorder.dc(), recorder.rst(), &mut delay)
+            .unwrap();
+        let frame = std::vec![0xFF; buffer_len(WIDTH as usize, HEIGHT as usize)];
+
+        epd.sleep_with_mode(&mut spi, &mut delay, DeepSleepMode::Normal)
+            .unwrap();
+        recorder.clear();
+        epd.update_frame(&mut spi, &frame, &mut delay).unwrap();
+        // not woken up by a reset
+        assert!(!recorder
+            .transcript()
+            .entries()
+            .contains(&Entry::Reset(PinState::Low)));
+    }
+
+    #[test]
+    fn reset_timing() {
+        use crate::testing::{Entry, Recorder};
+        use embedded_hal::digital::PinState;
+
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let pulse = |high_us: u64, low_us: u64| {
+            [
+                Entry::Reset(PinState::High),
+                Entry::Delay(high_us * 1000),
+                Entry::Reset(PinState::Low),
+                Entry::Delay(low_us * 1000),
+                Entry::Reset(PinState::High),
+                Entry::Delay(200_000_000),
+            ]
+        };
+
+        let mut epd = Epd2in13bBuilder::new()
+            .reset_high_us(5_000)
+            .build(&mut spi, busy, recorder.dc(), recorder.rst(), &mut delay)
+            .unwrap();
+        assert_eq!(recorder.transcript().entries()[..6], pulse(5_000, 10_000));
+
+        recorder.clear();
+        epd.set_reset_timing(30_000, 15_000);
+        epd.wake_up(&mut spi, &mut delay).unwrap();
+        assert_eq!(recorder.transcript().entries()[..6], pulse(30_000, 15_000));
+    }
+
+    #[test]
+    fn builder_registers() {
+        use crate::testing::{Entry, Recorder};
+        use embedded_hal::digital::PinState;
+
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let mut epd = Epd2in13bBuilder::new()
+            .background_color(TriColor::Chromatic)
+            .border_waveform(0x05)
+            .vcom(0x28)
+            .gate_voltage(GateVoltage::from_decivolts(150).unwrap())
+            .source_voltage(SourceVoltage::from_decivolts(140, 50, -140).unwrap())
+            .booster_soft_start(BoosterSoftStart::DEFAULT)
+            .auto_init(false)
+            .build(&mut spi, busy, recorder.dc(), recorder.rst(), &mut delay)
+            .unwrap();
+        assert!(recorder.transcript().entries().is_empty());
+        assert_eq!(epd.background_color(), &TriColor::Chromatic);
+
+        epd.wake_up(&mut spi, &mut delay).unwrap();
+        let transcript = recorder.transcript();
+        let register = |command: Command| {
+            let entries = transcript.entries();
+            let at = entries
+                .iter()
+                .position(|entry| *entry == Entry::Command(command as u8))
+                .unwrap();
+            entries[at + 1].clone()
+        };
+        assert_eq!(
+            register(Command::BorderWaveformControl),
+            Entry::Data(std::vec![0x05])
+        );
+        assert_eq!(
+            register(Command::WriteVcomRegister),
+            Entry::Data(std::vec![0x28])
+        );
+        assert_eq!(
+            register(Command::GateDrivingVoltageCtrl),
+            Entry::Data(std::vec![0x0D])
+        );
+        assert_eq!(
+            register(Command::SourceDrivingVoltageCtrl),
+            Entry::Data(std::vec![0x3C, 0xA8, 0x2E])
+        );
+        assert_eq!(
+            register(Command::BoosterSoftStartControl),
+            Entry::Data(std::vec![0x8B, 0x9C, 0x96, 0x0F])
+        );
+    }
+
+    #[test]
+    fn nonblocking_refresh() {
+        let (spi_expectations, dc_expectations) =
+            expectations(&cmd(Command::MasterActivation, &[]));
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&[
+            digital::Transaction::get(digital::State::High),
+            digital::Transaction::get(digital::State::High),
+            digital::Transaction::get(digital::State::Low),
+        ]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&busy, &dc, &rst);
+
+        epd.display_frame_nonblocking(&mut spi).unwrap();
+        assert!(epd.is_busy().unwrap());
+        epd.finish_refresh(&mut spi, &mut NoopDelay).unwrap();
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
+
+    #[test]
+    fn prepare_ram_writes() {
+        let traffic = [cmd(Command::WriteRam, &[]), cmd(Command::WriteRamRed, &[])].concat();
+        let (mut spi_expectations, mut dc_expectations) = expectations(&traffic);
+        // the frames are written by the user, with DC high
+        dc_expectations.insert(1, digital::Transaction::set(digital::State::High));
+        dc_expectations.push(digital::Transaction::set(digital::State::High));
+        spi_expectations.insert(3, spi::Transaction::write_vec(std::vec![0xFF; 4]));
+        spi_expectations.push(spi::Transaction::write_vec(std::vec![0x00; 4]));
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised::<NoopDelay>(&pin, &dc, &pin);
+
+        epd.prepare_ram_write(&mut spi).unwrap();
+        embedded_hal::spi::SpiBus::write(&mut spi, &[0xFF; 4]).unwrap();
+        epd.prepare_chromatic_ram_write(&mut spi).unwrap();
+        embedded_hal::spi::SpiBus::write(&mut spi, &[0x00; 4]).unwrap();
+        assert!(!epd.take_red_clobbered());
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[test]
+    fn clear_frame_fills_both_rams() {
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize);
+        let traffic = [
+            cmd(Command::WriteRam, &std::vec![0xFF; len]),
+            cmd(Command::WriteRamRed, &std::vec![0xFF; len]),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&pin, &dc, &pin);
+
+        epd.set_background_color(TriColor::Chromatic);
+        epd.clear_frame(&mut spi, &mut NoopDelay).unwrap();
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[test]
+    fn read_status() {
+        let (mut spi_expectations, mut dc_expectations) =
+            expectations(&cmd(Command::StatusBitRead, &[]));
+        spi_expectations.extend([
+            spi::Transaction::transaction_start(),
+            spi::Transaction::transfer(std::vec![], std::vec![0x23]),
+            spi::Transaction::transaction_end(),
+        ]);
+        dc_expectations.push(digital::Transaction::set(digital::State::High));
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised::<NoopDelay>(&pin, &dc, &pin);
+
+        assert_eq!(epd.read_status(&mut spi), Ok(0x23));
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[test]
+    fn temperature() {
+        let override_traffic = [
+            cmd(Command::TemperatureSensorWrite, &[0xFB, 0x00]),
+            cmd(Command::DisplayUpdateControl2, &[0xDF]),
+        ]
+        .concat();
+        let (mut spi_expectations, mut dc_expectations) = expectations(
+            &[
+                override_traffic.clone(),
+                cmd(Command::DisplayUpdateControl2, &[0xA1]),
+                cmd(Command::MasterActivation, &[]),
+                cmd(Command::TemperatureSensorRead, &[]),
+            ]
+            .concat(),
+        );
+        spi_expectations.extend([
+            spi::Transaction::transaction_start(),
+            spi::Transaction::transfer(std::vec![], std::vec![0x17, 0x80]),
+            spi::Transaction::transaction_end(),
+        ]);
+        dc_expectations.push(digital::Transaction::set(digital::State::High));
+        let (restore_spi, restore_dc) = expectations(&override_traffic);
+        spi_expectations.extend(restore_spi);
+        dc_expectations.extend(restore_dc);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&std::vec![
+            digital::Transaction::get(digital::State::Low);
+            4
+        ]);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&busy, &dc, &pin);
+
+        epd.set_temperature_override(&mut spi, &mut NoopDelay, Some(-5))
+            .unwrap();
+        assert_eq!(epd.read_temperature(&mut spi, &mut NoopDelay), Ok(23));
+
+        spi.done();
+        dc.done();
+        busy.done();
+        pin.done();
+    }
+
+    #[test]
+    fn temperature_luts() {
+        use crate::testing::{Entry, Recorder};
+        use embedded_hal::digital::PinState;
+
+        static LUTS: [TemperatureLut; 2] = [
+            TemperatureLut {
+                min: -20,
+                lut: [0x01; LUT_SIZE],
+            },
+            TemperatureLut {
+                min: 10,
+                lut: [0x02; LUT_SIZE],
+            },
+        ];
+
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let (dc, rst) = (recorder.dc(), recorder.rst());
+        let mut epd = Epd2in13b::new(&mut spi, busy, dc, rst, &mut delay, None).unwrap();
+        // first byte of the waveforms loaded
+        let loaded = || {
+            let transcript = recorder.transcript();
+            let entries = transcript.entries();
+            let write_lut = Entry::Command(Command::WriteLutRegister as u8);
+            let luts = entries
+                .windows(2)
+                .filter_map(|pair| match pair {
+                    [command, Entry::Data(data)] if *command == write_lut => Some(data[0]),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            recorder.clear();
+            luts
+        };
+
+        epd.set_temperature_luts(&mut spi, &mut delay, Some(&LUTS))
+            .unwrap();
+        recorder.clear();
+        // the sensor of the recorder reads 0°C
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+        assert_eq!(loaded(), [0x01]);
+
+        epd.set_temperature_override(&mut spi, &mut delay, Some(25))
+            .unwrap();
+        recorder.clear();
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+        let transcript = recorder.transcript();
+        let sensor_read = Entry::Command(Command::TemperatureSensorRead as u8);
+        assert!(!transcript.entries().contains(&sensor_read));
+        assert_eq!(loaded(), [0x02]);
+
+        // other refreshes keep their waveform
+        epd.set_lut(&mut spi, &mut delay, Some(RefreshLut::Fast))
+            .unwrap();
+        recorder.clear();
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+        assert_eq!(loaded(), []);
+
+        epd.set_lut(&mut spi, &mut delay, Some(RefreshLut::Full))
+            .unwrap();
+        epd.set_temperature_luts(&mut spi, &mut delay, None)
+            .unwrap();
+        recorder.clear();
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+        assert_eq!(loaded(), []);
+    }
+
+    /// Traffic reading `data` from the RAM, starting at `row`
+    fn read_ram_traffic(
+        chromatic: bool,
+        row: u8,
+        data: &[u8],
+    ) -> (
+        std::vec::Vec<spi::Transaction<u8>>,
+        std::vec::Vec<digital::Transaction>,
+    ) {
+        let (mut spi_expectations, mut dc_expectations) = expectations(
+            &[
+                cmd(Command::SetRamYAddressCounter, &[row, 0]),
+                cmd(Command::ReadRamOption, &[chromatic as u8]),
+                cmd(Command::ReadRam, &[]),
+            ]
+            .concat(),
+        );
+        spi_expectations.extend([
+            spi::Transaction::transaction_start(),
+            spi::Transaction::transfer(std::vec![], std::vec![0xA5]),
+            spi::Transaction::transfer(std::vec![], data.to_vec()),
+            spi::Transaction::transaction_end(),
+        ]);
+        dc_expectations.push(digital::Transaction::set(digital::State::High));
+        let (reset_spi, reset_dc) = expectations(
+            &[
+                cmd(Command::SetRamXAddressCounter, &[0]),
+                cmd(Command::SetRamYAddressCounter, &[0, 0]),
+            ]
+            .concat(),
+        );
+        spi_expectations.extend(reset_spi);
+        dc_expectations.extend(reset_dc);
+        (spi_expectations, dc_expectations)
+    }
+
+    #[test]
+    fn read_ram() {
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize);
+        let red: std::vec::Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let (spi_expectations, dc_expectations) = read_ram_traffic(true, 0, &red);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised::<NoopDelay>(&pin, &dc, &pin);
+
+        let mut buffer = std::vec![0; len];
+        epd.read_chromatic_ram(&mut spi, &mut buffer).unwrap();
+        assert_eq!(buffer, red);
+        assert_eq!(
+            epd.read_ram(&mut spi, &mut buffer[1..]),
+            Err(Error::WrongBufferSize {
+                expected: len,
+                got: len - 1
+            })
+        );
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[test]
+    fn gray_frame() {
+        // light gray pixels, the last byte of each line only holding 2 of them
+        let buffer = std::vec![0xAA; Controller::GRAY_FRAME_LEN];
+        let line = |high: u8| [[high; 15].as_slice(), &[high & 0xF0]].concat();
+        let traffic = [
+            cmd(Command::SetRamXAddressCounter, &[0]),
+            cmd(Command::SetRamYAddressCounter, &[0, 0]),
+            cmd(Command::WriteRam, &line(0xFF).repeat(HEIGHT as usize)),
+            cmd(Command::SetRamXAddressCounter, &[0]),
+            cmd(Command::SetRamYAddressCounter, &[0, 0]),
+            cmd(Command::WriteRamRed, &line(0x00).repeat(HEIGHT as usize)),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&[
+            digital::Transaction::get(digital::State::Low),
+            digital::Transaction::get(digital::State::Low),
+        ]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd = uninitialised::<NoopDelay>(&busy, &dc, &rst);
+
+        epd.update_gray_frame(&mut spi, &mut NoopDelay, &buffer)
+            .unwrap();
+        assert_eq!(
+            epd.update_gray_frame(&mut spi, &mut NoopDelay, &buffer[1..]),
+            Err(Error::WrongBufferSize {
+                expected: 31 * 250,
+                got: 31 * 250 - 1
+            })
+        );
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
+
+    #[test]
+    fn verify_writes() {
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize);
+        let black = std::vec![0xF0; len];
+        let (mut spi_expectations, mut dc_expectations) =
+            expectations(&cmd(Command::WriteRam, &black));
+        // the second block of rows comes back with a flipped bit
+        let (read_spi, read_dc) = read_ram_traffic(false, 0, &black[..64]);
+        spi_expectations.extend(read_spi);
+        dc_expectations.extend(read_dc);
+        let mut corrupted = black[64..128].to_vec();
+        corrupted[3] ^= 0x04;
+        let (read_spi, read_dc) = read_ram_traffic(false, 4, &corrupted);
+        spi_expectations.extend(read_spi);
+        dc_expectations.extend(read_dc);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut pin = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&pin, &dc, &pin);
+        epd.set_verify_writes(true);
+
+        assert_eq!(
+            epd.update_achromatic_frame(&mut spi, &mut NoopDelay, &black),
+            Err(Error::VerifyMismatch)
+        );
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[test]
+    fn busy_timeout() {
+        let mut spi = spi::Mock::new(&[]);
+        let mut pin = digital::Mock::new(&[]);
+        // polled after 0, 10ms, 20ms and 30ms with the default 10ms delay
+        let mut busy = digital::Mock::new(&std::vec![
+            digital::Transaction::get(digital::State::High);
+            4
+        ]);
+        let mut epd = uninitialised(&busy, &pin, &pin);
+        epd.set_busy_timeout(Some(25_000));
+
+        assert_eq!(
+            epd.wait_until_idle(&mut spi, &mut NoopDelay),
+            Err(Error::BusyTimeout)
+        );
+
+        spi.done();
+        pin.done();
+        busy.done();
+    }
+
+    #[test]
+    fn poll_backoff() {
+        use embedded_hal_mock::eh1::delay::{CheckedDelay, Transaction as DelayTransaction};
+
+        let mut spi = spi::Mock::new(&[]);
+        let mut pin = digital::Mock::new(&[]);
+        let mut busy = digital::Mock::new(
+            &[
+                std::vec![digital::Transaction::get(digital::State::High); 4],
+                std::vec![digital::Transaction::get(digital::State::Low)],
+            ]
+            .concat(),
+        );
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_us(20_000),
+            DelayTransaction::delay_us(40_000),
+            DelayTransaction::delay_us(50_000),
+            DelayTransaction::delay_us(50_000),
+        ]);
+        let mut epd = uninitialised(&busy, &pin, &pin);
+        epd.set_poll_interval(20_000, Some(50_000));
+
+        epd.wait_until_idle(&mut spi, &mut delay).unwrap();
+
+        spi.done();
+        pin.done();
+        busy.done();
+        delay.done();
+    }
+
+    #[test]
+    fn busy_yield() {
+        use core::sync::atomic::{AtomicU32, Ordering};
+        static YIELDS: AtomicU32 = AtomicU32::new(0);
+        static WAITED_US: AtomicU32 = AtomicU32::new(0);
+        fn busy_yield(waited_us: u32) {
+            YIELDS.fetch_add(1, Ordering::Relaxed);
+            WAITED_US.store(waited_us, Ordering::Relaxed);
+        }
+
+        let mut spi = spi::Mock::new(&[]);
+        let mut pin = digital::Mock::new(&[]);
+        let mut busy = digital::Mock::new(&[
+            digital::Transaction::get(digital::State::High),
+            digital::Transaction::get(digital::State::High),
+            digital::Transaction::get(digital::State::Low),
+        ]);
+        let mut epd = uninitialised(&busy, &pin, &pin);
+        epd.set_busy_yield(Some(busy_yield));
+
+        epd.wait_until_idle(&mut spi, &mut NoopDelay).unwrap();
+        // called while busy only, before each delay
+        assert_eq!(YIELDS.load(Ordering::Relaxed), 2);
+        assert_eq!(WAITED_US.load(Ordering::Relaxed), 10_000);
+
+        spi.done();
+        pin.done();
+        busy.done();
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn replace_black_with_chromatic() {
+        use embedded_graphics::{
+            prelude::*,
+            primitives::{PrimitiveStyle, Rectangle},
+        };
+
+        let mut display = Display2in13b::default();
+        display.clear(TriColor::White).unwrap();
+        Rectangle::new(Point::new(8, 1), Size::new(16, 2))
+            .into_styled(PrimitiveStyle::with_fill(TriColor::Black))
+            .draw(&mut display)
+            .unwrap();
+
+        display.replace_color(TriColor::Black, TriColor::Chromatic);
+
+        let line = buffer_len(WIDTH as usize, 1);
+        for row in 0..HEIGHT as usize {
+            for col in 0..line {
+                let (bw, chromatic) = if (1..3).contains(&row) && (1..3).contains(&col) {
+                    (0xFF, 0xFF)
+                } else if col == line - 1 {
+                    // only the two first bits of the last byte are pixels
+                    (0xC0, 0x00)
+                } else {
+                    (0xFF, 0x00)
+                };
+                assert_eq!(display.bw_buffer()[row * line + col], bw);
+                assert_eq!(display.chromatic_buffer()[row * line + col], chromatic);
+            }
+        }
+
+        // and back again
+        display.replace_color(TriColor::Chromatic, TriColor::Black);
+        assert_eq!(display.bw_buffer()[line + 1], 0x00);
+        assert_eq!(display.chromatic_buffer()[line + 1], 0x00);
+    }
+
+    #[test]
+    fn golden_full_update() {
+        use crate::testing::Recorder;
+        use embedded_hal::digital::PinState;
+
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let mut epd = Epd2in13b::new(
+            &mut spi,
+            busy,
+            recorder.dc(),
+            recorder.rst(),
+            &mut delay,
+            None,
+        )
+        .unwrap();
+
+        let mut frame = std::vec![0xFF; buffer_len(WIDTH as usize, HEIGHT as usize)];
+        frame[..16].fill(0x00);
+        frame[100] = 0x5A;
+        epd.update_and_display_frame(&mut spi, &frame, &mut delay)
+            .unwrap();
+        epd.sleep(&mut spi, &mut delay).unwrap();
+
+        recorder.transcript().assert_golden(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/golden/epd2in13b_v4_full_update.txt"
+        ));
+    }
 }
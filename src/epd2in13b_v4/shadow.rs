@@ -0,0 +1,225 @@
+//! Copy of the black/white RAM of [`Epd2in13b`], to only send what changed
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{InputPin, OutputPin},
+    spi::SpiDevice,
+};
+
+use super::command::Command;
+use super::{Epd2in13b, HEIGHT, WIDTH};
+use crate::buffer_len;
+use crate::error::Error;
+use crate::rect::Rect;
+
+/// Bytes of a row of the RAM
+const LINE: usize = buffer_len(WIDTH as usize, 1);
+/// Bytes of the RAM
+const LEN: usize = buffer_len(WIDTH as usize, HEIGHT as usize);
+
+/// What was last written to the black/white RAM by
+/// [`update_frame_diff`](Epd2in13b::update_frame_diff)
+///
+/// The shadow only holds as long as nothing else writes to the RAM: any other frame update, a
+/// clear or a reset (e.g. a wake up) makes the next `update_frame_diff` send the whole frame
+/// again.
+#[derive(Clone)]
+pub struct RamShadow {
+    ram: [u8; LEN],
+    /// generation of the RAM of the driver when `ram` was written, `None` until then
+    generation: Option<u32>,
+}
+
+impl RamShadow {
+    /// Creates an empty shadow: the first [`update_frame_diff`](Epd2in13b::update_frame_diff)
+    /// sends the whole frame.
+    pub const fn new() -> Self {
+        RamShadow {
+            ram: [0; LEN],
+            generation: None,
+        }
+    }
+
+    /// Content of the RAM, if the shadow was written
+    pub fn ram(&self) -> Option<&[u8]> {
+        self.generation.map(|_| &self.ram[..])
+    }
+
+    /// Forgets the content of the RAM, e.g. after writing to it through another driver
+    pub fn invalidate(&mut self) {
+        self.generation = None;
+    }
+
+    /// Windows holding the bytes of `buffer` which differ from the shadow: one for each group of
+    /// consecutive changed rows, as wide as their changes. `x` and `w` are multiples of 8.
+    pub fn changes<'a>(&'a self, buffer: &'a [u8]) -> impl Iterator<Item = Rect> + 'a {
+        let rows = self.ram.chunks(LINE).zip(buffer.chunks(LINE));
+        let mut changed_rows = rows.enumerate().map(|(y, (old, new))| {
+            let first = old.iter().zip(new).position(|(old, new)| old != new)?;
+            let last = old.iter().zip(new).rposition(|(old, new)| old != new)?;
+            Some((y, first, last))
+        });
+
+        core::iter::from_fn(move || {
+            // skip to the first changed row, then extend the window until an unchanged one
+            let (y, mut first, mut last) = changed_rows.by_ref().flatten().next()?;
+            let mut height = 1;
+            while let Some(Some((_, row_first, row_last))) = changed_rows.next() {
+                first = first.min(row_first);
+                last = last.max(row_last);
+                height += 1;
+            }
+            Some(Rect::new(
+                first as u32 * 8,
+                y as u32,
+                (last - first + 1) as u32 * 8,
+                height,
+            ))
+        })
+    }
+}
+
+impl Default for RamShadow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Epd2in13b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Same as [`update_frame`](crate::traits::WaveshareDisplay::update_frame), but only sends
+    /// the rows which differ from `shadow`, through RAM windows. The shadow is then updated.
+    ///
+    /// The whole frame is sent if the shadow doesn't hold the content of the RAM, e.g. the first
+    /// time. Returns whether anything was sent: if not, the display doesn't need a refresh.
+    pub fn update_frame_diff(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        shadow: &mut RamShadow,
+    ) -> Result<bool, Error<SPI::Error>> {
+        self.wake_if_asleep(spi, delay)?;
+        Error::check_len(buffer, LEN)?;
+
+        if shadow.generation == Some(self.bw_ram_generation) {
+            let mut changes = shadow.changes(buffer).peekable();
+            if changes.peek().is_none() {
+                return Ok(false);
+            }
+            for window in changes {
+                let row = (window.w / 8) as usize;
+                let index = |i: usize| {
+                    (window.y as usize + i / row) * LINE + window.x as usize / 8 + i % row
+                };
+                let (x, y) = (window.x, window.y);
+                self.set_ram_area(spi, x, y, x + window.w - 1, y + window.h - 1)?;
+                self.set_ram_address_counters(spi, delay, x, y)?;
+                self.command(spi, Command::WriteRam)?;
+                self.interface
+                    .data_with(spi, |i| buffer[index(i)], row * window.h as usize)?;
+            }
+            self.set_full_window(spi, delay)?;
+            self.verify_ram(spi, false, buffer)?;
+            self.blank_red_ram(spi)?;
+        } else {
+            use crate::traits::WaveshareDisplay;
+            self.update_frame(spi, buffer, delay)?;
+        }
+
+        shadow.ram.copy_from_slice(buffer);
+        shadow.generation = Some(self.bw_ram_generation);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{cmd, expectations, uninitialised, Traffic};
+    use super::*;
+    use embedded_hal_mock::eh1::{delay::NoopDelay, digital, spi};
+
+    extern crate std;
+
+    #[test]
+    fn changes_of_separate_rows() {
+        let mut shadow = RamShadow::new();
+        assert_eq!(shadow.ram(), None);
+        shadow.generation = Some(0);
+        let mut buffer = [0; LEN];
+        assert_eq!(shadow.changes(&buffer).next(), None);
+
+        buffer[10 * LINE + 3] = 0xFF;
+        buffer[11 * LINE + 1] = 0xFF;
+        buffer[200 * LINE + 15] = 0x01;
+        let changes: std::vec::Vec<_> = shadow.changes(&buffer).collect();
+        assert_eq!(
+            changes,
+            [Rect::new(8, 10, 24, 2), Rect::new(120, 200, 8, 1)]
+        );
+    }
+
+    #[test]
+    fn update_frame_diff() {
+        let mut frame = [0xFF; LEN];
+        let mut shadow = RamShadow::new();
+        let traffic: Traffic = [
+            // the whole frame the first time
+            cmd(Command::WriteRam, &frame),
+            cmd(Command::WriteRamRed, &[0x00; LEN]),
+            // then the changed rows
+            cmd(Command::SetRamXAddressStartEndPosition, &[2, 3]),
+            cmd(Command::SetRamYAddressStartEndPosition, &[50, 0, 51, 0]),
+            cmd(Command::SetRamXAddressCounter, &[2]),
+            cmd(Command::SetRamYAddressCounter, &[50, 0]),
+            cmd(Command::WriteRam, &[0x00, 0xFF, 0xFF, 0x0F]),
+            cmd(Command::SetRamXAddressStartEndPosition, &[0, 15]),
+            cmd(Command::SetRamYAddressStartEndPosition, &[0, 0, 249, 0]),
+            cmd(Command::SetRamXAddressCounter, &[0]),
+            cmd(Command::SetRamYAddressCounter, &[0, 0]),
+            // and the whole frame again once the RAM was written by something else
+            cmd(Command::WriteRam, &[0xAA; LEN]),
+            cmd(Command::WriteRam, &[0xAA; LEN]),
+        ]
+        .concat();
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&std::vec![
+            digital::Transaction::get(digital::State::Low);
+            2
+        ]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd = uninitialised(&busy, &dc, &rst);
+
+        let sent = epd.update_frame_diff(&mut spi, &mut NoopDelay, &frame, &mut shadow);
+        assert_eq!(sent, Ok(true));
+        assert_eq!(
+            epd.update_frame_diff(&mut spi, &mut NoopDelay, &frame, &mut shadow),
+            Ok(false)
+        );
+
+        frame[50 * LINE + 2] = 0x00;
+        frame[51 * LINE + 3] = 0x0F;
+        let sent = epd.update_frame_diff(&mut spi, &mut NoopDelay, &frame, &mut shadow);
+        assert_eq!(sent, Ok(true));
+        assert_eq!(shadow.ram(), Some(&frame[..]));
+
+        use crate::traits::WaveshareDisplay;
+        let frame = [0xAA; LEN];
+        epd.update_frame(&mut spi, &frame, &mut NoopDelay).unwrap();
+        let sent = epd.update_frame_diff(&mut spi, &mut NoopDelay, &frame, &mut shadow);
+        assert_eq!(sent, Ok(true));
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
+}
@@ -0,0 +1,65 @@
+//! Command sequences written once for the blocking and the async drivers
+//!
+//! Each macro expands to the body of a method of both drivers. The last argument is empty
+//! for the blocking driver and `.await` for the async one: both interfaces have the same
+//! `cmd`, `cmd_with_data` and `data_x_times` methods, so the sequences can't drift apart.
+
+/// Writes the border, voltages, booster and display update control registers, at the end of
+/// `init`
+macro_rules! init_registers {
+    ($epd:expr, $spi:expr, [$($await:tt)*]) => {{
+        let border = $epd.border_waveform;
+        $epd.interface
+            .cmd_with_data($spi, Command::BorderWaveformControl, &[border])$($await)*?;
+
+        let vcom = $epd.vcom;
+        $epd.interface
+            .cmd_with_data($spi, Command::WriteVcomRegister, &[vcom])$($await)*?;
+        let gate = $epd.gate_voltage.to_raw();
+        $epd.interface
+            .cmd_with_data($spi, Command::GateDrivingVoltageCtrl, &[gate])$($await)*?;
+        let source = $epd.source_voltage.to_raw();
+        $epd.interface
+            .cmd_with_data($spi, Command::SourceDrivingVoltageCtrl, &source)$($await)*?;
+        if let Some(booster) = $epd.booster_soft_start {
+            $epd.interface
+                .cmd_with_data($spi, Command::BoosterSoftStartControl, &booster.to_raw())$($await)*?;
+        }
+
+        let update_control = DisplayUpdateControl {
+            red_ram_option: $epd.red_ram_option,
+            bw_ram_option: $epd.bw_ram_option,
+            source_output_mode: true,
+        };
+        let update_control = update_control.to_bytes();
+        $epd.interface
+            .cmd_with_data($spi, Command::DisplayUpdateControl1, &update_control)$($await)*?;
+    }};
+}
+pub(super) use init_registers;
+
+/// Fills both RAMs with the background color
+macro_rules! clear_frame {
+    ($epd:expr, $spi:expr, [$($await:tt)*]) => {{
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize) as u32;
+        let (black, chromatic) = match $epd.background_color {
+            TriColor::White => (0xFF, 0x00),
+            TriColor::Chromatic => (0xFF, 0xFF),
+            TriColor::Black => (0x00, 0x00),
+        };
+        $epd.interface.cmd($spi, Command::WriteRam)$($await)*?;
+        $epd.interface.data_x_times($spi, black, len)$($await)*?;
+        $epd.interface.cmd($spi, Command::WriteRamRed)$($await)*?;
+        $epd.interface.data_x_times($spi, chromatic, len)$($await)*
+    }};
+}
+pub(super) use clear_frame;
+
+/// Lets the display enter the deep sleep `mode`
+macro_rules! sleep {
+    ($epd:expr, $spi:expr, $mode:expr, [$($await:tt)*]) => {
+        $epd.interface
+            .cmd_with_data($spi, Command::DeepSleepMode, &[$mode as u8])$($await)*
+    };
+}
+pub(super) use sleep;
@@ -173,9 +173,12 @@ use embedded_hal::{
 };
 
 use crate::color::TriColor;
-use crate::interface::DisplayInterface;
+use crate::controllers::ssd1680::Ssd1680;
+use crate::error::Error;
+use crate::interface::{BusyYield, DisplayInterface};
 use crate::traits::{
-    InternalWiAdditions, RefreshLut, WaveshareDisplay, WaveshareThreeColorDisplay,
+    DeepSleepMode, InternalWiAdditions, PartialColorUpdate, PartialUpdate, Sleepable,
+    WaveshareDisplay, WaveshareThreeColorDisplay,
 };
 
 pub(crate) mod command;
@@ -189,6 +192,9 @@ pub const HEIGHT: u32 = 296;
 
 const SINGLE_BYTE_WRITE: bool = true;
 
+/// RAM addressing shared with the SSD1680 panels
+type Controller = Ssd1680<WIDTH, HEIGHT>;
+
 /// White, display this during long-term storage
 pub const DEFAULT_BACKGROUND_COLOR: TriColor = TriColor::White;
 
@@ -208,6 +214,57 @@ pub struct Epd2in66b<SPI, BUSY, DC, RST, DELAY> {
     background: TriColor,
 }
 
+impl<SPI, BUSY, DC, RST, DELAY> Epd2in66b<SPI, BUSY, DC, RST, DELAY> {
+    /// Gives back the busy, data/command and reset pins, e.g. to reuse them once the display
+    /// is asleep
+    ///
+    /// The display keeps its state, so a driver can be created again later with the pins.
+    pub fn release(self) -> (BUSY, DC, RST) {
+        self.interface.release()
+    }
+
+    /// Gives up waiting for the display with [`Error::BusyTimeout`] once it has been busy for
+    /// `timeout_us`, e.g. because of a loose cable, instead of waiting forever. `None` removes
+    /// the timeout.
+    ///
+    /// The time is counted by adding up the delays of the idle loop, each poll of the busy pin
+    /// counting as at least 1us.
+    pub fn set_busy_timeout(&mut self, timeout_us: Option<u32>) {
+        self.interface.set_busy_timeout(timeout_us);
+    }
+
+    /// Polls the busy pin every `interval_us` while the display is busy (0 busy waits), or,
+    /// with a `max_interval_us`, backs off: the interval doubles after every poll until
+    /// `max_interval_us`
+    ///
+    /// The interval is the `delay_us` given to `new` (10ms by default) until this is called.
+    pub fn set_poll_interval(&mut self, interval_us: u32, max_interval_us: Option<u32>) {
+        self.interface.set_poll_interval(interval_us);
+        self.interface.set_poll_backoff(max_interval_us);
+    }
+
+    /// Sets a [`BusyYield`] called on every poll of the busy pin while the display is busy, e.g.
+    /// to feed a watchdog during a refresh. `None` removes it.
+    pub fn set_busy_yield(&mut self, busy_yield: Option<BusyYield>) {
+        self.interface.set_busy_yield(busy_yield);
+    }
+
+    /// Time the display was busy during the last wait for it, counted like the busy timeout,
+    /// e.g. to choose the timeout
+    pub fn last_wait_us(&self) -> u32 {
+        self.interface.last_wait_us()
+    }
+
+    /// CRC-32 of the data of the last frame written to the display, e.g. by
+    /// [`update_frame`](WaveshareDisplay::update_frame), to compare with the
+    /// [`crc32`](crate::crc::crc32) of the expected image and detect a corrupted transfer. `None`
+    /// until a frame is written. The chromatic frame isn't covered.
+    #[cfg(any(feature = "crc", test))]
+    pub fn last_frame_crc(&self) -> Option<u32> {
+        self.interface.last_crc()
+    }
+}
+
 impl<SPI, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, BUSY, DC, RST, DELAY>
     for Epd2in66b<SPI, BUSY, DC, RST, DELAY>
 where
@@ -217,21 +274,19 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         // We follow the sequence of the Pi-Pico hat example code.
         self.hw_reset(delay)?;
         self.sw_reset(spi, delay)?;
         self.data_entry_mode(spi, DataEntryRow::XMinor, DataEntrySign::IncYIncX)?;
-        self.set_display_window(spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
+        Controller::set_ram_area(&mut self.interface, spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
         self.update_control1(
             spi,
             WriteMode::Normal,
             WriteMode::Normal,
             OutputSource::S8ToS167,
         )?;
-        self.set_cursor(spi, 0, 0)?;
-
-        Ok(())
+        Controller::set_ram_address_counters(&mut self.interface, spi, delay, 0, 0)
     }
 }
 
@@ -250,7 +305,9 @@ where
         delay: &mut DELAY,
         black: &[u8],
         chromatic: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        // check both buffers before writing anything
+        Error::check_len(chromatic, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         self.update_achromatic_frame(spi, delay, black)?;
         self.update_chromatic_frame(spi, delay, chromatic)
     }
@@ -258,10 +315,11 @@ where
     fn update_achromatic_frame(
         &mut self,
         spi: &mut SPI,
-        _delay: &mut DELAY,
+        delay: &mut DELAY,
         black: &[u8],
-    ) -> Result<(), SPI::Error> {
-        self.set_cursor(spi, 0, 0)?;
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(black, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        Controller::set_ram_address_counters(&mut self.interface, spi, delay, 0, 0)?;
         self.interface.cmd(spi, Command::WriteBlackWhiteRAM)?;
         self.interface.data(spi, black)
     }
@@ -269,15 +327,49 @@ where
     fn update_chromatic_frame(
         &mut self,
         spi: &mut SPI,
-        _delay: &mut DELAY,
+        delay: &mut DELAY,
         chromatic: &[u8],
-    ) -> Result<(), SPI::Error> {
-        self.set_cursor(spi, 0, 0)?;
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(chromatic, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        Controller::set_ram_address_counters(&mut self.interface, spi, delay, 0, 0)?;
         self.interface.cmd(spi, Command::WriteRedRAM)?;
         self.interface.data(spi, chromatic)
     }
 }
 
+impl<SPI, BUSY, DC, RST, DELAY> Sleepable<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in66b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.sleep_with_mode(spi, delay, DeepSleepMode::Mode2)
+    }
+
+    fn sleep_with_mode(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+        mode: DeepSleepMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        let mode = match mode {
+            DeepSleepMode::Normal => DeepSleep::Awake,
+            DeepSleepMode::Mode1 => DeepSleep::SleepKeepingRAM,
+            DeepSleepMode::Mode2 => DeepSleep::SleepLosingRAM,
+        };
+        self.interface
+            .cmd_with_data(spi, Command::DeepSleepMode, &[mode as u8])
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+}
+
 impl<SPI, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
     for Epd2in66b<SPI, BUSY, DC, RST, DELAY>
 where
@@ -296,7 +388,7 @@ where
         rst: RST,
         delay: &mut DELAY,
         delay_us: Option<u32>,
-    ) -> Result<Self, SPI::Error>
+    ) -> Result<Self, Error<SPI::Error>>
     where
         Self: Sized,
     {
@@ -304,22 +396,12 @@ where
             interface: DisplayInterface::new(busy, dc, rst, delay_us),
             background: DEFAULT_BACKGROUND_COLOR,
         };
+        #[cfg(any(feature = "crc", test))]
+        epd.interface.track_crc(Command::WriteBlackWhiteRAM);
         epd.init(spi, delay)?;
         Ok(epd)
     }
 
-    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.interface.cmd_with_data(
-            spi,
-            Command::DeepSleepMode,
-            &[DeepSleep::SleepLosingRAM as u8],
-        )
-    }
-
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.init(spi, delay)
-    }
-
     fn set_background_color(&mut self, color: Self::DisplayColor) {
         self.background = color;
     }
@@ -341,29 +423,12 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
-        self.set_cursor(spi, 0, 0)?;
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_achromatic_frame(spi, delay, buffer)?;
         self.red_pattern(spi, delay, PatW::W160, PatH::H296, StartWith::Zero) // do NOT consider background here since red overrides other colors
     }
 
-    fn update_partial_frame(
-        &mut self,
-        spi: &mut SPI,
-        delay: &mut DELAY,
-        buffer: &[u8],
-        x: u32,
-        y: u32,
-        width: u32,
-        height: u32,
-    ) -> Result<(), SPI::Error> {
-        self.set_display_window(spi, x, y, x + width, y + height)?;
-        self.set_cursor(spi, x, y)?;
-        self.update_achromatic_frame(spi, delay, buffer)?;
-        self.set_display_window(spi, 0, 0, WIDTH, HEIGHT)
-    }
-
-    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd(spi, Command::MasterActivation)?;
         self.wait_until_idle(delay)
     }
@@ -373,12 +438,12 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, buffer, delay)?;
         self.display_frame(spi, delay)
     }
 
-    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         let (white, red) = match self.background {
             TriColor::Black => (StartWith::Zero, StartWith::Zero),
             TriColor::White => (StartWith::One, StartWith::Zero),
@@ -388,17 +453,90 @@ where
         self.red_pattern(spi, delay, PatW::W160, PatH::H296, red)
     }
 
-    fn set_lut(
+    fn wait_until_idle(
         &mut self,
         _spi: &mut SPI,
-        _delay: &mut DELAY,
-        _refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
-        Ok(())
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(delay)
     }
+}
 
-    fn wait_until_idle(&mut self, _spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.wait_until_idle(delay)
+impl<SPI, BUSY, DC, RST, DELAY> PartialUpdate<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in66b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(width as usize, height as usize))?;
+        Controller::set_ram_area(
+            &mut self.interface,
+            spi,
+            x,
+            y,
+            x + width - 1,
+            y + height - 1,
+        )?;
+        Controller::set_ram_address_counters(&mut self.interface, spi, delay, x, y)?;
+        self.interface.cmd(spi, Command::WriteBlackWhiteRAM)?;
+        self.interface.data(spi, buffer)?;
+        Controller::set_full_window(&mut self.interface, spi, delay)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> PartialColorUpdate<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in66b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    #[allow(clippy::too_many_arguments)]
+    fn update_partial_color_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        black: &[u8],
+        chromatic: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        let expected = buffer_len(width as usize, height as usize);
+        Error::check_len(black, expected)?;
+        Error::check_len(chromatic, expected)?;
+        Error::check_aligned(x, width)?;
+        Controller::set_ram_area(
+            &mut self.interface,
+            spi,
+            x,
+            y,
+            x + width - 1,
+            y + height - 1,
+        )?;
+        Controller::set_ram_address_counters(&mut self.interface, spi, delay, x, y)?;
+        self.interface.cmd(spi, Command::WriteBlackWhiteRAM)?;
+        self.interface.data(spi, black)?;
+        Controller::set_ram_address_counters(&mut self.interface, spi, delay, x, y)?;
+        self.interface.cmd(spi, Command::WriteRedRAM)?;
+        self.interface.data(spi, chromatic)?;
+        Controller::set_full_window(&mut self.interface, spi, delay)
     }
 }
 
@@ -411,16 +549,16 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    fn wait_until_idle(&mut self, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.interface.wait_until_idle(delay, false);
+    fn wait_until_idle(&mut self, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_until_idle(delay, false)?;
         Ok(())
     }
-    fn hw_reset(&mut self, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn hw_reset(&mut self, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         // The initial delay is taken from other code here, the 2 ms comes from the SSD1675B datasheet.
-        self.interface.reset(delay, 20_000, 2_000);
+        self.interface.reset(delay, 20_000, 2_000)?;
         self.wait_until_idle(delay)
     }
-    fn sw_reset(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn sw_reset(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd(spi, Command::Reset)?;
         self.wait_until_idle(delay)
     }
@@ -429,41 +567,17 @@ where
         spi: &mut SPI,
         row: DataEntryRow,
         sign: DataEntrySign,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.interface
             .cmd_with_data(spi, Command::DataEntryMode, &[row as u8 | sign as u8])
     }
-    fn set_display_window(
-        &mut self,
-        spi: &mut SPI,
-        xstart: u32,
-        ystart: u32,
-        xend: u32,
-        yend: u32,
-    ) -> Result<(), SPI::Error> {
-        self.interface.cmd_with_data(
-            spi,
-            Command::SetXAddressRange,
-            &[(((xstart >> 3) & 0x1f) as u8), (((xend >> 3) & 0x1f) as u8)],
-        )?;
-        self.interface.cmd_with_data(
-            spi,
-            Command::SetYAddressRange,
-            &[
-                ((ystart & 0xff) as u8),
-                (((ystart >> 8) & 0x01) as u8),
-                ((yend & 0xff) as u8),
-                (((yend >> 8) & 0x01) as u8),
-            ],
-        )
-    }
     fn update_control1(
         &mut self,
         spi: &mut SPI,
         red_mode: WriteMode,
         bw_mode: WriteMode,
         source: OutputSource,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd_with_data(
             spi,
             Command::DisplayUpdateControl1,
@@ -471,19 +585,6 @@ where
         )
     }
 
-    fn set_cursor(&mut self, spi: &mut SPI, x: u32, y: u32) -> Result<(), SPI::Error> {
-        self.interface.cmd_with_data(
-            spi,
-            Command::SetXAddressCounter,
-            &[((x >> 3) & 0x1f) as u8],
-        )?;
-        self.interface.cmd_with_data(
-            spi,
-            Command::SetYAddressCounter,
-            &[((y & 0xff) as u8), (((y >> 8) & 0x01) as u8)],
-        )
-    }
-
     fn black_white_pattern(
         &mut self,
         spi: &mut SPI,
@@ -491,7 +592,7 @@ where
         w: PatW,
         h: PatH,
         phase: StartWith,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd_with_data(
             spi,
             Command::BlackWhiteRAMTestPattern,
@@ -506,7 +607,7 @@ where
         w: PatW,
         h: PatH,
         phase: StartWith,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd_with_data(
             spi,
             Command::RedRAMTestPattern,
@@ -515,3 +616,117 @@ where
         self.wait_until_idle(delay)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::eh1::{delay::NoopDelay, digital, spi};
+
+    extern crate std;
+    use std::vec::Vec;
+
+    /// SPI and DC transactions sending `traffic`, one byte at a time
+    fn expectations(
+        traffic: &[(Command, &[u8])],
+    ) -> (Vec<spi::Transaction<u8>>, Vec<digital::Transaction>) {
+        let (mut spi_expectations, mut dc_expectations) = (Vec::new(), Vec::new());
+        for &(command, data) in traffic {
+            dc_expectations.push(digital::Transaction::set(digital::State::Low));
+            for &byte in [command as u8].iter().chain(data) {
+                spi_expectations.push(spi::Transaction::transaction_start());
+                spi_expectations.push(spi::Transaction::write_vec(std::vec![byte]));
+                spi_expectations.push(spi::Transaction::transaction_end());
+            }
+            dc_expectations.push(digital::Transaction::set(digital::State::High));
+        }
+        (spi_expectations, dc_expectations)
+    }
+
+    #[test]
+    fn partial_window() {
+        let traffic: [(Command, &[u8]); 9] = [
+            (Command::SetXAddressRange, &[1, 2]),
+            (Command::SetYAddressRange, &[0x20, 0x01, 0x21, 0x01]),
+            (Command::SetXAddressCounter, &[1]),
+            (Command::SetYAddressCounter, &[0x20, 0x01]),
+            (Command::WriteBlackWhiteRAM, &[0xAA; 4]),
+            // the full window ends on the last byte and row
+            (Command::SetXAddressRange, &[0, 18]),
+            (Command::SetYAddressRange, &[0, 0, 0x27, 0x01]),
+            (Command::SetXAddressCounter, &[0]),
+            (Command::SetYAddressCounter, &[0, 0]),
+        ];
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&[
+            digital::Transaction::get(digital::State::Low),
+            digital::Transaction::get(digital::State::Low),
+        ]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd = Epd2in66b {
+            interface: DisplayInterface::new(busy.clone(), dc.clone(), rst.clone(), None),
+            background: DEFAULT_BACKGROUND_COLOR,
+        };
+
+        epd.update_partial_frame(&mut spi, &mut NoopDelay, &[0xAA; 4], 8, 288, 16, 2)
+            .unwrap();
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
+
+    #[test]
+    fn partial_color_window() {
+        let traffic: [(Command, &[u8]); 12] = [
+            (Command::SetXAddressRange, &[1, 2]),
+            (Command::SetYAddressRange, &[0x20, 0x01, 0x21, 0x01]),
+            (Command::SetXAddressCounter, &[1]),
+            (Command::SetYAddressCounter, &[0x20, 0x01]),
+            (Command::WriteBlackWhiteRAM, &[0xAA; 4]),
+            (Command::SetXAddressCounter, &[1]),
+            (Command::SetYAddressCounter, &[0x20, 0x01]),
+            (Command::WriteRedRAM, &[0x55; 4]),
+            (Command::SetXAddressRange, &[0, 18]),
+            (Command::SetYAddressRange, &[0, 0, 0x27, 0x01]),
+            (Command::SetXAddressCounter, &[0]),
+            (Command::SetYAddressCounter, &[0, 0]),
+        ];
+        let (spi_expectations, dc_expectations) = expectations(&traffic);
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&std::vec![
+            digital::Transaction::get(digital::State::Low);
+            3
+        ]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd = Epd2in66b {
+            interface: DisplayInterface::new(busy.clone(), dc.clone(), rst.clone(), None),
+            background: DEFAULT_BACKGROUND_COLOR,
+        };
+
+        let (black, chromatic) = ([0xAA; 4], [0x55; 4]);
+        assert_eq!(
+            epd.update_partial_color_frame(
+                &mut spi,
+                &mut NoopDelay,
+                &black,
+                &chromatic,
+                4,
+                288,
+                16,
+                2
+            ),
+            Err(Error::Window(crate::rect::WindowError::Misaligned))
+        );
+        epd.update_partial_color_frame(&mut spi, &mut NoopDelay, &black, &chromatic, 8, 288, 16, 2)
+            .unwrap();
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
+}
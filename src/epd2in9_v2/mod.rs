@@ -2,11 +2,15 @@
 //!
 //! Specification: <https://www.waveshare.com/w/upload/7/79/2.9inch-e-paper-v2-specification.pdf>
 //!
+//! Besides the [`QuickRefresh`] of the whole frame shown below, the display implements
+//! [`PartialRefresh`]: after `set_update_mode(.., RefreshLut::Quick)`, windows written with
+//! `update_partial_frame` are refreshed without flashing, several times per second.
+//!
 //! # Example for the 2.9 in E-Ink Display V2
 //!
 //!```rust, no_run
 //!# use embedded_hal_mock::eh1::*;
-//!# fn main() -> Result<(), embedded_hal::spi::ErrorKind> {
+//!# fn main() -> Result<(), epd_waveshare::error::Error<embedded_hal::spi::ErrorKind>> {
 //!use embedded_graphics::{
 //!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
 //!};
@@ -89,6 +93,8 @@ const WS_20_30: [u8; 159] = [
 
 use embedded_hal::{delay::*, digital::*, spi::SpiDevice};
 
+use crate::error::Error;
+use crate::rect::WindowError;
 use crate::type_a::command::Command;
 
 use crate::color::Color;
@@ -96,7 +102,7 @@ use crate::color::Color;
 use crate::traits::*;
 
 use crate::buffer_len;
-use crate::interface::DisplayInterface;
+use crate::interface::{BusyYield, DisplayInterface};
 use crate::traits::QuickRefresh;
 
 /// Display with Fullsize buffer for use with the 2in9 EPD V2
@@ -120,7 +126,59 @@ pub struct Epd2in9<SPI, BUSY, DC, RST, DELAY> {
     refresh: RefreshLut,
 }
 
-impl<SPI, BUSY, DC, RST, DELAY> Epd2in9<SPI, BUSY, DC, RST, DELAY>
+impl<SPI, BUSY, DC, RST, DELAY> Epd2in9<SPI, BUSY, DC, RST, DELAY> {
+    /// Gives back the busy, data/command and reset pins, e.g. to reuse them once the display
+    /// is asleep
+    ///
+    /// The display keeps its state, so a driver can be created again later with the pins.
+    pub fn release(self) -> (BUSY, DC, RST) {
+        self.interface.release()
+    }
+
+    /// Gives up waiting for the display with [`Error::BusyTimeout`] once it has been busy for
+    /// `timeout_us`, e.g. because of a loose cable, instead of waiting forever. `None` removes
+    /// the timeout.
+    ///
+    /// The time is counted by adding up the delays of the idle loop, each poll of the busy pin
+    /// counting as at least 1us.
+    pub fn set_busy_timeout(&mut self, timeout_us: Option<u32>) {
+        self.interface.set_busy_timeout(timeout_us);
+    }
+
+    /// Polls the busy pin every `interval_us` while the display is busy (0 busy waits), or,
+    /// with a `max_interval_us`, backs off: the interval doubles after every poll until
+    /// `max_interval_us`
+    ///
+    /// The interval is the `delay_us` given to `new` (10ms by default) until this is called.
+    pub fn set_poll_interval(&mut self, interval_us: u32, max_interval_us: Option<u32>) {
+        self.interface.set_poll_interval(interval_us);
+        self.interface.set_poll_backoff(max_interval_us);
+    }
+
+    /// Sets a [`BusyYield`] called on every poll of the busy pin while the display is busy, e.g.
+    /// to feed a watchdog during a refresh. `None` removes it.
+    pub fn set_busy_yield(&mut self, busy_yield: Option<BusyYield>) {
+        self.interface.set_busy_yield(busy_yield);
+    }
+
+    /// Time the display was busy during the last wait for it, counted like the busy timeout,
+    /// e.g. to choose the timeout
+    pub fn last_wait_us(&self) -> u32 {
+        self.interface.last_wait_us()
+    }
+
+    /// CRC-32 of the data of the last frame written to the display, e.g. by
+    /// [`update_frame`](WaveshareDisplay::update_frame), to compare with the
+    /// [`crc32`](crate::crc::crc32) of the expected image and detect a corrupted transfer. `None`
+    /// until a frame is written.
+    #[cfg(any(feature = "crc", test))]
+    pub fn last_frame_crc(&self) -> Option<u32> {
+        self.interface.last_crc()
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in9<SPI, BUSY, DC, RST, DELAY>
 where
     SPI: SpiDevice,
     BUSY: InputPin,
@@ -128,8 +186,8 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.interface.reset(delay, 10_000, 2_000);
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 10_000, 2_000)?;
 
         self.wait_until_idle(spi, delay)?;
         self.interface.cmd(spi, Command::SwReset)?;
@@ -157,7 +215,21 @@ where
 
         self.wait_until_idle(spi, delay)?;
 
-        // set LUT by host
+        self.refresh = RefreshLut::Full;
+        self.load_full_lut(spi, delay)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Epd2in9<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Sets the full refresh LUT by host, with its voltages
+    fn load_full_lut(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.set_lut_helper(spi, delay, &WS_20_30[0..153])?;
         self.interface
             .cmd_with_data(spi, Command::WriteLutRegisterEnd, &WS_20_30[153..154])?;
@@ -167,7 +239,115 @@ where
             .cmd_with_data(spi, Command::SourceDrivingVoltage, &WS_20_30[155..158])?;
         self.interface
             .cmd_with_data(spi, Command::WriteVcomRegister, &WS_20_30[158..159])?;
+        Ok(())
+    }
+
+    /// Loads the partial refresh LUT and keeps the analog circuits on between refreshes
+    ///
+    /// The display then only drives the pixels differing between the B/W RAM (new frame) and
+    /// the red RAM (old frame), without flashing.
+    fn enter_partial_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.interface.reset(delay, 10_000, 2_000)?;
+
+        self.set_lut_helper(spi, delay, &LUT_PARTIAL_2IN9)?;
+        self.interface.cmd_with_data(
+            spi,
+            Command::WriteOtpSelection,
+            &[0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00],
+        )?;
+        self.interface
+            .cmd_with_data(spi, Command::BorderWaveformControl, &[0x80])?;
+        self.interface
+            .cmd_with_data(spi, Command::DisplayUpdateControl2, &[0xC0])?;
+        self.interface.cmd(spi, Command::MasterActivation)?;
+
+        self.wait_until_idle(spi, delay)
+    }
+
+    /// Writes a window of the B/W RAM, and of the red RAM too if `old` is set
+    ///
+    /// `x` and `width` must be multiples of 8. The RAM window is restored to the full frame
+    /// afterwards.
+    #[allow(clippy::too_many_arguments)]
+    fn write_window(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        data: WindowData<'_>,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        old: bool,
+    ) -> Result<(), Error<SPI::Error>> {
+        if width == 0 || height == 0 {
+            return Err(Error::Window(WindowError::Empty));
+        }
+        let len = buffer_len(width as usize, height as usize);
+        if let WindowData::Buffer(buffer) = data {
+            Error::check_len(buffer, len)?;
+        }
+        self.wait_until_idle(spi, delay)?;
+
+        let commands: &[Command] = if old {
+            &[Command::WriteRam, Command::WriteRam2]
+        } else {
+            &[Command::WriteRam]
+        };
+        for &command in commands {
+            self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+            self.set_ram_counter(spi, delay, x, y)?;
+            match data {
+                WindowData::Buffer(buffer) => self.interface.cmd_with_data(spi, command, buffer)?,
+                WindowData::Fill(byte) => {
+                    self.interface.cmd(spi, command)?;
+                    self.interface.data_x_times(spi, byte, len as u32)?;
+                }
+            }
+        }
+
+        self.use_full_frame(spi, delay)
+    }
+}
+
+/// Content of a RAM window
+#[derive(Clone, Copy)]
+enum WindowData<'a> {
+    Buffer(&'a [u8]),
+    Fill(u8),
+}
 
+impl<SPI, BUSY, DC, RST, DELAY> Sleepable<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in9<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.sleep_with_mode(spi, delay, DeepSleepMode::Mode1)
+    }
+
+    fn sleep_with_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mode: DeepSleepMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.interface
+            .cmd_with_data(spi, Command::DeepSleepMode, &[mode as u8])
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)?;
         Ok(())
     }
 }
@@ -197,7 +377,7 @@ where
         rst: RST,
         delay: &mut DELAY,
         delay_us: Option<u32>,
-    ) -> Result<Self, SPI::Error> {
+    ) -> Result<Self, Error<SPI::Error>> {
         let interface = DisplayInterface::new(busy, dc, rst, delay_us);
 
         let mut epd = Epd2in9 {
@@ -206,56 +386,32 @@ where
             refresh: RefreshLut::Full,
         };
 
+        #[cfg(any(feature = "crc", test))]
+        epd.interface.track_crc(Command::WriteRam);
         epd.init(spi, delay)?;
 
         Ok(epd)
     }
 
-    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.wait_until_idle(spi, delay)?;
-        // 0x00 for Normal mode (Power on Reset), 0x01 for Deep Sleep Mode
-        self.interface
-            .cmd_with_data(spi, Command::DeepSleepMode, &[0x01])?;
-        Ok(())
-    }
-
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.init(spi, delay)?;
-        Ok(())
-    }
-
     fn update_frame(
         &mut self,
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         self.wait_until_idle(spi, delay)?;
         self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
     }
 
-    fn update_partial_frame(
-        &mut self,
-        spi: &mut SPI,
-        delay: &mut DELAY,
-        buffer: &[u8],
-        x: u32,
-        y: u32,
-        width: u32,
-        height: u32,
-    ) -> Result<(), SPI::Error> {
-        //TODO This is copied from epd2in9 but it seems not working. Partial refresh supported by version 2?
-        self.wait_until_idle(spi, delay)?;
-        self.set_ram_area(spi, x, y, x + width, y + height)?;
-        self.set_ram_counter(spi, delay, x, y)?;
-
-        self.interface
-            .cmd_with_data(spi, Command::WriteRam, buffer)?;
-        Ok(())
-    }
-
     /// actually is the "Turn on Display" sequence
-    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    ///
+    /// After [`RefreshLut::Quick`] was set, this is a partial refresh, see
+    /// [`QuickRefresh::display_new_frame`].
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        if self.refresh == RefreshLut::Quick {
+            return self.display_new_frame(spi, delay);
+        }
         self.wait_until_idle(spi, delay)?;
         // Enable clock signal, Enable Analog, Load temperature value, DISPLAY with DISPLAY Mode 1, Disable Analog, Disable OSC
         self.interface
@@ -270,13 +426,13 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, buffer, delay)?;
         self.display_frame(spi, delay)?;
         Ok(())
     }
 
-    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
 
         // clear the ram with the background color
@@ -297,21 +453,79 @@ where
         &self.background_color
     }
 
-    fn set_lut(
+    fn wait_until_idle(
         &mut self,
         _spi: &mut SPI,
-        _delay: &mut DELAY,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_until_idle(delay, IS_BUSY_LOW)?;
+        Ok(())
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> LutControl<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in9<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// [`RefreshLut::Quick`] switches to partial refreshes, the other LUTs back to full ones
+    fn set_lut(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
         refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
-        if let Some(refresh_lut) = refresh_rate {
-            self.refresh = refresh_lut;
+    ) -> Result<(), Error<SPI::Error>> {
+        let refresh = match refresh_rate {
+            Some(RefreshLut::Quick) => RefreshLut::Quick,
+            _ => RefreshLut::Full,
+        };
+        if refresh == self.refresh {
+            return Ok(());
         }
+        match refresh {
+            RefreshLut::Quick => self.enter_partial_mode(spi, delay)?,
+            _ => self.load_full_lut(spi, delay)?,
+        }
+        self.refresh = refresh;
         Ok(())
     }
+}
 
-    fn wait_until_idle(&mut self, _spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.interface.wait_until_idle(delay, IS_BUSY_LOW);
-        Ok(())
+impl<SPI, BUSY, DC, RST, DELAY> PartialUpdate<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in9<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        // with full refreshes, the old frame is kept equal to the shown one
+        let old = self.refresh != RefreshLut::Quick;
+        self.write_window(
+            spi,
+            delay,
+            WindowData::Buffer(buffer),
+            x,
+            y,
+            width,
+            height,
+            old,
+        )
     }
 }
 
@@ -323,7 +537,11 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    fn use_full_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn use_full_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
         // choose full frame/ram
         self.set_ram_area(spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
 
@@ -338,9 +556,10 @@ where
         start_y: u32,
         end_x: u32,
         end_y: u32,
-    ) -> Result<(), SPI::Error> {
-        assert!(start_x < end_x);
-        assert!(start_y < end_y);
+    ) -> Result<(), Error<SPI::Error>> {
+        if start_x >= end_x || start_y >= end_y {
+            return Err(Error::Window(WindowError::Empty));
+        }
 
         // x is positioned in bytes, so the last 3 bits which show the position inside a byte in the ram
         // aren't relevant
@@ -369,12 +588,12 @@ where
         delay: &mut DELAY,
         x: u32,
         y: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
         // x is positioned in bytes, so the last 3 bits which show the position inside a byte in the ram
         // aren't relevant
         self.interface
-            .cmd_with_data(spi, Command::SetRamXAddressCounter, &[x as u8])?;
+            .cmd_with_data(spi, Command::SetRamXAddressCounter, &[(x >> 3) as u8])?;
 
         // 2 Databytes: A[7:0] & 0..A[8]
         self.interface.cmd_with_data(
@@ -391,7 +610,7 @@ where
         spi: &mut SPI,
         delay: &mut DELAY,
         buffer: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
         self.interface
             .cmd_with_data(spi, Command::WriteLutRegister, buffer)?;
@@ -415,7 +634,8 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         self.wait_until_idle(spi, delay)?;
         self.interface
             .cmd_with_data(spi, Command::WriteRam, buffer)?;
@@ -429,23 +649,10 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
-        self.wait_until_idle(spi, delay)?;
-        self.interface.reset(delay, 10_000, 2_000);
-
-        self.set_lut_helper(spi, delay, &LUT_PARTIAL_2IN9)?;
-        self.interface.cmd_with_data(
-            spi,
-            Command::WriteOtpSelection,
-            &[0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00],
-        )?;
-        self.interface
-            .cmd_with_data(spi, Command::BorderWaveformControl, &[0x80])?;
-        self.interface
-            .cmd_with_data(spi, Command::DisplayUpdateControl2, &[0xC0])?;
-        self.interface.cmd(spi, Command::MasterActivation)?;
-
-        self.wait_until_idle(spi, delay)?;
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.enter_partial_mode(spi, delay)?;
+        self.refresh = RefreshLut::Quick;
 
         self.use_full_frame(spi, delay)?;
 
@@ -455,7 +662,11 @@ where
     }
 
     /// For a quick refresh of the new updated frame. To be used immediately after `update_new_frame`
-    fn display_new_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
         self.interface
             .cmd_with_data(spi, Command::DisplayUpdateControl2, &[0x0F])?;
@@ -470,14 +681,13 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_new_frame(spi, buffer, delay)?;
         self.display_new_frame(spi, delay)?;
         Ok(())
     }
 
-    /// Partial quick refresh not supported yet
-    #[allow(unused)]
+    /// Writes a window of both the old and the new frame, `x` and `width` being multiples of 8
     fn update_partial_old_frame(
         &mut self,
         spi: &mut SPI,
@@ -487,13 +697,21 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
-        //TODO supported by display?
-        unimplemented!()
+    ) -> Result<(), Error<SPI::Error>> {
+        self.write_window(
+            spi,
+            delay,
+            WindowData::Buffer(buffer),
+            x,
+            y,
+            width,
+            height,
+            true,
+        )
     }
 
-    /// Partial quick refresh not supported yet
-    #[allow(unused)]
+    /// Switches to partial refreshes and writes a window of the new frame, `x` and `width`
+    /// being multiples of 8
     fn update_partial_new_frame(
         &mut self,
         spi: &mut SPI,
@@ -503,13 +721,22 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
-        //TODO supported by display?
-        unimplemented!()
+    ) -> Result<(), Error<SPI::Error>> {
+        self.set_lut(spi, delay, Some(RefreshLut::Quick))?;
+        self.write_window(
+            spi,
+            delay,
+            WindowData::Buffer(buffer),
+            x,
+            y,
+            width,
+            height,
+            false,
+        )
     }
 
-    /// Partial quick refresh not supported yet
-    #[allow(unused)]
+    /// Clears a window of both frames with the background color, `x` and `width` being
+    /// multiples of 8
     fn clear_partial_frame(
         &mut self,
         spi: &mut SPI,
@@ -518,15 +745,122 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
-        //TODO supported by display?
-        unimplemented!()
+    ) -> Result<(), Error<SPI::Error>> {
+        let color = self.background_color.get_byte_value();
+        self.write_window(
+            spi,
+            delay,
+            WindowData::Fill(color),
+            x,
+            y,
+            width,
+            height,
+            true,
+        )
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> PartialRefresh<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in9<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn set_base_image(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.update_old_frame(spi, buffer, delay)
+    }
+
+    fn set_update_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mode: RefreshLut,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.set_lut(spi, delay, Some(mode))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use embedded_hal_mock::eh1::{delay::NoopDelay, digital, spi};
+
+    extern crate std;
+    use std::vec::Vec;
+
+    // a command and its data, each byte written in its own transaction
+    fn write(
+        spi: &mut Vec<spi::Transaction<u8>>,
+        dc: &mut Vec<digital::Transaction>,
+        command: Command,
+        data: &[u8],
+    ) {
+        for (state, bytes) in [
+            (digital::State::Low, &[command as u8][..]),
+            (digital::State::High, data),
+        ] {
+            if bytes.is_empty() {
+                continue;
+            }
+            dc.push(digital::Transaction::set(state));
+            for byte in bytes {
+                spi.push(spi::Transaction::transaction_start());
+                spi.push(spi::Transaction::write_vec(std::vec![*byte]));
+                spi.push(spi::Transaction::transaction_end());
+            }
+        }
+    }
+
+    #[test]
+    fn partial_window() {
+        let (mut spi_expectations, mut dc_expectations) = (Vec::new(), Vec::new());
+        let mut push = |command, data: &[u8]| {
+            write(&mut spi_expectations, &mut dc_expectations, command, data)
+        };
+        push(Command::SetRamXAddressStartEndPosition, &[1, 2]);
+        push(Command::SetRamYAddressStartEndPosition, &[4, 0, 5, 0]);
+        push(Command::SetRamXAddressCounter, &[1]);
+        push(Command::SetRamYAddressCounter, &[4, 0]);
+        push(Command::WriteRam, &[0xAA; 4]);
+        push(Command::SetRamXAddressStartEndPosition, &[0, 15]);
+        push(Command::SetRamYAddressStartEndPosition, &[0, 0, 39, 1]);
+        push(Command::SetRamXAddressCounter, &[0]);
+        push(Command::SetRamYAddressCounter, &[0, 0]);
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&std::vec![
+            digital::Transaction::get(digital::State::Low);
+            3
+        ]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd = Epd2in9 {
+            interface: DisplayInterface::new(busy.clone(), dc.clone(), rst.clone(), None),
+            background_color: DEFAULT_BACKGROUND_COLOR,
+            refresh: RefreshLut::Quick,
+        };
+
+        // the window ends on its last pixel, and only the new frame is written
+        epd.update_partial_frame(&mut spi, &mut NoopDelay, &[0xAA; 4], 8, 4, 16, 2)
+            .unwrap();
+        assert!(epd
+            .update_partial_frame(&mut spi, &mut NoopDelay, &[0xAA; 3], 8, 4, 16, 2)
+            .is_err());
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
+    }
 
     #[test]
     fn epd_size() {
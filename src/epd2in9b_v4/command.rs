@@ -11,6 +11,7 @@ pub(crate) enum Command {
     TurnOnDisplay = 0x22,
     ActivateDisplayUpdateSequence = 0x20,
     ReadBuiltInTemperatureSensor = 0x18,
+    WriteTemperatureRegister = 0x1a,
     RamXPosition = 0x44,
     RamYPosition = 0x45,
     RamXAddressCount = 0x4e,
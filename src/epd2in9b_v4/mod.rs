@@ -3,12 +3,20 @@
 //! [Documentation](https://www.waveshare.com/wiki/2.9inch_e-Paper_Module_(B)_Manual)
 //!
 //! [Reference code](https://github.com/waveshareteam/e-Paper/blob/master/RaspberryPi_JetsonNano/c/lib/e-Paper/EPD_2in9b_V4.c)
+//!
+//! Besides the full refreshes, it has a fast mode refreshing in ~1.5s, selected with
+//! [`RefreshMode::Fast`](crate::prelude::RefreshMode::Fast).
 
+use crate::error::Error;
+use crate::rect::WindowError;
 use crate::{
     buffer_len,
     color::TriColor,
-    interface::DisplayInterface,
-    traits::{InternalWiAdditions, WaveshareDisplay, WaveshareThreeColorDisplay},
+    interface::{BusyYield, DisplayInterface},
+    traits::{
+        InternalWiAdditions, LutControl, PartialRefresh, PartialUpdate, RefreshLut, Sleepable,
+        WaveshareDisplay, WaveshareThreeColorDisplay,
+    },
 };
 use embedded_hal::{
     delay::DelayNs,
@@ -30,6 +38,9 @@ pub const HEIGHT: u32 = 296;
 
 const IS_BUSY_LOW: bool = false;
 
+/// Temperature written to the controller to select the waveform of the fast mode
+const FAST_TEMPERATURE: u8 = 0x5A;
+
 #[cfg(feature = "graphics")]
 /// Full size buffer for use with the 2.9" black/red EPD
 pub type Display2in9b = crate::graphics::Display<
@@ -46,13 +57,65 @@ pub struct Epd2in9b<SPI, BUSY, DC, RST, DELAY> {
     interface: DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
     /// Color
     background_color: TriColor,
+    /// Refresh mode of `display_frame`
+    refresh: RefreshLut,
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Epd2in9b<SPI, BUSY, DC, RST, DELAY> {
+    /// Gives back the busy, data/command and reset pins, e.g. to reuse them once the display
+    /// is asleep
+    ///
+    /// The display keeps its state, so a driver can be created again later with the pins.
+    pub fn release(self) -> (BUSY, DC, RST) {
+        self.interface.release()
+    }
+
+    /// Gives up waiting for the display with [`Error::BusyTimeout`] once it has been busy for
+    /// `timeout_us`, e.g. because of a loose cable, instead of waiting forever. `None` removes
+    /// the timeout.
+    ///
+    /// The time is counted by adding up the delays of the idle loop, each poll of the busy pin
+    /// counting as at least 1us.
+    pub fn set_busy_timeout(&mut self, timeout_us: Option<u32>) {
+        self.interface.set_busy_timeout(timeout_us);
+    }
+
+    /// Polls the busy pin every `interval_us` while the display is busy (0 busy waits), or,
+    /// with a `max_interval_us`, backs off: the interval doubles after every poll until
+    /// `max_interval_us`
+    ///
+    /// The interval is the `delay_us` given to `new` (10ms by default) until this is called.
+    pub fn set_poll_interval(&mut self, interval_us: u32, max_interval_us: Option<u32>) {
+        self.interface.set_poll_interval(interval_us);
+        self.interface.set_poll_backoff(max_interval_us);
+    }
+
+    /// Sets a [`BusyYield`] called on every poll of the busy pin while the display is busy, e.g.
+    /// to feed a watchdog during a refresh. `None` removes it.
+    pub fn set_busy_yield(&mut self, busy_yield: Option<BusyYield>) {
+        self.interface.set_busy_yield(busy_yield);
+    }
+
+    /// Time the display was busy during the last wait for it, counted like the busy timeout,
+    /// e.g. to choose the timeout
+    pub fn last_wait_us(&self) -> u32 {
+        self.interface.last_wait_us()
+    }
+
+    /// CRC-32 of the data of the last frame written to the display, e.g. by
+    /// [`update_frame`](WaveshareDisplay::update_frame), to compare with the
+    /// [`crc32`](crate::crc::crc32) of the expected image and detect a corrupted transfer. `None`
+    /// until a frame is written. The chromatic frame isn't covered.
+    #[cfg(any(feature = "crc", test))]
+    pub fn last_frame_crc(&self) -> Option<u32> {
+        self.interface.last_crc()
+    }
 }
 
-#[allow(dead_code)]
 enum DisplayMode {
     Default,
     Partial,
-    Fast, // TODO: Add support in future
+    Fast,
     Base,
 }
 
@@ -64,6 +127,15 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
+    /// Sets the largest number of bytes written in a single SPI transfer, longer writes being
+    /// split, e.g. for DMA engines with a limited transfer size. `None` writes a whole frame
+    /// at once.
+    ///
+    /// It is 4096 bytes on Linux, the default limit of spidev, and unlimited elsewhere.
+    pub fn set_max_transfer_size(&mut self, size: Option<usize>) {
+        self.interface.set_max_transfer_size(size);
+    }
+
     /// set the base image before partially update
     ///
     /// <https://github.com/waveshareteam/e-Paper/blob/bc23f8ee814486edb6a364c802847224e079e523/RaspberryPi_JetsonNano/c/examples/EPD_2in9b_V4_test.c#L130>
@@ -73,7 +145,7 @@ where
         black: &[u8],
         chromatic: Option<&[u8]>,
         delay: &mut DELAY,
-    ) -> Result<(), <SPI>::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, black, delay)?;
         if let Some(chromatic) = chromatic {
             self.update_chromatic_frame(spi, delay, chromatic)?;
@@ -95,16 +167,16 @@ where
         &mut self,
         spi: &mut SPI,
         delay: &mut DELAY,
-    ) -> Result<(), <SPI>::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.turn_on_display(spi, delay, DisplayMode::Partial)?;
         Ok(())
     }
 
-    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), SPI::Error> {
+    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd(spi, command)
     }
 
-    fn send_data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
+    fn send_data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), Error<SPI::Error>> {
         self.interface.data(spi, data)
     }
 
@@ -113,7 +185,7 @@ where
         spi: &mut SPI,
         delay: &mut DELAY,
         mode: DisplayMode,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.command(spi, Command::TurnOnDisplay)?;
 
         let data = match mode {
@@ -139,11 +211,11 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         let w = self.width();
         let h = self.height();
 
-        self.interface.reset(delay, 200_000, 2_000);
+        self.interface.reset(delay, 200_000, 2_000)?;
 
         self.wait_until_idle(spi, delay)?;
         self.command(spi, Command::SwReset)?;
@@ -185,6 +257,11 @@ where
 
         self.wait_until_idle(spi, delay)?;
 
+        // the temperature register is lost on reset
+        if self.refresh == RefreshLut::Fast {
+            self.set_lut(spi, delay, None)?;
+        }
+
         Ok(())
     }
 }
@@ -204,7 +281,9 @@ where
         delay: &mut DELAY,
         black: &[u8],
         chromatic: &[u8],
-    ) -> Result<(), <SPI>::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        // check both buffers before writing anything
+        Error::check_len(chromatic, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         self.update_achromatic_frame(spi, delay, black)?;
         self.update_chromatic_frame(spi, delay, chromatic)?;
         Ok(())
@@ -215,7 +294,8 @@ where
         spi: &mut SPI,
         _delay: &mut DELAY,
         black: &[u8],
-    ) -> Result<(), <SPI>::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(black, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         self.command(spi, Command::WriteBlackData)?;
         self.send_data(spi, black)?;
         Ok(())
@@ -226,13 +306,37 @@ where
         spi: &mut SPI,
         _delay: &mut DELAY,
         chromatic: &[u8],
-    ) -> Result<(), <SPI>::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(chromatic, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         self.command(spi, Command::WriteRedData)?;
         self.send_data(spi, chromatic)?;
         Ok(())
     }
 }
 
+impl<SPI, BUSY, DC, RST, DELAY> Sleepable<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in9b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.command(spi, Command::DeepSleep)?;
+        self.send_data(spi, &[1])?;
+        delay.delay_ms(100);
+
+        Ok(())
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)?;
+        Ok(())
+    }
+}
+
 impl<SPI, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
     for Epd2in9b<SPI, BUSY, DC, RST, DELAY>
 where
@@ -251,7 +355,7 @@ where
         rst: RST,
         delay: &mut DELAY,
         delay_us: Option<u32>,
-    ) -> Result<Self, <SPI>::Error>
+    ) -> Result<Self, Error<SPI::Error>>
     where
         Self: Sized,
     {
@@ -261,26 +365,16 @@ where
         let mut epd = Epd2in9b {
             interface,
             background_color,
+            refresh: RefreshLut::Full,
         };
 
+        #[cfg(any(feature = "crc", test))]
+        epd.interface.track_crc(Command::WriteBlackData);
         epd.init(spi, delay)?;
 
         Ok(epd)
     }
 
-    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), <SPI>::Error> {
-        self.command(spi, Command::DeepSleep)?;
-        self.send_data(spi, &[1])?;
-        delay.delay_ms(100);
-
-        Ok(())
-    }
-
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), <SPI>::Error> {
-        self.init(spi, delay)?;
-        Ok(())
-    }
-
     fn set_background_color(&mut self, color: Self::DisplayColor) {
         self.background_color = color
     }
@@ -302,7 +396,8 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         _delay: &mut DELAY,
-    ) -> Result<(), <SPI>::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         self.command(spi, Command::WriteBlackData)?;
         self.send_data(spi, buffer)?;
 
@@ -311,6 +406,102 @@ where
         Ok(())
     }
 
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        let mode = match self.refresh {
+            RefreshLut::Full => DisplayMode::Default,
+            RefreshLut::Fast => DisplayMode::Fast,
+            RefreshLut::Quick => DisplayMode::Partial,
+        };
+        self.turn_on_display(spi, delay, mode)?;
+
+        Ok(())
+    }
+
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.display_frame(spi, delay)?;
+
+        Ok(())
+    }
+
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        const SIZE: u32 = WIDTH / 8 * HEIGHT;
+
+        self.command(spi, Command::WriteBlackData)?;
+        self.interface.data_x_times(spi, 0xff, SIZE)?;
+
+        self.command(spi, Command::WriteRedData)?;
+        self.interface.data_x_times(spi, 0, SIZE)?;
+
+        self.display_frame(spi, delay)?;
+        Ok(())
+    }
+
+    fn wait_until_idle(
+        &mut self,
+        _spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_until_idle(delay, IS_BUSY_LOW)?;
+        Ok(())
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> LutControl<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in9b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// [`RefreshLut::Fast`] writes a high temperature to the controller, whose waveform for
+    /// it is short, and refreshes without measuring the temperature again, like the fast
+    /// initialisation of the reference code. [`RefreshLut::Quick`] makes
+    /// [`display_frame`](WaveshareDisplay::display_frame) a partial refresh like
+    /// [`display_frame_partial`](Epd2in9b::display_frame_partial), after a base image was
+    /// set with [`update_and_display_frame_base`](Epd2in9b::update_and_display_frame_base).
+    /// The full refreshes measure the temperature again.
+    fn set_lut(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(refresh_lut) = refresh_rate {
+            self.refresh = refresh_lut;
+        }
+        if self.refresh == RefreshLut::Fast {
+            self.interface.cmd_with_data(
+                spi,
+                Command::WriteTemperatureRegister,
+                &[FAST_TEMPERATURE, 0x00],
+            )?;
+            // load the waveform of that temperature
+            self.command(spi, Command::TurnOnDisplay)?;
+            self.send_data(spi, &[0x91])?;
+            self.command(spi, Command::ActivateDisplayUpdateSequence)?;
+            self.wait_until_idle(spi, delay)?;
+        }
+        Ok(())
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> PartialUpdate<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in9b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
     fn update_partial_frame(
         &mut self,
         spi: &mut SPI,
@@ -320,8 +511,11 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), <SPI>::Error> {
-        assert!(width % 8 == 0, "width must multiple of 8");
+    ) -> Result<(), Error<SPI::Error>> {
+        // width must be a multiple of 8
+        if width % 8 != 0 {
+            return Err(Error::Window(WindowError::Misaligned));
+        }
         let mut x_start = x;
         let mut x_end = x + width;
 
@@ -371,49 +565,155 @@ where
 
         Ok(())
     }
+}
 
-    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), <SPI>::Error> {
-        self.turn_on_display(spi, delay, DisplayMode::Default)?;
+impl<SPI, BUSY, DC, RST, DELAY> PartialRefresh<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in9b<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Writes `buffer` to the black and the chromatic RAM, the partial waveform comparing
+    /// them, like [`update_and_display_frame_base`](Epd2in9b::update_and_display_frame_base)
+    /// without the refresh
+    ///
+    /// The chromatic RAM holds the base image until the next
+    /// [`update_frame`](WaveshareDisplay::update_frame): write a frame before going back to
+    /// full refreshes.
+    fn set_base_image(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.command(spi, Command::WriteBlackData)?;
+        self.send_data(spi, buffer)?;
 
-        Ok(())
+        self.command(spi, Command::WriteRedData)?;
+        self.send_data(spi, buffer)
     }
 
-    fn update_and_display_frame(
+    fn set_update_mode(
         &mut self,
         spi: &mut SPI,
-        buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), <SPI>::Error> {
-        self.update_frame(spi, buffer, delay)?;
-        self.display_frame(spi, delay)?;
-
-        Ok(())
+        mode: RefreshLut,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.set_lut(spi, delay, Some(mode))
     }
+}
 
-    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), <SPI>::Error> {
-        const SIZE: u32 = WIDTH / 8 * HEIGHT;
-
-        self.command(spi, Command::WriteBlackData)?;
-        self.interface.data_x_times(spi, 0xff, SIZE)?;
-
-        self.command(spi, Command::WriteRedData)?;
-        self.interface.data_x_times(spi, 0, SIZE)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Entry, Recorder};
+    use crate::traits::RefreshMode;
+    use embedded_hal::digital::PinState;
+
+    extern crate std;
+
+    #[test]
+    fn fast_refresh_mode() {
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let (dc, rst) = (recorder.dc(), recorder.rst());
+        let mut epd = Epd2in9b::new(&mut spi, busy, dc, rst, &mut delay, None).unwrap();
+        // commands and data sent, without the waits
+        let traffic = || {
+            let transcript = recorder.transcript();
+            let entries = transcript.entries();
+            let traffic = entries
+                .iter()
+                .filter(|entry| matches!(entry, Entry::Command(_) | Entry::Data(_)))
+                .cloned()
+                .collect::<std::vec::Vec<_>>();
+            recorder.clear();
+            traffic
+        };
+        let refresh = |sequence: u8| {
+            [
+                Entry::Command(Command::TurnOnDisplay as u8),
+                Entry::Data(std::vec![sequence]),
+                Entry::Command(Command::ActivateDisplayUpdateSequence as u8),
+            ]
+        };
 
-        self.display_frame(spi, delay)?;
-        Ok(())
+        recorder.clear();
+        epd.set_refresh_mode(&mut spi, &mut delay, RefreshMode::Fast)
+            .unwrap();
+        let mut fast = std::vec![
+            Entry::Command(Command::WriteTemperatureRegister as u8),
+            Entry::Data(std::vec![FAST_TEMPERATURE, 0x00]),
+        ];
+        fast.extend(refresh(0x91));
+        assert_eq!(traffic(), fast);
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+        assert_eq!(traffic(), refresh(0xC7));
+
+        // kept on wake up
+        epd.wake_up(&mut spi, &mut delay).unwrap();
+        assert!(traffic().ends_with(&fast));
+
+        epd.set_refresh_mode(&mut spi, &mut delay, RefreshMode::Full)
+            .unwrap();
+        assert_eq!(traffic(), []);
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+        assert_eq!(traffic(), refresh(0xF7));
     }
 
-    fn set_lut(
-        &mut self,
-        _spi: &mut SPI,
-        _delay: &mut DELAY,
-        _refresh_rate: Option<crate::traits::RefreshLut>,
-    ) -> Result<(), <SPI>::Error> {
-        Ok(())
+    #[test]
+    fn wrong_buffer_is_rejected() {
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let (dc, rst) = (recorder.dc(), recorder.rst());
+        let mut epd = Epd2in9b::new(&mut spi, busy, dc, rst, &mut delay, None).unwrap();
+
+        recorder.clear();
+        assert_eq!(
+            epd.update_frame(&mut spi, &[0xFF; 10], &mut delay),
+            Err(Error::WrongBufferSize {
+                expected: buffer_len(WIDTH as usize, HEIGHT as usize),
+                got: 10
+            })
+        );
+        assert!(recorder.transcript().entries().is_empty());
     }
 
-    fn wait_until_idle(&mut self, _spi: &mut SPI, delay: &mut DELAY) -> Result<(), <SPI>::Error> {
-        self.interface.wait_until_idle(delay, IS_BUSY_LOW);
-        Ok(())
+    #[test]
+    fn partial_refresh() {
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let (dc, rst) = (recorder.dc(), recorder.rst());
+        let mut epd = Epd2in9b::new(&mut spi, busy, dc, rst, &mut delay, None).unwrap();
+
+        epd.set_update_mode(&mut spi, &mut delay, RefreshLut::Quick)
+            .unwrap();
+        recorder.clear();
+        let frame = [0xA5; buffer_len(WIDTH as usize, HEIGHT as usize)];
+        epd.set_base_image(&mut spi, &mut delay, &frame).unwrap();
+        // the partial waveform compares the black RAM with the chromatic one
+        assert_eq!(
+            recorder.transcript().entries(),
+            [
+                Entry::Command(Command::WriteBlackData as u8),
+                Entry::Data(frame.to_vec()),
+                Entry::Command(Command::WriteRedData as u8),
+                Entry::Data(frame.to_vec()),
+            ]
+        );
+
+        recorder.clear();
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+        assert!(recorder.transcript().entries().starts_with(&[
+            Entry::Command(Command::TurnOnDisplay as u8),
+            Entry::Data(std::vec![0x1C])
+        ]));
     }
 }
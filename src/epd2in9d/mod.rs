@@ -13,8 +13,12 @@ use embedded_hal::{
     spi::SpiDevice,
 };
 
-use crate::interface::DisplayInterface;
-use crate::traits::{InternalWiAdditions, RefreshLut, WaveshareDisplay};
+use crate::error::Error;
+use crate::interface::{BusyYield, DisplayInterface};
+use crate::traits::{
+    InternalWiAdditions, LutControl, PartialRefresh, PartialUpdate, RefreshLut, Sleepable,
+    WaveshareDisplay,
+};
 
 //The Lookup Tables for the Display
 mod constants;
@@ -35,8 +39,21 @@ const SINGLE_BYTE_WRITE: bool = true;
 use crate::color::Color;
 
 pub(crate) mod command;
-use self::command::Command;
+use self::command::{Command, PanelResolution, PanelSetting, PowerSetting, VcomDataInterval};
 use crate::buffer_len;
+use crate::controllers::uc8151::Uc8151;
+
+/// Commands shared with the other UC8151 panels
+type Controller = Uc8151<WIDTH, HEIGHT>;
+
+/// Panel setting of the full refreshes, with the LUTs of the OTP
+const PANEL_SETTING: PanelSetting = PanelSetting {
+    resolution: PanelResolution::R96x230,
+    lut_from_register: false,
+    black_white: true,
+    scan_up: true,
+    shift_right: true,
+};
 
 /// Display with Fullsize buffer for use with the 2in9 EPD D
 #[cfg(feature = "graphics")]
@@ -64,6 +81,57 @@ pub struct Epd2in9d<'a, SPI, BUSY, DC, RST, DELAY> {
     is_partial_refresh: bool,
 }
 
+impl<'a, SPI, BUSY, DC, RST, DELAY> Epd2in9d<'a, SPI, BUSY, DC, RST, DELAY> {
+    /// Gives back the busy, data/command and reset pins, e.g. to reuse them once the display
+    /// is asleep
+    ///
+    /// The display keeps its state, so a driver can be created again later with the pins.
+    pub fn release(self) -> (BUSY, DC, RST) {
+        self.interface.release()
+    }
+
+    /// Gives up waiting for the display with [`Error::BusyTimeout`] once it has been busy for
+    /// `timeout_us`, e.g. because of a loose cable, instead of waiting forever. `None` removes
+    /// the timeout.
+    ///
+    /// The time is counted by adding up the delays of the idle loop, each poll of the busy pin
+    /// counting as at least 1us.
+    pub fn set_busy_timeout(&mut self, timeout_us: Option<u32>) {
+        self.interface.set_busy_timeout(timeout_us);
+    }
+
+    /// Polls the busy pin every `interval_us` while the display is busy (0 busy waits), or,
+    /// with a `max_interval_us`, backs off: the interval doubles after every poll until
+    /// `max_interval_us`
+    ///
+    /// The interval is the `delay_us` given to `new` (10ms by default) until this is called.
+    pub fn set_poll_interval(&mut self, interval_us: u32, max_interval_us: Option<u32>) {
+        self.interface.set_poll_interval(interval_us);
+        self.interface.set_poll_backoff(max_interval_us);
+    }
+
+    /// Sets a [`BusyYield`] called on every poll of the busy pin while the display is busy, e.g.
+    /// to feed a watchdog during a refresh. `None` removes it.
+    pub fn set_busy_yield(&mut self, busy_yield: Option<BusyYield>) {
+        self.interface.set_busy_yield(busy_yield);
+    }
+
+    /// Time the display was busy during the last wait for it, counted like the busy timeout,
+    /// e.g. to choose the timeout
+    pub fn last_wait_us(&self) -> u32 {
+        self.interface.last_wait_us()
+    }
+
+    /// CRC-32 of the data of the last frame written to the display, e.g. by
+    /// [`update_frame`](WaveshareDisplay::update_frame), to compare with the
+    /// [`crc32`](crate::crc::crc32) of the expected image and detect a corrupted transfer. `None`
+    /// until a frame is written.
+    #[cfg(any(feature = "crc", test))]
+    pub fn last_frame_crc(&self) -> Option<u32> {
+        self.interface.last_crc()
+    }
+}
+
 impl<SPI, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, BUSY, DC, RST, DELAY>
     for Epd2in9d<'_, SPI, BUSY, DC, RST, DELAY>
 where
@@ -73,25 +141,66 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.interface.reset(delay, 10_000, 2_000);
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 10_000, 2_000)?;
 
         //panel setting
         //LUT from OTP，KW-BF   KWR-AF	BWROTP 0f	BWOTP 1f
         self.interface
-            .cmd_with_data(spi, Command::PanelSetting, &[0x1f, 0x0D])?;
+            .cmd_with_data(spi, Command::PanelSetting, &[PANEL_SETTING.to_u8(), 0x0D])?;
 
-        //resolution setting
-        self.interface
-            .cmd_with_data(spi, Command::ResolutionSetting, &[0x80, 0x01, 0x28])?;
+        Controller::set_resolution(&mut self.interface, spi)?;
 
         self.interface.cmd(spi, Command::PowerOn)?;
         self.wait_until_idle(spi, delay)?;
 
-        //VCOM AND DATA INTERVAL SETTING
-        self.interface
-            .cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x97])?;
+        let interval = VcomDataInterval {
+            border: 0b10,
+            data_polarity: 0b01,
+            interval: 0x7,
+        };
+        self.interface.cmd_with_data(
+            spi,
+            Command::VcomAndDataIntervalSetting,
+            &[interval.to_u8()],
+        )?;
+
+        Ok(())
+    }
+}
 
+impl<SPI, BUSY, DC, RST, DELAY> Sleepable<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in9d<'_, SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.is_partial_refresh = false;
+        // floating border
+        let interval = VcomDataInterval {
+            border: 0b11,
+            data_polarity: 0b11,
+            interval: 0x7,
+        };
+        self.interface.cmd_with_data(
+            spi,
+            Command::VcomAndDataIntervalSetting,
+            &[interval.to_u8()],
+        )?;
+        self.interface.cmd(spi, Command::PowerOff)?;
+        self.wait_until_idle(spi, delay)?;
+        delay.delay_us(100_000);
+        Controller::deep_sleep(&mut self.interface, spi)?;
+
+        Ok(())
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)?;
         Ok(())
     }
 }
@@ -113,7 +222,7 @@ where
         rst: RST,
         delay: &mut DELAY,
         delay_us: Option<u32>,
-    ) -> Result<Self, SPI::Error> {
+    ) -> Result<Self, Error<SPI::Error>> {
         let interface = DisplayInterface::new(busy, dc, rst, delay_us);
         let color = DEFAULT_BACKGROUND_COLOR;
         let old_data: &[u8] = &[];
@@ -127,29 +236,13 @@ where
             is_partial_refresh,
         };
 
+        #[cfg(any(feature = "crc", test))]
+        epd.interface.track_crc(Command::DataStartTransmission2);
         epd.init(spi, delay)?;
 
         Ok(epd)
     }
 
-    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.is_partial_refresh = false;
-        self.interface
-            .cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0xf7])?;
-        self.interface.cmd(spi, Command::PowerOff)?;
-        self.wait_until_idle(spi, delay)?;
-        delay.delay_us(100_000);
-        self.interface
-            .cmd_with_data(spi, Command::DeepSleep, &[0xA5])?;
-
-        Ok(())
-    }
-
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.init(spi, delay)?;
-        Ok(())
-    }
-
     fn set_background_color(&mut self, background_color: Color) {
         self.color = background_color;
     }
@@ -173,7 +266,8 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         if self.is_partial_refresh {
             // Modify local refresh status if full refresh is performed.
             self.is_partial_refresh = false;
@@ -191,47 +285,9 @@ where
 
     // 这个是DisplayPart
     // Partial refresh write address and data
-    fn update_partial_frame(
-        &mut self,
-        spi: &mut SPI,
-        delay: &mut DELAY,
-        buffer: &[u8],
-        x: u32,
-        y: u32,
-        width: u32,
-        height: u32,
-    ) -> Result<(), SPI::Error> {
-        if !self.is_partial_refresh {
-            // Initialize only on first call
-            self.set_part_reg(spi, delay)?;
-            self.is_partial_refresh = true;
-        }
-        self.interface.cmd(spi, Command::PartialIn)?;
-
-        self.interface.cmd(spi, Command::PartialWindow)?;
-        self.interface.data(spi, &[(x - x % 8) as u8])?;
-        self.interface
-            .data(spi, &[(((x - x % 8) + width - 1) - 1) as u8])?;
-        self.interface.data(spi, &[(y / 256) as u8])?;
-        self.interface.data(spi, &[(y % 256) as u8])?;
-        self.interface
-            .data(spi, &[((y + height - 1) / 256) as u8])?;
-        self.interface
-            .data(spi, &[((y + height - 1) % 256 - 1) as u8])?;
-        self.interface.data(spi, &[0x28])?;
-
-        self.interface
-            .cmd_with_data(spi, Command::DataStartTransmission1, self.old_data)?;
-
-        self.interface
-            .cmd_with_data(spi, Command::DataStartTransmission2, buffer)?;
-        self.old_data = unsafe { from_raw_parts(buffer.as_ptr(), buffer.len()) };
-
-        Ok(())
-    }
 
     /// actually is the "Turn on Display" sequence
-    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd(spi, Command::DisplayRefresh)?;
         delay.delay_us(1_000);
         self.wait_until_idle(spi, delay)?;
@@ -243,13 +299,13 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, buffer, delay)?;
         self.display_frame(spi, delay)?;
         Ok(())
     }
 
-    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd(spi, Command::DataStartTransmission1)?;
         self.interface.data_x_times(spi, 0x00, EPD_ARRAY)?;
 
@@ -261,24 +317,123 @@ where
         Ok(())
     }
 
+    fn wait_until_idle(
+        &mut self,
+        _spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_until_idle(delay, IS_BUSY_LOW)?;
+        Ok(())
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> LutControl<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in9d<'_, SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
     fn set_lut(
         &mut self,
         spi: &mut SPI,
         delay: &mut DELAY,
         refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         if let Some(refresh_lut) = refresh_rate {
             self.refresh = refresh_lut;
         }
-        self.set_lut_helper(
-            spi, delay, &LUT_VCOM1, &LUT_WW1, &LUT_BW1, &LUT_WB1, &LUT_BB1,
-        )?;
+        let _ = delay;
+        Controller::set_luts(
+            &mut self.interface,
+            spi,
+            [&LUT_VCOM1, &LUT_WW1, &LUT_BW1, &LUT_WB1, &LUT_BB1],
+        )
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> PartialUpdate<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in9d<'_, SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        if !self.is_partial_refresh {
+            // Initialize only on first call
+            self.set_part_reg(spi, delay)?;
+            self.is_partial_refresh = true;
+        }
+        self.interface.cmd(spi, Command::PartialIn)?;
+
+        Controller::set_partial_window(&mut self.interface, spi, x, y, width, height, false)?;
+
+        self.interface
+            .cmd_with_data(spi, Command::DataStartTransmission1, self.old_data)?;
+
+        self.interface
+            .cmd_with_data(spi, Command::DataStartTransmission2, buffer)?;
+        self.old_data = unsafe { from_raw_parts(buffer.as_ptr(), buffer.len()) };
 
         Ok(())
     }
+}
 
-    fn wait_until_idle(&mut self, _spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.interface.wait_until_idle(delay, IS_BUSY_LOW);
+impl<SPI, BUSY, DC, RST, DELAY> PartialRefresh<SPI, BUSY, DC, RST, DELAY>
+    for Epd2in9d<'_, SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Keeps `buffer` as the old data sent with the next partial update, like
+    /// [`update_frame`](WaveshareDisplay::update_frame) does
+    fn set_base_image(
+        &mut self,
+        _spi: &mut SPI,
+        _delay: &mut DELAY,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.old_data = unsafe { from_raw_parts(buffer.as_ptr(), buffer.len()) };
+        Ok(())
+    }
+
+    fn set_update_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mode: RefreshLut,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.refresh = mode;
+        match mode {
+            RefreshLut::Quick if !self.is_partial_refresh => {
+                self.set_part_reg(spi, delay)?;
+                self.is_partial_refresh = true;
+            }
+            // the full refreshes use the waveforms of the OTP again
+            RefreshLut::Full | RefreshLut::Fast if self.is_partial_refresh => {
+                self.init(spi, delay)?;
+                self.is_partial_refresh = false;
+            }
+            _ => {}
+        }
         Ok(())
     }
 }
@@ -295,7 +450,7 @@ where
     // ///
     // /// After the screen sleeps, it enters deep sleep mode. If you need to refresh the screen while in deep sleep mode, you must first execute awaken().
     // /// Wake the screen.
-    // fn awaken(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    // fn awaken(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
     //     // reset the device
     //     self.interface.reset(delay, 20_000, 2_000);
     //     self.wait_until_idle(spi, delay)?;
@@ -315,26 +470,37 @@ where
     //     Ok(())
     // }
 
-    fn set_part_reg(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn set_part_reg(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         // Reset the EPD driver circuit
         //TODO: 这里在微雪的例程中反复刷新了3次，后面有显示问题再进行修改
-        self.interface.reset(delay, 10_000, 2_000);
+        self.interface.reset(delay, 10_000, 2_000)?;
 
         // Power settings
         //TODO: The data in the document is [0x03,0x00,0x2b,0x2b,0x09].
-        self.interface.cmd_with_data(
-            spi,
-            Command::PowerSetting,
-            &[0x03, 0x00, 0x2b, 0x2b, 0x03],
-        )?;
+        let power = PowerSetting {
+            internal_source: true,
+            internal_gate: true,
+            vcom_high_voltage: false,
+            gate_level: 0,
+            vdh: 0x2b,
+            vdl: 0x2b,
+            vdhr: 0x03,
+        };
+        self.interface
+            .cmd_with_data(spi, Command::PowerSetting, &power.to_bytes())?;
 
         // Soft start
         self.interface
             .cmd_with_data(spi, Command::BoosterSoftStart, &[0x17, 0x17, 0x17])?;
 
-        // Panel settings
+        // Panel settings, with the LUTs of `set_lut`
+        let panel = PanelSetting {
+            resolution: PanelResolution::R128x296,
+            lut_from_register: true,
+            ..PANEL_SETTING
+        };
         self.interface
-            .cmd_with_data(spi, Command::PanelSetting, &[0xbf, 0x0D])?;
+            .cmd_with_data(spi, Command::PanelSetting, &[panel.to_u8(), 0x0D])?;
 
         // Setting the refresh rate
         // 3a 100HZ | 29 150Hz | 39 200HZ | 31 171HZ
@@ -342,9 +508,7 @@ where
         self.interface
             .cmd_with_data(spi, Command::PllControl, &[0x3C])?;
 
-        // Resolution Settings
-        self.interface
-            .cmd_with_data(spi, Command::ResolutionSetting, &[0x80, 0x01, 0x28])?;
+        Controller::set_resolution(&mut self.interface, spi)?;
 
         // vcom_DC settings
         self.interface
@@ -370,38 +534,4 @@ where
 
         Ok(())
     }
-
-    #[allow(clippy::too_many_arguments)]
-    fn set_lut_helper(
-        &mut self,
-        spi: &mut SPI,
-        delay: &mut DELAY,
-        lut_vcom: &[u8],
-        lut_ww: &[u8],
-        lut_bw: &[u8],
-        lut_wb: &[u8],
-        lut_bb: &[u8],
-    ) -> Result<(), SPI::Error> {
-        let _ = delay;
-        // LUT VCOM
-        self.interface
-            .cmd_with_data(spi, Command::LutForVcom, lut_vcom)?;
-
-        // LUT WHITE to WHITE
-        self.interface
-            .cmd_with_data(spi, Command::LutWhiteToWhite, lut_ww)?;
-
-        // LUT BLACK to WHITE
-        self.interface
-            .cmd_with_data(spi, Command::LutBlackToWhite, lut_bw)?;
-
-        // LUT WHITE to BLACK
-        self.interface
-            .cmd_with_data(spi, Command::LutWhiteToBlack, lut_wb)?;
-
-        // LUT BLACK to BLACK
-        self.interface
-            .cmd_with_data(spi, Command::LutBlackToBlack, lut_bb)?;
-        Ok(())
-    }
 }
@@ -2,6 +2,20 @@
 //!
 //!
 //! Build with the help of documentation/code from [Waveshare](https://www.waveshare.com/wiki/3.7inch_e-Paper_HAT),
+//!
+//! Besides black and white, the display can show 4 gray levels: switch to them with
+//! [`EPD3in7::set_gray_mode`] and send a [`Display3in7Gray`] with [`EPD3in7::update_gray_frame`].
+//!
+//!```rust, ignore
+//!use epd_waveshare::{epd3in7::*, prelude::*};
+//!
+//!let mut epd = EPD3in7::new(&mut spi, busy_in, dc, rst, &mut delay, None)?;
+//!let mut display = Display3in7Gray::default();
+//!display.clear(Gray4Epd::LightGray)?;
+//!epd.set_gray_mode(&mut spi, &mut delay, true)?;
+//!epd.update_gray_frame(&mut spi, display.buffer())?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!```
 use embedded_hal::{
     delay::DelayNs,
     digital::{InputPin, OutputPin},
@@ -15,9 +29,10 @@ use self::command::Command;
 use self::constants::*;
 
 use crate::buffer_len;
-use crate::color::Color;
-use crate::interface::DisplayInterface;
-use crate::traits::{InternalWiAdditions, RefreshLut, WaveshareDisplay};
+use crate::color::{Color, Gray4Epd};
+use crate::error::Error;
+use crate::interface::{BusyYield, DisplayInterface};
+use crate::traits::{InternalWiAdditions, LutControl, RefreshLut, Sleepable, WaveshareDisplay};
 
 /// Width of the display.
 pub const WIDTH: u32 = 280;
@@ -42,12 +57,75 @@ pub type Display3in7 = crate::graphics::Display<
     Color,
 >;
 
+/// Display with Fullsize buffer of 4 gray levels for use with the 3in7 EPD
+#[cfg(feature = "graphics")]
+pub type Display3in7Gray = crate::graphics::Display<
+    WIDTH,
+    HEIGHT,
+    false,
+    { buffer_len(WIDTH as usize, HEIGHT as usize) * 2 },
+    crate::color::Gray4Epd,
+>;
+
 /// EPD3in7 driver
 pub struct EPD3in7<SPI, BUSY, DC, RST, DELAY> {
     /// Connection Interface
     interface: DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
     /// Background Color
     background_color: Color,
+    /// Refreshes with the 4 gray levels waveform
+    gray: bool,
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> EPD3in7<SPI, BUSY, DC, RST, DELAY> {
+    /// Gives back the busy, data/command and reset pins, e.g. to reuse them once the display
+    /// is asleep
+    ///
+    /// The display keeps its state, so a driver can be created again later with the pins.
+    pub fn release(self) -> (BUSY, DC, RST) {
+        self.interface.release()
+    }
+
+    /// Gives up waiting for the display with [`Error::BusyTimeout`] once it has been busy for
+    /// `timeout_us`, e.g. because of a loose cable, instead of waiting forever. `None` removes
+    /// the timeout.
+    ///
+    /// The time is counted by adding up the delays of the idle loop, each poll of the busy pin
+    /// counting as at least 1us.
+    pub fn set_busy_timeout(&mut self, timeout_us: Option<u32>) {
+        self.interface.set_busy_timeout(timeout_us);
+    }
+
+    /// Polls the busy pin every `interval_us` while the display is busy (0 busy waits), or,
+    /// with a `max_interval_us`, backs off: the interval doubles after every poll until
+    /// `max_interval_us`
+    ///
+    /// The interval is the `delay_us` given to `new` (10ms by default) until this is called.
+    pub fn set_poll_interval(&mut self, interval_us: u32, max_interval_us: Option<u32>) {
+        self.interface.set_poll_interval(interval_us);
+        self.interface.set_poll_backoff(max_interval_us);
+    }
+
+    /// Sets a [`BusyYield`] called on every poll of the busy pin while the display is busy, e.g.
+    /// to feed a watchdog during a refresh. `None` removes it.
+    pub fn set_busy_yield(&mut self, busy_yield: Option<BusyYield>) {
+        self.interface.set_busy_yield(busy_yield);
+    }
+
+    /// Time the display was busy during the last wait for it, counted like the busy timeout,
+    /// e.g. to choose the timeout
+    pub fn last_wait_us(&self) -> u32 {
+        self.interface.last_wait_us()
+    }
+
+    /// CRC-32 of the data of the last frame written to the display, e.g. by
+    /// [`update_frame`](WaveshareDisplay::update_frame), to compare with the
+    /// [`crc32`](crate::crc::crc32) of the expected image and detect a corrupted transfer. `None`
+    /// until a frame is written.
+    #[cfg(any(feature = "crc", test))]
+    pub fn last_frame_crc(&self) -> Option<u32> {
+        self.interface.last_crc()
+    }
 }
 
 impl<SPI, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, BUSY, DC, RST, DELAY>
@@ -59,19 +137,19 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         // reset the device
-        self.interface.reset(delay, 30, 10);
+        self.interface.reset(delay, 30, 10)?;
 
         self.interface.cmd(spi, Command::SwReset)?;
         delay.delay_us(300000u32);
 
         self.interface
             .cmd_with_data(spi, Command::AutoWriteRedRamRegularPattern, &[0xF7])?;
-        self.interface.wait_until_idle(delay, IS_BUSY_LOW);
+        self.interface.wait_until_idle(delay, IS_BUSY_LOW)?;
         self.interface
             .cmd_with_data(spi, Command::AutoWriteBwRamRegularPattern, &[0xF7])?;
-        self.interface.wait_until_idle(delay, IS_BUSY_LOW);
+        self.interface.wait_until_idle(delay, IS_BUSY_LOW)?;
 
         self.interface
             .cmd_with_data(spi, Command::GateSetting, &[0xDF, 0x01, 0x00])?;
@@ -123,6 +201,28 @@ where
     }
 }
 
+impl<SPI, BUSY, DC, RST, DELAY> Sleepable<SPI, BUSY, DC, RST, DELAY>
+    for EPD3in7<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.cmd_with_data(spi, Command::Sleep, &[0xF7])?;
+        self.interface.cmd(spi, Command::PowerOff)?;
+        self.interface
+            .cmd_with_data(spi, Command::Sleep2, &[0xA5])?;
+        Ok(())
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+}
+
 impl<SPI, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
     for EPD3in7<SPI, BUSY, DC, RST, DELAY>
 where
@@ -141,28 +241,19 @@ where
         rst: RST,
         delay: &mut DELAY,
         delay_us: Option<u32>,
-    ) -> Result<Self, SPI::Error> {
+    ) -> Result<Self, Error<SPI::Error>> {
         let mut epd = EPD3in7 {
             interface: DisplayInterface::new(busy, dc, rst, delay_us),
             background_color: DEFAULT_BACKGROUND_COLOR,
+            gray: false,
         };
 
+        #[cfg(any(feature = "crc", test))]
+        epd.interface.track_crc(Command::WriteRam);
         epd.init(spi, delay)?;
         Ok(epd)
     }
 
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.init(spi, delay)
-    }
-
-    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.interface.cmd_with_data(spi, Command::Sleep, &[0xF7])?;
-        self.interface.cmd(spi, Command::PowerOff)?;
-        self.interface
-            .cmd_with_data(spi, Command::Sleep2, &[0xA5])?;
-        Ok(())
-    }
-
     fn set_background_color(&mut self, color: Self::DisplayColor) {
         self.background_color = color;
     }
@@ -184,8 +275,8 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         _delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
-        assert!(buffer.len() == buffer_len(WIDTH as usize, HEIGHT as usize));
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         self.interface
             .cmd_with_data(spi, Command::SetRamXAddressCounter, &[0x00, 0x00])?;
         self.interface
@@ -197,25 +288,11 @@ where
         Ok(())
     }
 
-    #[allow(unused)]
-    fn update_partial_frame(
-        &mut self,
-        spi: &mut SPI,
-        delay: &mut DELAY,
-        buffer: &[u8],
-        x: u32,
-        y: u32,
-        width: u32,
-        height: u32,
-    ) -> Result<(), SPI::Error> {
-        todo!()
-    }
-
-    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         //self.interface
         //    .cmd_with_data(spi, Command::WRITE_LUT_REGISTER, &LUT_1GRAY_GC)?;
         self.interface.cmd(spi, Command::DisplayUpdateSequence)?;
-        self.interface.wait_until_idle(delay, IS_BUSY_LOW);
+        self.interface.wait_until_idle(delay, IS_BUSY_LOW)?;
         Ok(())
     }
 
@@ -224,13 +301,13 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, buffer, delay)?;
         self.display_frame(spi, delay)?;
         Ok(())
     }
 
-    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.interface
             .cmd_with_data(spi, Command::SetRamXAddressCounter, &[0x00, 0x00])?;
         self.interface
@@ -243,14 +320,34 @@ where
         Ok(())
     }
 
+    fn wait_until_idle(
+        &mut self,
+        _spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_until_idle(delay, IS_BUSY_LOW)?;
+        Ok(())
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> LutControl<SPI, BUSY, DC, RST, DELAY>
+    for EPD3in7<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
     fn set_lut(
         &mut self,
         spi: &mut SPI,
         _delay: &mut DELAY,
         refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         let buffer = match refresh_rate {
-            Some(RefreshLut::Full) | None => &LUT_1GRAY_GC,
+            Some(RefreshLut::Full | RefreshLut::Fast) | None if self.gray => &LUT_4GRAY_GC,
+            Some(RefreshLut::Full | RefreshLut::Fast) | None => &LUT_1GRAY_GC,
             Some(RefreshLut::Quick) => &LUT_1GRAY_DU,
         };
 
@@ -258,9 +355,59 @@ where
             .cmd_with_data(spi, Command::WriteLutRegister, buffer)?;
         Ok(())
     }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> EPD3in7<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Switches between the 4 gray levels and the black/white full refreshes
+    ///
+    /// In gray mode, frames are sent with [`update_gray_frame`](Self::update_gray_frame).
+    /// `RefreshLut::Quick` keeps refreshing in black and white only.
+    pub fn set_gray_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        gray: bool,
+    ) -> Result<(), Error<SPI::Error>> {
+        if self.gray && !gray {
+            // the black and white waveforms expect the red RAM to be left as on init
+            self.interface
+                .cmd_with_data(spi, Command::AutoWriteRedRamRegularPattern, &[0xF7])?;
+            self.interface.wait_until_idle(delay, IS_BUSY_LOW)?;
+        }
+        self.gray = gray;
+        self.set_lut(spi, delay, Some(RefreshLut::Full))
+    }
 
-    fn wait_until_idle(&mut self, _spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.interface.wait_until_idle(delay, IS_BUSY_LOW);
+    /// Returns `true` if full refreshes show 4 gray levels
+    pub fn is_gray_mode(&self) -> bool {
+        self.gray
+    }
+
+    /// Transmits a full frame of 4 gray levels, 2 bits per pixel, to the SRAM of the EPD
+    ///
+    /// The high bits of the pixels go to the B/W RAM and the low bits to the red RAM.
+    pub fn update_gray_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize) * 2)?;
+        for (command, bit) in [(Command::WriteRam, 1), (Command::WriteRam2, 0)] {
+            self.interface
+                .cmd_with_data(spi, Command::SetRamXAddressCounter, &[0x00, 0x00])?;
+            self.interface
+                .cmd_with_data(spi, Command::SetRamYAddressCounter, &[0x00, 0x00])?;
+            self.interface.cmd(spi, command)?;
+            self.interface
+                .data_iter(spi, Gray4Epd::plane(buffer, WIDTH, bit))?;
+        }
         Ok(())
     }
 }
@@ -0,0 +1,607 @@
+//! A simple Driver for the Waveshare 4.26" E-Ink Display via SPI
+//!
+//! # References
+//!
+//! - [Wiki](https://www.waveshare.com/wiki/4.26inch_e-Paper_HAT)
+//! - [Waveshare C driver](https://github.com/waveshareteam/e-Paper/blob/master/RaspberryPi_JetsonNano/c/lib/e-Paper/EPD_4in26.c)
+//!
+//! The display is driven by a SSD1677, addressing its 800 columns on 16 bits. Besides the full
+//! refreshes, it has a fast mode selected with [`RefreshLut::Fast`], partial refreshes
+//! selected with [`RefreshLut::Quick`], and can show 4 gray levels: switch to them with
+//! [`Epd4in26::set_gray_mode`] and send a [`Display4in26Gray`] with
+//! [`Epd4in26::update_gray_frame`].
+//!
+//!```rust, ignore
+//!use epd_waveshare::{epd4in26::*, prelude::*};
+//!
+//!let mut epd = Epd4in26::new(&mut spi, busy_in, dc, rst, &mut delay, None)?;
+//!let mut display = Display4in26Gray::default();
+//!display.clear(Gray4Epd::DarkGray)?;
+//!epd.set_gray_mode(&mut spi, &mut delay, true)?;
+//!epd.update_gray_frame(&mut spi, &mut delay, display.buffer())?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!```
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{InputPin, OutputPin},
+    spi::SpiDevice,
+};
+
+use crate::buffer_len;
+use crate::color::{Color, Gray4Epd};
+use crate::controllers::ssd1677::{Command, Ssd1677, IS_BUSY_LOW};
+use crate::error::Error;
+use crate::interface::{BusyYield, DisplayInterface};
+use crate::traits::{
+    DeepSleepMode, InternalWiAdditions, LutControl, PartialRefresh, PartialUpdate, RefreshLut,
+    Sleepable, WaveshareDisplay,
+};
+
+/// Full size buffer for use with the 4in26 EPD
+#[cfg(feature = "graphics")]
+pub type Display4in26 = crate::graphics::Display<
+    WIDTH,
+    HEIGHT,
+    false,
+    { buffer_len(WIDTH as usize, HEIGHT as usize) },
+    Color,
+>;
+
+/// Full size buffer of 4 gray levels for use with the 4in26 EPD
+#[cfg(feature = "graphics")]
+pub type Display4in26Gray = crate::graphics::Display<
+    WIDTH,
+    HEIGHT,
+    false,
+    { buffer_len(WIDTH as usize, HEIGHT as usize) * 2 },
+    Gray4Epd,
+>;
+
+/// Width of the display
+pub const WIDTH: u32 = 800;
+/// Height of the display
+pub const HEIGHT: u32 = 480;
+/// Default Background Color
+pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+const SINGLE_BYTE_WRITE: bool = false;
+
+/// Temperature written to the controller to select the waveforms of the fast and gray modes
+const FAST_TEMPERATURE: u8 = 0x5A;
+
+type Controller = Ssd1677<WIDTH, HEIGHT>;
+
+/// Epd4in26 driver
+pub struct Epd4in26<SPI, BUSY, DC, RST, DELAY> {
+    /// Connection Interface
+    interface: DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+    /// Background Color
+    color: Color,
+    /// Refresh LUT
+    refresh: RefreshLut,
+    /// Refreshes with the 4 gray levels waveform
+    gray: bool,
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Epd4in26<SPI, BUSY, DC, RST, DELAY> {
+    /// Gives back the busy, data/command and reset pins, e.g. to reuse them once the display
+    /// is asleep
+    ///
+    /// The display keeps its state, so a driver can be created again later with the pins.
+    pub fn release(self) -> (BUSY, DC, RST) {
+        self.interface.release()
+    }
+
+    /// Gives up waiting for the display with [`Error::BusyTimeout`] once it has been busy for
+    /// `timeout_us`, e.g. because of a loose cable, instead of waiting forever. `None` removes
+    /// the timeout.
+    ///
+    /// The time is counted by adding up the delays of the idle loop, each poll of the busy pin
+    /// counting as at least 1us.
+    pub fn set_busy_timeout(&mut self, timeout_us: Option<u32>) {
+        self.interface.set_busy_timeout(timeout_us);
+    }
+
+    /// Polls the busy pin every `interval_us` while the display is busy (0 busy waits), or,
+    /// with a `max_interval_us`, backs off: the interval doubles after every poll until
+    /// `max_interval_us`
+    ///
+    /// The interval is the `delay_us` given to `new` (10ms by default) until this is called.
+    pub fn set_poll_interval(&mut self, interval_us: u32, max_interval_us: Option<u32>) {
+        self.interface.set_poll_interval(interval_us);
+        self.interface.set_poll_backoff(max_interval_us);
+    }
+
+    /// Sets a [`BusyYield`] called on every poll of the busy pin while the display is busy, e.g.
+    /// to feed a watchdog during a refresh. `None` removes it.
+    pub fn set_busy_yield(&mut self, busy_yield: Option<BusyYield>) {
+        self.interface.set_busy_yield(busy_yield);
+    }
+
+    /// Time the display was busy during the last wait for it, counted like the busy timeout,
+    /// e.g. to choose the timeout
+    pub fn last_wait_us(&self) -> u32 {
+        self.interface.last_wait_us()
+    }
+
+    /// CRC-32 of the data of the last frame written to the display, e.g. by
+    /// [`update_frame`](WaveshareDisplay::update_frame), to compare with the
+    /// [`crc32`](crate::crc::crc32) of the expected image and detect a corrupted transfer. `None`
+    /// until a frame is written.
+    #[cfg(any(feature = "crc", test))]
+    pub fn last_frame_crc(&self) -> Option<u32> {
+        self.interface.last_crc()
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, BUSY, DC, RST, DELAY>
+    for Epd4in26<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 20_000, 2_000)?;
+        self.wait_until_idle(spi, delay)?;
+        self.interface.cmd(spi, Command::SwReset)?;
+        self.wait_until_idle(spi, delay)?;
+
+        // internal temperature sensor
+        self.interface
+            .cmd_with_data(spi, Command::TemperatureSensorSelection, &[0x80])?;
+        self.interface.cmd_with_data(
+            spi,
+            Command::BoosterSoftStartControl,
+            &[0xAE, 0xC7, 0xC3, 0xC0, 0x80],
+        )?;
+        self.interface.cmd_with_data(
+            spi,
+            Command::DriverOutputControl,
+            &[(HEIGHT - 1) as u8, ((HEIGHT - 1) >> 8) as u8, 0x02],
+        )?;
+        // x increment, y increment, address counter is updated in x direction
+        self.interface
+            .cmd_with_data(spi, Command::DataEntryModeSetting, &[0x03])?;
+        Controller::set_full_window(&mut self.interface, spi, delay)?;
+        self.wait_until_idle(spi, delay)?;
+
+        let refresh = self.refresh;
+        self.set_lut(spi, delay, Some(refresh))
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Sleepable<SPI, BUSY, DC, RST, DELAY>
+    for Epd4in26<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.sleep_with_mode(spi, delay, DeepSleepMode::Mode1)
+    }
+
+    fn sleep_with_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mode: DeepSleepMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        // the SSD1677 uses 0x03 for its second deep sleep mode
+        let mode = match mode {
+            DeepSleepMode::Normal => 0x00,
+            DeepSleepMode::Mode1 => 0x01,
+            DeepSleepMode::Mode2 => 0x03,
+        };
+        self.wait_until_idle(spi, delay)?;
+        self.interface
+            .cmd_with_data(spi, Command::DeepSleepMode, &[mode])
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
+    for Epd4in26<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    type DisplayColor = Color;
+
+    fn new(
+        spi: &mut SPI,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+        delay_us: Option<u32>,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let mut epd = Epd4in26 {
+            interface: DisplayInterface::new(busy, dc, rst, delay_us),
+            color: DEFAULT_BACKGROUND_COLOR,
+            refresh: RefreshLut::Full,
+            gray: false,
+        };
+
+        #[cfg(any(feature = "crc", test))]
+        epd.interface.track_crc(Command::WriteRam);
+        epd.init(spi, delay)?;
+        Ok(epd)
+    }
+
+    fn set_background_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    fn background_color(&self) -> &Color {
+        &self.color
+    }
+
+    fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        Controller::set_ram_address_counters(&mut self.interface, spi, delay, 0, 0)?;
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.cmd_with_data(
+            spi,
+            Command::DisplayUpdateControl2,
+            &[display_update(self.refresh, self.gray)],
+        )?;
+        self.interface.cmd(spi, Command::MasterActivation)?;
+        self.wait_until_idle(spi, delay)
+    }
+
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.display_frame(spi, delay)
+    }
+
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        Controller::set_ram_address_counters(&mut self.interface, spi, delay, 0, 0)?;
+        self.interface.cmd(spi, Command::WriteRam)?;
+        self.interface.data_x_times(
+            spi,
+            self.color.get_byte_value(),
+            buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
+        )
+    }
+
+    fn wait_until_idle(
+        &mut self,
+        _spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_until_idle(delay, IS_BUSY_LOW)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> LutControl<SPI, BUSY, DC, RST, DELAY>
+    for Epd4in26<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Selects the full, fast or partial refreshes
+    ///
+    /// The partial refreshes of `RefreshLut::Quick` compare the new frame with the previous
+    /// one, only changing the pixels which differ.
+    fn set_lut(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(refresh_lut) = refresh_rate {
+            self.refresh = refresh_lut;
+        }
+        // the border keeps its color during the partial refreshes
+        let border = if self.refresh == RefreshLut::Quick {
+            0x80
+        } else {
+            0x01
+        };
+        self.interface
+            .cmd_with_data(spi, Command::BorderWaveformControl, &[border])?;
+        if self.gray || self.refresh == RefreshLut::Fast {
+            // the waveforms of the fast and gray modes are loaded for a fixed temperature
+            self.interface.cmd_with_data(
+                spi,
+                Command::TemperatureSensorWrite,
+                &[FAST_TEMPERATURE],
+            )?;
+            self.interface
+                .cmd_with_data(spi, Command::DisplayUpdateControl2, &[0x91])?;
+            self.interface.cmd(spi, Command::MasterActivation)?;
+            self.wait_until_idle(spi, delay)?;
+        }
+        Ok(())
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> PartialUpdate<SPI, BUSY, DC, RST, DELAY>
+    for Epd4in26<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// `x` and `width` must be multiples of 8
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(width as usize, height as usize))?;
+        self.wait_until_idle(spi, delay)?;
+        Controller::set_ram_area(
+            &mut self.interface,
+            spi,
+            x,
+            y,
+            x + width - 1,
+            y + height - 1,
+        )?;
+        Controller::set_ram_address_counters(&mut self.interface, spi, delay, x, y)?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam, buffer)?;
+        Controller::set_full_window(&mut self.interface, spi, delay)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> PartialRefresh<SPI, BUSY, DC, RST, DELAY>
+    for Epd4in26<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Writes `buffer` to the B/W RAM and to the red RAM, which holds the previous frame
+    /// during the partial refreshes
+    fn set_base_image(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.wait_until_idle(spi, delay)?;
+        for command in [Command::WriteRam, Command::WriteRamRed] {
+            Controller::set_ram_address_counters(&mut self.interface, spi, delay, 0, 0)?;
+            self.interface.cmd_with_data(spi, command, buffer)?;
+        }
+        Ok(())
+    }
+
+    fn set_update_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mode: RefreshLut,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.set_lut(spi, delay, Some(mode))
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Epd4in26<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Sets the largest number of bytes written in a single SPI transfer, longer writes being
+    /// split, e.g. for DMA engines with a limited transfer size. `None` writes a whole frame
+    /// at once.
+    ///
+    /// It is 4096 bytes on Linux, the default limit of spidev, and unlimited elsewhere.
+    pub fn set_max_transfer_size(&mut self, size: Option<usize>) {
+        self.interface.set_max_transfer_size(size);
+    }
+
+    /// Switches between the 4 gray levels and the black/white refreshes
+    ///
+    /// In gray mode, frames are sent with [`update_gray_frame`](Self::update_gray_frame).
+    pub fn set_gray_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        gray: bool,
+    ) -> Result<(), Error<SPI::Error>> {
+        if self.gray && !gray {
+            // the black and white refreshes expect an empty red RAM
+            Controller::set_ram_address_counters(&mut self.interface, spi, delay, 0, 0)?;
+            self.interface.cmd(spi, Command::WriteRamRed)?;
+            self.interface.data_x_times(
+                spi,
+                0x00,
+                buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
+            )?;
+        }
+        self.gray = gray;
+        self.set_lut(spi, delay, None)
+    }
+
+    /// Returns `true` if refreshes show 4 gray levels
+    pub fn is_gray_mode(&self) -> bool {
+        self.gray
+    }
+
+    /// Transmits a full frame of 4 gray levels, 2 bits per pixel, to the SRAM of the EPD
+    ///
+    /// The high bits of the pixels go to the B/W RAM and the low bits to the red RAM.
+    pub fn update_gray_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize) * 2)?;
+        for (command, bit) in [(Command::WriteRam, 1), (Command::WriteRamRed, 0)] {
+            Controller::set_ram_address_counters(&mut self.interface, spi, delay, 0, 0)?;
+            self.interface.cmd(spi, command)?;
+            self.interface
+                .data_iter(spi, Gray4Epd::plane(buffer, WIDTH, bit))?;
+        }
+        Ok(())
+    }
+}
+
+/// Display update sequence of a refresh: mode 1 for the full refreshes, mode 2 with the
+/// waveform loaded for [`FAST_TEMPERATURE`] for the fast and gray ones, and mode 2 with the
+/// measured temperature for the partial ones
+fn display_update(refresh: RefreshLut, gray: bool) -> u8 {
+    match (refresh, gray) {
+        (_, true) => 0xCF,
+        (RefreshLut::Full, false) => 0xF7,
+        (RefreshLut::Fast, false) => 0xC7,
+        (RefreshLut::Quick, false) => 0xFF,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epd_size() {
+        assert_eq!(WIDTH, 800);
+        assert_eq!(HEIGHT, 480);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+    }
+
+    #[test]
+    fn update_sequences() {
+        assert_eq!(display_update(RefreshLut::Full, false), 0xF7);
+        assert_eq!(display_update(RefreshLut::Fast, false), 0xC7);
+        assert_eq!(display_update(RefreshLut::Quick, false), 0xFF);
+        // the gray levels take precedence over the fast mode
+        assert_eq!(display_update(RefreshLut::Full, true), 0xCF);
+        assert_eq!(display_update(RefreshLut::Fast, true), 0xCF);
+    }
+
+    #[test]
+    fn sleep_with_mode() {
+        extern crate std;
+        use crate::testing::{Entry, Recorder};
+        use embedded_hal::digital::PinState;
+
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let (dc, rst) = (recorder.dc(), recorder.rst());
+        let mut epd = Epd4in26::new(&mut spi, busy, dc, rst, &mut delay, None).unwrap();
+
+        for (mode, value) in [
+            (DeepSleepMode::Normal, 0x00),
+            (DeepSleepMode::Mode1, 0x01),
+            (DeepSleepMode::Mode2, 0x03),
+        ] {
+            recorder.clear();
+            epd.sleep_with_mode(&mut spi, &mut delay, mode).unwrap();
+            assert_eq!(
+                recorder.transcript().entries(),
+                [
+                    Entry::Command(Command::DeepSleepMode as u8),
+                    Entry::Data(std::vec![value]),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn partial_refresh() {
+        extern crate std;
+        use crate::testing::{Entry, Recorder};
+        use embedded_hal::digital::PinState;
+
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let (dc, rst) = (recorder.dc(), recorder.rst());
+        let mut epd = Epd4in26::new(&mut spi, busy, dc, rst, &mut delay, None).unwrap();
+
+        recorder.clear();
+        epd.set_update_mode(&mut spi, &mut delay, RefreshLut::Quick)
+            .unwrap();
+        // no waveform is loaded for a fixed temperature
+        assert_eq!(
+            recorder.transcript().entries(),
+            [
+                Entry::Command(Command::BorderWaveformControl as u8),
+                Entry::Data(std::vec![0x80]),
+            ]
+        );
+
+        let frame = [0xA5; buffer_len(WIDTH as usize, HEIGHT as usize)];
+        assert_eq!(
+            epd.set_base_image(&mut spi, &mut delay, &frame[1..]),
+            Err(Error::WrongBufferSize {
+                expected: frame.len(),
+                got: frame.len() - 1
+            })
+        );
+        recorder.clear();
+        epd.set_base_image(&mut spi, &mut delay, &frame).unwrap();
+        let transcript = recorder.transcript();
+        let written = |command: Command| {
+            transcript
+                .entries()
+                .iter()
+                .skip_while(|e| **e != Entry::Command(command as u8))
+                .nth(1)
+                .cloned()
+        };
+        assert_eq!(
+            written(Command::WriteRam),
+            Some(Entry::Data(frame.to_vec()))
+        );
+        assert_eq!(
+            written(Command::WriteRamRed),
+            Some(Entry::Data(frame.to_vec()))
+        );
+    }
+}
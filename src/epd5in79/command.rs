@@ -0,0 +1,58 @@
+//! SSD1683 commands of the 5.79" display
+
+use crate::traits;
+
+/// Commands of the master controller, driving the left half of the display
+///
+/// The slave controller, driving the right half, takes the same commands through [`Slave`].
+#[allow(dead_code)]
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Command {
+    DriverOutputControl = 0x01,
+    DeepSleepMode = 0x10,
+    DataEntryModeSetting = 0x11,
+    SwReset = 0x12,
+    TemperatureSensorSelection = 0x18,
+    MasterActivation = 0x20,
+    DisplayUpdateControl1 = 0x21,
+    DisplayUpdateControl2 = 0x22,
+    WriteRam = 0x24,
+    WriteRamRed = 0x26,
+    BorderWaveformControl = 0x3C,
+    SetRamXAddressStartEndPosition = 0x44,
+    SetRamYAddressStartEndPosition = 0x45,
+    SetRamXAddressCounter = 0x4E,
+    SetRamYAddressCounter = 0x4F,
+}
+
+impl traits::Command for Command {
+    /// Returns the address of the command
+    fn address(self) -> u8 {
+        self as u8
+    }
+}
+
+/// A command sent to the slave controller only, the address having its highest bit set
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Slave(pub(crate) Command);
+
+impl traits::Command for Slave {
+    /// Returns the address of the command for the slave controller
+    fn address(self) -> u8 {
+        self.0 as u8 | 0x80
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::Command as CommandTrait;
+
+    #[test]
+    fn command_addr() {
+        assert_eq!(Command::WriteRam.address(), 0x24);
+        assert_eq!(Slave(Command::WriteRam).address(), 0xA4);
+        assert_eq!(Slave(Command::DataEntryModeSetting).address(), 0x91);
+        assert_eq!(Slave(Command::SetRamYAddressCounter).address(), 0xCF);
+    }
+}
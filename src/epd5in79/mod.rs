@@ -0,0 +1,374 @@
+//! A simple Driver for the Waveshare 5.79" E-Ink Display via SPI
+//!
+//! # References
+//!
+//! - [Wiki](https://www.waveshare.com/wiki/5.79inch_e-Paper_Module)
+//! - [Waveshare C driver](https://github.com/waveshareteam/e-Paper/blob/master/RaspberryPi_JetsonNano/c/lib/e-Paper/EPD_5in79.c)
+//!
+//! The display is driven by two SSD1683 on the same SPI bus: the master drives the left half
+//! and the slave the right half, mirrored. The slave takes the same commands as the master
+//! with the highest bit of their address set.
+//!
+//! The two halves overlap by one byte: the master gets the first 50 bytes of each line of the
+//! buffer and the slave the last 50, which [`update_frame`](WaveshareDisplay::update_frame)
+//! does with a buffer of the whole display.
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{InputPin, OutputPin},
+    spi::SpiDevice,
+};
+
+use crate::buffer_len;
+use crate::color::Color;
+use crate::error::Error;
+use crate::interface::{BusyYield, DisplayInterface};
+use crate::traits::{DeepSleepMode, InternalWiAdditions, Sleepable, WaveshareDisplay};
+
+pub(crate) mod command;
+use self::command::{Command, Slave};
+
+/// Full size buffer for use with the 5in79 EPD
+#[cfg(feature = "graphics")]
+pub type Display5in79 = crate::graphics::Display<
+    WIDTH,
+    HEIGHT,
+    false,
+    { buffer_len(WIDTH as usize, HEIGHT as usize) },
+    Color,
+>;
+
+/// Width of the display
+pub const WIDTH: u32 = 792;
+/// Height of the display
+pub const HEIGHT: u32 = 272;
+/// Default Background Color
+pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+const IS_BUSY_LOW: bool = false;
+const SINGLE_BYTE_WRITE: bool = false;
+
+/// Bytes of a line of the buffer
+const LINE_LEN: usize = buffer_len(WIDTH as usize, 1);
+/// Bytes of a line driven by each controller
+const HALF_LEN: usize = 50;
+/// First byte of a line driven by the slave
+const SLAVE_START: usize = LINE_LEN - HALF_LEN;
+
+/// Epd5in79 driver
+pub struct Epd5in79<SPI, BUSY, DC, RST, DELAY> {
+    /// Connection Interface
+    interface: DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>,
+    /// Background Color
+    color: Color,
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Epd5in79<SPI, BUSY, DC, RST, DELAY> {
+    /// Gives back the busy, data/command and reset pins, e.g. to reuse them once the display
+    /// is asleep
+    ///
+    /// The display keeps its state, so a driver can be created again later with the pins.
+    pub fn release(self) -> (BUSY, DC, RST) {
+        self.interface.release()
+    }
+
+    /// Gives up waiting for the display with [`Error::BusyTimeout`] once it has been busy for
+    /// `timeout_us`, e.g. because of a loose cable, instead of waiting forever. `None` removes
+    /// the timeout.
+    ///
+    /// The time is counted by adding up the delays of the idle loop, each poll of the busy pin
+    /// counting as at least 1us.
+    pub fn set_busy_timeout(&mut self, timeout_us: Option<u32>) {
+        self.interface.set_busy_timeout(timeout_us);
+    }
+
+    /// Polls the busy pin every `interval_us` while the display is busy (0 busy waits), or,
+    /// with a `max_interval_us`, backs off: the interval doubles after every poll until
+    /// `max_interval_us`
+    ///
+    /// The interval is the `delay_us` given to `new` (10ms by default) until this is called.
+    pub fn set_poll_interval(&mut self, interval_us: u32, max_interval_us: Option<u32>) {
+        self.interface.set_poll_interval(interval_us);
+        self.interface.set_poll_backoff(max_interval_us);
+    }
+
+    /// Sets a [`BusyYield`] called on every poll of the busy pin while the display is busy, e.g.
+    /// to feed a watchdog during a refresh. `None` removes it.
+    pub fn set_busy_yield(&mut self, busy_yield: Option<BusyYield>) {
+        self.interface.set_busy_yield(busy_yield);
+    }
+
+    /// Time the display was busy during the last wait for it, counted like the busy timeout,
+    /// e.g. to choose the timeout
+    pub fn last_wait_us(&self) -> u32 {
+        self.interface.last_wait_us()
+    }
+
+    /// CRC-32 of the data of the last frame written to the display, e.g. by
+    /// [`update_frame`](WaveshareDisplay::update_frame), to compare with the
+    /// [`crc32`](crate::crc::crc32) of the expected image and detect a corrupted transfer. `None`
+    /// until a frame is written. Only the left half of the frame, sent to the master controller, is
+    /// covered.
+    #[cfg(any(feature = "crc", test))]
+    pub fn last_frame_crc(&self) -> Option<u32> {
+        self.interface.last_crc()
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, BUSY, DC, RST, DELAY>
+    for Epd5in79<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 20_000, 2_000)?;
+        self.wait_until_idle(spi, delay)?;
+        self.interface.cmd(spi, Command::SwReset)?;
+        self.wait_until_idle(spi, delay)?;
+
+        self.interface
+            .cmd_with_data(spi, Command::BorderWaveformControl, &[0x01])?;
+        // internal temperature sensor
+        self.interface
+            .cmd_with_data(spi, Command::TemperatureSensorSelection, &[0x80])?;
+
+        // the master scans x increasing and y decreasing
+        self.interface
+            .cmd_with_data(spi, Command::DataEntryModeSetting, &[0x01])?;
+        self.interface.cmd_with_data(
+            spi,
+            Command::SetRamXAddressStartEndPosition,
+            &[0x00, (HALF_LEN - 1) as u8],
+        )?;
+        self.interface.cmd_with_data(
+            spi,
+            Command::SetRamYAddressStartEndPosition,
+            &[(HEIGHT - 1) as u8, ((HEIGHT - 1) >> 8) as u8, 0x00, 0x00],
+        )?;
+
+        // the slave is mirrored, scanning x decreasing
+        self.interface
+            .cmd_with_data(spi, Slave(Command::DataEntryModeSetting), &[0x00])?;
+        self.interface.cmd_with_data(
+            spi,
+            Slave(Command::SetRamXAddressStartEndPosition),
+            &[(HALF_LEN - 1) as u8, 0x00],
+        )?;
+        self.interface.cmd_with_data(
+            spi,
+            Slave(Command::SetRamYAddressStartEndPosition),
+            &[(HEIGHT - 1) as u8, ((HEIGHT - 1) >> 8) as u8, 0x00, 0x00],
+        )?;
+
+        self.wait_until_idle(spi, delay)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Sleepable<SPI, BUSY, DC, RST, DELAY>
+    for Epd5in79<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.sleep_with_mode(spi, delay, DeepSleepMode::Mode2)
+    }
+
+    fn sleep_with_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mode: DeepSleepMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        // like the SSD1677, the controller uses 0x03 for its second deep sleep mode
+        let mode = match mode {
+            DeepSleepMode::Normal => 0x00,
+            DeepSleepMode::Mode1 => 0x01,
+            DeepSleepMode::Mode2 => 0x03,
+        };
+        self.wait_until_idle(spi, delay)?;
+        self.interface
+            .cmd_with_data(spi, Command::DeepSleepMode, &[mode])
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
+    for Epd5in79<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    type DisplayColor = Color;
+
+    fn new(
+        spi: &mut SPI,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+        delay_us: Option<u32>,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let mut epd = Epd5in79 {
+            interface: DisplayInterface::new(busy, dc, rst, delay_us),
+            color: DEFAULT_BACKGROUND_COLOR,
+        };
+
+        #[cfg(any(feature = "crc", test))]
+        epd.interface.track_crc(Command::WriteRam);
+        epd.init(spi, delay)?;
+        Ok(epd)
+    }
+
+    fn set_background_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    fn background_color(&self) -> &Color {
+        &self.color
+    }
+
+    fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
+        self.wait_until_idle(spi, delay)?;
+        self.set_ram_address_counters(spi)?;
+
+        self.interface.cmd(spi, Command::WriteRam)?;
+        self.interface.data_iter(spi, half(buffer, 0))?;
+        self.interface.cmd(spi, Slave(Command::WriteRam))?;
+        self.interface.data_iter(spi, half(buffer, SLAVE_START))
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface
+            .cmd_with_data(spi, Command::DisplayUpdateControl2, &[0xF7])?;
+        self.interface.cmd(spi, Command::MasterActivation)?;
+        self.wait_until_idle(spi, delay)
+    }
+
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.display_frame(spi, delay)
+    }
+
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        let len = (HALF_LEN * HEIGHT as usize) as u32;
+        let bg = self.color.get_byte_value();
+
+        self.wait_until_idle(spi, delay)?;
+        self.set_ram_address_counters(spi)?;
+        self.interface.cmd(spi, Command::WriteRam)?;
+        self.interface.data_x_times(spi, bg, len)?;
+        self.interface.cmd(spi, Slave(Command::WriteRam))?;
+        self.interface.data_x_times(spi, bg, len)
+    }
+
+    fn wait_until_idle(
+        &mut self,
+        _spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_until_idle(delay, IS_BUSY_LOW)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY> Epd5in79<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Sets the largest number of bytes written in a single SPI transfer, longer writes being
+    /// split, e.g. for DMA engines with a limited transfer size. `None` writes a whole frame
+    /// at once.
+    ///
+    /// It is 4096 bytes on Linux, the default limit of spidev, and unlimited elsewhere.
+    pub fn set_max_transfer_size(&mut self, size: Option<usize>) {
+        self.interface.set_max_transfer_size(size);
+    }
+
+    /// Moves the address counters of both controllers to the start of their window
+    fn set_ram_address_counters(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        let y = [(HEIGHT - 1) as u8, ((HEIGHT - 1) >> 8) as u8];
+        self.interface
+            .cmd_with_data(spi, Command::SetRamXAddressCounter, &[0x00])?;
+        self.interface
+            .cmd_with_data(spi, Command::SetRamYAddressCounter, &y)?;
+        self.interface.cmd_with_data(
+            spi,
+            Slave(Command::SetRamXAddressCounter),
+            &[(HALF_LEN - 1) as u8],
+        )?;
+        self.interface
+            .cmd_with_data(spi, Slave(Command::SetRamYAddressCounter), &y)
+    }
+}
+
+/// The bytes of `buffer` driven by one controller, its half of each line starting at `start`
+fn half(buffer: &[u8], start: usize) -> impl Iterator<Item = u8> + '_ {
+    buffer
+        .chunks(LINE_LEN)
+        .flat_map(move |line| line[start..start + HALF_LEN].iter().copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epd_size() {
+        assert_eq!(WIDTH, 792);
+        assert_eq!(HEIGHT, 272);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+    }
+
+    #[test]
+    fn halves() {
+        // each byte holds its position in the line
+        let mut buffer = [0u8; LINE_LEN * 2];
+        for (i, byte) in buffer.iter_mut().enumerate() {
+            *byte = (i % LINE_LEN) as u8;
+        }
+
+        let mut master = half(&buffer, 0);
+        assert!(master.by_ref().take(HALF_LEN).eq(0..50));
+        assert!(master.eq(0..50));
+
+        // the byte 49 is driven by both controllers
+        let mut slave = half(&buffer, SLAVE_START);
+        assert!(slave.by_ref().take(HALF_LEN).eq(49..99));
+        assert!(slave.eq(49..99));
+    }
+}
@@ -9,6 +9,10 @@
 //! Important note for V2:
 //! Revision V2 has been released on 2019.11, the resolution is upgraded to 800×480, from 640×384 of V1.
 //! The hardware and interface of V2 are compatible with V1, however, the related software should be updated.
+//!
+//! A full frame takes 48KB, more than the RAM of many small MCUs. The frame can also be sent
+//! in bands with [`Epd7in5::update_frame_in_bands`], or streamed with
+//! [`Epd7in5::update_frame_iter`].
 
 use embedded_hal::{
     delay::DelayNs,
@@ -17,8 +21,11 @@ use embedded_hal::{
 };
 
 use crate::color::Color;
-use crate::interface::DisplayInterface;
-use crate::traits::{InternalWiAdditions, RefreshLut, WaveshareDisplay};
+use crate::error::Error;
+#[cfg(feature = "graphics")]
+use crate::graphics::{band_height, render_in_bands, Band, BandError, VarDisplay};
+use crate::interface::{BusyYield, DisplayInterface};
+use crate::traits::{InternalWiAdditions, Sleepable, WaveshareDisplay};
 
 pub(crate) mod command;
 use self::command::Command;
@@ -52,6 +59,57 @@ pub struct Epd7in5<SPI, BUSY, DC, RST, DELAY> {
     color: Color,
 }
 
+impl<SPI, BUSY, DC, RST, DELAY> Epd7in5<SPI, BUSY, DC, RST, DELAY> {
+    /// Gives back the busy, data/command and reset pins, e.g. to reuse them once the display
+    /// is asleep
+    ///
+    /// The display keeps its state, so a driver can be created again later with the pins.
+    pub fn release(self) -> (BUSY, DC, RST) {
+        self.interface.release()
+    }
+
+    /// Gives up waiting for the display with [`Error::BusyTimeout`] once it has been busy for
+    /// `timeout_us`, e.g. because of a loose cable, instead of waiting forever. `None` removes
+    /// the timeout.
+    ///
+    /// The time is counted by adding up the delays of the idle loop, each poll of the busy pin
+    /// counting as at least 1us.
+    pub fn set_busy_timeout(&mut self, timeout_us: Option<u32>) {
+        self.interface.set_busy_timeout(timeout_us);
+    }
+
+    /// Polls the busy pin every `interval_us` while the display is busy (0 busy waits), or,
+    /// with a `max_interval_us`, backs off: the interval doubles after every poll until
+    /// `max_interval_us`
+    ///
+    /// The interval is the `delay_us` given to `new` (10ms by default) until this is called.
+    pub fn set_poll_interval(&mut self, interval_us: u32, max_interval_us: Option<u32>) {
+        self.interface.set_poll_interval(interval_us);
+        self.interface.set_poll_backoff(max_interval_us);
+    }
+
+    /// Sets a [`BusyYield`] called on every poll of the busy pin while the display is busy, e.g.
+    /// to feed a watchdog during a refresh. `None` removes it.
+    pub fn set_busy_yield(&mut self, busy_yield: Option<BusyYield>) {
+        self.interface.set_busy_yield(busy_yield);
+    }
+
+    /// Time the display was busy during the last wait for it, counted like the busy timeout,
+    /// e.g. to choose the timeout
+    pub fn last_wait_us(&self) -> u32 {
+        self.interface.last_wait_us()
+    }
+
+    /// CRC-32 of the data of the last frame written to the display, e.g. by
+    /// [`update_frame`](WaveshareDisplay::update_frame), to compare with the
+    /// [`crc32`](crate::crc::crc32) of the expected image and detect a corrupted transfer. `None`
+    /// until a frame is written.
+    #[cfg(any(feature = "crc", test))]
+    pub fn last_frame_crc(&self) -> Option<u32> {
+        self.interface.last_crc()
+    }
+}
+
 impl<SPI, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, BUSY, DC, RST, DELAY>
     for Epd7in5<SPI, BUSY, DC, RST, DELAY>
 where
@@ -61,9 +119,9 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         // Reset the device
-        self.interface.reset(delay, 10_000, 2_000);
+        self.interface.reset(delay, 10_000, 2_000)?;
 
         // V2 procedure as described here:
         // https://github.com/waveshare/e-Paper/blob/master/RaspberryPi%26JetsonNano/python/lib/waveshare_epd/epd7in5bc_V2.py
@@ -84,6 +142,28 @@ where
     }
 }
 
+impl<SPI, BUSY, DC, RST, DELAY> Sleepable<SPI, BUSY, DC, RST, DELAY>
+    for Epd7in5<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.command(spi, Command::PowerOff)?;
+        self.wait_until_idle(spi, delay)?;
+        self.cmd_with_data(spi, Command::DeepSleep, &[0xA5])?;
+        Ok(())
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+}
+
 impl<SPI, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
     for Epd7in5<SPI, BUSY, DC, RST, DELAY>
 where
@@ -101,54 +181,32 @@ where
         rst: RST,
         delay: &mut DELAY,
         delay_us: Option<u32>,
-    ) -> Result<Self, SPI::Error> {
+    ) -> Result<Self, Error<SPI::Error>> {
         let interface = DisplayInterface::new(busy, dc, rst, delay_us);
         let color = DEFAULT_BACKGROUND_COLOR;
 
         let mut epd = Epd7in5 { interface, color };
 
+        #[cfg(any(feature = "crc", test))]
+        epd.interface.track_crc(Command::DataStartTransmission2);
         epd.init(spi, delay)?;
 
         Ok(epd)
     }
 
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.init(spi, delay)
-    }
-
-    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.wait_until_idle(spi, delay)?;
-        self.command(spi, Command::PowerOff)?;
-        self.wait_until_idle(spi, delay)?;
-        self.cmd_with_data(spi, Command::DeepSleep, &[0xA5])?;
-        Ok(())
-    }
-
     fn update_frame(
         &mut self,
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, buffer_len(WIDTH as usize, HEIGHT as usize))?;
         self.wait_until_idle(spi, delay)?;
         self.cmd_with_data(spi, Command::DataStartTransmission2, buffer)?;
         Ok(())
     }
 
-    fn update_partial_frame(
-        &mut self,
-        _spi: &mut SPI,
-        _delay: &mut DELAY,
-        _buffer: &[u8],
-        _x: u32,
-        _y: u32,
-        _width: u32,
-        _height: u32,
-    ) -> Result<(), SPI::Error> {
-        unimplemented!();
-    }
-
-    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
         self.command(spi, Command::DisplayRefresh)?;
         Ok(())
@@ -159,13 +217,13 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, buffer, delay)?;
         self.command(spi, Command::DisplayRefresh)?;
         Ok(())
     }
 
-    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle(spi, delay)?;
         self.send_resolution(spi)?;
 
@@ -195,16 +253,11 @@ where
         HEIGHT
     }
 
-    fn set_lut(
+    fn wait_until_idle(
         &mut self,
-        _spi: &mut SPI,
-        _delay: &mut DELAY,
-        _refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
-        unimplemented!();
-    }
-
-    fn wait_until_idle(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
         self.interface
             .wait_until_idle_with_cmd(spi, delay, IS_BUSY_LOW, Command::GetStatus)
     }
@@ -218,11 +271,71 @@ where
     RST: OutputPin,
     DELAY: DelayNs,
 {
-    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), SPI::Error> {
+    /// Sets the largest number of bytes written in a single SPI transfer, longer writes being
+    /// split, e.g. for DMA engines with a limited transfer size. `None` writes a whole frame
+    /// at once.
+    ///
+    /// It is 4096 bytes on Linux, the default limit of spidev, and unlimited elsewhere.
+    pub fn set_max_transfer_size(&mut self, size: Option<usize>) {
+        self.interface.set_max_transfer_size(size);
+    }
+
+    /// Same as [`update_frame`](WaveshareDisplay::update_frame), with the data streamed from
+    /// an iterator, e.g. reading an image from a file or decompressing it
+    ///
+    /// At most a full frame is taken from the iterator, so endless iterators can be used.
+    /// If it is shorter, the rest of the RAM is left unchanged.
+    pub fn update_frame_iter(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        data: impl IntoIterator<Item = u8>,
+    ) -> Result<(), Error<SPI::Error>> {
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize);
+        self.wait_until_idle(spi, delay)?;
+        self.command(spi, Command::DataStartTransmission2)?;
+        self.interface.data_iter(spi, data.into_iter().take(len))
+    }
+
+    /// Same as [`update_frame`](WaveshareDisplay::update_frame), drawing the frame in
+    /// `BANDS` horizontal bands with [`render_in_bands`]
+    ///
+    /// `buffer` only needs to hold one band, e.g. 6KB with 8 bands instead of the 48KB of a
+    /// full frame. `draw` is called for each band, in the coordinates of the band.
+    #[cfg(feature = "graphics")]
+    pub fn update_frame_in_bands<const BANDS: u32>(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &mut [u8],
+        draw: impl FnMut(&mut VarDisplay<'_, Color>, Band),
+    ) -> Result<(), Error<SPI::Error>> {
+        let expected = buffer_len(WIDTH as usize, band_height(HEIGHT, BANDS) as usize);
+        let too_small = Error::WrongBufferSize {
+            expected,
+            got: buffer.len(),
+        };
+        if buffer.len() < expected {
+            return Err(too_small);
+        }
+
+        self.wait_until_idle(spi, delay)?;
+        self.command(spi, Command::DataStartTransmission2)?;
+        // the bands follow each other in the same data transmission
+        render_in_bands::<BANDS, Color, _>(WIDTH, HEIGHT, false, buffer, draw, |_, data| {
+            self.interface.data(spi, data)
+        })
+        .map_err(|e| match e {
+            BandError::BufferTooSmall => too_small,
+            BandError::Send(e) => e,
+        })
+    }
+
+    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd(spi, command)
     }
 
-    fn send_data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
+    fn send_data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), Error<SPI::Error>> {
         self.interface.data(spi, data)
     }
 
@@ -231,11 +344,11 @@ where
         spi: &mut SPI,
         command: Command,
         data: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd_with_data(spi, command, data)
     }
 
-    fn send_resolution(&mut self, spi: &mut SPI) -> Result<(), SPI::Error> {
+    fn send_resolution(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
         let w = self.width();
         let h = self.height();
 
@@ -250,6 +363,108 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::traits::Command as _;
+    use embedded_hal_mock::eh1::{delay::NoopDelay, digital, spi};
+
+    extern crate std;
+    use std::vec::Vec;
+
+    fn write(data: &[u8]) -> [spi::Transaction<u8>; 3] {
+        [
+            spi::Transaction::transaction_start(),
+            spi::Transaction::write_vec(data.to_vec()),
+            spi::Transaction::transaction_end(),
+        ]
+    }
+
+    /// Traffic of waiting for the display, then starting the transmission of a frame
+    fn start_frame() -> (Vec<spi::Transaction<u8>>, Vec<digital::Transaction>) {
+        let spi = [
+            write(&[Command::GetStatus.address()]),
+            write(&[Command::DataStartTransmission2.address()]),
+        ]
+        .concat();
+        let dc = std::vec![
+            digital::Transaction::set(digital::State::Low),
+            digital::Transaction::set(digital::State::Low),
+        ];
+        (spi, dc)
+    }
+
+    fn uninitialised(
+        dc: &digital::Mock,
+        busy: &digital::Mock,
+    ) -> Epd7in5<spi::Mock<u8>, digital::Mock, digital::Mock, digital::Mock, NoopDelay> {
+        Epd7in5 {
+            interface: DisplayInterface::new(busy.clone(), dc.clone(), busy.clone(), None),
+            color: DEFAULT_BACKGROUND_COLOR,
+        }
+    }
+
+    #[test]
+    fn update_frame_iter() {
+        let (mut spi_expectations, mut dc_expectations) = start_frame();
+        // an endless iterator is cut at the end of the frame, and sent in blocks
+        for _ in 0..buffer_len(WIDTH as usize, HEIGHT as usize) / 64 {
+            spi_expectations.extend(write(&[0xAA; 64]));
+        }
+        dc_expectations.push(digital::Transaction::set(digital::State::High));
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&[digital::Transaction::get(digital::State::High)]);
+        let mut epd = uninitialised(&dc, &busy);
+
+        epd.update_frame_iter(&mut spi, &mut NoopDelay, core::iter::repeat(0xAA))
+            .unwrap();
+
+        spi.done();
+        dc.done();
+        busy.done();
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn update_frame_in_bands() {
+        use embedded_graphics_core::prelude::*;
+
+        let band_len = buffer_len(WIDTH as usize, 30);
+        let (mut spi_expectations, mut dc_expectations) = start_frame();
+        for index in 0..16 {
+            let mut band = std::vec![0xFF; band_len];
+            if index == 1 {
+                band[0] = 0x7F;
+            }
+            spi_expectations.extend(write(&band));
+            dc_expectations.push(digital::Transaction::set(digital::State::High));
+        }
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&dc_expectations);
+        let mut busy = digital::Mock::new(&[digital::Transaction::get(digital::State::High)]);
+        let mut epd = uninitialised(&dc, &busy);
+
+        let mut buffer = std::vec![0; band_len];
+        assert_eq!(
+            epd.update_frame_in_bands::<8>(&mut spi, &mut NoopDelay, &mut buffer, |_, _| {}),
+            Err(Error::WrongBufferSize {
+                expected: 2 * band_len,
+                got: band_len
+            })
+        );
+        epd.update_frame_in_bands::<16>(&mut spi, &mut NoopDelay, &mut buffer, |display, band| {
+            let _ = display.clear(Color::White);
+            // the first pixel of the second band is the first pixel of row 30
+            if band.index == 1 {
+                let _ = Pixel(Point::new(0, 0), Color::Black).draw(display);
+            }
+        })
+        .unwrap();
+
+        spi.done();
+        dc.done();
+        busy.done();
+    }
 
     #[test]
     fn epd_size() {
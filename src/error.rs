@@ -0,0 +1,106 @@
+//! Errors returned by the drivers
+
+use embedded_hal::digital;
+
+use crate::rect::WindowError;
+use crate::rle::RleError;
+
+/// Error returned by the drivers
+///
+/// Pin errors are converted to their [`ErrorKind`](digital::ErrorKind), as the busy, DC and
+/// reset pins may all have different error types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<SpiE> {
+    /// The SPI transfer failed
+    Spi(SpiE),
+    /// Reading the busy pin or setting the DC or reset pins failed
+    Pin(digital::ErrorKind),
+    /// The display stayed busy for longer than the configured timeout
+    BusyTimeout,
+    /// A buffer can't be used for the requested operation, e.g. a window outside of the
+    /// display
+    InvalidBuffer,
+    /// The window of a partial update can't be used, see
+    /// [`PartialWindow`](crate::rect::PartialWindow)
+    Window(WindowError),
+    /// Run-length encoded data can't be decoded, see [`rle`](crate::rle)
+    Rle(RleError),
+    /// A buffer doesn't have the length the display expects
+    WrongBufferSize {
+        /// Length needed by the display
+        expected: usize,
+        /// Length of the provided buffer
+        got: usize,
+    },
+    /// The display doesn't support the operation, e.g. a partial update or a custom LUT
+    Unsupported,
+    /// Data read back from the display RAM differs from what was written to it, e.g.
+    /// because of bit errors on long SPI wires
+    VerifyMismatch,
+}
+
+impl<SpiE> From<SpiE> for Error<SpiE> {
+    fn from(e: SpiE) -> Self {
+        Error::Spi(e)
+    }
+}
+
+impl<SpiE: core::fmt::Debug> core::fmt::Display for Error<SpiE> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::Spi(e) => write!(f, "SPI error: {:?}", e),
+            Error::Pin(e) => write!(f, "Pin error: {:?}", e),
+            Error::BusyTimeout => write!(f, "Timeout waiting for the display"),
+            Error::InvalidBuffer => write!(f, "Invalid buffer"),
+            Error::Window(e) => write!(f, "Invalid window: {}", e),
+            Error::Rle(e) => write!(f, "Invalid RLE data: {}", e),
+            Error::WrongBufferSize { expected, got } => {
+                write!(f, "Wrong buffer size: expected {}, got {}", expected, got)
+            }
+            Error::Unsupported => write!(f, "Operation not supported by the display"),
+            Error::VerifyMismatch => write!(f, "RAM content differs from the written data"),
+        }
+    }
+}
+
+impl<SpiE> Error<SpiE> {
+    /// Checks that a buffer has exactly the expected length
+    pub(crate) fn check_len(buffer: &[u8], expected: usize) -> Result<(), Self> {
+        Self::check_size(buffer.len(), expected)
+    }
+
+    /// Checks that `got` bytes, e.g. the length given with a generator, are exactly the
+    /// expected number
+    pub(crate) fn check_size(got: usize, expected: usize) -> Result<(), Self> {
+        if got == expected {
+            Ok(())
+        } else {
+            Err(Error::WrongBufferSize { expected, got })
+        }
+    }
+
+    /// Checks that a window starting at `x` and `width` pixels wide starts and ends on byte
+    /// boundaries
+    #[cfg(any(
+        feature = "epd2in13b_v4",
+        feature = "epd2in66b",
+        feature = "epd12in48b_v2"
+    ))]
+    pub(crate) fn check_aligned(x: u32, width: u32) -> Result<(), Self> {
+        if x % 8 == 0 && width % 8 == 0 {
+            Ok(())
+        } else {
+            Err(Error::Window(WindowError::Misaligned))
+        }
+    }
+}
+
+cfg_interface! {
+    #[or(feature = "it8951")]
+    impl<SpiE> Error<SpiE> {
+        /// Converts the error of a pin
+        pub(crate) fn pin<E: digital::Error>(e: E) -> Self {
+            Error::Pin(e.kind())
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! Frame data which isn't a slice in RAM, e.g. images stored in the flash of an AVR
+//!
+//! Drivers accepting a [`FrameSource`] read it one byte at a time while sending it, so the
+//! frame never needs to be copied to RAM.
+//!
+//! The crate doesn't depend on `avr-progmem` and has no feature for it: a [`FnFrame`] loads
+//! the bytes with any function, e.g. the `load_at` method of a `ProgMem` array.
+//!
+//! ```rust, ignore
+//! use avr_progmem::progmem;
+//! use epd_waveshare::frame::FnFrame;
+//!
+//! progmem! {
+//!     static progmem BLACK: [u8; 4000] = *include_bytes!("black.gray");
+//!     static progmem RED: [u8; 4000] = *include_bytes!("red.gray");
+//! }
+//! let black = FnFrame::new(BLACK.len(), |i| BLACK.load_at(i));
+//! let red = FnFrame::new(RED.len(), |i| RED.load_at(i));
+//! epd.update_color_frame_from(&mut spi, &mut delay, &black, &red)?;
+//! ```
+//!
+//! Frames which can only be read in order, e.g. from a file on an SD card or from a UART, are
+//! [`FrameStream`]s instead: [`ReadStream`] adapts the `read` method of any reader, like
+//! `embedded_io::Read` or `std::io::Read`, and [`StorageStream`] the one of a storage, like
+//! `embedded_storage::ReadStorage`.
+
+use crate::error::Error;
+
+/// Frame data read one byte at a time
+pub trait FrameSource {
+    /// Number of bytes of the frame
+    fn len(&self) -> usize;
+
+    /// Returns `true` if the frame has no bytes
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads the byte at `index`, which is less than [`len`](Self::len)
+    fn byte_at(&self, index: usize) -> u8;
+}
+
+impl FrameSource for [u8] {
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+
+    fn byte_at(&self, index: usize) -> u8 {
+        self[index]
+    }
+}
+
+impl<const N: usize> FrameSource for [u8; N] {
+    fn len(&self) -> usize {
+        N
+    }
+
+    fn byte_at(&self, index: usize) -> u8 {
+        self[index]
+    }
+}
+
+impl<T: FrameSource + ?Sized> FrameSource for &T {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn byte_at(&self, index: usize) -> u8 {
+        (**self).byte_at(index)
+    }
+}
+
+/// A frame of `len` bytes loaded with a function, e.g. the `load_at` method of an
+/// `avr_progmem::ProgMem` array
+#[derive(Debug, Clone, Copy)]
+pub struct FnFrame<F> {
+    len: usize,
+    load: F,
+}
+
+impl<F: Fn(usize) -> u8> FnFrame<F> {
+    /// Creates a frame of `len` bytes, `load` being called with the index of each of them
+    pub const fn new(len: usize, load: F) -> Self {
+        FnFrame { len, load }
+    }
+}
+
+impl<F: Fn(usize) -> u8> FrameSource for FnFrame<F> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn byte_at(&self, index: usize) -> u8 {
+        (self.load)(index)
+    }
+}
+
+/// Frame data read in order, a chunk at a time, e.g. from a file or a serial port
+pub trait FrameStream {
+    /// Error of the reads
+    type Error;
+
+    /// Reads the next bytes of the frame into `buf`, and returns how many were read
+    ///
+    /// `0` means that the frame has ended, or that `buf` is empty.
+    fn next_chunk(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+}
+
+/// The bytes not read yet, like `std::io::Read` for slices
+impl FrameStream for &[u8] {
+    type Error = core::convert::Infallible;
+
+    fn next_chunk(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let len = buf.len().min(self.len());
+        let (chunk, rest) = self.split_at(len);
+        buf[..len].copy_from_slice(chunk);
+        *self = rest;
+        Ok(len)
+    }
+}
+
+impl<T: FrameStream + ?Sized> FrameStream for &mut T {
+    type Error = T::Error;
+
+    fn next_chunk(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        (**self).next_chunk(buf)
+    }
+}
+
+/// A frame read by a function with the signature of a `read` method, e.g. of an
+/// `embedded_io::Read` reader
+///
+/// ```rust, ignore
+/// let mut file = sd_card_volume.open_file_in_dir("IMAGE.BIN", Mode::ReadOnly)?;
+/// let mut frame = ReadStream::new(|buf: &mut [u8]| file.read(buf));
+/// epd.update_frame_from_source(&mut spi, &mut delay, &mut frame)?;
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ReadStream<F> {
+    read: F,
+}
+
+impl<F> ReadStream<F> {
+    /// Creates a frame read by `read`, which returns the number of bytes put in its buffer,
+    /// `0` at the end of the frame
+    pub const fn new(read: F) -> Self {
+        ReadStream { read }
+    }
+}
+
+impl<F: FnMut(&mut [u8]) -> Result<usize, E>, E> FrameStream for ReadStream<F> {
+    type Error = E;
+
+    fn next_chunk(&mut self, buf: &mut [u8]) -> Result<usize, E> {
+        (self.read)(buf)
+    }
+}
+
+/// A frame of `len` bytes at `offset` in a storage, read by a function with the signature of
+/// the `read` method of `embedded_storage::ReadStorage`, e.g. from an external SPI flash
+#[derive(Debug, Clone, Copy)]
+pub struct StorageStream<F> {
+    read: F,
+    offset: u32,
+    left: usize,
+}
+
+impl<F> StorageStream<F> {
+    /// Creates a frame of `len` bytes starting at `offset`, `read` filling its buffer with
+    /// the bytes at the given offset
+    pub const fn new(read: F, offset: u32, len: usize) -> Self {
+        StorageStream {
+            read,
+            offset,
+            left: len,
+        }
+    }
+}
+
+impl<F: FnMut(u32, &mut [u8]) -> Result<(), E>, E> FrameStream for StorageStream<F> {
+    type Error = E;
+
+    fn next_chunk(&mut self, buf: &mut [u8]) -> Result<usize, E> {
+        let len = buf.len().min(self.left);
+        (self.read)(self.offset, &mut buf[..len])?;
+        self.offset += len as u32;
+        self.left -= len;
+        Ok(len)
+    }
+}
+
+/// Error of a frame update from a [`FrameStream`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamError<SpiE, E> {
+    /// Sending the frame to the display failed
+    Display(Error<SpiE>),
+    /// Reading the frame failed
+    Source(E),
+}
+
+impl<SpiE, E> From<Error<SpiE>> for StreamError<SpiE, E> {
+    fn from(e: Error<SpiE>) -> Self {
+        StreamError::Display(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+
+    #[test]
+    fn slice_stream() {
+        let mut frame = &[1u8, 2, 3, 4, 5][..];
+        let mut buf = [0; 2];
+        let mut read = |frame: &mut &[u8]| {
+            let len = frame.next_chunk(&mut buf).unwrap();
+            buf[..len].to_vec()
+        };
+        assert_eq!(read(&mut frame), [1, 2]);
+        assert_eq!(read(&mut frame), [3, 4]);
+        assert_eq!(read(&mut frame), [5]);
+        assert_eq!(read(&mut frame), []);
+    }
+
+    #[test]
+    fn read_stream() {
+        let mut calls = 0;
+        let mut frame = ReadStream::new(|buf: &mut [u8]| {
+            calls += 1;
+            if calls > 2 {
+                return Err("disconnected");
+            }
+            buf[0] = calls;
+            Ok(1)
+        });
+        let mut buf = [0; 4];
+        assert_eq!(frame.next_chunk(&mut buf), Ok(1));
+        assert_eq!(frame.next_chunk(&mut buf), Ok(1));
+        assert_eq!(buf[0], 2);
+        assert_eq!(frame.next_chunk(&mut buf), Err("disconnected"));
+    }
+
+    #[test]
+    fn storage_stream() {
+        let flash: std::vec::Vec<u8> = (0..=255).collect();
+        let read = |offset: u32, buf: &mut [u8]| {
+            let offset = offset as usize;
+            buf.copy_from_slice(&flash[offset..offset + buf.len()]);
+            Ok::<_, ()>(())
+        };
+        let mut frame = StorageStream::new(read, 100, 5);
+        let mut buf = [0; 4];
+        assert_eq!(frame.next_chunk(&mut buf), Ok(4));
+        assert_eq!(buf, [100, 101, 102, 103]);
+        assert_eq!(frame.next_chunk(&mut buf), Ok(1));
+        assert_eq!(buf[0], 104);
+        assert_eq!(frame.next_chunk(&mut buf), Ok(0));
+    }
+}
@@ -1,8 +1,10 @@
 //! Graphics Support for EPDs
 
-use crate::color::{ColorType, TriColor};
+use crate::color::{ColorMapper, ColorType, TriColor};
 use core::marker::PhantomData;
-use embedded_graphics_core::prelude::*;
+use embedded_graphics_core::{
+    image::GetPixel, pixelcolor::Rgb888, prelude::*, primitives::Rectangle,
+};
 
 /// Display rotation, only 90° increments supported
 #[derive(Clone, Copy, Default)]
@@ -18,12 +20,156 @@ pub enum DisplayRotation {
     Rotate270,
 }
 
-/// count the number of bytes per line knowing that it may contains padding bits
-const fn line_bytes(width: u32, bits_per_pixel: usize) -> usize {
+/// Order of the pixels in the bytes of a [`Display`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BitOrder {
+    /// The first pixel is in the most significant bits, as expected by the drivers
+    #[default]
+    MsbFirst,
+    /// The first pixel is in the least significant bits
+    LsbFirst,
+}
+
+/// Meaning of the bits of a [`Display`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Polarity {
+    /// The bits of the colors are the ones of [`ColorType::bitmask`], as expected by the drivers
+    #[default]
+    Normal,
+    /// Every bit is flipped, e.g. a set bit is black on a black and white display
+    Inverted,
+}
+
+/// How the pixels are packed in the buffer of a [`Display`]
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+struct Packing {
+    bit_order: BitOrder,
+    polarity: Polarity,
+    /// whether black and white are swapped
+    inverted: bool,
+}
+
+impl Packing {
+    /// Position of the pixel `x` of a line, in the MSB first order of [`ColorType::bitmask`]
+    fn position(self, x: u32, bits: usize) -> u32 {
+        match self.bit_order {
+            BitOrder::MsbFirst => x,
+            BitOrder::LsbFirst => {
+                let per_byte = (8 / bits) as u32;
+                x - x % per_byte + per_byte - 1 - x % per_byte
+            }
+        }
+    }
+
+    /// First bit of the pixel `x` of a line, counted from the most significant bit of its first
+    /// byte
+    fn bit(self, x: usize, bits: usize) -> usize {
+        self.position(x as u32, bits) as usize * bits
+    }
+
+    /// Mask of some pixels of a byte, given in the MSB first order
+    fn mask(self, mask: u8, bits: usize) -> u8 {
+        match self.bit_order {
+            BitOrder::MsbFirst => mask,
+            BitOrder::LsbFirst => mirror(mask, bits),
+        }
+    }
+
+    /// Color stored for `color`
+    fn color<COLOR: ColorType>(self, color: COLOR) -> COLOR {
+        if self.inverted {
+            color.inverse()
+        } else {
+            color
+        }
+    }
+
+    /// Bits stored for the `bits` of a color
+    fn value(self, bits: u8) -> u8 {
+        match self.polarity {
+            Polarity::Normal => bits,
+            Polarity::Inverted => !bits,
+        }
+    }
+}
+
+/// Reverses the order of the `bits` wide pixels of `byte`
+fn mirror(byte: u8, bits: usize) -> u8 {
+    let pixel = ((1u16 << bits) - 1) as u8;
+    (0..8 / bits).fold(0, |mirrored, i| {
+        mirrored | (byte >> (i * bits) & pixel) << (8 - bits - i * bits)
+    })
+}
+
+/// Number of bytes of a row of `width` pixels, each row starting on a new byte
+///
+/// The last byte of a row is padded with unused bits when `width * bits_per_pixel` is not a
+/// multiple of 8, see [`buffer_len`](crate::buffer_len).
+pub const fn bytes_per_row(width: u32, bits_per_pixel: usize) -> usize {
     // round to upper 8 bit count
     (width as usize * bits_per_pixel + 7) / 8
 }
 
+/// Packs a row of pixels, `true` for a set bit (e.g. white on black and white displays), in
+/// `row` with the first pixel in the most significant bit
+///
+/// Packing stops at the end of `pixels` or of `row`, and returns the number of pixels packed.
+/// The bits after the last pixel, up to the end of its byte, are cleared.
+pub fn pack_row_from_bits(pixels: impl IntoIterator<Item = bool>, row: &mut [u8]) -> usize {
+    let mut count = 0;
+    for (i, pixel) in pixels.into_iter().take(row.len() * 8).enumerate() {
+        let bit = 0x80 >> (i % 8);
+        if i % 8 == 0 {
+            row[i / 8] = 0;
+        }
+        if pixel {
+            row[i / 8] |= bit;
+        }
+        count = i + 1;
+    }
+    count
+}
+
+/// Copies the rows of a 1 bit per pixel image `width` pixels wide from `src` to `dst`, which
+/// have different strides
+///
+/// A stride is the number of bits from the start of a row to the start of the next one:
+/// `width` for an image packed without padding, `8 * bytes_per_row(width, 1)` for the buffers
+/// of this crate. The padding bits of `dst` are left untouched. Returns the number of rows
+/// copied, as many as both buffers hold.
+///
+/// ```rust
+/// use epd_waveshare::graphics::{bytes_per_row, repack_1bpp};
+///
+/// // a 122x250 image, exported without padding
+/// let image = [0xFFu8; (122 * 250 + 7) / 8];
+/// let mut buffer = [0u8; epd_waveshare::buffer_len(122, 250)];
+/// let stride = 8 * bytes_per_row(122, 1);
+/// assert_eq!(repack_1bpp(&image, 122, &mut buffer, stride, 122), 250);
+/// // the last 2 pixels of the first row, followed by the padding
+/// assert_eq!(buffer[15], 0b1100_0000);
+/// ```
+pub fn repack_1bpp(
+    src: &[u8],
+    src_stride: usize,
+    dst: &mut [u8],
+    dst_stride: usize,
+    width: u32,
+) -> usize {
+    let width = width as usize;
+    // the last row doesn't need the padding of its stride
+    let rows = |len: usize, stride: usize| match stride {
+        0 => 0,
+        _ if len * 8 < width => 0,
+        _ => (len * 8 - width) / stride + 1,
+    };
+    let rows = rows(src.len(), src_stride).min(rows(dst.len(), dst_stride));
+    for row in 0..rows {
+        copy_bits(dst, row * dst_stride, src, row * src_stride, width);
+    }
+    rows
+}
+
 /// Display buffer used for drawing with embedded graphics
 /// This can be rendered on EPD using ...
 ///
@@ -46,6 +192,13 @@ const fn line_bytes(width: u32, bits_per_pixel: usize) -> usize {
 ///
 /// BWRBIT=true: chromatic doesn't override white, white bit cleared for black, white bit set for white, both bits set for chromatic
 /// BWRBIT=false: chromatic does override white, both bits cleared for black, white bit set for white, red bit set for black
+///
+/// The pixels are packed as expected by the drivers of this crate. Image assets or other
+/// controllers may expect the first pixel in the least significant bits, or the bits flipped:
+/// [`set_bit_order`](Self::set_bit_order) and [`set_polarity`](Self::set_polarity) make the
+/// display draw that way directly.
+#[allow(clippy::doc_overindented_list_items)]
+#[derive(Clone)]
 pub struct Display<
     const WIDTH: u32,
     const HEIGHT: u32,
@@ -55,6 +208,9 @@ pub struct Display<
 > {
     buffer: [u8; BYTECOUNT],
     rotation: DisplayRotation,
+    packing: Packing,
+    /// pixels modified since the last `reset_dirty`, in buffer coordinates
+    dirty: Rectangle,
     _color: PhantomData<COLOR>,
 }
 
@@ -80,6 +236,8 @@ impl<
             // default color must be 0 for every bit in a pixel to make this work everywere
             buffer: [0u8; BYTECOUNT],
             rotation: DisplayRotation::default(),
+            packing: Packing::default(),
+            dirty: Rectangle::zero(),
             _color: PhantomData,
         }
     }
@@ -106,6 +264,44 @@ impl<
         }
         Ok(())
     }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let drawable = area.intersection(&self.bounding_box());
+        if drawable.is_zero_sized() {
+            return Ok(());
+        }
+        // the dirty area is updated once, not for every pixel
+        for (point, color) in area.points().zip(colors) {
+            if drawable.contains(point) {
+                set_pixel(
+                    &mut self.buffer,
+                    WIDTH,
+                    HEIGHT,
+                    self.rotation,
+                    BWRBIT,
+                    self.packing,
+                    Pixel(point, color),
+                );
+            }
+        }
+        let rect = buffer_rect(&drawable, self.rotation, WIDTH, HEIGHT);
+        self.dirty = union(&self.dirty, &rect);
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let drawable = area.intersection(&self.bounding_box());
+        if drawable.is_zero_sized() {
+            return Ok(());
+        }
+        let rect = buffer_rect(&drawable, self.rotation, WIDTH, HEIGHT);
+        fill_rect(&mut self.buffer, WIDTH, BWRBIT, self.packing, &rect, color);
+        self.dirty = union(&self.dirty, &rect);
+        Ok(())
+    }
 }
 
 /// For use with embedded_grahics
@@ -125,6 +321,22 @@ impl<
     }
 }
 
+/// For use with embedded_grahics
+impl<
+        const WIDTH: u32,
+        const HEIGHT: u32,
+        const BWRBIT: bool,
+        const BYTECOUNT: usize,
+        COLOR: ColorType + PixelColor,
+    > GetPixel for Display<WIDTH, HEIGHT, BWRBIT, BYTECOUNT, COLOR>
+{
+    type Color = COLOR;
+
+    fn pixel(&self, p: Point) -> Option<COLOR> {
+        self.get_pixel(p)
+    }
+}
+
 impl<
         const WIDTH: u32,
         const HEIGHT: u32,
@@ -134,10 +346,39 @@ impl<
     > Display<WIDTH, HEIGHT, BWRBIT, BYTECOUNT, COLOR>
 {
     /// get internal buffer to use it (to draw in epd)
+    ///
+    /// It is a single contiguous slice, borrowed for as long as the display, so it can be
+    /// handed to a DMA transfer, e.g. after `prepare_ram_write` on the drivers which have it.
     pub fn buffer(&self) -> &[u8] {
         &self.buffer
     }
 
+    /// Creates a display from an already packed `buffer`, e.g. an image decoded from flash,
+    /// drawing with `rotation` from now on
+    ///
+    /// The buffer is used as is, with the default bit order and polarity. Nothing is
+    /// considered drawn, the dirty area is empty.
+    // inline is necessary here to allow heap allocation via Box on stack limited programs
+    #[inline(always)]
+    pub fn from_raw(buffer: [u8; BYTECOUNT], rotation: DisplayRotation) -> Self {
+        Self {
+            buffer,
+            rotation,
+            packing: Packing::default(),
+            dirty: Rectangle::zero(),
+            _color: PhantomData,
+        }
+    }
+
+    /// Internal buffer, to modify the packed pixels directly
+    ///
+    /// The bytes are packed like for [`buffer`](Self::buffer), with the current bit order and
+    /// polarity. The whole display is considered drawn.
+    pub fn buffer_mut(&mut self) -> &mut [u8] {
+        self.dirty = Rectangle::new(Point::zero(), Size::new(WIDTH, HEIGHT));
+        &mut self.buffer
+    }
+
     /// Set the display rotation.
     ///
     /// This only concerns future drawing made to it. Anything aready drawn
@@ -151,16 +392,632 @@ impl<
         self.rotation
     }
 
+    /// Sets the order of the pixels in the bytes of the buffer
+    ///
+    /// What is already drawn is converted in place, and the whole display is then considered
+    /// drawn. Pre-rendered images given to [`blit_raw`](Self::blit_raw) must use the same order.
+    pub fn set_bit_order(&mut self, bit_order: BitOrder) {
+        if bit_order != self.packing.bit_order {
+            for byte in self.buffer.iter_mut() {
+                *byte = mirror(*byte, COLOR::BITS_PER_PIXEL_PER_BUFFER);
+            }
+            self.packing.bit_order = bit_order;
+            self.dirty = Rectangle::new(Point::zero(), Size::new(WIDTH, HEIGHT));
+        }
+    }
+
+    /// Order of the pixels in the bytes of the buffer
+    pub fn bit_order(&self) -> BitOrder {
+        self.packing.bit_order
+    }
+
+    /// Sets the meaning of the bits of the buffer
+    ///
+    /// What is already drawn is converted in place, and the whole display is then considered
+    /// drawn. Pre-rendered images given to [`blit_raw`](Self::blit_raw) must use the same
+    /// polarity.
+    pub fn set_polarity(&mut self, polarity: Polarity) {
+        if polarity != self.packing.polarity {
+            for byte in self.buffer.iter_mut() {
+                *byte = !*byte;
+            }
+            self.packing.polarity = polarity;
+            self.dirty = Rectangle::new(Point::zero(), Size::new(WIDTH, HEIGHT));
+        }
+    }
+
+    /// Meaning of the bits of the buffer
+    pub fn polarity(&self) -> Polarity {
+        self.packing.polarity
+    }
+
+    /// Swaps black and white in everything drawn from now on, e.g. for a night mode
+    ///
+    /// The colors are swapped when they are packed in the buffer, see [`ColorType::inverse`],
+    /// so the same drawing code shows either mode. What is already drawn is left as is.
+    pub fn set_inverted(&mut self, inverted: bool) {
+        self.packing.inverted = inverted;
+    }
+
+    /// Whether black and white are swapped
+    pub fn is_inverted(&self) -> bool {
+        self.packing.inverted
+    }
+
     /// Set a specific pixel color on this display
     pub fn set_pixel(&mut self, pixel: Pixel<COLOR>) {
-        set_pixel(
+        if let Some(point) = set_pixel(
             &mut self.buffer,
             WIDTH,
             HEIGHT,
             self.rotation,
             BWRBIT,
+            self.packing,
             pixel,
-        );
+        ) {
+            self.dirty = union(&self.dirty, &Rectangle::new(point, Size::new(1, 1)));
+        }
+    }
+
+    /// Color of the pixel at `point`, `None` if it is outside of the display, or if the color
+    /// type can't be read back, see [`ColorType::from_pixel_bits`]
+    ///
+    /// It is read back from the buffer, with the current rotation, bit order, polarity and
+    /// inversion, e.g. to draw a cursor in the opposite color of what is below it.
+    pub fn get_pixel(&self, point: Point) -> Option<COLOR> {
+        get_pixel(
+            &self.buffer,
+            WIDTH,
+            HEIGHT,
+            self.rotation,
+            self.packing,
+            point,
+        )
+    }
+
+    /// Smallest rectangle containing all the pixels drawn since the last
+    /// [`reset_dirty`](Self::reset_dirty), zero sized if nothing was drawn
+    ///
+    /// It is in the coordinates of the buffer, i.e. ignoring the rotation, like the window of
+    /// a partial update of the display. Pixels are counted even if their color didn't change.
+    pub fn dirty_area(&self) -> Rectangle {
+        self.dirty
+    }
+
+    /// Forgets the drawn pixels, e.g. once the [`dirty_area`](Self::dirty_area) has been sent
+    /// to the display
+    pub fn reset_dirty(&mut self) {
+        self.dirty = Rectangle::zero();
+    }
+
+    /// Draws `samples` as a bar graph (sparkline) inside `rect`
+    ///
+    /// Samples are scaled so that the biggest one fills the height of `rect`, and spread over
+    /// its width (each column shows the sample closest to its position, so there may be
+    /// fewer or more columns than samples). Bars are drawn bottom-up with `color`, the rest of
+    /// `rect` is left untouched.
+    ///
+    /// Returns the part of the display that has been modified, which can be used for a
+    /// partial refresh.
+    pub fn draw_sparkline(&mut self, samples: &[u16], rect: Rectangle, color: COLOR) -> Rectangle {
+        let rect = rect.intersection(&self.bounding_box());
+        let max = samples.iter().copied().max().unwrap_or(0) as u32;
+        if max == 0 || rect.is_zero_sized() {
+            return rect;
+        }
+
+        let bottom = rect.top_left.y + rect.size.height as i32;
+        for column in 0..rect.size.width {
+            let sample = samples[(column as usize * samples.len()) / rect.size.width as usize];
+            let height = (sample as u32 * rect.size.height + max / 2) / max;
+            let bar = Rectangle::new(
+                Point::new(rect.top_left.x + column as i32, bottom - height as i32),
+                Size::new(1, height),
+            );
+            // infallible
+            let _ = self.fill_solid(&bar, color);
+        }
+        rect
+    }
+
+    /// Draws all `drawables` and returns the smallest rectangle containing all of them
+    ///
+    /// The rectangle is clipped to the display, it can be used for a single partial refresh
+    /// covering everything that was drawn. It is zero sized if nothing was drawn.
+    pub fn draw_tracked<D>(&mut self, drawables: impl IntoIterator<Item = D>) -> Rectangle
+    where
+        D: Drawable<Color = COLOR> + Dimensions,
+    {
+        let mut dirty = Rectangle::zero();
+        for drawable in drawables {
+            // infallible
+            let _ = drawable.draw(self);
+            dirty = union(&dirty, &drawable.bounding_box());
+        }
+        dirty.intersection(&self.bounding_box())
+    }
+
+    /// Number of bytes of the buffer (of all the banks for multi color displays) which differ
+    /// from `prev`, e.g. to choose between a partial and a full refresh
+    ///
+    /// The padding bits at the end of each line are ignored.
+    pub fn dirty_byte_count(&self, prev: &Self) -> usize {
+        let bits = COLOR::BITS_PER_PIXEL_PER_BUFFER;
+        let line_bits = WIDTH * bits as u32;
+        let line_bytes = bytes_per_row(WIDTH, bits);
+        let valid_bits = |i: usize| {
+            self.packing
+                .mask(valid_bits(line_bits, i % line_bytes), bits)
+        };
+        self.buffer
+            .iter()
+            .zip(prev.buffer.iter())
+            .enumerate()
+            .filter(|(i, (a, b))| (*a ^ *b) & valid_bits(*i) != 0)
+            .count()
+    }
+
+    /// Copies the `src_rect` part of `other` to this display, its top left corner at `dest`
+    ///
+    /// See [`blit_raw`](Self::blit_raw).
+    pub fn blit<const OTHER_WIDTH: u32, const OTHER_HEIGHT: u32, const OTHER_BYTECOUNT: usize>(
+        &mut self,
+        other: &Display<OTHER_WIDTH, OTHER_HEIGHT, BWRBIT, OTHER_BYTECOUNT, COLOR>,
+        src_rect: Rectangle,
+        dest: Point,
+    ) -> Rectangle {
+        self.blit_raw(other.buffer(), OTHER_WIDTH, src_rect, dest)
+    }
+
+    /// Copies the `src_rect` part of a pre-rendered image, e.g. an icon, to this display, its
+    /// top left corner at `dest`
+    ///
+    /// `src` is packed like the buffer of a display `src_width` pixels wide of the same color
+    /// type. Whole rows are copied at once, byte by byte when the source and destination pixels
+    /// share the same position in their bytes, which is much faster than drawing the image
+    /// pixel by pixel.
+    ///
+    /// Coordinates are the ones of the buffers, ignoring the rotation. Returns the part of the
+    /// display that has been modified, which is clipped to both images.
+    pub fn blit_raw(
+        &mut self,
+        src: &[u8],
+        src_width: u32,
+        src_rect: Rectangle,
+        dest: Point,
+    ) -> Rectangle {
+        let bits = COLOR::BITS_PER_PIXEL_PER_BUFFER;
+        let src_line = bytes_per_row(src_width, bits);
+        if src_line == 0 {
+            return Rectangle::zero();
+        }
+        let src_plane = src.len() / COLOR::BUFFER_COUNT;
+        let src_height = (src_plane / src_line) as u32;
+
+        // clip the source to its image, then the destination to the display
+        let clipped = src_rect.intersection(&Rectangle::new(
+            Point::zero(),
+            Size::new(src_width, src_height),
+        ));
+        let dest = dest + (clipped.top_left - src_rect.top_left);
+        let src_rect = clipped;
+        let moved = Rectangle::new(dest, src_rect.size)
+            .intersection(&Rectangle::new(Point::zero(), Size::new(WIDTH, HEIGHT)));
+        if moved.is_zero_sized() {
+            return Rectangle::zero();
+        }
+        let src_origin = src_rect.top_left + (moved.top_left - dest);
+
+        let dest_line = bytes_per_row(WIDTH, bits);
+        let dest_plane = self.buffer.len() / COLOR::BUFFER_COUNT;
+        for plane in 0..COLOR::BUFFER_COUNT {
+            for row in 0..moved.size.height as usize {
+                let src_start = plane * src_plane + (src_origin.y as usize + row) * src_line;
+                let dest_start = plane * dest_plane + (moved.top_left.y as usize + row) * dest_line;
+                let dest = &mut self.buffer[dest_start..dest_start + dest_line];
+                let src = &src[src_start..src_start + src_line];
+                match self.packing.bit_order {
+                    BitOrder::MsbFirst => copy_bits(
+                        dest,
+                        moved.top_left.x as usize * bits,
+                        src,
+                        src_origin.x as usize * bits,
+                        moved.size.width as usize * bits,
+                    ),
+                    BitOrder::LsbFirst => copy_pixels(
+                        dest,
+                        moved.top_left.x as usize,
+                        src,
+                        src_origin.x as usize,
+                        moved.size.width as usize,
+                        bits,
+                        self.packing,
+                    ),
+                }
+            }
+        }
+        self.dirty = union(&self.dirty, &moved);
+        moved
+    }
+
+    /// Moves the content of the display up by `rows`, or down if negative, and fills the
+    /// exposed rows with `fill_color`
+    ///
+    /// Rows are in the coordinates of the buffer, ignoring the rotation. Returns the exposed
+    /// band, which only needs to be drawn before a partial refresh of the display.
+    pub fn scroll_y(&mut self, rows: i32, fill_color: COLOR) -> Rectangle {
+        let shift = rows.unsigned_abs().min(HEIGHT);
+        let line = bytes_per_row(WIDTH, COLOR::BITS_PER_PIXEL_PER_BUFFER);
+        let kept = (HEIGHT - shift) as usize * line;
+        let shift_bytes = shift as usize * line;
+        for plane in self.buffer.chunks_mut(BYTECOUNT / COLOR::BUFFER_COUNT) {
+            if rows > 0 {
+                plane.copy_within(shift_bytes..shift_bytes + kept, 0);
+            } else {
+                plane.copy_within(0..kept, shift_bytes);
+            }
+        }
+
+        let exposed = if rows > 0 {
+            Rectangle::new(
+                Point::new(0, (HEIGHT - shift) as i32),
+                Size::new(WIDTH, shift),
+            )
+        } else {
+            Rectangle::new(Point::zero(), Size::new(WIDTH, shift))
+        };
+        self.scrolled(exposed, fill_color)
+    }
+
+    /// Moves the content of the display left by `cols`, or right if negative, and fills the
+    /// exposed columns with `fill_color`
+    ///
+    /// Columns are in the coordinates of the buffer, ignoring the rotation. Returns the exposed
+    /// band, which only needs to be drawn before a partial refresh of the display.
+    pub fn scroll_x(&mut self, cols: i32, fill_color: COLOR) -> Rectangle {
+        let bits = COLOR::BITS_PER_PIXEL_PER_BUFFER;
+        let shift = cols.unsigned_abs().min(WIDTH);
+        let kept = (WIDTH - shift) as usize * bits;
+        let shift_bits = shift as usize * bits;
+        let (from, to) = if cols > 0 {
+            (shift_bits, 0)
+        } else {
+            (0, shift_bits)
+        };
+        if kept > 0 {
+            for line in self.buffer.chunks_mut(bytes_per_row(WIDTH, bits)) {
+                if self.packing.bit_order == BitOrder::LsbFirst {
+                    let (from, to) = (from / bits, to / bits);
+                    move_pixels(line, from, to, kept / bits, bits, self.packing);
+                } else if shift_bits % 8 == 0 {
+                    // whole bytes, only the end of a line may be a partial byte
+                    let bytes = kept / 8;
+                    line.copy_within(from / 8..from / 8 + bytes, to / 8);
+                    move_bits(line, from + bytes * 8, to + bytes * 8, kept % 8);
+                } else {
+                    move_bits(line, from, to, kept);
+                }
+            }
+        }
+
+        let exposed = if cols > 0 {
+            Rectangle::new(
+                Point::new((WIDTH - shift) as i32, 0),
+                Size::new(shift, HEIGHT),
+            )
+        } else {
+            Rectangle::new(Point::zero(), Size::new(shift, HEIGHT))
+        };
+        self.scrolled(exposed, fill_color)
+    }
+
+    /// Fills the band exposed by a scroll, everything being moved
+    fn scrolled(&mut self, exposed: Rectangle, fill_color: COLOR) -> Rectangle {
+        if !exposed.is_zero_sized() {
+            fill_rect(
+                &mut self.buffer,
+                WIDTH,
+                BWRBIT,
+                self.packing,
+                &exposed,
+                fill_color,
+            );
+        }
+        self.dirty = Rectangle::new(Point::zero(), Size::new(WIDTH, HEIGHT));
+        exposed
+    }
+}
+
+/// Rectangle of the buffer covered by `rect`, a non empty rectangle of the rotated display
+pub(crate) fn buffer_rect(
+    rect: &Rectangle,
+    rotation: DisplayRotation,
+    width: u32,
+    height: u32,
+) -> Rectangle {
+    let rotate = |point: Point| match rotation {
+        DisplayRotation::Rotate0 => point,
+        DisplayRotation::Rotate90 => Point::new(width as i32 - 1 - point.y, point.x),
+        DisplayRotation::Rotate180 => {
+            Point::new(width as i32 - 1 - point.x, height as i32 - 1 - point.y)
+        }
+        DisplayRotation::Rotate270 => Point::new(point.y, height as i32 - 1 - point.x),
+    };
+    let a = rotate(rect.top_left);
+    let b = rotate(rect.bottom_right().unwrap_or(rect.top_left));
+    Rectangle::with_corners(a.component_min(b), a.component_max(b))
+}
+
+/// Fills `rect`, in the coordinates of the buffer, with `color`, a whole byte at a time
+fn fill_rect<COLOR: ColorType + PixelColor>(
+    buffer: &mut [u8],
+    width: u32,
+    bwrbit: bool,
+    packing: Packing,
+    rect: &Rectangle,
+    color: COLOR,
+) {
+    let bits = COLOR::BITS_PER_PIXEL_PER_BUFFER;
+    let color = packing.color(color);
+    // full bytes of the color, for each buffer, the same in both bit orders
+    let mut pattern = 0u16;
+    for pos in 0..(8 / bits) as u32 {
+        pattern |= color.bitmask(bwrbit, pos).1;
+    }
+
+    let line = bytes_per_row(width, bits);
+    let plane_len = buffer.len() / COLOR::BUFFER_COUNT;
+    for (plane, pattern) in [pattern as u8, (pattern >> 8) as u8]
+        .into_iter()
+        .take(COLOR::BUFFER_COUNT)
+        .enumerate()
+    {
+        for y in 0..rect.size.height as usize {
+            let start = plane * plane_len + (rect.top_left.y as usize + y) * line;
+            fill_bits(
+                &mut buffer[start..start + line],
+                rect.top_left.x as usize * bits,
+                rect.size.width as usize * bits,
+                packing.value(pattern),
+                |mask| packing.mask(mask, bits),
+            );
+        }
+    }
+}
+
+/// Sets `len` bits of `line` from bit `start` to the matching bits of `pattern`
+///
+/// The bits are counted in the MSB first order, `order` converts the mask of a byte to the
+/// order of the line.
+fn fill_bits(line: &mut [u8], start: usize, len: usize, pattern: u8, order: impl Fn(u8) -> u8) {
+    let end = start + len;
+    let (first, last) = (start / 8, (end - 1) / 8);
+    for (i, byte) in line[first..=last].iter_mut().enumerate() {
+        let mut mask = 0xFF;
+        if i == 0 {
+            mask &= 0xFF >> (start % 8);
+        }
+        if first + i == last {
+            mask &= 0xFF << (7 - (end - 1) % 8);
+        }
+        let mask = order(mask);
+        *byte = *byte & !mask | pattern & mask;
+    }
+}
+
+/// Copies `len` bits of `src`, starting at bit `src_bit`, to `dest` from bit `dest_bit`,
+/// the most significant bit of a byte being the first one
+fn copy_bits(dest: &mut [u8], dest_bit: usize, src: &[u8], src_bit: usize, len: usize) {
+    let mut done = 0;
+    if dest_bit % 8 == src_bit % 8 {
+        // the bits before the first full byte are copied below
+        done = ((8 - dest_bit % 8) % 8).min(len);
+        copy_bits_shifted(dest, dest_bit, src, src_bit, done);
+        let bytes = (len - done) / 8;
+        let (d, s) = ((dest_bit + done) / 8, (src_bit + done) / 8);
+        dest[d..d + bytes].copy_from_slice(&src[s..s + bytes]);
+        done += bytes * 8;
+    }
+    copy_bits_shifted(dest, dest_bit + done, src, src_bit + done, len - done);
+}
+
+/// Same as `copy_bits`, filling one destination byte at a time
+fn copy_bits_shifted(dest: &mut [u8], dest_bit: usize, src: &[u8], src_bit: usize, len: usize) {
+    let mut done = 0;
+    while done < len {
+        let count = (len - done).min(8 - (dest_bit + done) % 8);
+        let value = read_bits(src, src_bit + done, count);
+        write_bits(dest, dest_bit + done, count, value);
+        done += count;
+    }
+}
+
+/// Moves `len` bits of `line` from bit `from` to bit `to`, the two ranges may overlap
+fn move_bits(line: &mut [u8], from: usize, to: usize, len: usize) {
+    if to <= from {
+        // front to back, each source bit being read before it may be overwritten
+        let mut done = 0;
+        while done < len {
+            let count = (len - done).min(8 - (to + done) % 8);
+            let value = read_bits(line, from + done, count);
+            write_bits(line, to + done, count, value);
+            done += count;
+        }
+    } else {
+        let mut remaining = len;
+        while remaining > 0 {
+            let count = remaining.min((to + remaining - 1) % 8 + 1);
+            remaining -= count;
+            let value = read_bits(line, from + remaining, count);
+            write_bits(line, to + remaining, count, value);
+        }
+    }
+}
+
+/// Same as `copy_bits` for lines in any bit order, one pixel of `bits` bits at a time, `dest_x`,
+/// `src_x` and `len` being counted in pixels
+fn copy_pixels(
+    dest: &mut [u8],
+    dest_x: usize,
+    src: &[u8],
+    src_x: usize,
+    len: usize,
+    bits: usize,
+    packing: Packing,
+) {
+    for i in 0..len {
+        let value = read_bits(src, packing.bit(src_x + i, bits), bits);
+        write_bits(dest, packing.bit(dest_x + i, bits), bits, value);
+    }
+}
+
+/// Same as `move_bits` for lines in any bit order, one pixel of `bits` bits at a time, `from`,
+/// `to` and `len` being counted in pixels
+fn move_pixels(line: &mut [u8], from: usize, to: usize, len: usize, bits: usize, packing: Packing) {
+    let mut move_pixel = |i: usize| {
+        let value = read_bits(line, packing.bit(from + i, bits), bits);
+        write_bits(line, packing.bit(to + i, bits), bits, value);
+    };
+    // each source pixel is read before it may be overwritten
+    if to <= from {
+        (0..len).for_each(&mut move_pixel);
+    } else {
+        (0..len).rev().for_each(&mut move_pixel);
+    }
+}
+
+/// `count` (at most 8) bits of `buffer` from bit `bit`, in the lowest bits of the result
+fn read_bits(buffer: &[u8], bit: usize, count: usize) -> u8 {
+    // the bits may span two bytes
+    let s = bit / 8;
+    let word = (buffer[s] as u16) << 8 | buffer.get(s + 1).copied().unwrap_or(0) as u16;
+    ((word << (bit % 8)) >> (16 - count)) as u8
+}
+
+/// Writes the `count` lowest bits of `value` to `buffer` from bit `bit`, all in the same byte
+fn write_bits(buffer: &mut [u8], bit: usize, count: usize, value: u8) {
+    let shift = 8 - bit % 8 - count;
+    let mask = (((1u16 << count) - 1) << shift) as u8;
+    buffer[bit / 8] = buffer[bit / 8] & !mask | (value << shift) & mask;
+}
+
+/// Length of the header of a serialized display: width, height, rotation and packing
+const SERIALIZED_HEADER_LEN: usize = 10;
+
+/// Bits of the packing byte of a serialized display
+const SERIALIZED_LSB_FIRST: u8 = 0b001;
+const SERIALIZED_POLARITY_INVERTED: u8 = 0b010;
+const SERIALIZED_INVERTED: u8 = 0b100;
+
+/// Error of [`Display::deserialize`] and [`Display::serialize_into`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerialError {
+    /// The bytes don't have the length of a serialized display
+    WrongLength,
+    /// The serialized display has a different width or height
+    WrongSize,
+    /// The rotation byte is not a [`DisplayRotation`]
+    InvalidRotation,
+    /// The packing byte has unknown bits set
+    InvalidPacking,
+}
+
+impl<
+        const WIDTH: u32,
+        const HEIGHT: u32,
+        const BWRBIT: bool,
+        const BYTECOUNT: usize,
+        COLOR: ColorType + PixelColor,
+    > Display<WIDTH, HEIGHT, BWRBIT, BYTECOUNT, COLOR>
+{
+    /// Length of the display serialized by [`serialize_into`](Self::serialize_into)
+    pub const SERIALIZED_LEN: usize = SERIALIZED_HEADER_LEN + BYTECOUNT;
+
+    /// Serializes the display into `out`, which must be
+    /// [`SERIALIZED_LEN`](Self::SERIALIZED_LEN) long, and returns the number of bytes written
+    ///
+    /// The width and the height, as little endian `u32`, the rotation (0 to 3) and the packing
+    /// are followed by the buffer unchanged, so a host can render a frame which a small MCU
+    /// deserializes and sends to the display as is. The bits of the packing byte are set for
+    /// [`BitOrder::LsbFirst`] (1), [`Polarity::Inverted`] (2) and an
+    /// [inverted](Self::set_inverted) display (4).
+    pub fn serialize_into(&self, out: &mut [u8]) -> Result<usize, SerialError> {
+        if out.len() != Self::SERIALIZED_LEN {
+            return Err(SerialError::WrongLength);
+        }
+        let (header, buffer) = out.split_at_mut(SERIALIZED_HEADER_LEN);
+        header[..4].copy_from_slice(&WIDTH.to_le_bytes());
+        header[4..8].copy_from_slice(&HEIGHT.to_le_bytes());
+        header[8] = self.rotation as u8;
+        let flag = |set: bool, bit: u8| if set { bit } else { 0 };
+        header[9] = flag(
+            self.packing.bit_order == BitOrder::LsbFirst,
+            SERIALIZED_LSB_FIRST,
+        ) | flag(
+            self.packing.polarity == Polarity::Inverted,
+            SERIALIZED_POLARITY_INVERTED,
+        ) | flag(self.packing.inverted, SERIALIZED_INVERTED);
+        buffer.copy_from_slice(&self.buffer);
+        Ok(Self::SERIALIZED_LEN)
+    }
+
+    /// Creates a display from bytes written by [`serialize_into`](Self::serialize_into), for a
+    /// display of the same size
+    ///
+    /// The buffer is used as is, with the bit order, the polarity and the inversion it was
+    /// serialized with. Nothing is considered drawn, the dirty area is empty.
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, SerialError> {
+        if bytes.len() != Self::SERIALIZED_LEN {
+            return Err(SerialError::WrongLength);
+        }
+        let (header, buffer) = bytes.split_at(SERIALIZED_HEADER_LEN);
+        let size = |bytes: &[u8]| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        if size(&header[..4]) != WIDTH || size(&header[4..8]) != HEIGHT {
+            return Err(SerialError::WrongSize);
+        }
+        let rotation = match header[8] {
+            0 => DisplayRotation::Rotate0,
+            1 => DisplayRotation::Rotate90,
+            2 => DisplayRotation::Rotate180,
+            3 => DisplayRotation::Rotate270,
+            _ => return Err(SerialError::InvalidRotation),
+        };
+        let packing = header[9];
+        if packing & !(SERIALIZED_LSB_FIRST | SERIALIZED_POLARITY_INVERTED | SERIALIZED_INVERTED)
+            != 0
+        {
+            return Err(SerialError::InvalidPacking);
+        }
+
+        let mut display = Self::default();
+        display.buffer.copy_from_slice(buffer);
+        display.rotation = rotation;
+        display.packing = Packing {
+            bit_order: if packing & SERIALIZED_LSB_FIRST != 0 {
+                BitOrder::LsbFirst
+            } else {
+                BitOrder::MsbFirst
+            },
+            polarity: if packing & SERIALIZED_POLARITY_INVERTED != 0 {
+                Polarity::Inverted
+            } else {
+                Polarity::Normal
+            },
+            inverted: packing & SERIALIZED_INVERTED != 0,
+        };
+        Ok(display)
+    }
+}
+
+/// Smallest rectangle containing both `a` and `b`, zero sized rectangles are ignored
+fn union(a: &Rectangle, b: &Rectangle) -> Rectangle {
+    match (a.bottom_right(), b.bottom_right()) {
+        (None, _) => *b,
+        (_, None) => *a,
+        (Some(a_bottom_right), Some(b_bottom_right)) => Rectangle::with_corners(
+            a.top_left.component_min(b.top_left),
+            a_bottom_right.component_max(b_bottom_right),
+        ),
     }
 }
 
@@ -169,25 +1026,118 @@ impl<const WIDTH: u32, const HEIGHT: u32, const BWRBIT: bool, const BYTECOUNT: u
     Display<WIDTH, HEIGHT, BWRBIT, BYTECOUNT, TriColor>
 {
     /// get black/white internal buffer to use it (to draw in epd)
+    ///
+    /// Like [`buffer`](Self::buffer), it is contiguous and can be handed to a DMA transfer.
     pub fn bw_buffer(&self) -> &[u8] {
         &self.buffer[..self.buffer.len() / 2]
     }
 
     /// get chromatic internal buffer to use it (to draw in epd)
+    ///
+    /// Like [`buffer`](Self::buffer), it is contiguous and can be handed to a DMA transfer.
     pub fn chromatic_buffer(&self) -> &[u8] {
         &self.buffer[self.buffer.len() / 2..]
     }
+
+    /// Recolor every pixel of color `from` to color `to`
+    ///
+    /// This works directly on the two buffers, so it is much cheaper than drawing
+    /// everything again. A pixel with the chromatic bit set is always considered
+    /// `Chromatic`, whatever the value of its B/W bit. Padding bits are left untouched.
+    pub fn replace_color(&mut self, from: TriColor, to: TriColor) {
+        if from == to {
+            return;
+        }
+        let half = self.buffer.len() / 2;
+        let (bw_buffer, chromatic_buffer) = self.buffer.split_at_mut(half);
+        let packing = self.packing;
+        let (from, to) = (packing.color(from), packing.color(to));
+        let (to_bw, to_chromatic) = tricolor_bytes(to, BWRBIT);
+        let (to_bw, to_chromatic) = (packing.value(to_bw), packing.value(to_chromatic));
+        let line_bytes = bytes_per_row(WIDTH, TriColor::BITS_PER_PIXEL_PER_BUFFER);
+
+        for (i, (bw, chromatic)) in bw_buffer
+            .iter_mut()
+            .zip(chromatic_buffer.iter_mut())
+            .enumerate()
+        {
+            let (bw_bits, chromatic_bits) = (packing.value(*bw), packing.value(*chromatic));
+            let matching = match from {
+                TriColor::Chromatic => chromatic_bits,
+                TriColor::White => bw_bits & !chromatic_bits,
+                TriColor::Black => !bw_bits & !chromatic_bits,
+            } & packing.mask(valid_bits(WIDTH, i % line_bytes), 1);
+
+            *bw = *bw & !matching | to_bw & matching;
+            *chromatic = *chromatic & !matching | to_chromatic & matching;
+        }
+        // the recolored pixels may be anywhere
+        self.dirty = Rectangle::new(Point::zero(), Size::new(WIDTH, HEIGHT));
+    }
+
+    /// Draws RGB pixels, mapping their colors to `TriColor` with `mapper`
+    ///
+    /// Use it to draw UI elements in RGB colors, tuning the mapper so that their red-ish
+    /// colors land on the chromatic plane.
+    pub fn draw_mapped(
+        &mut self,
+        pixels: impl IntoIterator<Item = Pixel<Rgb888>>,
+        mapper: &ColorMapper,
+    ) {
+        for Pixel(point, color) in pixels {
+            self.set_pixel(Pixel(point, mapper.map_tricolor(color)));
+        }
+    }
+
+    /// Draws a BMP image with its top left corner at `origin`
+    ///
+    /// Colors are mapped to the closest `TriColor` by the default [`ColorMapper`]: saturated
+    /// colors (e.g. red or yellow) become `Chromatic`, the others `White` or `Black` depending
+    /// on their brightness.
+    #[cfg(feature = "tinybmp")]
+    pub fn draw_bmp(&mut self, bmp: &tinybmp::Bmp<'_, Rgb888>, origin: Point) {
+        let pixels = bmp
+            .pixels()
+            .map(|Pixel(point, color)| Pixel(origin + point, color));
+        self.draw_mapped(pixels, &ColorMapper::default());
+    }
+}
+
+// Full bytes of the B/W and chromatic buffers for a given color
+fn tricolor_bytes(color: TriColor, bwrbit: bool) -> (u8, u8) {
+    match color {
+        TriColor::Black => (0x00, 0x00),
+        TriColor::White => (0xFF, 0x00),
+        TriColor::Chromatic if bwrbit => (0x00, 0xFF),
+        TriColor::Chromatic => (0xFF, 0xFF),
+    }
+}
+
+// Mask of the bits representing real pixels in the n-th byte of a 1 bit per pixel line,
+// the trailing padding bits of the last byte are cleared.
+fn valid_bits(width: u32, byte_in_line: usize) -> u8 {
+    let remaining = width as usize - byte_in_line * 8;
+    if remaining >= 8 {
+        0xFF
+    } else {
+        0xFF << (8 - remaining)
+    }
 }
 
 /// Same as `Display`, except that its characteristics are defined at runtime.
 /// See display for documentation as everything is the same except that default
 /// is replaced by a `new` method.
+///
+/// The buffer is borrowed, so it can be placed anywhere, e.g. in a `static` or in external
+/// RAM, and shared by displays of different sizes.
 pub struct VarDisplay<'a, COLOR: ColorType + PixelColor> {
     width: u32,
     height: u32,
     bwrbit: bool,
     buffer: &'a mut [u8],
     rotation: DisplayRotation,
+    /// pixels modified since the last `reset_dirty`, in buffer coordinates
+    dirty: Rectangle,
     _color: PhantomData<COLOR>,
 }
 
@@ -221,6 +1171,15 @@ impl<COLOR: ColorType + PixelColor> OriginDimensions for VarDisplay<'_, COLOR> {
     }
 }
 
+/// For use with embedded_grahics
+impl<COLOR: ColorType + PixelColor> GetPixel for VarDisplay<'_, COLOR> {
+    type Color = COLOR;
+
+    fn pixel(&self, p: Point) -> Option<COLOR> {
+        self.get_pixel(p)
+    }
+}
+
 /// Error found during usage of VarDisplay
 #[derive(Debug)]
 pub enum VarDisplayError {
@@ -245,6 +1204,7 @@ impl<'a, COLOR: ColorType + PixelColor> VarDisplay<'a, COLOR> {
             bwrbit,
             buffer,
             rotation: DisplayRotation::default(),
+            dirty: Rectangle::zero(),
             _color: PhantomData,
         };
         // enfore some constraints dynamicly
@@ -257,7 +1217,7 @@ impl<'a, COLOR: ColorType + PixelColor> VarDisplay<'a, COLOR> {
     /// get the number of used bytes in the buffer
     fn buffer_size(&self) -> usize {
         self.height as usize
-            * line_bytes(
+            * bytes_per_row(
                 self.width,
                 COLOR::BITS_PER_PIXEL_PER_BUFFER * COLOR::BUFFER_COUNT,
             )
@@ -268,6 +1228,13 @@ impl<'a, COLOR: ColorType + PixelColor> VarDisplay<'a, COLOR> {
         &self.buffer[..self.buffer_size()]
     }
 
+    /// See [`Display::buffer_mut`]
+    pub fn buffer_mut(&mut self) -> &mut [u8] {
+        let size = self.buffer_size();
+        self.dirty = Rectangle::new(Point::zero(), Size::new(self.width, self.height));
+        &mut self.buffer[..size]
+    }
+
     /// Set the display rotation.
     ///
     /// This only concerns future drawing made to it. Anything aready drawn
@@ -284,14 +1251,39 @@ impl<'a, COLOR: ColorType + PixelColor> VarDisplay<'a, COLOR> {
     /// Set a specific pixel color on this display
     pub fn set_pixel(&mut self, pixel: Pixel<COLOR>) {
         let size = self.buffer_size();
-        set_pixel(
+        if let Some(point) = set_pixel(
             &mut self.buffer[..size],
             self.width,
             self.height,
             self.rotation,
             self.bwrbit,
+            Packing::default(),
             pixel,
-        );
+        ) {
+            self.dirty = union(&self.dirty, &Rectangle::new(point, Size::new(1, 1)));
+        }
+    }
+
+    /// See [`Display::get_pixel`]
+    pub fn get_pixel(&self, point: Point) -> Option<COLOR> {
+        get_pixel(
+            self.buffer(),
+            self.width,
+            self.height,
+            self.rotation,
+            Packing::default(),
+            point,
+        )
+    }
+
+    /// See [`Display::dirty_area`]
+    pub fn dirty_area(&self) -> Rectangle {
+        self.dirty
+    }
+
+    /// See [`Display::reset_dirty`]
+    pub fn reset_dirty(&mut self) {
+        self.dirty = Rectangle::zero();
     }
 }
 
@@ -308,8 +1300,115 @@ impl VarDisplay<'_, TriColor> {
     }
 }
 
+/// A horizontal band of the display, rendered by [`render_in_bands`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Band {
+    /// zero-indexed position of the band, from the top
+    pub index: u32,
+    /// first row of the display covered by this band
+    pub y_offset: u32,
+    /// number of rows of the display covered by this band
+    pub height: u32,
+}
+
+/// Height of the bands of [`render_in_bands`], the last band only takes the remaining rows
+///
+/// `bands` must not be 0.
+pub const fn band_height(height: u32, bands: u32) -> u32 {
+    height / bands + (height % bands != 0) as u32
+}
+
+/// Makes [`render_in_bands`] fail to build with 0 bands, instead of dividing by zero
+struct Bands<const BANDS: u32>;
+
+impl<const BANDS: u32> Bands<BANDS> {
+    const NOT_ZERO: () = assert!(BANDS > 0, "render_in_bands needs at least one band");
+}
+
+/// Error of [`render_in_bands`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BandError<E> {
+    /// The buffer can't hold a band
+    BufferTooSmall,
+    /// Sending a band failed
+    Send(E),
+}
+
+/// Renders a `width` x `height` display in `BANDS` horizontal bands, so that only the buffer
+/// of one band is needed instead of the buffer of the whole display
+///
+/// For each band, `buffer` is used as a [`VarDisplay`] of the size of the band which is
+/// passed to `draw`, then its content is passed to `send`, e.g. to write it to the matching
+/// window of the display RAM. `draw` works in the coordinates of the band: (0, 0) is at
+/// `(0, band.y_offset)` on the display. The buffer is not cleared between two bands.
+///
+/// `buffer` needs to hold a band of [`band_height`] rows, and `bwrbit` is the same as for
+/// [`Display`]. `BANDS` must not be 0, which doesn't build.
+///
+/// ```rust
+/// use embedded_graphics_core::prelude::*;
+/// use epd_waveshare::{buffer_len, color::Color, graphics::*};
+///
+/// let mut buffer = [0u8; buffer_len(128, band_height(64, 8) as usize)];
+/// let mut rows = 0;
+/// render_in_bands::<8, Color, ()>(
+///     128,
+///     64,
+///     false,
+///     &mut buffer,
+///     |display, band| {
+///         let _ = display.clear(Color::White);
+///         display.set_pixel(Pixel(Point::new(band.index as i32, 0), Color::Black));
+///     },
+///     |band, data| {
+///         // e.g. epd.update_partial_frame(spi, delay, data, 0, band.y_offset, 128, band.height)
+///         rows += band.height;
+///         Ok(())
+///     },
+/// )
+/// .unwrap();
+/// assert_eq!(rows, 64);
+/// ```
+///
+/// ```rust,compile_fail
+/// # use epd_waveshare::{color::Color, graphics::*};
+/// let mut buffer = [0u8; 16];
+/// render_in_bands::<0, Color, ()>(128, 64, false, &mut buffer, |_, _| {}, |_, _| Ok(()));
+/// ```
+pub fn render_in_bands<const BANDS: u32, COLOR, E>(
+    width: u32,
+    height: u32,
+    bwrbit: bool,
+    buffer: &mut [u8],
+    mut draw: impl FnMut(&mut VarDisplay<'_, COLOR>, Band),
+    mut send: impl FnMut(Band, &[u8]) -> Result<(), E>,
+) -> Result<(), BandError<E>>
+where
+    COLOR: ColorType + PixelColor,
+{
+    let () = Bands::<BANDS>::NOT_ZERO;
+    let rows = band_height(height, BANDS);
+    for index in 0..BANDS {
+        let y_offset = index * rows;
+        if y_offset >= height {
+            break;
+        }
+        let band = Band {
+            index,
+            y_offset,
+            height: rows.min(height - y_offset),
+        };
+        let mut display = VarDisplay::new(width, band.height, &mut *buffer, bwrbit)
+            .map_err(|_| BandError::BufferTooSmall)?;
+        draw(&mut display, band);
+        send(band, display.buffer()).map_err(BandError::Send)?;
+    }
+    Ok(())
+}
+
 // This is a function to share code between `Display` and `VarDisplay`
-// It sets a specific pixel in a buffer to a given color.
+// It sets a specific pixel in a buffer to a given color, and returns its position in the
+// buffer if it is inside the display.
 // The big number of parameters is due to the fact that it is an internal function to both
 // strctures.
 fn set_pixel<COLOR: ColorType + PixelColor>(
@@ -318,11 +1417,39 @@ fn set_pixel<COLOR: ColorType + PixelColor>(
     height: u32,
     rotation: DisplayRotation,
     bwrbit: bool,
+    packing: Packing,
     pixel: Pixel<COLOR>,
-) {
+) -> Option<Point> {
     let Pixel(point, color) = pixel;
+    // don't do anything in case of out of range
+    let (x, y) = buffer_point(point, width, height, rotation)?;
 
-    // final coordinates
+    let index = x as usize * COLOR::BITS_PER_PIXEL_PER_BUFFER / 8
+        + y as usize * bytes_per_row(width, COLOR::BITS_PER_PIXEL_PER_BUFFER);
+    let position = packing.position(x as u32, COLOR::BITS_PER_PIXEL_PER_BUFFER);
+    let (mask, bits) = packing.color(color).bitmask(bwrbit, position);
+    let bits = u16::from_le_bytes([packing.value(bits as u8), packing.value((bits >> 8) as u8)]);
+    // the flipped bits of the other pixels are not part of the color
+    let bits = bits & u16::from_le_bytes([!mask, !mask]);
+
+    if COLOR::BUFFER_COUNT == 2 {
+        // split buffer is for tricolor displays that use 2 buffer for 2 bits per pixel
+        buffer[index] = buffer[index] & mask | (bits & 0xFF) as u8;
+        let index = index + buffer.len() / 2;
+        buffer[index] = buffer[index] & mask | (bits >> 8) as u8;
+    } else {
+        buffer[index] = buffer[index] & mask | bits as u8;
+    }
+    Some(Point::new(x, y))
+}
+
+/// Coordinates in the buffer of the drawn `point`, `None` if it is outside of the display
+fn buffer_point(
+    point: Point,
+    width: u32,
+    height: u32,
+    rotation: DisplayRotation,
+) -> Option<(i32, i32)> {
     let (x, y) = match rotation {
         // as i32 = never use more than 2 billion pixel per line or per column
         DisplayRotation::Rotate0 => (point.x, point.y),
@@ -330,25 +1457,35 @@ fn set_pixel<COLOR: ColorType + PixelColor>(
         DisplayRotation::Rotate180 => (width as i32 - 1 - point.x, height as i32 - 1 - point.y),
         DisplayRotation::Rotate270 => (point.y, height as i32 - 1 - point.x),
     };
-
-    // Out of range check
     if (x < 0) || (x >= width as i32) || (y < 0) || (y >= height as i32) {
-        // don't do anything in case of out of range
-        return;
+        return None;
     }
+    Some((x, y))
+}
 
-    let index = x as usize * COLOR::BITS_PER_PIXEL_PER_BUFFER / 8
-        + y as usize * line_bytes(width, COLOR::BITS_PER_PIXEL_PER_BUFFER);
-    let (mask, bits) = color.bitmask(bwrbit, x as u32);
+// Reads back the color of a pixel drawn with `set_pixel`, shared by `Display` and `VarDisplay`
+fn get_pixel<COLOR: ColorType + PixelColor>(
+    buffer: &[u8],
+    width: u32,
+    height: u32,
+    rotation: DisplayRotation,
+    packing: Packing,
+    point: Point,
+) -> Option<COLOR> {
+    let (x, y) = buffer_point(point, width, height, rotation)?;
+    let bits = COLOR::BITS_PER_PIXEL_PER_BUFFER;
 
+    let index = x as usize * bits / 8 + y as usize * bytes_per_row(width, bits);
+    // shift moving the bits of the pixel to the lowest ones
+    let shift = 8 - bits - packing.bit(x as usize, bits) % 8;
+    let pixel = |byte: u8| u16::from(packing.value(byte) >> shift & ((1u16 << bits) - 1) as u8);
+
+    let mut stored = pixel(buffer[index]);
     if COLOR::BUFFER_COUNT == 2 {
-        // split buffer is for tricolor displays that use 2 buffer for 2 bits per pixel
-        buffer[index] = buffer[index] & mask | (bits & 0xFF) as u8;
-        let index = index + buffer.len() / 2;
-        buffer[index] = buffer[index] & mask | (bits >> 8) as u8;
-    } else {
-        buffer[index] = buffer[index] & mask | bits as u8;
+        stored |= pixel(buffer[index + buffer.len() / 2]) << 8;
     }
+    // the inverse of an inverted color is the drawn one
+    COLOR::from_pixel_bits(stored).map(|color| packing.color(color))
 }
 
 #[cfg(test)]
@@ -450,6 +1587,503 @@ mod tests {
         }
     }
 
+    #[test]
+    fn graphics_sparkline() {
+        let mut display = Display::<16, 8, false, { 16 * 8 / 8 }, Color>::default();
+        display.clear(Color::White).unwrap();
+        let rect = display.draw_sparkline(
+            &[0, 4, 8, 2],
+            Rectangle::new(Point::new(4, 0), Size::new(4, 8)),
+            Color::Black,
+        );
+        assert_eq!(rect, Rectangle::new(Point::new(4, 0), Size::new(4, 8)));
+
+        // height of the black bar in the given column
+        let column_height = |x: usize| {
+            (0..8)
+                .filter(|y| display.buffer()[y * 2 + x / 8] & (0x80 >> (x % 8)) == 0)
+                .count()
+        };
+        assert_eq!(column_height(3), 0);
+        assert_eq!(column_height(4), 0);
+        assert_eq!(column_height(5), 4);
+        assert_eq!(column_height(6), 8);
+        assert_eq!(column_height(7), 2);
+        assert_eq!(column_height(8), 0);
+        // bars start at the bottom
+        assert_eq!(display.buffer()[7 * 2] & 0x04, 0);
+        assert_eq!(display.buffer()[3 * 2] & 0x04, 0x04);
+    }
+
+    #[test]
+    fn graphics_draw_tracked() {
+        let mut display = Display::<32, 16, false, { 32 * 16 * 2 / 8 }, TriColor>::default();
+        let style = PrimitiveStyle::with_fill(TriColor::Chromatic);
+        let rect = display.draw_tracked([
+            Rectangle::new(Point::new(2, 1), Size::new(3, 2)).into_styled(style),
+            Rectangle::new(Point::new(20, 10), Size::new(4, 4)).into_styled(style),
+        ]);
+        assert_eq!(rect, Rectangle::new(Point::new(2, 1), Size::new(22, 13)));
+        // both were drawn
+        assert_eq!(display.chromatic_buffer()[4], 0b0011_1000);
+        assert_eq!(display.chromatic_buffer()[10 * 4 + 2], 0b0000_1111);
+
+        // partly outside of the display
+        let rect = display
+            .draw_tracked([Rectangle::new(Point::new(30, 14), Size::new(4, 4)).into_styled(style)]);
+        assert_eq!(rect, Rectangle::new(Point::new(30, 14), Size::new(2, 2)));
+    }
+
+    #[test]
+    #[cfg(feature = "tinybmp")]
+    fn graphics_draw_bmp() {
+        use embedded_graphics::pixelcolor::Rgb888;
+        use tinybmp::Bmp;
+
+        // 8x2, 8 bit palette of white, black and red
+        // first row: W K R W K R W W, second row: all black
+        const BMP: [u8; 82] = [
+            0x42, 0x4d, 0x52, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42, 0x00, 0x00, 0x00,
+            0x28, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00,
+            0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x13, 0x0b, 0x00, 0x00,
+            0x13, 0x0b, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff,
+            0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x00, 0x01, 0x01, 0x01, 0x01,
+            0x01, 0x01, 0x01, 0x01, 0x00, 0x01, 0x02, 0x00, 0x01, 0x02, 0x00, 0x00,
+        ];
+
+        let bmp = Bmp::<Rgb888>::from_slice(&BMP).unwrap();
+        let mut display = Display::<16, 4, false, { 16 * 4 * 2 / 8 }, TriColor>::default();
+        display.clear(TriColor::White).unwrap();
+        display.draw_bmp(&bmp, Point::new(8, 1));
+
+        assert_eq!(display.bw_buffer()[..4], [0xFF, 0xFF, 0xFF, 0b1011_0111]);
+        assert_eq!(display.bw_buffer()[4..6], [0xFF, 0x00]);
+        assert_eq!(
+            display.chromatic_buffer()[..6],
+            [0, 0, 0, 0b0010_0100, 0, 0]
+        );
+    }
+
+    #[test]
+    fn graphics_dirty_byte_count() {
+        let prev = Display::<12, 4, false, { 2 * 4 * 2 }, TriColor>::default();
+        let mut display = prev.clone();
+        assert_eq!(display.dirty_byte_count(&prev), 0);
+
+        // two pixels in the same byte of the B/W bank
+        display.set_pixel(Pixel(Point::new(0, 0), TriColor::White));
+        display.set_pixel(Pixel(Point::new(1, 0), TriColor::White));
+        display.set_pixel(Pixel(Point::new(9, 0), TriColor::White));
+        display.set_pixel(Pixel(Point::new(3, 3), TriColor::White));
+        // sets both banks
+        display.set_pixel(Pixel(Point::new(11, 2), TriColor::Chromatic));
+        // padding bits
+        display.buffer[1] |= 0x0F;
+        display.buffer[8 + 3] |= 0x01;
+        assert_eq!(display.dirty_byte_count(&prev), 5);
+    }
+
+    #[test]
+    fn graphics_blit() {
+        // a 12x3 icon, with a distinct pattern on each line
+        let mut icon = Display::<12, 3, false, 6, Color>::default();
+        for (x, y) in [
+            (0, 0),
+            (3, 0),
+            (11, 0),
+            (1, 1),
+            (10, 1),
+            (2, 2),
+            (7, 2),
+            (8, 2),
+        ] {
+            icon.set_pixel(Pixel(Point::new(x, y), Color::White));
+        }
+        let pixel = |icon: &Display<12, 3, false, 6, Color>, x: u32, y: u32| {
+            icon.buffer()[y as usize * 2 + x as usize / 8] >> (7 - x % 8) & 1
+        };
+
+        for (src_rect, dest) in [
+            // aligned fast path
+            (
+                Rectangle::new(Point::new(0, 0), Size::new(12, 3)),
+                Point::new(8, 2),
+            ),
+            (
+                Rectangle::new(Point::new(1, 0), Size::new(10, 3)),
+                Point::new(9, 2),
+            ),
+            // shifted slow path
+            (
+                Rectangle::new(Point::new(0, 0), Size::new(12, 3)),
+                Point::new(5, 1),
+            ),
+            (
+                Rectangle::new(Point::new(2, 1), Size::new(9, 2)),
+                Point::new(27, 4),
+            ),
+            // clipped by both images
+            (
+                Rectangle::new(Point::new(-4, 0), Size::new(16, 8)),
+                Point::new(-3, 6),
+            ),
+        ] {
+            let mut display = Display::<32, 8, false, 32, Color>::default();
+            display.blit(&icon, src_rect, dest);
+
+            // same as copying pixel by pixel
+            let mut expected = Display::<32, 8, false, 32, Color>::default();
+            for y in 0..3u32 {
+                for x in 0..12u32 {
+                    let point = Point::new(x as i32, y as i32);
+                    if pixel(&icon, x, y) == 1 && src_rect.contains(point) {
+                        let point = dest + (point - src_rect.top_left);
+                        expected.set_pixel(Pixel(point, Color::White));
+                    }
+                }
+            }
+            assert_eq!(display.buffer(), expected.buffer());
+        }
+
+        let mut display = Display::<32, 8, false, 32, Color>::default();
+        let icon_rect = Rectangle::new(Point::zero(), Size::new(12, 3));
+        assert_eq!(
+            display.blit(&icon, icon_rect, Point::new(24, 7)),
+            Rectangle::new(Point::new(24, 7), Size::new(8, 1))
+        );
+        assert!(display
+            .blit(&icon, icon_rect, Point::new(40, 0))
+            .is_zero_sized());
+        assert_eq!(
+            display.dirty_area(),
+            Rectangle::new(Point::new(24, 7), Size::new(8, 1))
+        );
+
+        // both planes of tricolor images
+        let mut icon = Display::<8, 1, true, 2, TriColor>::default();
+        icon.set_pixel(Pixel(Point::new(1, 0), TriColor::Chromatic));
+        icon.set_pixel(Pixel(Point::new(2, 0), TriColor::White));
+        let mut display = Display::<16, 2, true, 8, TriColor>::default();
+        display.blit(&icon, icon_rect, Point::new(4, 1));
+        assert_eq!(display.bw_buffer(), [0, 0, 0b0000_0010, 0]);
+        assert_eq!(display.chromatic_buffer(), [0, 0, 0b0000_0100, 0]);
+    }
+
+    #[test]
+    fn graphics_scroll() {
+        // a pixel in each corner, and one in the middle, of a 12x4 display
+        let points = [(0, 0), (11, 0), (0, 3), (11, 3), (5, 2)];
+        let scrolled = |scroll: &dyn Fn(&mut Display<12, 4, false, 8, Color>) -> Rectangle,
+                        (dx, dy): (i32, i32),
+                        exposed: Rectangle| {
+            let mut display = Display::<12, 4, false, 8, Color>::default();
+            for (x, y) in points {
+                display.set_pixel(Pixel(Point::new(x, y), Color::White));
+            }
+            assert_eq!(scroll(&mut display), exposed);
+
+            let mut expected = Display::<12, 4, false, 8, Color>::default();
+            let _ = expected.fill_solid(&exposed, Color::White);
+            for (x, y) in points {
+                expected.set_pixel(Pixel(Point::new(x + dx, y + dy), Color::White));
+            }
+            // the padding bits are not kept
+            let mask = [0xFF, 0xF0].iter().cycle();
+            for ((a, b), mask) in display.buffer().iter().zip(expected.buffer()).zip(mask) {
+                assert_eq!(a & mask, b & mask);
+            }
+        };
+
+        let band = |x, y, w, h| Rectangle::new(Point::new(x, y), Size::new(w, h));
+        scrolled(&|d| d.scroll_y(1, Color::White), (0, -1), band(0, 3, 12, 1));
+        scrolled(&|d| d.scroll_y(-2, Color::White), (0, 2), band(0, 0, 12, 2));
+        scrolled(&|d| d.scroll_x(3, Color::White), (-3, 0), band(9, 0, 3, 4));
+        scrolled(&|d| d.scroll_x(-5, Color::White), (5, 0), band(0, 0, 5, 4));
+        scrolled(&|d| d.scroll_x(8, Color::White), (-8, 0), band(4, 0, 8, 4));
+        scrolled(&|d| d.scroll_x(-8, Color::White), (8, 0), band(0, 0, 8, 4));
+        scrolled(&|d| d.scroll_y(9, Color::White), (0, -9), band(0, 0, 12, 4));
+
+        let mut display = Display::<8, 2, true, 4, TriColor>::default();
+        display.set_pixel(Pixel(Point::new(0, 1), TriColor::Chromatic));
+        display.scroll_y(1, TriColor::White);
+        assert_eq!(display.bw_buffer(), [0x00, 0xFF]);
+        assert_eq!(display.chromatic_buffer(), [0x80, 0x00]);
+    }
+
+    #[test]
+    fn graphics_fill_solid() {
+        let rects = [
+            Rectangle::new(Point::new(0, 0), Size::new(20, 6)),
+            Rectangle::new(Point::new(3, 1), Size::new(4, 2)),
+            Rectangle::new(Point::new(2, 2), Size::new(13, 3)),
+            Rectangle::new(Point::new(-5, 4), Size::new(30, 9)),
+        ];
+        let rotations = [
+            DisplayRotation::Rotate0,
+            DisplayRotation::Rotate90,
+            DisplayRotation::Rotate180,
+            DisplayRotation::Rotate270,
+        ];
+        for rect in rects {
+            for rotation in rotations {
+                // same as drawing pixel by pixel
+                let mut display = Display::<20, 6, true, 36, TriColor>::default();
+                let mut expected = Display::<20, 6, true, 36, TriColor>::default();
+                for display in [&mut display, &mut expected] {
+                    display.set_rotation(rotation);
+                    let _ = display.fill_solid(&display.bounding_box(), TriColor::White);
+                    display.reset_dirty();
+                }
+                let _ = display.fill_solid(&rect, TriColor::Chromatic);
+                for point in rect.points() {
+                    expected.set_pixel(Pixel(point, TriColor::Chromatic));
+                }
+                assert_eq!(display.buffer(), expected.buffer());
+                assert_eq!(display.dirty_area(), expected.dirty_area());
+
+                let colors = [OctColor::Red, OctColor::Blue, OctColor::Yellow];
+                let mut display = Display::<20, 6, false, 60, OctColor>::default();
+                let mut expected = Display::<20, 6, false, 60, OctColor>::default();
+                display.set_rotation(rotation);
+                expected.set_rotation(rotation);
+                let _ = display.fill_solid(&rect, OctColor::Green);
+                let _ = display.fill_contiguous(&rect, colors.iter().copied().cycle());
+                for (point, color) in rect.points().zip(colors.iter().cycle()) {
+                    expected.set_pixel(Pixel(point, *color));
+                }
+                assert_eq!(display.buffer(), expected.buffer());
+                assert_eq!(display.dirty_area(), expected.dirty_area());
+            }
+        }
+    }
+
+    #[test]
+    fn graphics_serialize() {
+        type Small = Display<12, 3, true, 12, TriColor>;
+        let mut display = Small::default();
+        display.set_rotation(DisplayRotation::Rotate180);
+        display.set_pixel(Pixel(Point::new(0, 0), TriColor::Chromatic));
+        display.set_pixel(Pixel(Point::new(5, 1), TriColor::White));
+
+        display.set_bit_order(BitOrder::LsbFirst);
+        display.set_inverted(true);
+
+        let mut bytes = [0; Small::SERIALIZED_LEN];
+        assert_eq!(display.serialize_into(&mut bytes), Ok(22));
+        assert_eq!(bytes[..10], [12, 0, 0, 0, 3, 0, 0, 0, 2, 0b101]);
+        assert_eq!(bytes[10..], *display.buffer());
+
+        let mut copy = Small::deserialize(&bytes).unwrap();
+        assert_eq!(copy.buffer(), display.buffer());
+        assert!(matches!(copy.rotation(), DisplayRotation::Rotate180));
+        assert_eq!(copy.bit_order(), BitOrder::LsbFirst);
+        assert_eq!(copy.polarity(), Polarity::Normal);
+        assert!(copy.is_inverted());
+        // drawing on the copy packs the pixels like on the display
+        display.set_pixel(Pixel(Point::new(7, 2), TriColor::Black));
+        copy.set_pixel(Pixel(Point::new(7, 2), TriColor::Black));
+        assert_eq!(copy.buffer(), display.buffer());
+
+        bytes[9] = 0b1000;
+        assert_eq!(
+            Small::deserialize(&bytes).err(),
+            Some(SerialError::InvalidPacking)
+        );
+        bytes[9] = 0;
+
+        assert_eq!(
+            Small::deserialize(&bytes[1..]).err(),
+            Some(SerialError::WrongLength)
+        );
+        assert_eq!(
+            display.serialize_into(&mut [0; 8]),
+            Err(SerialError::WrongLength)
+        );
+        bytes[8] = 4;
+        assert_eq!(
+            Small::deserialize(&bytes).err(),
+            Some(SerialError::InvalidRotation)
+        );
+        bytes[0] = 16;
+        assert_eq!(
+            Small::deserialize(&bytes).err(),
+            Some(SerialError::WrongSize)
+        );
+    }
+
+    #[test]
+    fn graphics_get_pixel() {
+        let rotations = [
+            DisplayRotation::Rotate0,
+            DisplayRotation::Rotate90,
+            DisplayRotation::Rotate180,
+            DisplayRotation::Rotate270,
+        ];
+        let packings = [
+            (BitOrder::MsbFirst, Polarity::Normal, false),
+            (BitOrder::LsbFirst, Polarity::Inverted, false),
+            (BitOrder::LsbFirst, Polarity::Normal, true),
+        ];
+        for rotation in rotations {
+            for (bit_order, polarity, inverted) in packings {
+                let mut display = Display::<12, 4, true, 16, TriColor>::default();
+                display.set_rotation(rotation);
+                display.set_bit_order(bit_order);
+                display.set_polarity(polarity);
+                display.set_inverted(inverted);
+                display.clear(TriColor::White).unwrap();
+                display.set_pixel(Pixel(Point::new(1, 2), TriColor::Black));
+                display.set_pixel(Pixel(Point::new(3, 1), TriColor::Chromatic));
+
+                assert_eq!(display.get_pixel(Point::new(1, 2)), Some(TriColor::Black));
+                assert_eq!(display.pixel(Point::new(3, 1)), Some(TriColor::Chromatic));
+                assert_eq!(display.get_pixel(Point::new(2, 1)), Some(TriColor::White));
+                assert_eq!(display.get_pixel(Point::new(-1, 0)), None);
+                assert_eq!(display.get_pixel(Point::new(0, 12)), None);
+            }
+        }
+
+        let mut display = Display::<5, 2, false, 6, OctColor>::default();
+        display.set_bit_order(BitOrder::LsbFirst);
+        display.set_pixel(Pixel(Point::new(4, 1), OctColor::Orange));
+        assert_eq!(display.get_pixel(Point::new(4, 1)), Some(OctColor::Orange));
+        assert_eq!(display.get_pixel(Point::new(3, 1)), Some(OctColor::Black));
+
+        let mut buffer = [0u8; 4];
+        let mut display = VarDisplay::<Gray4Epd>::new(7, 2, &mut buffer, false).unwrap();
+        display.set_pixel(Pixel(Point::new(6, 1), Gray4Epd::LightGray));
+        assert_eq!(display.pixel(Point::new(6, 1)), Some(Gray4Epd::LightGray));
+        assert_eq!(display.get_pixel(Point::new(7, 1)), None);
+    }
+
+    #[test]
+    fn graphics_raw_buffer() {
+        type Small = Display<16, 2, false, 4, Color>;
+        let mut display = Small::from_raw([0xFF, 0x00, 0x0F, 0xF0], DisplayRotation::Rotate90);
+        assert!(display.dirty_area().is_zero_sized());
+        assert_eq!(display.size(), Size::new(2, 16));
+        // drawn with the rotation over the raw buffer
+        display.set_pixel(Pixel(Point::new(1, 0), Color::White));
+        assert_eq!(display.buffer(), [0xFF, 0x00, 0x0F, 0xF1]);
+
+        display.reset_dirty();
+        display.buffer_mut()[1] = 0xAA;
+        assert_eq!(display.buffer(), [0xFF, 0xAA, 0x0F, 0xF1]);
+        assert_eq!(
+            display.dirty_area(),
+            Rectangle::new(Point::zero(), Size::new(16, 2))
+        );
+
+        let mut buffer = [0u8; 4];
+        let mut var = VarDisplay::<Color>::new(16, 2, &mut buffer, false).unwrap();
+        var.buffer_mut().copy_from_slice(display.buffer());
+        assert_eq!(var.buffer(), display.buffer());
+        assert_eq!(var.dirty_area(), display.dirty_area());
+    }
+
+    #[test]
+    fn graphics_dirty_area() {
+        let mut display = Display::<32, 16, false, { 32 * 16 / 8 }, Color>::default();
+        assert!(display.dirty_area().is_zero_sized());
+
+        display.set_pixel(Pixel(Point::new(3, 2), Color::Black));
+        display.set_pixel(Pixel(Point::new(10, 7), Color::White));
+        // outside of the display
+        display.set_pixel(Pixel(Point::new(40, 20), Color::White));
+        assert_eq!(
+            display.dirty_area(),
+            Rectangle::new(Point::new(3, 2), Size::new(8, 6))
+        );
+
+        // in buffer coordinates
+        display.reset_dirty();
+        display.set_rotation(DisplayRotation::Rotate90);
+        display.set_pixel(Pixel(Point::new(0, 0), Color::Black));
+        assert_eq!(
+            display.dirty_area(),
+            Rectangle::new(Point::new(31, 0), Size::new(1, 1))
+        );
+    }
+
+    #[test]
+    fn graphics_var_display() {
+        let mut buffer = [0u8; 64];
+        assert!(VarDisplay::<Color>::new(32, 32, &mut buffer, false).is_err());
+
+        let mut display = VarDisplay::<Color>::new(20, 10, &mut buffer, false).unwrap();
+        assert_eq!(display.size(), Size::new(20, 10));
+        // 3 bytes per line
+        assert_eq!(display.buffer().len(), 30);
+
+        display.set_pixel(Pixel(Point::new(19, 9), Color::White));
+        assert_eq!(display.buffer()[29], 0x10);
+        assert_eq!(
+            display.dirty_area(),
+            Rectangle::new(Point::new(19, 9), Size::new(1, 1))
+        );
+        display.reset_dirty();
+        assert!(display.dirty_area().is_zero_sized());
+    }
+
+    #[test]
+    fn graphics_render_in_bands() {
+        extern crate std;
+        use std::vec::Vec;
+
+        let mut buffer = [0u8; 2 * 3];
+        let mut sent = Vec::new();
+        render_in_bands::<4, Color, ()>(
+            16,
+            10,
+            false,
+            &mut buffer,
+            |display, band| {
+                let _ = display.clear(Color::Black);
+                display.set_pixel(Pixel(Point::new(band.index as i32, 0), Color::White));
+            },
+            |band, data| {
+                sent.push((band, data.to_vec()));
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        // 3 rows per band, the last one only gets 1
+        assert_eq!(sent.len(), 4);
+        assert_eq!(
+            sent[3].0,
+            Band {
+                index: 3,
+                y_offset: 9,
+                height: 1
+            }
+        );
+        assert_eq!(sent[1].1, [0x40, 0, 0, 0, 0, 0]);
+        assert_eq!(sent[3].1, [0x10, 0]);
+
+        let mut buffer = [0u8; 4];
+        assert_eq!(
+            render_in_bands::<4, Color, ()>(16, 10, false, &mut buffer, |_, _| {}, |_, _| Ok(())),
+            Err(BandError::BufferTooSmall)
+        );
+
+        // more bands than rows: one row per band, the bands past the last row are skipped
+        let mut bands = 0;
+        render_in_bands::<16, Color, ()>(
+            16,
+            10,
+            false,
+            &mut buffer,
+            |_, _| bands += 1,
+            |_, _| Ok(()),
+        )
+        .unwrap();
+        assert_eq!(bands, 10);
+        assert_eq!(band_height(10, 16), 1);
+        assert_eq!(band_height(10, u32::MAX), 1);
+        assert_eq!(band_height(u32::MAX, 2), u32::MAX / 2 + 1);
+    }
+
     #[test]
     fn graphics_set_pixel_tricolor_false() {
         let mut display = Display::<4, 4, false, { 4 * 4 * 2 / 8 }, TriColor>::default();
@@ -485,4 +2119,125 @@ mod tests {
         assert_eq!(bw_buffer, [128, 0]);
         assert_eq!(chromatic_buffer, [64, 0]);
     }
+
+    #[test]
+    fn graphics_bit_order_and_polarity() {
+        let mut display = Display::<12, 2, false, 4, Color>::default();
+        display.set_bit_order(BitOrder::LsbFirst);
+        display.set_pixel(Pixel(Point::new(0, 0), Color::White));
+        let _ = display.fill_solid(
+            &Rectangle::new(Point::new(6, 1), Size::new(4, 1)),
+            Color::White,
+        );
+        assert_eq!(display.buffer(), [0x01, 0x00, 0xC0, 0x03]);
+
+        display.set_polarity(Polarity::Inverted);
+        assert_eq!(display.buffer(), [0xFE, 0xFF, 0x3F, 0xFC]);
+        display.set_pixel(Pixel(Point::new(1, 0), Color::White));
+        assert_eq!(display.buffer()[0], 0xFC);
+
+        // 2 bits per pixel
+        let mut display = Display::<4, 1, false, 1, Gray4Epd>::default();
+        display.set_bit_order(BitOrder::LsbFirst);
+        display.set_pixel(Pixel(Point::new(0, 0), Gray4Epd::LightGray));
+        display.set_pixel(Pixel(Point::new(3, 0), Gray4Epd::DarkGray));
+        assert_eq!(display.buffer(), [0b01_00_00_10]);
+    }
+
+    #[test]
+    fn graphics_packing_converts_in_place() {
+        type Tri = Display<20, 4, true, 24, TriColor>;
+        let draw = |display: &mut Tri| {
+            let _ = display.clear(TriColor::White);
+            let _ = Line::new(Point::new(1, 0), Point::new(17, 3))
+                .into_styled(PrimitiveStyle::with_stroke(TriColor::Black, 1))
+                .draw(display);
+            let _ = display.fill_solid(
+                &Rectangle::new(Point::new(3, 1), Size::new(9, 2)),
+                TriColor::Chromatic,
+            );
+            display.scroll_x(3, TriColor::Black);
+            display.scroll_x(-2, TriColor::White);
+            display.replace_color(TriColor::Black, TriColor::Chromatic);
+            let icon = display.clone();
+            display.blit(
+                &icon,
+                Rectangle::new(Point::new(2, 0), Size::new(7, 2)),
+                Point::new(11, 2),
+            );
+        };
+
+        let mut expected = Tri::default();
+        draw(&mut expected);
+        for (bit_order, polarity) in [
+            (BitOrder::LsbFirst, Polarity::Normal),
+            (BitOrder::MsbFirst, Polarity::Inverted),
+            (BitOrder::LsbFirst, Polarity::Inverted),
+        ] {
+            let mut display = Tri::default();
+            display.set_bit_order(bit_order);
+            display.set_polarity(polarity);
+            draw(&mut display);
+            assert_eq!(display.dirty_byte_count(&display.clone()), 0);
+
+            display.set_bit_order(BitOrder::MsbFirst);
+            display.set_polarity(Polarity::Normal);
+            // the padding bits are not kept
+            let mask = [0xFF, 0xFF, 0xF0].iter().cycle();
+            for ((a, b), mask) in display.buffer().iter().zip(expected.buffer()).zip(mask) {
+                assert_eq!(a & mask, b & mask);
+            }
+        }
+    }
+
+    #[test]
+    fn graphics_inverted() {
+        let mut display = Display::<8, 2, false, 2, Color>::default();
+        display.set_inverted(true);
+        let _ = display.clear(Color::White);
+        display.set_pixel(Pixel(Point::new(1, 1), Color::Black));
+        assert_eq!(display.buffer(), [0x00, 0x40]);
+
+        let mut display = Display::<8, 1, true, 2, TriColor>::default();
+        display.set_inverted(true);
+        let _ = display.clear(TriColor::Black);
+        display.set_pixel(Pixel(Point::new(0, 0), TriColor::Chromatic));
+        display.set_pixel(Pixel(Point::new(1, 0), TriColor::White));
+        assert_eq!(display.bw_buffer(), [0x3F]);
+        assert_eq!(display.chromatic_buffer(), [0x80]);
+        // the colors to replace are the drawn ones
+        display.replace_color(TriColor::Black, TriColor::Chromatic);
+        assert_eq!(display.bw_buffer(), [0x00]);
+        assert_eq!(display.chromatic_buffer(), [0xBF]);
+
+        assert_eq!(Gray4Epd::DarkGray.inverse(), Gray4Epd::LightGray);
+        assert_eq!(OctColor::Red.inverse(), OctColor::Red);
+    }
+
+    #[test]
+    fn graphics_padding_helpers() {
+        assert_eq!(bytes_per_row(122, 1), 16);
+        assert_eq!(bytes_per_row(122, 2), 31);
+
+        let mut row = [0xAA; 3];
+        let packed = pack_row_from_bits(
+            [
+                true, false, true, true, false, false, true, true, true, true,
+            ],
+            &mut row,
+        );
+        assert_eq!(packed, 10);
+        assert_eq!(row, [0b1011_0011, 0b1100_0000, 0xAA]);
+        assert_eq!(pack_row_from_bits(core::iter::repeat(true), &mut row), 24);
+
+        // 3 rows of 10 pixels without padding: row y has its pixel y set
+        let src = [0b1000_0000, 0b0001_0000, 0b0000_0010, 0b0000_0000];
+        let mut dst = [0u8; 6];
+        assert_eq!(repack_1bpp(&src, 10, &mut dst, 16, 10), 3);
+        assert_eq!(dst, [0x80, 0x00, 0x40, 0x00, 0x20, 0x00]);
+        // and back, the last row being cut
+        let mut back = [0u8; 3];
+        assert_eq!(repack_1bpp(&dst, 16, &mut back, 10, 10), 2);
+        assert_eq!(back, [0b1000_0000, 0b0001_0000, 0x00]);
+    }
 }
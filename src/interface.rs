@@ -1,7 +1,67 @@
+#[cfg(any(feature = "crc", test))]
+use crate::crc::Crc32;
+use crate::error::Error;
 use crate::traits::Command;
 use core::marker::PhantomData;
+#[cfg(feature = "epd2in13b_v4")]
+use embedded_hal::spi::Operation;
 use embedded_hal::{delay::*, digital::*, spi::SpiDevice};
 
+/// Traces the traffic of the drivers at the trace level, with the `log` and `defmt` features,
+/// e.g. to compare an init sequence with the vendor code
+macro_rules! trace {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::trace!($($arg)*);
+        #[cfg(feature = "defmt")]
+        defmt::trace!($($arg)*);
+    }};
+}
+#[cfg(all(feature = "async", feature = "epd2in13b_v4"))]
+pub(crate) use trace;
+
+/// Kind of the bytes sent to the display, as seen by a [`Tap`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrafficKind {
+    /// Command byte, sent with DC low
+    Command,
+    /// Data bytes, sent with DC high
+    Data,
+}
+
+/// Observer called with every chunk of bytes right before it is written to the SPI device
+///
+/// This is a plain function pointer so it can be stored without adding a lifetime to the
+/// drivers; use a `static` if you need to keep some state.
+///
+/// To only trace the traffic, enable the `log` or `defmt` feature instead: the drivers then
+/// log every command byte, the length of the data and the time waited for the busy pin.
+///
+/// ```rust, ignore
+/// static SENT: AtomicUsize = AtomicUsize::new(0);
+/// fn count(_kind: TrafficKind, data: &[u8]) {
+///     SENT.fetch_add(data.len(), Ordering::Relaxed);
+/// }
+/// epd.set_traffic_tap(Some(count));
+/// ```
+pub type Tap = fn(TrafficKind, &[u8]);
+
+/// Function called on every poll of the busy pin while the display is busy, with the time
+/// waited so far in us, right before the delay of the idle loop
+///
+/// A refresh takes seconds: this lets an RTOS run other tasks or feed a watchdog meanwhile.
+/// Like the [`Tap`], it is a plain function pointer. The time is counted like the busy timeout,
+/// by adding up the delays between two polls.
+///
+/// ```rust, ignore
+/// fn service(_waited_us: u32) {
+///     watchdog_feed();
+///     scheduler_yield();
+/// }
+/// epd.set_busy_yield(Some(service));
+/// ```
+pub type BusyYield = fn(u32);
+
 /// The Connection Interface of all (?) Waveshare EPD-Devices
 ///
 /// SINGLE_BYTE_WRITE defines if a data block is written bytewise
@@ -17,8 +77,100 @@ pub(crate) struct DisplayInterface<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_
     dc: DC,
     /// Pin for Resetting
     rst: RST,
-    /// number of ms the idle loop should sleep on
+    /// number of us the idle loop should sleep on
     delay_us: u32,
+    /// longest sleep of the idle loop, the sleep doubling after every poll until it, if set
+    max_delay_us: Option<u32>,
+    /// number of us after which waiting for the busy pin gives up
+    busy_timeout_us: Option<u32>,
+    /// Observer of all the traffic sent to the display
+    tap: Option<Tap>,
+    /// called on every poll of the busy pin while the display is busy
+    busy_yield: Option<BusyYield>,
+    /// largest number of bytes written in a single SPI transfer
+    max_transfer_size: Option<usize>,
+    /// time spent in the last wait for the busy pin
+    last_wait_us: u32,
+    /// CRC of the data following the command given to [`track_crc`](Self::track_crc)
+    #[cfg(any(feature = "crc", test))]
+    crc: CrcTracker,
+}
+
+/// CRC of the data following a command, e.g. the one writing the RAM
+#[cfg(any(feature = "crc", test))]
+#[derive(Default)]
+struct CrcTracker {
+    /// address of the tracked command
+    command: Option<u8>,
+    /// CRC of the data sent since the tracked command, until the next command
+    current: Option<Crc32>,
+    /// CRC of the data of the last tracked command followed by another command
+    last: Option<u32>,
+}
+
+impl<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>
+    DisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>
+{
+    /// Gives back the busy, data/command and reset pins
+    pub(crate) fn release(self) -> (BUSY, DC, RST) {
+        (self.busy, self.dc, self.rst)
+    }
+
+    /// Sets how long the idle loop sleeps between two polls of the busy pin, 0 busy waiting
+    pub(crate) fn set_poll_interval(&mut self, interval_us: u32) {
+        self.delay_us = interval_us;
+    }
+
+    /// Makes the idle loop sleep twice as long after every poll of the busy pin, until
+    /// `max_interval_us`. `None` keeps the interval constant.
+    ///
+    /// The backoff keeps quick waits short while polling a long refresh only a few times.
+    pub(crate) fn set_poll_backoff(&mut self, max_interval_us: Option<u32>) {
+        self.max_delay_us = max_interval_us;
+    }
+
+    /// Sets or removes the function called while waiting for the busy pin
+    pub(crate) fn set_busy_yield(&mut self, busy_yield: Option<BusyYield>) {
+        self.busy_yield = busy_yield;
+    }
+
+    /// Sets how long [`wait_until_idle`](Self::wait_until_idle) waits for the display before
+    /// returning [`Error::BusyTimeout`]. `None` waits forever.
+    ///
+    /// There is no clock, so the time is counted by adding up the delays between two polls of
+    /// the busy pin. Without a delay (busy waiting), each poll counts as 1us.
+    pub(crate) fn set_busy_timeout(&mut self, timeout_us: Option<u32>) {
+        self.busy_timeout_us = timeout_us;
+    }
+
+    /// Time the last [`wait_until_idle`](Self::wait_until_idle) waited for the display, counted
+    /// like the busy timeout
+    pub(crate) fn last_wait_us(&self) -> u32 {
+        self.last_wait_us
+    }
+
+    /// Computes the CRC of the data following `command`, given by
+    /// [`last_crc`](Self::last_crc). It replaces the command tracked before.
+    #[cfg(any(feature = "crc", test))]
+    pub(crate) fn track_crc<T: Command>(&mut self, command: T) {
+        self.crc = CrcTracker {
+            command: Some(command.address()),
+            ..CrcTracker::default()
+        };
+    }
+
+    /// CRC of the data following the last tracked command, so far if no other command was sent
+    /// since, `None` before the tracked command is sent
+    ///
+    /// The data written directly to the SPI device after
+    /// [`prepare_data`](Self::prepare_data) is not seen.
+    #[cfg(any(feature = "crc", test))]
+    pub(crate) fn last_crc(&self) -> Option<u32> {
+        match self.crc.current {
+            Some(crc) => Some(crc.finish()),
+            None => self.crc.last,
+        }
+    }
 }
 
 impl<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>
@@ -43,26 +195,128 @@ where
             dc,
             rst,
             delay_us,
+            max_delay_us: None,
+            busy_timeout_us: None,
+            tap: None,
+            busy_yield: None,
+            // Be careful!! Linux has a default limit of 4096 bytes per spi transfer
+            // see https://raspberrypi.stackexchange.com/questions/65595/spi-transfer-fails-with-buffer-size-greater-than-4096
+            max_transfer_size: if cfg!(target_os = "linux") {
+                Some(4096)
+            } else {
+                None
+            },
+            last_wait_us: 0,
+            #[cfg(any(feature = "crc", test))]
+            crc: CrcTracker::default(),
+        }
+    }
+
+    /// Sets or removes the observer called with all the traffic sent to the display
+    #[cfg(feature = "epd2in13b_v4")]
+    pub(crate) fn set_tap(&mut self, tap: Option<Tap>) {
+        self.tap = tap;
+    }
+
+    // sleep of the idle loop after one of `delay_us`, following the backoff
+    fn next_delay_us(&self, delay_us: u32) -> u32 {
+        match self.max_delay_us {
+            Some(max_delay_us) => delay_us.max(1).saturating_mul(2).min(max_delay_us),
+            None => delay_us,
+        }
+    }
+
+    /// Sets the largest number of bytes written in a single SPI transfer, longer writes being
+    /// split. `None` writes everything at once.
+    ///
+    /// It is 4096 bytes on Linux, the default limit of spidev, and unlimited elsewhere.
+    #[cfg(any(
+        feature = "epd2in9b_v4",
+        feature = "epd4in26",
+        feature = "epd5in79",
+        feature = "epd7in5",
+        feature = "epd7in5_hd",
+        feature = "epd7in5_v2",
+        feature = "epd7in5b_v2",
+        feature = "epd13in3k"
+    ))]
+    pub(crate) fn set_max_transfer_size(&mut self, size: Option<usize>) {
+        self.max_transfer_size = size.map(|size| size.max(1));
+    }
+
+    // counts a poll of the busy pin followed by a sleep of `delay_us`, failing once the timeout
+    // is reached
+    fn check_busy_timeout(
+        &self,
+        waited_us: &mut u32,
+        delay_us: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        match self.busy_timeout_us {
+            Some(timeout_us) if *waited_us >= timeout_us => Err(Error::BusyTimeout),
+            _ => {
+                *waited_us = waited_us.saturating_add(delay_us.max(1));
+                Ok(())
+            }
+        }
+    }
+
+    // gives the hand to the busy yield, if there is one
+    fn busy_yield(&self, waited_us: u32) {
+        if let Some(busy_yield) = self.busy_yield {
+            busy_yield(waited_us);
+        }
+    }
+
+    // forwards some traffic to the tap, if there is one, to the CRC and to the trace
+    fn tap(&mut self, kind: TrafficKind, data: &[u8]) {
+        match kind {
+            TrafficKind::Command => trace!("command {:#x}", data[0]),
+            TrafficKind::Data => trace!("{} data bytes", data.len()),
+        }
+        if let Some(tap) = self.tap {
+            tap(kind, data);
+        }
+        #[cfg(any(feature = "crc", test))]
+        match kind {
+            TrafficKind::Command => {
+                if let Some(crc) = self.crc.current.take() {
+                    self.crc.last = Some(crc.finish());
+                }
+                if data.first().copied() == self.crc.command {
+                    self.crc.current = Some(Crc32::new());
+                }
+            }
+            TrafficKind::Data => {
+                if let Some(crc) = self.crc.current.as_mut() {
+                    crc.update(data);
+                }
+            }
         }
     }
 
     /// Basic function for sending [Commands](Command).
     ///
     /// Enables direct interaction with the device with the help of [data()](DisplayInterface::data())
-    pub(crate) fn cmd<T: Command>(&mut self, spi: &mut SPI, command: T) -> Result<(), SPI::Error> {
+    pub(crate) fn cmd<T: Command>(
+        &mut self,
+        spi: &mut SPI,
+        command: T,
+    ) -> Result<(), Error<SPI::Error>> {
         // low for commands
-        let _ = self.dc.set_low();
+        self.dc.set_low().map_err(Error::pin)?;
 
         // Transfer the command over spi
+        self.tap(TrafficKind::Command, &[command.address()]);
         self.write(spi, &[command.address()])
     }
 
     /// Basic function for sending an array of u8-values of data over spi
     ///
     /// Enables direct interaction with the device with the help of [command()](Epd4in2::command())
-    pub(crate) fn data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
+    pub(crate) fn data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), Error<SPI::Error>> {
         // high for data
-        let _ = self.dc.set_high();
+        self.dc.set_high().map_err(Error::pin)?;
+        self.tap(TrafficKind::Data, data);
 
         if SINGLE_BYTE_WRITE {
             for val in data.iter().copied() {
@@ -76,27 +330,62 @@ where
         Ok(())
     }
 
+    /// Sets the DC pin for data, so the data following a command can be written directly to
+    /// the SPI device, e.g. by a DMA transfer
+    #[cfg(feature = "epd2in13b_v4")]
+    pub(crate) fn prepare_data(&mut self) -> Result<(), Error<SPI::Error>> {
+        // high for data
+        self.dc.set_high().map_err(Error::pin)
+    }
+
     /// Basic function for sending u8-values with the provided generator.
     ///
     /// Intented for use with rendering from progmem buffers.
+    #[cfg(feature = "epd2in13b_v4")]
     pub(crate) fn data_with(
         &mut self,
         spi: &mut SPI,
         data: impl Fn(usize) -> u8,
         len: usize,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        self.data_iter(spi, (0..len).map(data))
+    }
+
+    /// Basic function for sending the u8-values of an iterator, e.g. streamed from a file
+    ///
+    /// Without `SINGLE_BYTE_WRITE`, the values are collected in small blocks before being
+    /// written.
+    #[cfg(any(
+        feature = "epd2in13b_v4",
+        feature = "epd3in7",
+        feature = "epd4in26",
+        feature = "epd5in79",
+        feature = "epd7in5_v2",
+        feature = "epd13in3k"
+    ))]
+    pub(crate) fn data_iter(
+        &mut self,
+        spi: &mut SPI,
+        data: impl IntoIterator<Item = u8>,
+    ) -> Result<(), Error<SPI::Error>> {
         // high for data
-        let _ = self.dc.set_high();
+        self.dc.set_high().map_err(Error::pin)?;
 
-        if SINGLE_BYTE_WRITE {
-            for i in 0..len {
-                // Transfer data one u8 at a time over spi
-                self.write(spi, &[data(i)])?;
+        let mut block = [0u8; 64];
+        let mut len = 0;
+        for val in data {
+            block[len] = val;
+            len += 1;
+            if SINGLE_BYTE_WRITE || len == block.len() {
+                self.tap(TrafficKind::Data, &block[..len]);
+                self.write(spi, &block[..len])?;
+                len = 0;
             }
-        } else {
-            unimplemented!();
         }
-
+        if len > 0 {
+            self.tap(TrafficKind::Data, &block[..len]);
+            self.write(spi, &block[..len])?;
+        }
         Ok(())
     }
 
@@ -108,42 +397,100 @@ where
         spi: &mut SPI,
         command: T,
         data: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.cmd(spi, command)?;
         self.data(spi, data)
     }
 
+    /// Basic function for reading data from the display, e.g. after a command returning a
+    /// status
+    ///
+    /// The bytes sent while reading are unspecified. This needs the data line to be readable,
+    /// e.g. MISO connected to DIN for displays using a 3-wire interface.
+    #[cfg(feature = "epd2in13b_v4")]
+    pub(crate) fn read_data(
+        &mut self,
+        spi: &mut SPI,
+        data: &mut [u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        // high for data
+        self.dc.set_high().map_err(Error::pin)?;
+        spi.transfer(data, &[])?;
+        Ok(())
+    }
+
+    /// Same as [`read_data`](Self::read_data), but first discards the dummy byte some
+    /// controllers send before the data, e.g. the SSD1680 when reading its RAM
+    ///
+    /// Both are read within the same SPI transaction, as releasing CS ends the read.
+    #[cfg(feature = "epd2in13b_v4")]
+    pub(crate) fn read_data_after_dummy(
+        &mut self,
+        spi: &mut SPI,
+        data: &mut [u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        // high for data
+        self.dc.set_high().map_err(Error::pin)?;
+        let mut dummy = [0];
+        spi.transaction(&mut [
+            Operation::Transfer(&mut dummy, &[]),
+            Operation::Transfer(data, &[]),
+        ])?;
+        Ok(())
+    }
+
+    /// Basic function for sending a [Command](Command) and reading the data it returns
+    #[cfg(feature = "epd2in13b_v4")]
+    pub(crate) fn cmd_with_read<T: Command>(
+        &mut self,
+        spi: &mut SPI,
+        command: T,
+        data: &mut [u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd(spi, command)?;
+        self.read_data(spi, data)
+    }
+
     /// Basic function for sending the same byte of data (one u8) multiple times over spi
     ///
+    /// Without `SINGLE_BYTE_WRITE`, the bytes are written in blocks of 64.
+    ///
     /// Enables direct interaction with the device with the help of [command()](ConnectionInterface::command())
+    // not used by the 2.66" (B), which fills its RAM with the pattern commands of its controller
+    #[cfg_attr(feature = "epd2in66b", allow(dead_code))]
     pub(crate) fn data_x_times(
         &mut self,
         spi: &mut SPI,
         val: u8,
         repetitions: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         // high for data
-        let _ = self.dc.set_high();
-        // Transfer data (u8) over spi
-        for _ in 0..repetitions {
-            self.write(spi, &[val])?;
+        self.dc.set_high().map_err(Error::pin)?;
+
+        let block = [val; 64];
+        let block_len = if SINGLE_BYTE_WRITE { 1 } else { block.len() };
+        let mut remaining = repetitions as usize;
+        while remaining > 0 {
+            let len = remaining.min(block_len);
+            self.tap(TrafficKind::Data, &block[..len]);
+            self.write(spi, &block[..len])?;
+            remaining -= len;
         }
         Ok(())
     }
 
     // spi write helper/abstraction function
-    fn write(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
+    fn write(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), Error<SPI::Error>> {
         // transfer spi data
-        // Be careful!! Linux has a default limit of 4096 bytes per spi transfer
-        // see https://raspberrypi.stackexchange.com/questions/65595/spi-transfer-fails-with-buffer-size-greater-than-4096
-        if cfg!(target_os = "linux") {
-            for data_chunk in data.chunks(4096) {
-                spi.write(data_chunk)?;
+        match self.max_transfer_size {
+            Some(size) => {
+                for data_chunk in data.chunks(size) {
+                    spi.write(data_chunk)?;
+                }
             }
-            Ok(())
-        } else {
-            spi.write(data)
+            None => spi.write(data)?,
         }
+        Ok(())
     }
 
     /// Waits until device isn't busy anymore (busy == HIGH)
@@ -158,38 +505,63 @@ where
     ///  - FALSE for epd2in9, epd1in54 (for all Display Type A ones?)
     ///
     /// Most likely there was a mistake with the 2in9 busy connection
-    pub(crate) fn wait_until_idle(&mut self, delay: &mut DELAY, is_busy_low: bool) {
-        while self.is_busy(is_busy_low) {
+    ///
+    /// Fails with [`Error::BusyTimeout`] if a timeout is set with
+    /// [`set_busy_timeout`](Self::set_busy_timeout) and the display is still busy after it.
+    // the only one not used by the 7.5" V2 and (B) V2, which poll with a status command
+    #[cfg_attr(any(feature = "epd7in5_v2", feature = "epd7in5b_v2"), allow(dead_code))]
+    pub(crate) fn wait_until_idle(
+        &mut self,
+        delay: &mut DELAY,
+        is_busy_low: bool,
+    ) -> Result<(), Error<SPI::Error>> {
+        let mut waited_us = 0;
+        let mut delay_us = self.delay_us;
+        while self.is_busy(is_busy_low)? {
+            self.busy_yield(waited_us);
+            self.check_busy_timeout(&mut waited_us, delay_us)?;
             // This has been removed and added many time :
             // - it is faster to not have it
             // - it is complicated to pass the delay everywhere all the time
             // - busy waiting can consume more power that delaying
             // - delay waiting enables task switching on realtime OS
             // -> keep it and leave the decision to the user
-            if self.delay_us > 0 {
-                delay.delay_us(self.delay_us);
+            if delay_us > 0 {
+                delay.delay_us(delay_us);
             }
+            delay_us = self.next_delay_us(delay_us);
         }
+        self.last_wait_us = waited_us;
+        trace!("busy for {}us", waited_us);
+        Ok(())
     }
 
     /// Same as `wait_until_idle` for device needing a command to probe Busy pin
+    #[cfg(any(feature = "epd7in5_v2", feature = "epd7in5b_v2"))]
     pub(crate) fn wait_until_idle_with_cmd<T: Command>(
         &mut self,
         spi: &mut SPI,
         delay: &mut DELAY,
         is_busy_low: bool,
         status_command: T,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.cmd(spi, status_command)?;
         if self.delay_us > 0 {
             delay.delay_us(self.delay_us);
         }
-        while self.is_busy(is_busy_low) {
+        let mut waited_us = 0;
+        let mut delay_us = self.next_delay_us(self.delay_us);
+        while self.is_busy(is_busy_low)? {
+            self.busy_yield(waited_us);
+            self.check_busy_timeout(&mut waited_us, delay_us)?;
             self.cmd(spi, status_command)?;
-            if self.delay_us > 0 {
-                delay.delay_us(self.delay_us);
+            if delay_us > 0 {
+                delay.delay_us(delay_us);
             }
+            delay_us = self.next_delay_us(delay_us);
         }
+        self.last_wait_us = waited_us;
+        trace!("busy for {}us", waited_us);
         Ok(())
     }
 
@@ -206,9 +578,13 @@ where
     ///
     /// Most likely there was a mistake with the 2in9 busy connection
     /// //TODO: use the #cfg feature to make this compile the right way for the certain types
-    pub(crate) fn is_busy(&mut self, is_busy_low: bool) -> bool {
-        (is_busy_low && self.busy.is_low().unwrap_or(false))
-            || (!is_busy_low && self.busy.is_high().unwrap_or(false))
+    pub(crate) fn is_busy(&mut self, is_busy_low: bool) -> Result<bool, Error<SPI::Error>> {
+        if is_busy_low {
+            self.busy.is_low()
+        } else {
+            self.busy.is_high()
+        }
+        .map_err(Error::pin)
     }
 
     /// Resets the device.
@@ -218,15 +594,145 @@ where
     /// The timing of keeping the reset pin low seems to be important and different per device.
     /// Most displays seem to require keeping it low for 10ms, but the 7in5_v2 only seems to reset
     /// properly with 2ms
-    pub(crate) fn reset(&mut self, delay: &mut DELAY, initial_delay: u32, duration: u32) {
-        let _ = self.rst.set_high();
+    pub(crate) fn reset(
+        &mut self,
+        delay: &mut DELAY,
+        initial_delay: u32,
+        duration: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.rst.set_high().map_err(Error::pin)?;
         delay.delay_us(initial_delay);
 
-        let _ = self.rst.set_low();
+        self.rst.set_low().map_err(Error::pin)?;
         delay.delay_us(duration);
-        let _ = self.rst.set_high();
+        self.rst.set_high().map_err(Error::pin)?;
         //TODO: the upstream libraries always sleep for 200ms here
         // 10ms works fine with just for the 7in5_v2 but this needs to be validated for other devices
         delay.delay_us(200_000);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use embedded_hal_mock::eh1::{delay::NoopDelay, digital, spi};
+
+    #[test]
+    fn data_x_times_blocks() {
+        let mut spi_expectations = std::vec![];
+        for len in [64, 64, 2] {
+            spi_expectations.extend([
+                spi::Transaction::transaction_start(),
+                spi::Transaction::write_vec(std::vec![0xFF; len]),
+                spi::Transaction::transaction_end(),
+            ]);
+        }
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&[digital::Transaction::set(digital::State::High)]);
+        let mut pin = digital::Mock::new(&[]);
+        let mut interface: DisplayInterface<_, _, _, _, NoopDelay, false> =
+            DisplayInterface::new(pin.clone(), dc.clone(), pin.clone(), None);
+
+        interface.data_x_times(&mut spi, 0xFF, 130).unwrap();
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[test]
+    fn max_transfer_size() {
+        let mut spi_expectations = std::vec![];
+        for data in [&[1, 2, 3][..], &[4, 5, 6], &[7]] {
+            spi_expectations.extend([
+                spi::Transaction::transaction_start(),
+                spi::Transaction::write_vec(data.to_vec()),
+                spi::Transaction::transaction_end(),
+            ]);
+        }
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut dc = digital::Mock::new(&[digital::Transaction::set(digital::State::High)]);
+        let mut pin = digital::Mock::new(&[]);
+        let mut interface: DisplayInterface<_, _, _, _, NoopDelay, false> =
+            DisplayInterface::new(pin.clone(), dc.clone(), pin.clone(), None);
+
+        interface.set_max_transfer_size(Some(3));
+        interface.data(&mut spi, &[1, 2, 3, 4, 5, 6, 7]).unwrap();
+
+        spi.done();
+        dc.done();
+        pin.done();
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn log_traffic() {
+        use std::{string::String, sync::Mutex, vec::Vec};
+
+        static LOGGED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        struct Logger;
+        impl log::Log for Logger {
+            fn enabled(&self, _: &log::Metadata<'_>) -> bool {
+                true
+            }
+            fn log(&self, record: &log::Record<'_>) {
+                if record.target().starts_with("epd_waveshare") {
+                    LOGGED
+                        .lock()
+                        .unwrap()
+                        .push(std::format!("{}", record.args()));
+                }
+            }
+            fn flush(&self) {}
+        }
+        #[derive(Clone, Copy)]
+        struct SwReset;
+        impl Command for SwReset {
+            fn address(self) -> u8 {
+                0x12
+            }
+        }
+
+        log::set_logger(&Logger).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let mut spi = spi::Mock::new(&[
+            spi::Transaction::transaction_start(),
+            spi::Transaction::write_vec(std::vec![0x12]),
+            spi::Transaction::transaction_end(),
+            spi::Transaction::transaction_start(),
+            spi::Transaction::write_vec(std::vec![1, 2, 3]),
+            spi::Transaction::transaction_end(),
+        ]);
+        let mut dc = digital::Mock::new(&[
+            digital::Transaction::set(digital::State::Low),
+            digital::Transaction::set(digital::State::High),
+        ]);
+        let mut busy = digital::Mock::new(&[
+            digital::Transaction::get(digital::State::High),
+            digital::Transaction::get(digital::State::High),
+            digital::Transaction::get(digital::State::Low),
+        ]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut interface: DisplayInterface<_, _, _, _, NoopDelay, false> =
+            DisplayInterface::new(busy.clone(), dc.clone(), rst.clone(), None);
+
+        interface
+            .cmd_with_data(&mut spi, SwReset, &[1, 2, 3])
+            .unwrap();
+        interface.wait_until_idle(&mut NoopDelay, false).unwrap();
+        // two polls of the default 10ms delay
+        assert_eq!(
+            *LOGGED.lock().unwrap(),
+            ["command 0x12", "3 data bytes", "busy for 20000us"]
+        );
+
+        spi.done();
+        dc.done();
+        busy.done();
+        rst.done();
     }
 }
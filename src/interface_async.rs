@@ -0,0 +1,172 @@
+use crate::error::Error;
+use crate::interface::trace;
+use crate::traits::Command;
+use core::marker::PhantomData;
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::{delay::DelayNs, digital::Wait, spi::SpiDevice};
+
+/// Same as [`DisplayInterface`](crate::interface::DisplayInterface), built on the
+/// `embedded-hal-async` traits
+///
+/// Instead of polling the busy pin, it is awaited with [`Wait`], so the executor can run
+/// other tasks during the refresh.
+pub(crate) struct AsyncDisplayInterface<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool> {
+    /// SPI
+    _spi: PhantomData<SPI>,
+    /// DELAY
+    _delay: PhantomData<DELAY>,
+    /// Wait until display is ready!
+    busy: BUSY,
+    /// Data/Command Control Pin (High for data, Low for command)
+    dc: DC,
+    /// Pin for Resetting
+    rst: RST,
+}
+
+impl<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>
+    AsyncDisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>
+{
+    /// Gives back the busy, data/command and reset pins
+    pub(crate) fn release(self) -> (BUSY, DC, RST) {
+        (self.busy, self.dc, self.rst)
+    }
+}
+
+impl<SPI, BUSY, DC, RST, DELAY, const SINGLE_BYTE_WRITE: bool>
+    AsyncDisplayInterface<SPI, BUSY, DC, RST, DELAY, SINGLE_BYTE_WRITE>
+where
+    SPI: SpiDevice,
+    BUSY: Wait,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Creates a new `AsyncDisplayInterface` struct
+    pub fn new(busy: BUSY, dc: DC, rst: RST) -> Self {
+        AsyncDisplayInterface {
+            _spi: PhantomData,
+            _delay: PhantomData,
+            busy,
+            dc,
+            rst,
+        }
+    }
+
+    /// Basic function for sending [Commands](Command).
+    pub(crate) async fn cmd<T: Command>(
+        &mut self,
+        spi: &mut SPI,
+        command: T,
+    ) -> Result<(), Error<SPI::Error>> {
+        // low for commands
+        self.dc.set_low().map_err(Error::pin)?;
+
+        // Transfer the command over spi
+        trace!("command {:#x}", command.address());
+        self.write(spi, &[command.address()]).await
+    }
+
+    /// Basic function for sending an array of u8-values of data over spi
+    pub(crate) async fn data(
+        &mut self,
+        spi: &mut SPI,
+        data: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        // high for data
+        self.dc.set_high().map_err(Error::pin)?;
+        trace!("{} data bytes", data.len());
+
+        if SINGLE_BYTE_WRITE {
+            for val in data.iter().copied() {
+                // Transfer data one u8 at a time over spi
+                self.write(spi, &[val]).await?;
+            }
+        } else {
+            self.write(spi, data).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Basic function for sending [Commands](Command) and the data belonging to it.
+    pub(crate) async fn cmd_with_data<T: Command>(
+        &mut self,
+        spi: &mut SPI,
+        command: T,
+        data: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd(spi, command).await?;
+        self.data(spi, data).await
+    }
+
+    /// Basic function for sending the same byte of data (one u8) multiple times over spi
+    pub(crate) async fn data_x_times(
+        &mut self,
+        spi: &mut SPI,
+        val: u8,
+        repetitions: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        // high for data
+        self.dc.set_high().map_err(Error::pin)?;
+        trace!("{} data bytes", repetitions);
+
+        // in blocks of 64 bytes without SINGLE_BYTE_WRITE, like `DisplayInterface`
+        let block = [val; 64];
+        let block_len = if SINGLE_BYTE_WRITE { 1 } else { block.len() };
+        let mut remaining = repetitions as usize;
+        while remaining > 0 {
+            let len = remaining.min(block_len);
+            self.write(spi, &block[..len]).await?;
+            remaining -= len;
+        }
+        Ok(())
+    }
+
+    // spi write helper/abstraction function
+    async fn write(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), Error<SPI::Error>> {
+        // Linux has a default limit of 4096 bytes per spi transfer, see `DisplayInterface`
+        if cfg!(target_os = "linux") {
+            for data_chunk in data.chunks(4096) {
+                spi.write(data_chunk).await?;
+            }
+        } else {
+            spi.write(data).await?;
+        }
+        Ok(())
+    }
+
+    /// Waits until device isn't busy anymore
+    ///
+    /// See [`DisplayInterface::wait_until_idle`](crate::interface::DisplayInterface::wait_until_idle)
+    /// for `is_busy_low`
+    pub(crate) async fn wait_until_idle(
+        &mut self,
+        is_busy_low: bool,
+    ) -> Result<(), Error<SPI::Error>> {
+        if is_busy_low {
+            self.busy.wait_for_high().await
+        } else {
+            self.busy.wait_for_low().await
+        }
+        .map_err(Error::pin)
+    }
+
+    /// Resets the device.
+    ///
+    /// See [`DisplayInterface::reset`](crate::interface::DisplayInterface::reset)
+    pub(crate) async fn reset(
+        &mut self,
+        delay: &mut DELAY,
+        initial_delay: u32,
+        duration: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.rst.set_high().map_err(Error::pin)?;
+        delay.delay_us(initial_delay).await;
+
+        self.rst.set_low().map_err(Error::pin)?;
+        delay.delay_us(duration).await;
+        self.rst.set_high().map_err(Error::pin)?;
+        delay.delay_us(200_000).await;
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+//! SPI Commands and registers of the IT8951 controller
+
+/// First word of every SPI packet, telling what follows
+#[derive(Copy, Clone)]
+pub(crate) enum Preamble {
+    Command = 0x6000,
+    WriteData = 0x0000,
+    ReadData = 0x1000,
+}
+
+/// IT8951 commands, including the user defined ones of the Waveshare firmware
+///
+/// For more infos about the addresses and what they are doing look into the datasheet
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub(crate) enum Command {
+    SysRun = 0x0001,
+    Standby = 0x0002,
+    Sleep = 0x0003,
+    RegisterRead = 0x0010,
+    RegisterWrite = 0x0011,
+    LoadImage = 0x0020,
+    LoadImageArea = 0x0021,
+    LoadImageEnd = 0x0022,
+    DisplayArea = 0x0034,
+    DisplayBufferArea = 0x0037,
+    Vcom = 0x0039,
+    GetDeviceInfo = 0x0302,
+}
+
+/// Registers used by the driver
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub(crate) enum Register {
+    /// Host interface control, 1 enables packed pixel writes
+    I80Cpcr = 0x0004,
+    /// Load image start address, low word (the high word follows it)
+    Lisar = 0x0208,
+    /// LUT engines status, 0 once they are all free
+    Lutafsr = 0x1224,
+}
@@ -0,0 +1,752 @@
+//! A driver for the Waveshare e-Paper HATs based on the IT8951 controller, used by the
+//! 6", 7.8", 9.7" and 10.3" HD displays
+//!
+//! Unlike the other displays, the IT8951 is a controller with its own image buffer and a
+//! 16 bit packet protocol: there is no data/command pin, and the busy pin (HRDY) needs to be
+//! checked before each packet. The size of the panel is read from the controller.
+//!
+//! Images are loaded in an area of the image buffer with [`It8951::load_image_area`], then
+//! shown with [`It8951::display_area`] in one of the [`DisplayMode`]s.
+//!
+//! # References
+//!
+//! - [Wiki](https://www.waveshare.com/wiki/10.3inch_e-Paper_HAT)
+//! - [IT8951 datasheet](https://www.waveshare.com/w/upload/1/18/IT8951_D_V0.2.4.3_20170728.pdf)
+//! - [Waveshare C drivers](https://github.com/waveshareteam/IT8951-ePaper)
+//!
+//! # Example
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::eh1::*;
+//!# fn main() -> Result<(), epd_waveshare::error::Error<embedded_hal::spi::ErrorKind>> {
+//!use epd_waveshare::{it8951::*, rect::Rect};
+//!#
+//!# let mut spi = spi::Mock::new(&[]);
+//!# let busy_in = digital::Mock::new(&[]);
+//!# let rst = digital::Mock::new(&[]);
+//!# let mut delay = delay::NoopDelay::new();
+//!
+//!let mut epd = It8951::new(&mut spi, busy_in, rst, &mut delay)?;
+//!// the VCOM is printed on the flex cable of the panel, e.g. -1.50V
+//!epd.set_vcom(&mut spi, &mut delay, 1500)?;
+//!
+//!// a white square, 1 byte per pixel
+//!let area = Rect::new(0, 0, 64, 64);
+//!epd.load_image_area(&mut spi, &mut delay, PixelFormat::Bpp8, &area, &[0xF0; 64 * 64])?;
+//!epd.display_area(&mut spi, &mut delay, &area, DisplayMode::Gc16)?;
+//!
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use core::marker::PhantomData;
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{InputPin, OutputPin},
+    spi::{Operation, SpiDevice},
+};
+
+use crate::error::Error;
+use crate::rect::Rect;
+
+pub(crate) mod command;
+use self::command::{Command, Preamble, Register};
+
+/// Maximum number of data bytes sent in one packet
+const MAX_PACKET_LEN: usize = 2048;
+
+/// Interval between two polls of the busy pin (HRDY), which is ready again within microseconds
+const HRDY_POLL_INTERVAL_US: u32 = 10;
+
+/// Interval between two reads of the status of the LUT engines, busy during the refreshes
+const LUT_POLL_INTERVAL_US: u32 = 1_000;
+
+/// Default busy timeout, well above the few seconds of the slowest refresh
+const DEFAULT_BUSY_TIMEOUT_US: u32 = 10_000_000;
+
+/// Panel and firmware information read from the controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceInfo {
+    /// Width of the panel in pixels
+    pub width: u16,
+    /// Height of the panel in pixels
+    pub height: u16,
+    /// Address of the image buffer in the controller memory
+    pub image_buffer_address: u32,
+    /// Firmware version, as a nul padded string
+    pub firmware_version: [u8; 16],
+    /// LUT (waveform) version, as a nul padded string
+    pub lut_version: [u8; 16],
+}
+
+impl DeviceInfo {
+    // parses the 20 words returned by `GetDeviceInfo`
+    fn from_words(words: &[u16; 20]) -> Self {
+        let mut firmware_version = [0; 16];
+        let mut lut_version = [0; 16];
+        for i in 0..8 {
+            // strings are stored low byte first
+            firmware_version[2 * i..2 * i + 2].copy_from_slice(&words[4 + i].to_le_bytes());
+            lut_version[2 * i..2 * i + 2].copy_from_slice(&words[12 + i].to_le_bytes());
+        }
+        DeviceInfo {
+            width: words[0],
+            height: words[1],
+            image_buffer_address: words[2] as u32 | (words[3] as u32) << 16,
+            firmware_version,
+            lut_version,
+        }
+    }
+}
+
+/// Waveforms used to refresh an area
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayMode {
+    /// Clears the display to white with a long flashing refresh, to remove any ghosting
+    Init,
+    /// Fast refresh to black or white, for any starting gray level
+    Du,
+    /// High quality 16 gray levels refresh, flashing
+    Gc16,
+    /// 16 gray levels refresh with less flashing, leaving some ghosting
+    Gl16,
+    /// Fastest refresh of black and white pixels, e.g. for animations, leaving ghosting
+    A2,
+}
+
+/// Number of bits per pixel of the image data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 4 pixels per byte
+    Bpp2 = 0,
+    /// 2 pixels per byte
+    Bpp4 = 2,
+    /// 1 pixel per byte
+    Bpp8 = 3,
+}
+
+impl PixelFormat {
+    fn bits(self) -> u32 {
+        match self {
+            PixelFormat::Bpp2 => 2,
+            PixelFormat::Bpp4 => 4,
+            PixelFormat::Bpp8 => 8,
+        }
+    }
+
+    /// Number of bytes of an image of `width` x `height` pixels, each line being padded to
+    /// 16 bits
+    pub fn buffer_len(self, width: u32, height: u32) -> usize {
+        ((width * self.bits() + 15) / 16 * 2 * height) as usize
+    }
+}
+
+/// IT8951 driver
+pub struct It8951<SPI, BUSY, RST, DELAY> {
+    /// SPI
+    _spi: PhantomData<SPI>,
+    /// DELAY
+    _delay: PhantomData<DELAY>,
+    /// Host ready (HRDY), low while the controller is busy
+    busy: BUSY,
+    /// Pin for Resetting
+    rst: RST,
+    /// Panel information read during initialisation
+    info: DeviceInfo,
+    /// Time after which waiting for the controller fails, if any
+    busy_timeout_us: Option<u32>,
+}
+
+impl<SPI, BUSY, RST, DELAY> It8951<SPI, BUSY, RST, DELAY> {
+    /// Gives back the busy (HRDY) and reset pins, e.g. to reuse them once the controller sleeps
+    pub fn release(self) -> (BUSY, RST) {
+        (self.busy, self.rst)
+    }
+}
+
+impl<SPI, BUSY, RST, DELAY> It8951<SPI, BUSY, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Resets the controller and reads the panel information from it
+    pub fn new(
+        spi: &mut SPI,
+        busy: BUSY,
+        rst: RST,
+        delay: &mut DELAY,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let mut epd = It8951 {
+            _spi: PhantomData,
+            _delay: PhantomData,
+            busy,
+            rst,
+            info: DeviceInfo::from_words(&[0; 20]),
+            busy_timeout_us: Some(DEFAULT_BUSY_TIMEOUT_US),
+        };
+        epd.reset(delay)?;
+        epd.init(spi, delay)?;
+        Ok(epd)
+    }
+
+    fn reset(&mut self, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.rst.set_high().map_err(Error::pin)?;
+        delay.delay_ms(200);
+        self.rst.set_low().map_err(Error::pin)?;
+        delay.delay_ms(10);
+        self.rst.set_high().map_err(Error::pin)?;
+        delay.delay_ms(200);
+        Ok(())
+    }
+
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.command(spi, delay, Command::SysRun)?;
+
+        self.command(spi, delay, Command::GetDeviceInfo)?;
+        let mut words = [0; 20];
+        self.read_words(spi, delay, &mut words)?;
+        self.info = DeviceInfo::from_words(&words);
+
+        // pixels are sent packed, several of them in each word
+        self.write_register(spi, delay, Register::I80Cpcr as u16, 0x0001)
+    }
+
+    /// Gives up waiting for the controller with [`Error::BusyTimeout`] once it has been busy
+    /// for `timeout_us`, 10s by default. `None` waits forever.
+    ///
+    /// The time is counted by adding up the delays between two polls, of the busy pin or of
+    /// the LUT engines.
+    pub fn set_busy_timeout(&mut self, timeout_us: Option<u32>) {
+        self.busy_timeout_us = timeout_us;
+    }
+
+    /// Panel and firmware information read during initialisation
+    pub fn device_info(&self) -> &DeviceInfo {
+        &self.info
+    }
+
+    /// Width of the panel
+    pub fn width(&self) -> u32 {
+        self.info.width as u32
+    }
+
+    /// Height of the panel
+    pub fn height(&self) -> u32 {
+        self.info.height as u32
+    }
+
+    /// Wakes the controller up from standby or sleep
+    pub fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.command(spi, delay, Command::SysRun)
+    }
+
+    /// Stops the clocks of the controller, it is woken up by [`wake_up`](Self::wake_up)
+    pub fn standby(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.command(spi, delay, Command::Standby)
+    }
+
+    /// Lets the controller and the panel enter sleep mode to save power, it is woken up by
+    /// [`wake_up`](Self::wake_up)
+    pub fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.command(spi, delay, Command::Sleep)
+    }
+
+    /// Reads the VCOM voltage, in mV (e.g. 1500 for -1.50V)
+    pub fn vcom(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<u16, Error<SPI::Error>> {
+        self.command(spi, delay, Command::Vcom)?;
+        self.write_words(spi, delay, &[0x0000])?;
+        let mut vcom = [0];
+        self.read_words(spi, delay, &mut vcom)?;
+        Ok(vcom[0])
+    }
+
+    /// Sets the VCOM voltage, in mV (e.g. 1500 for -1.50V)
+    ///
+    /// It should match the value printed on the flex cable of the panel.
+    pub fn set_vcom(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        vcom: u16,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.command(spi, delay, Command::Vcom)?;
+        self.write_words(spi, delay, &[0x0001, vcom])
+    }
+
+    /// Reads a register of the controller
+    pub fn read_register(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        register: u16,
+    ) -> Result<u16, Error<SPI::Error>> {
+        self.command(spi, delay, Command::RegisterRead)?;
+        self.write_words(spi, delay, &[register])?;
+        let mut value = [0];
+        self.read_words(spi, delay, &mut value)?;
+        Ok(value[0])
+    }
+
+    /// Writes a register of the controller
+    pub fn write_register(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        register: u16,
+        value: u16,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.command(spi, delay, Command::RegisterWrite)?;
+        self.write_words(spi, delay, &[register, value])
+    }
+
+    /// Loads an image to an area of the image buffer, without showing it
+    ///
+    /// `data` holds the pixels line by line, packed as described by `format` (the first pixel
+    /// in the lowest bits), each line being padded to 16 bits. See
+    /// [`PixelFormat::buffer_len`].
+    pub fn load_image_area(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        format: PixelFormat,
+        area: &Rect,
+        data: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(data, format.buffer_len(area.w, area.h))?;
+        self.check_area(area)?;
+
+        let address = self.info.image_buffer_address;
+        self.write_register(
+            spi,
+            delay,
+            Register::Lisar as u16 + 2,
+            (address >> 16) as u16,
+        )?;
+        self.write_register(spi, delay, Register::Lisar as u16, address as u16)?;
+
+        self.command(spi, delay, Command::LoadImageArea)?;
+        // little endian, no rotation
+        let arg = (format as u16) << 4;
+        self.write_words(
+            spi,
+            delay,
+            &[
+                arg,
+                area.x as u16,
+                area.y as u16,
+                area.w as u16,
+                area.h as u16,
+            ],
+        )?;
+        for packet in data.chunks(MAX_PACKET_LEN) {
+            self.write_packet(spi, delay, Preamble::WriteData, packet)?;
+        }
+        self.command(spi, delay, Command::LoadImageEnd)
+    }
+
+    /// Refreshes an area of the display with the content of the image buffer
+    ///
+    /// This waits for the previous refreshes to be done, but not for this one.
+    pub fn display_area(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        area: &Rect,
+        mode: DisplayMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.check_area(area)?;
+        self.wait_for_lut(spi, delay)?;
+
+        let mode = self.mode_value(mode);
+        self.command(spi, delay, Command::DisplayArea)?;
+        self.write_words(
+            spi,
+            delay,
+            &[
+                area.x as u16,
+                area.y as u16,
+                area.w as u16,
+                area.h as u16,
+                mode,
+            ],
+        )
+    }
+
+    /// Clears the whole display to white with the [`DisplayMode::Init`] waveform
+    pub fn clear_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        let area = Rect::new(0, 0, self.width(), self.height());
+        let address = self.info.image_buffer_address;
+        self.write_register(
+            spi,
+            delay,
+            Register::Lisar as u16 + 2,
+            (address >> 16) as u16,
+        )?;
+        self.write_register(spi, delay, Register::Lisar as u16, address as u16)?;
+
+        self.command(spi, delay, Command::LoadImageArea)?;
+        let arg = (PixelFormat::Bpp8 as u16) << 4;
+        self.write_words(spi, delay, &[arg, 0, 0, area.w as u16, area.h as u16])?;
+        let white = [0xFF; MAX_PACKET_LEN];
+        let mut remaining = PixelFormat::Bpp8.buffer_len(area.w, area.h);
+        while remaining > 0 {
+            let len = remaining.min(MAX_PACKET_LEN);
+            self.write_packet(spi, delay, Preamble::WriteData, &white[..len])?;
+            remaining -= len;
+        }
+        self.command(spi, delay, Command::LoadImageEnd)?;
+
+        self.display_area(spi, delay, &area, DisplayMode::Init)
+    }
+
+    /// Waits until the LUT engines are done with the previous refreshes
+    ///
+    /// Fails with [`Error::BusyTimeout`] if they are still busy after the busy timeout.
+    pub fn wait_for_lut(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        let mut waited_us = 0;
+        while self.read_register(spi, delay, Register::Lutafsr as u16)? != 0 {
+            self.check_busy_timeout(&mut waited_us, LUT_POLL_INTERVAL_US)?;
+            delay.delay_us(LUT_POLL_INTERVAL_US);
+        }
+        Ok(())
+    }
+
+    // value of a mode for the waveforms of the panel
+    fn mode_value(&self, mode: DisplayMode) -> u16 {
+        match mode {
+            DisplayMode::Init => 0,
+            DisplayMode::Du => 1,
+            DisplayMode::Gc16 => 2,
+            DisplayMode::Gl16 => 3,
+            // the waveforms of the 6" (800x600) panel have A2 at another position
+            DisplayMode::A2 if self.info.lut_version.starts_with(b"M641") => 4,
+            DisplayMode::A2 => 6,
+        }
+    }
+
+    // rejects areas outside of the panel
+    fn check_area(&self, area: &Rect) -> Result<(), Error<SPI::Error>> {
+        let fits =
+            |start: u32, len: u32, max: u32| start.checked_add(len).map_or(false, |end| end <= max);
+        if !fits(area.x, area.w, self.width()) || !fits(area.y, area.h, self.height()) {
+            return Err(Error::InvalidBuffer);
+        }
+        Ok(())
+    }
+
+    fn command(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        command: Command,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.write_packet(
+            spi,
+            delay,
+            Preamble::Command,
+            &(command as u16).to_be_bytes(),
+        )
+    }
+
+    // sends each word as a data packet, like the arguments of the commands
+    fn write_words(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        words: &[u16],
+    ) -> Result<(), Error<SPI::Error>> {
+        for word in words {
+            self.write_packet(spi, delay, Preamble::WriteData, &word.to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn write_packet(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        preamble: Preamble,
+        data: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(delay)?;
+        spi.transaction(&mut [
+            Operation::Write(&(preamble as u16).to_be_bytes()),
+            Operation::Write(data),
+        ])?;
+        Ok(())
+    }
+
+    fn read_words(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        words: &mut [u16],
+    ) -> Result<(), Error<SPI::Error>> {
+        let mut bytes = [0; 40];
+        let bytes = &mut bytes[..words.len() * 2];
+        self.wait_until_idle(delay)?;
+        spi.transaction(&mut [
+            Operation::Write(&(Preamble::ReadData as u16).to_be_bytes()),
+            // the first word read is a dummy
+            Operation::Read(&mut [0; 2]),
+            Operation::Read(bytes),
+        ])?;
+        for (word, bytes) in words.iter_mut().zip(bytes.chunks(2)) {
+            *word = u16::from_be_bytes([bytes[0], bytes[1]]);
+        }
+        Ok(())
+    }
+
+    /// Waits until the controller is ready for the next packet
+    ///
+    /// Fails with [`Error::BusyTimeout`] if it is still busy after the busy timeout.
+    pub fn wait_until_idle(&mut self, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        let mut waited_us = 0;
+        while self.busy.is_low().map_err(Error::pin)? {
+            self.check_busy_timeout(&mut waited_us, HRDY_POLL_INTERVAL_US)?;
+            delay.delay_us(HRDY_POLL_INTERVAL_US);
+        }
+        Ok(())
+    }
+
+    // counts a poll followed by a sleep of `delay_us`, failing once the timeout is reached
+    fn check_busy_timeout(
+        &self,
+        waited_us: &mut u32,
+        delay_us: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        match self.busy_timeout_us {
+            Some(timeout_us) if *waited_us >= timeout_us => Err(Error::BusyTimeout),
+            _ => {
+                *waited_us = waited_us.saturating_add(delay_us);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::eh1::{delay::NoopDelay, digital, spi};
+
+    extern crate std;
+    use std::vec::Vec;
+
+    // SPI transactions of a packet
+    fn packet(preamble: u16, data: &[u8]) -> Vec<spi::Transaction<u8>> {
+        std::vec![
+            spi::Transaction::transaction_start(),
+            spi::Transaction::write_vec(preamble.to_be_bytes().to_vec()),
+            spi::Transaction::write_vec(data.to_vec()),
+            spi::Transaction::transaction_end(),
+        ]
+    }
+
+    fn read(words: &[u8]) -> Vec<spi::Transaction<u8>> {
+        std::vec![
+            spi::Transaction::transaction_start(),
+            spi::Transaction::write_vec(std::vec![0x10, 0x00]),
+            spi::Transaction::read_vec(std::vec![0, 0]),
+            spi::Transaction::read_vec(words.to_vec()),
+            spi::Transaction::transaction_end(),
+        ]
+    }
+
+    fn ready(count: usize) -> Vec<digital::Transaction> {
+        std::vec![digital::Transaction::get(digital::State::High); count]
+    }
+
+    #[test]
+    fn init_reads_device_info() {
+        let mut info = [0u8; 40];
+        // 1872x1404, buffer at 0x00123456
+        info[..8].copy_from_slice(&[0x07, 0x50, 0x05, 0x7C, 0x34, 0x56, 0x00, 0x12]);
+        // "M841" as words, low byte first
+        info[24..28].copy_from_slice(b"8M14");
+        let spi_expectations = [
+            packet(0x6000, &[0x00, 0x01]),
+            packet(0x6000, &[0x03, 0x02]),
+            read(&info),
+            packet(0x6000, &[0x00, 0x11]),
+            packet(0x0000, &[0x00, 0x04]),
+            packet(0x0000, &[0x00, 0x01]),
+        ]
+        .concat();
+
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut busy = digital::Mock::new(&ready(6));
+        let mut rst = digital::Mock::new(&[
+            digital::Transaction::set(digital::State::High),
+            digital::Transaction::set(digital::State::Low),
+            digital::Transaction::set(digital::State::High),
+        ]);
+
+        let epd = It8951::new(&mut spi, busy.clone(), rst.clone(), &mut NoopDelay).unwrap();
+        assert_eq!(epd.width(), 1872);
+        assert_eq!(epd.height(), 1404);
+        assert_eq!(epd.device_info().image_buffer_address, 0x0012_3456);
+        assert_eq!(&epd.device_info().lut_version[..5], b"M841\0");
+        assert_eq!(epd.mode_value(DisplayMode::A2), 6);
+
+        spi.done();
+        busy.done();
+        rst.done();
+    }
+
+    #[test]
+    fn vcom() {
+        let spi_expectations = [
+            packet(0x6000, &[0x00, 0x39]),
+            packet(0x0000, &[0x00, 0x01]),
+            packet(0x0000, &[0x05, 0xDC]),
+            packet(0x6000, &[0x00, 0x39]),
+            packet(0x0000, &[0x00, 0x00]),
+            read(&[0x07, 0xD0]),
+        ]
+        .concat();
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut busy = digital::Mock::new(&ready(6));
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd: It8951<_, _, _, NoopDelay> = It8951 {
+            _spi: PhantomData,
+            _delay: PhantomData,
+            busy: busy.clone(),
+            rst: rst.clone(),
+            info: DeviceInfo::from_words(&[0; 20]),
+            busy_timeout_us: None,
+        };
+
+        epd.set_vcom(&mut spi, &mut NoopDelay, 1500).unwrap();
+        assert_eq!(epd.vcom(&mut spi, &mut NoopDelay).unwrap(), 2000);
+
+        spi.done();
+        busy.done();
+        rst.done();
+    }
+
+    #[test]
+    fn load_and_display_area() {
+        let mut words = [0; 20];
+        words[0] = 800;
+        words[1] = 600;
+        words[3] = 0x0012;
+        let area = Rect::new(8, 4, 4, 2);
+        let spi_expectations = [
+            // image buffer address
+            packet(0x6000, &[0x00, 0x11]),
+            packet(0x0000, &[0x02, 0x0A]),
+            packet(0x0000, &[0x00, 0x12]),
+            packet(0x6000, &[0x00, 0x11]),
+            packet(0x0000, &[0x02, 0x08]),
+            packet(0x0000, &[0x00, 0x00]),
+            // area of 4bpp pixels
+            packet(0x6000, &[0x00, 0x21]),
+            packet(0x0000, &[0x00, 0x20]),
+            packet(0x0000, &[0x00, 0x08]),
+            packet(0x0000, &[0x00, 0x04]),
+            packet(0x0000, &[0x00, 0x04]),
+            packet(0x0000, &[0x00, 0x02]),
+            packet(0x0000, &[0x12, 0x34, 0x56, 0x78]),
+            packet(0x6000, &[0x00, 0x22]),
+            // LUT engines busy, then free
+            packet(0x6000, &[0x00, 0x10]),
+            packet(0x0000, &[0x12, 0x24]),
+            read(&[0x00, 0x01]),
+            packet(0x6000, &[0x00, 0x10]),
+            packet(0x0000, &[0x12, 0x24]),
+            read(&[0x00, 0x00]),
+            packet(0x6000, &[0x00, 0x34]),
+            packet(0x0000, &[0x00, 0x08]),
+            packet(0x0000, &[0x00, 0x04]),
+            packet(0x0000, &[0x00, 0x04]),
+            packet(0x0000, &[0x00, 0x02]),
+            packet(0x0000, &[0x00, 0x02]),
+        ]
+        .concat();
+        let mut spi = spi::Mock::new(&spi_expectations);
+        let mut busy = digital::Mock::new(&ready(26));
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd: It8951<_, _, _, NoopDelay> = It8951 {
+            _spi: PhantomData,
+            _delay: PhantomData,
+            busy: busy.clone(),
+            rst: rst.clone(),
+            info: DeviceInfo::from_words(&words),
+            busy_timeout_us: None,
+        };
+
+        assert_eq!(
+            epd.load_image_area(&mut spi, &mut NoopDelay, PixelFormat::Bpp4, &area, &[0; 3]),
+            Err(Error::WrongBufferSize {
+                expected: 4,
+                got: 3
+            })
+        );
+        assert_eq!(
+            epd.display_area(
+                &mut spi,
+                &mut NoopDelay,
+                &Rect::new(799, 0, 2, 2),
+                DisplayMode::Du
+            ),
+            Err(Error::InvalidBuffer)
+        );
+        assert_eq!(
+            epd.display_area(
+                &mut spi,
+                &mut NoopDelay,
+                &Rect::new(u32::MAX, 0, 2, 2),
+                DisplayMode::Du
+            ),
+            Err(Error::InvalidBuffer)
+        );
+        epd.load_image_area(
+            &mut spi,
+            &mut NoopDelay,
+            PixelFormat::Bpp4,
+            &area,
+            &[0x12, 0x34, 0x56, 0x78],
+        )
+        .unwrap();
+        epd.display_area(&mut spi, &mut NoopDelay, &area, DisplayMode::Gc16)
+            .unwrap();
+
+        spi.done();
+        busy.done();
+        rst.done();
+    }
+
+    #[test]
+    fn busy_timeout() {
+        let mut busy =
+            digital::Mock::new(&std::vec![digital::Transaction::get(digital::State::Low); 4]);
+        let mut rst = digital::Mock::new(&[]);
+        let mut epd: It8951<spi::Mock<u8>, _, _, NoopDelay> = It8951 {
+            _spi: PhantomData,
+            _delay: PhantomData,
+            busy: busy.clone(),
+            rst: rst.clone(),
+            info: DeviceInfo::from_words(&[0; 20]),
+            busy_timeout_us: None,
+        };
+
+        // the 4th poll is done after 30us
+        epd.set_busy_timeout(Some(3 * HRDY_POLL_INTERVAL_US));
+        assert_eq!(epd.wait_until_idle(&mut NoopDelay), Err(Error::BusyTimeout));
+
+        busy.done();
+        rst.done();
+    }
+}
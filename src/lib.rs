@@ -12,7 +12,7 @@
 //!
 //!```rust, no_run
 //!# use embedded_hal_mock::eh1::*;
-//!# fn main() -> Result<(), embedded_hal::spi::ErrorKind> {
+//!# fn main() -> Result<(), epd_waveshare::error::Error<embedded_hal::spi::ErrorKind>> {
 //!use embedded_graphics::{
 //!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
 //!};
@@ -56,62 +56,269 @@
 //!
 //! ### SPI
 //!
-//! MISO is not connected/available. SPI_MODE_0 is used (CPHL = 0, CPOL = 0) with 8 bits per word, MSB first.
+//! SPI_MODE_0 is used (CPHL = 0, CPOL = 0) with 8 bits per word, MSB first.
+//!
+//! MISO is only needed to read from the display, e.g. for the status, temperature and RAM reads
+//! of the Epd2in13b (V4) and for its `set_verify_writes`.
+//! The displays have a single data line, so MISO is then connected to DIN too. Otherwise it can
+//! be left unconnected.
 //!
 //! Maximum speed tested by myself was 8Mhz but more should be possible (Ben Krasnow used 18Mhz with his implemenation)
 //!
 #![no_std]
 #![deny(missing_docs)]
 
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
+/// Keeps the items only when a driver built on the shared `DisplayInterface` is, i.e. any driver
+/// but the IT8951 and the 12.48" (B) V2, or one of the features given with `#[or(...)]`
+macro_rules! cfg_interface {
+    (#[or $extra:tt] $($item:item)*) => {
+        $(cfg_interface!(@item $extra $item);)*
+    };
+    (@item ($($extra:tt)*) $item:item) => {
+        #[cfg(any(
+            feature = "epd1in02",
+            feature = "epd1in54",
+            feature = "epd1in54_v2",
+            feature = "epd1in54b",
+            feature = "epd1in54c",
+            feature = "epd1in64g",
+            feature = "epd2in13_v2",
+            feature = "epd2in13_v3",
+            feature = "epd2in13b_v4",
+            feature = "epd2in13bc",
+            feature = "epd2in13g",
+            feature = "epd2in36g",
+            feature = "epd2in66",
+            feature = "epd2in66b",
+            feature = "epd2in7",
+            feature = "epd2in7_v2",
+            feature = "epd2in7b",
+            feature = "epd2in9",
+            feature = "epd2in9_v2",
+            feature = "epd2in9b_v4",
+            feature = "epd2in9bc",
+            feature = "epd2in9d",
+            feature = "epd3in0g",
+            feature = "epd3in7",
+            feature = "epd4in2",
+            feature = "epd4in26",
+            feature = "epd5in65f",
+            feature = "epd5in79",
+            feature = "epd5in83_v2",
+            feature = "epd5in83b_v2",
+            feature = "epd7in3e",
+            feature = "epd7in3f",
+            feature = "epd7in5",
+            feature = "epd7in5_hd",
+            feature = "epd7in5_v2",
+            feature = "epd7in5b_v2",
+            feature = "epd13in3k",
+            $($extra)*
+        ))]
+        $item
+    };
+    ($($item:item)*) => {
+        $(cfg_interface!(@item () $item);)*
+    };
+}
+
 #[cfg(feature = "graphics")]
 pub mod graphics;
 
+#[cfg(feature = "graphics")]
+pub mod tiling;
+
+#[cfg(any(feature = "console", all(test, feature = "graphics")))]
+pub mod console;
+
+#[cfg(feature = "dither")]
+pub mod dither;
+
+#[cfg(feature = "simulator")]
+pub mod simulator;
+
+#[cfg(any(feature = "testing", test))]
+pub mod testing;
+
+#[cfg(any(feature = "bus", test))]
+pub mod bus;
+
+#[cfg(all(feature = "linux", unix))]
+pub mod linux;
+
+#[cfg(any(feature = "any_epd", all(test, feature = "all-displays")))]
+pub mod any_epd;
+
 mod traits;
 
 pub mod color;
 
 pub mod rect;
 
+pub mod error;
+
+pub mod frame;
+
+pub mod rle;
+
+#[cfg(any(feature = "crc", feature = "simulator", test))]
+pub mod crc;
+
+#[cfg(feature = "epd2in13b_v4")]
+pub mod slideshow;
+
+pub mod lut;
+
+pub mod refresh;
+
+mod controllers;
+
 /// Interface for the physical connection between display and the controlling device
+// same drivers as `cfg_interface!`, which would hide the module from rustfmt
+#[cfg(any(
+    feature = "epd1in02",
+    feature = "epd1in54",
+    feature = "epd1in54_v2",
+    feature = "epd1in54b",
+    feature = "epd1in54c",
+    feature = "epd1in64g",
+    feature = "epd2in13_v2",
+    feature = "epd2in13_v3",
+    feature = "epd2in13b_v4",
+    feature = "epd2in13bc",
+    feature = "epd2in13g",
+    feature = "epd2in36g",
+    feature = "epd2in66",
+    feature = "epd2in66b",
+    feature = "epd2in7",
+    feature = "epd2in7_v2",
+    feature = "epd2in7b",
+    feature = "epd2in9",
+    feature = "epd2in9_v2",
+    feature = "epd2in9b_v4",
+    feature = "epd2in9bc",
+    feature = "epd2in9d",
+    feature = "epd3in0g",
+    feature = "epd3in7",
+    feature = "epd4in2",
+    feature = "epd4in26",
+    feature = "epd5in65f",
+    feature = "epd5in79",
+    feature = "epd5in83_v2",
+    feature = "epd5in83b_v2",
+    feature = "epd7in3e",
+    feature = "epd7in3f",
+    feature = "epd7in5",
+    feature = "epd7in5_hd",
+    feature = "epd7in5_v2",
+    feature = "epd7in5b_v2",
+    feature = "epd13in3k"
+))]
 mod interface;
+cfg_interface! {
+    pub use interface::{BusyYield, Tap, TrafficKind};
+}
+#[cfg(all(feature = "async", feature = "epd2in13b_v4"))]
+mod interface_async;
 
+#[cfg(feature = "epd1in02")]
 pub mod epd1in02;
+#[cfg(feature = "epd1in54")]
 pub mod epd1in54;
+#[cfg(feature = "epd1in54_v2")]
 pub mod epd1in54_v2;
+#[cfg(feature = "epd1in54b")]
 pub mod epd1in54b;
+#[cfg(feature = "epd1in54c")]
 pub mod epd1in54c;
+#[cfg(feature = "epd1in64g")]
+pub mod epd1in64g;
+#[cfg(any(feature = "epd2in13_v2", feature = "epd2in13_v3"))]
 pub mod epd2in13_v2;
+#[cfg(feature = "epd2in13b_v4")]
 pub mod epd2in13b_v4;
+#[cfg(feature = "epd2in13bc")]
 pub mod epd2in13bc;
+#[cfg(feature = "epd2in13g")]
+pub mod epd2in13g;
+#[cfg(feature = "epd2in36g")]
+pub mod epd2in36g;
+#[cfg(feature = "epd2in66")]
+pub mod epd2in66;
+#[cfg(feature = "epd2in66b")]
 pub mod epd2in66b;
+#[cfg(feature = "epd2in7")]
 pub mod epd2in7;
+#[cfg(feature = "epd2in7_v2")]
 pub mod epd2in7_v2;
+#[cfg(feature = "epd2in7b")]
 pub mod epd2in7b;
+#[cfg(feature = "epd2in9")]
 pub mod epd2in9;
+#[cfg(feature = "epd2in9_v2")]
 pub mod epd2in9_v2;
+#[cfg(feature = "epd2in9b_v4")]
 pub mod epd2in9b_v4;
+#[cfg(feature = "epd2in9bc")]
 pub mod epd2in9bc;
+#[cfg(feature = "epd2in9d")]
 pub mod epd2in9d;
+#[cfg(feature = "epd3in0g")]
+pub mod epd3in0g;
+#[cfg(feature = "epd3in7")]
 pub mod epd3in7;
+#[cfg(feature = "epd4in2")]
 pub mod epd4in2;
+#[cfg(feature = "epd4in26")]
+pub mod epd4in26;
+#[cfg(feature = "epd5in65f")]
 pub mod epd5in65f;
+#[cfg(feature = "epd5in79")]
+pub mod epd5in79;
+#[cfg(feature = "epd5in83_v2")]
 pub mod epd5in83_v2;
+#[cfg(feature = "epd5in83b_v2")]
 pub mod epd5in83b_v2;
+#[cfg(feature = "epd7in3e")]
+pub mod epd7in3e;
+#[cfg(feature = "epd7in3f")]
 pub mod epd7in3f;
+#[cfg(feature = "epd7in5")]
 pub mod epd7in5;
+#[cfg(feature = "epd7in5_hd")]
 pub mod epd7in5_hd;
+#[cfg(feature = "epd7in5_v2")]
 pub mod epd7in5_v2;
+#[cfg(feature = "epd7in5b_v2")]
 pub mod epd7in5b_v2;
+#[cfg(feature = "epd7in5b_v2")]
 pub use epd7in5b_v2 as epd7in5b_v3;
+#[cfg(feature = "epd12in48b_v2")]
 pub mod epd12in48b_v2;
+#[cfg(feature = "epd13in3k")]
+pub mod epd13in3k;
+#[cfg(feature = "it8951")]
+pub mod it8951;
 
+#[cfg(any(
+    feature = "epd1in54",
+    feature = "epd1in54_v2",
+    feature = "epd2in7_v2",
+    feature = "epd2in9",
+    feature = "epd2in9_v2"
+))]
 pub(crate) mod type_a;
 
 /// Includes everything important besides the chosen Display
 pub mod prelude {
-    pub use crate::color::{Color, OctColor, TriColor};
+    pub use crate::color::{Color, Gray4Epd, HexColor, OctColor, QuadColor, TriColor};
     pub use crate::traits::{
-        QuickRefresh, RefreshLut, WaveshareDisplay, WaveshareThreeColorDisplay,
+        BorderColor, DeepSleepMode, LutControl, PartialColorUpdate, PartialRefresh, PartialUpdate,
+        PowerControl, QuickRefresh, RefreshLut, RefreshMode, Sleepable, WaveshareDisplay,
+        WaveshareThreeColorDisplay,
     };
 
     pub use crate::SPI_MODE;
@@ -120,15 +327,22 @@ pub mod prelude {
     pub use crate::graphics::{Display, DisplayRotation};
 }
 
-/// Computes the needed buffer length. Takes care of rounding up in case width
-/// is not divisible by 8.
+/// Computes the needed buffer length of a display with 1 bit per pixel.
+///
+/// Each row starts on a new byte: when the width is not divisible by 8, the last byte of each
+/// row is padded with unused bits. A 122 pixels wide display takes 16 bytes (128 bits) per
+/// row, its last 6 bits being unused.
+///
+/// ```text
+///             width             unused
+/// <-------------------------------><--->
+/// [76543210][76543210]...[76543210][76XXXXXX] ^
+/// [76543210][76543210]...[76543210][76XXXXXX] | height
+/// [76543210][76543210]...[76543210][76XXXXXX] v
+/// ```
 ///
-///  unused
-///  bits        width
-/// <----><------------------------>
-/// \[XXXXX210\]\[76543210\]...\[76543210\] ^
-/// \[XXXXX210\]\[76543210\]...\[76543210\] | height
-/// \[XXXXX210\]\[76543210\]...\[76543210\] v
+/// Raw images packed without this padding show up skewed: see `graphics::repack_1bpp` to
+/// convert them, and `graphics::bytes_per_row` for the other bit depths.
 pub const fn buffer_len(width: usize, height: usize) -> usize {
     (width + 7) / 8 * height
 }
@@ -0,0 +1,148 @@
+//! Setup of the displays on a Raspberry Pi, with [`linux-embedded-hal`]
+//!
+//! Opening the spidev device and exporting the pins through sysfs is the same for all the
+//! displays: [`new_rpi`] does it for any driver, with the pins of the Waveshare HATs by
+//! default. Needs the `linux` feature.
+//!
+//! ```rust, no_run
+//! use epd_waveshare::{epd2in13b_v4::*, linux, prelude::*};
+//!
+//! let (mut epd, mut spi, mut delay) = Epd2in13b::new_rpi(
+//!     linux::SPIDEV_PATH,
+//!     linux::BUSY_GPIO,
+//!     linux::DC_GPIO,
+//!     linux::RST_GPIO,
+//! )?;
+//! epd.clear_frame(&mut spi, &mut delay)?;
+//! epd.sleep(&mut spi, &mut delay)?;
+//! # Ok::<(), linux::LinuxError>(())
+//! ```
+//!
+//! The spidev device and the GPIOs must be enabled (e.g. with `raspi-config`), and exporting
+//! the pins may need root.
+//!
+//! [`linux-embedded-hal`]: https://docs.rs/linux-embedded-hal
+
+use std::{fmt, io, path::Path};
+
+use embedded_hal::{delay::DelayNs, digital::PinState};
+use linux_embedded_hal::{
+    spidev::{SpiModeFlags, SpidevOptions},
+    sysfs_gpio, Delay, SPIError, SpidevDevice, SysfsPin,
+};
+
+use crate::error::Error;
+use crate::traits::WaveshareDisplay;
+
+/// SPI device of the HATs, using the CE0 chip select
+pub const SPIDEV_PATH: &str = "/dev/spidev0.0";
+/// GPIO of the busy pin of the HATs (board pin 18)
+pub const BUSY_GPIO: u64 = 24;
+/// GPIO of the data/command pin of the HATs (board pin 22)
+pub const DC_GPIO: u64 = 25;
+/// GPIO of the reset pin of the HATs (board pin 11)
+pub const RST_GPIO: u64 = 17;
+/// Clock of the SPI bus, which all the displays support
+pub const SPI_SPEED_HZ: u32 = 4_000_000;
+
+/// A driver created by [`new_rpi`], with the SPI device and delay to use with it
+pub type Rpi<EPD> = (EPD, SpidevDevice, Delay);
+
+/// Error of [`new_rpi`]
+#[derive(Debug)]
+pub enum LinuxError {
+    /// Opening or configuring the spidev device failed
+    Spidev(SPIError),
+    /// Exporting or configuring a GPIO failed
+    Gpio(sysfs_gpio::Error),
+    /// The driver failed to initialise the display
+    Epd(Error<SPIError>),
+}
+
+impl fmt::Display for LinuxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinuxError::Spidev(e) => write!(f, "can't open the SPI device: {}", e),
+            LinuxError::Gpio(e) => write!(f, "can't set up a GPIO: {}", e),
+            LinuxError::Epd(e) => write!(f, "can't initialise the display: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LinuxError {}
+
+impl From<SPIError> for LinuxError {
+    fn from(e: SPIError) -> Self {
+        LinuxError::Spidev(e)
+    }
+}
+
+impl From<sysfs_gpio::Error> for LinuxError {
+    fn from(e: sysfs_gpio::Error) -> Self {
+        LinuxError::Gpio(e)
+    }
+}
+
+impl From<Error<SPIError>> for LinuxError {
+    fn from(e: Error<SPIError>) -> Self {
+        LinuxError::Epd(e)
+    }
+}
+
+/// Opens the spidev device at `path` in mode 0, at [`SPI_SPEED_HZ`]
+pub fn open_spi(path: impl AsRef<Path>) -> Result<SpidevDevice, LinuxError> {
+    let mut spi = SpidevDevice::open(path)?;
+    let options = SpidevOptions::new()
+        .bits_per_word(8)
+        .max_speed_hz(SPI_SPEED_HZ)
+        .mode(SpiModeFlags::SPI_MODE_0)
+        .build();
+    spi.configure(&options).map_err(SPIError::from)?;
+    Ok(spi)
+}
+
+/// Interval between two checks of an exported GPIO
+const EXPORT_POLL_INTERVAL_MS: u32 = 10;
+/// Number of checks of an exported GPIO before giving up, a second in all
+const EXPORT_RETRIES: u32 = 100;
+
+/// Exports the GPIO `number`
+fn export(number: u64) -> Result<SysfsPin, LinuxError> {
+    let pin = SysfsPin::new(number);
+    pin.export()?;
+    // the sysfs files appear a bit after the export
+    for _ in 0..EXPORT_RETRIES {
+        if pin.is_exported() {
+            return Ok(pin);
+        }
+        Delay.delay_ms(EXPORT_POLL_INTERVAL_MS);
+    }
+    Err(LinuxError::Gpio(sysfs_gpio::Error::Io(io::Error::new(
+        io::ErrorKind::TimedOut,
+        "the exported GPIO didn't show up in sysfs",
+    ))))
+}
+
+/// Opens the spidev device at `path`, exports the pins and initialises the display
+///
+/// The GPIOs are numbered like the Broadcom pins (not the board pins), as the defaults
+/// [`BUSY_GPIO`], [`DC_GPIO`] and [`RST_GPIO`]. The reset pin starts high, so the display
+/// isn't reset until the driver does.
+pub fn new_rpi<EPD>(
+    spidev_path: impl AsRef<Path>,
+    busy_gpio: u64,
+    dc_gpio: u64,
+    rst_gpio: u64,
+) -> Result<Rpi<EPD>, LinuxError>
+where
+    EPD: WaveshareDisplay<SpidevDevice, SysfsPin, SysfsPin, SysfsPin, Delay>,
+{
+    let mut spi = open_spi(spidev_path)?;
+    let busy = export(busy_gpio)?.into_input_pin()?;
+    let dc = export(dc_gpio)?.into_output_pin(PinState::Low)?;
+    let rst = export(rst_gpio)?.into_output_pin(PinState::High)?;
+    let mut delay = Delay;
+
+    let epd = EPD::new(&mut spi, busy, dc, rst, &mut delay, None)?;
+    Ok((epd, spi, delay))
+}
@@ -0,0 +1,341 @@
+//! Waveforms of the SSD1680 controller, used by the 2.13" B V4
+//!
+//! A waveform (LUT) is made of 12 groups played one after the other. Each group has 4 phases
+//! (A to D), each of them applying a [`Voltage`] for a number of frames. There are 5 LUTs
+//! of voltages: LUT0 to LUT3 drive the pixels, selected by their bits in the two RAMs, and
+//! LUT4 drives VCOM.
+//!
+//! [`LutBuilder`] builds a [`Lut`] without copying raw byte blobs from vendor code, and
+//! [`Lut::from_bytes`] decodes such a blob to tweak it:
+//!
+//! ```rust
+//! use epd_waveshare::lut::{LutBuilder, Voltage};
+//!
+//! // drive black pixels to VSH1 and white ones to VSL for 10 frames, twice
+//! let lut = LutBuilder::new()
+//!     .phases(0, 0, [Voltage::Vsh1, Voltage::Vss, Voltage::Vss, Voltage::Vss])
+//!     .phases(0, 1, [Voltage::Vsl, Voltage::Vss, Voltage::Vss, Voltage::Vss])
+//!     .frames(0, [10, 0, 0, 0])
+//!     .repeat(0, 2)
+//!     .build();
+//! assert_eq!(lut.to_bytes()[0], 0x40);
+//! ```
+
+/// Size of a waveform with the voltages following it, as sent to the controller
+pub const LUT_SIZE: usize = 159;
+/// Number of groups of a waveform
+pub const GROUPS: usize = 12;
+/// Number of LUTs of voltages, the last one driving VCOM
+pub const LUTS: usize = 5;
+
+// offsets of the parts of the waveform
+const TIMINGS: usize = LUTS * GROUPS;
+const FRAME_RATES: usize = TIMINGS + 7 * GROUPS;
+const GATES_ON: usize = FRAME_RATES + GROUPS / 2;
+const EOPT: usize = GATES_ON + GROUPS / 4;
+
+/// Voltage applied during a phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Voltage {
+    /// Ground
+    #[default]
+    Vss = 0,
+    /// First positive source voltage
+    Vsh1 = 1,
+    /// Negative source voltage
+    Vsl = 2,
+    /// Second positive source voltage
+    Vsh2 = 3,
+}
+
+impl Voltage {
+    fn from_bits(bits: u8) -> Self {
+        match bits & 0b11 {
+            0 => Voltage::Vss,
+            1 => Voltage::Vsh1,
+            2 => Voltage::Vsl,
+            _ => Voltage::Vsh2,
+        }
+    }
+}
+
+/// One of the 12 groups of a waveform
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Group {
+    /// Voltages of the phases A to D, for each LUT
+    pub voltages: [[Voltage; 4]; LUTS],
+    /// Number of frames of the phases A to D, 0 skipping the phase
+    pub frames: [u8; 4],
+    /// Number of repetitions of the phases A and B, then of C and D
+    pub sub_repeats: [u8; 2],
+    /// Number of repetitions of the whole group
+    pub repeat: u8,
+    /// Frame rate setting, 4 bits
+    pub frame_rate: u8,
+    /// Whether all gates are on during the phases A and B, then C and D
+    pub gates_on: [bool; 2],
+}
+
+/// A waveform with the voltages used by it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lut {
+    /// The groups, played in order
+    pub groups: [Group; GROUPS],
+    /// End option (EOPT), the gate voltage after the refresh
+    pub end_option: u8,
+    /// Gate driving voltage (VGH)
+    pub gate_voltage: u8,
+    /// Source driving voltages (VSH1, VSH2 and VSL)
+    pub source_voltages: [u8; 3],
+    /// VCOM voltage
+    pub vcom: u8,
+}
+
+impl Default for Lut {
+    /// An empty waveform, with the voltages set by the drivers on init
+    fn default() -> Self {
+        Lut {
+            groups: [Group::default(); GROUPS],
+            end_option: 0x22,
+            gate_voltage: 0x17,
+            source_voltages: [0x41, 0x00, 0x32],
+            vcom: 0x36,
+        }
+    }
+}
+
+impl Lut {
+    /// Decodes a waveform in the layout of the controller
+    pub fn from_bytes(bytes: &[u8; LUT_SIZE]) -> Self {
+        let mut lut = Lut::default();
+        for (n, group) in lut.groups.iter_mut().enumerate() {
+            for (l, voltages) in group.voltages.iter_mut().enumerate() {
+                let byte = bytes[l * GROUPS + n];
+                for (p, voltage) in voltages.iter_mut().enumerate() {
+                    *voltage = Voltage::from_bits(byte >> (6 - 2 * p));
+                }
+            }
+
+            let timing = &bytes[TIMINGS + 7 * n..TIMINGS + 7 * (n + 1)];
+            group.frames = [timing[0], timing[1], timing[3], timing[4]];
+            group.sub_repeats = [timing[2], timing[5]];
+            group.repeat = timing[6];
+
+            group.frame_rate = bytes[FRAME_RATES + n / 2] >> (4 * (1 - n % 2)) & 0x0F;
+            for (h, on) in group.gates_on.iter_mut().enumerate() {
+                let bit = 2 * n + h;
+                *on = bytes[GATES_ON + bit / 8] & (0x80 >> (bit % 8)) != 0;
+            }
+        }
+        lut.end_option = bytes[EOPT];
+        lut.gate_voltage = bytes[EOPT + 1];
+        lut.source_voltages = [bytes[EOPT + 2], bytes[EOPT + 3], bytes[EOPT + 4]];
+        lut.vcom = bytes[EOPT + 5];
+        lut
+    }
+
+    /// Encodes the waveform in the layout of the controller
+    pub fn to_bytes(&self) -> [u8; LUT_SIZE] {
+        let mut bytes = [0; LUT_SIZE];
+        for (n, group) in self.groups.iter().enumerate() {
+            for (l, voltages) in group.voltages.iter().enumerate() {
+                bytes[l * GROUPS + n] = voltages
+                    .iter()
+                    .enumerate()
+                    .fold(0, |byte, (p, &v)| byte | (v as u8) << (6 - 2 * p));
+            }
+
+            let [a, b, c, d] = group.frames;
+            bytes[TIMINGS + 7 * n..TIMINGS + 7 * (n + 1)].copy_from_slice(&[
+                a,
+                b,
+                group.sub_repeats[0],
+                c,
+                d,
+                group.sub_repeats[1],
+                group.repeat,
+            ]);
+
+            bytes[FRAME_RATES + n / 2] |= (group.frame_rate & 0x0F) << (4 * (1 - n % 2));
+            for (h, &on) in group.gates_on.iter().enumerate() {
+                let bit = 2 * n + h;
+                if on {
+                    bytes[GATES_ON + bit / 8] |= 0x80 >> (bit % 8);
+                }
+            }
+        }
+        bytes[EOPT] = self.end_option;
+        bytes[EOPT + 1] = self.gate_voltage;
+        bytes[EOPT + 2..EOPT + 5].copy_from_slice(&self.source_voltages);
+        bytes[EOPT + 5] = self.vcom;
+        bytes
+    }
+}
+
+/// Builder for a [`Lut`], starting from an empty waveform
+///
+/// The `group` and `lut` arguments are indices, which must be smaller than [`GROUPS`]
+/// and [`LUTS`]: the methods panic otherwise.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LutBuilder {
+    lut: Lut,
+}
+
+impl LutBuilder {
+    /// Creates a builder of an empty waveform, see [`Lut::default`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Voltages of the phases A to D of a group, for one LUT
+    pub fn phases(mut self, group: usize, lut: usize, voltages: [Voltage; 4]) -> Self {
+        self.lut.groups[group].voltages[lut] = voltages;
+        self
+    }
+
+    /// Number of frames of the phases A to D of a group
+    pub fn frames(mut self, group: usize, frames: [u8; 4]) -> Self {
+        self.lut.groups[group].frames = frames;
+        self
+    }
+
+    /// Number of repetitions of the phases A and B, then C and D of a group
+    pub fn sub_repeats(mut self, group: usize, sub_repeats: [u8; 2]) -> Self {
+        self.lut.groups[group].sub_repeats = sub_repeats;
+        self
+    }
+
+    /// Number of repetitions of a whole group
+    pub fn repeat(mut self, group: usize, repeat: u8) -> Self {
+        self.lut.groups[group].repeat = repeat;
+        self
+    }
+
+    /// Frame rate setting of a group, 4 bits
+    pub fn frame_rate(mut self, group: usize, frame_rate: u8) -> Self {
+        self.lut.groups[group].frame_rate = frame_rate;
+        self
+    }
+
+    /// Keeps all gates on during the phases A and B, then C and D of a group
+    pub fn gates_on(mut self, group: usize, gates_on: [bool; 2]) -> Self {
+        self.lut.groups[group].gates_on = gates_on;
+        self
+    }
+
+    /// End option (EOPT)
+    pub fn end_option(mut self, end_option: u8) -> Self {
+        self.lut.end_option = end_option;
+        self
+    }
+
+    /// Gate driving voltage (VGH)
+    pub fn gate_voltage(mut self, gate_voltage: u8) -> Self {
+        self.lut.gate_voltage = gate_voltage;
+        self
+    }
+
+    /// Source driving voltages (VSH1, VSH2 and VSL)
+    pub fn source_voltages(mut self, vsh1: u8, vsh2: u8, vsl: u8) -> Self {
+        self.lut.source_voltages = [vsh1, vsh2, vsl];
+        self
+    }
+
+    /// VCOM voltage
+    pub fn vcom(mut self, vcom: u8) -> Self {
+        self.lut.vcom = vcom;
+        self
+    }
+
+    /// Returns the waveform
+    pub fn build(self) -> Lut {
+        self.lut
+    }
+}
+
+/// Waveform used from the temperature `min` (in °C), up to the `min` of the next one of a
+/// table sorted by temperature
+///
+/// The waveforms of the OTP depend on the temperature, but a single custom waveform doesn't:
+/// a table of them, e.g. one with longer phases for the cold, keeps the refreshes clean
+/// outdoors. [`Epd2in13b::set_temperature_luts`](crate::epd2in13b_v4::Epd2in13b::set_temperature_luts)
+/// picks the waveform before each full refresh.
+///
+/// ```rust
+/// use epd_waveshare::lut::{for_temperature, TemperatureLut, LUT_SIZE};
+///
+/// static LUTS: [TemperatureLut; 3] = [
+///     TemperatureLut { min: -20, lut: [1; LUT_SIZE] },
+///     TemperatureLut { min: 5, lut: [2; LUT_SIZE] },
+///     TemperatureLut { min: 30, lut: [3; LUT_SIZE] },
+/// ];
+/// assert_eq!(for_temperature(&LUTS, 5).unwrap().lut[0], 2);
+/// // the first waveform is also used below its range
+/// assert_eq!(for_temperature(&LUTS, -30).unwrap().lut[0], 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TemperatureLut {
+    /// Lowest temperature of the waveform
+    pub min: i8,
+    /// Waveform, in the layout of [`Lut::to_bytes`]
+    pub lut: [u8; LUT_SIZE],
+}
+
+/// Waveform of `luts` for `temperature`: the last one whose `min` is at most `temperature`,
+/// or the first one if it is colder than all of them. `None` for an empty table.
+///
+/// `luts` must be sorted by `min`.
+pub fn for_temperature(luts: &[TemperatureLut], temperature: i8) -> Option<&TemperatureLut> {
+    luts.iter()
+        .rev()
+        .find(|lut| lut.min <= temperature)
+        .or_else(|| luts.first())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lut_layout() {
+        let lut = LutBuilder::new()
+            .phases(
+                1,
+                2,
+                [Voltage::Vsh2, Voltage::Vsl, Voltage::Vsh1, Voltage::Vss],
+            )
+            .frames(11, [1, 2, 3, 4])
+            .sub_repeats(11, [5, 6])
+            .repeat(11, 7)
+            .frame_rate(0, 0x3)
+            .frame_rate(1, 0x4)
+            .gates_on(4, [false, true])
+            .vcom(0x2C)
+            .build();
+        let bytes = lut.to_bytes();
+
+        assert_eq!(bytes[2 * GROUPS + 1], 0b11_10_01_00);
+        assert_eq!(&bytes[TIMINGS + 77..FRAME_RATES], &[1, 2, 5, 3, 4, 6, 7]);
+        assert_eq!(bytes[FRAME_RATES], 0x34);
+        assert_eq!(&bytes[GATES_ON..EOPT], &[0x00, 0x40, 0x00]);
+        assert_eq!(&bytes[EOPT..], &[0x22, 0x17, 0x41, 0x00, 0x32, 0x2C]);
+        assert_eq!(Lut::from_bytes(&bytes), lut);
+    }
+
+    #[test]
+    fn temperature_ranges() {
+        let lut = |min, byte| TemperatureLut {
+            min,
+            lut: [byte; LUT_SIZE],
+        };
+        let luts = [lut(0, 1), lut(10, 2), lut(25, 3)];
+        let picked = |temperature| for_temperature(&luts, temperature).map(|lut| lut.lut[0]);
+
+        assert_eq!(picked(-40), Some(1));
+        assert_eq!(picked(9), Some(1));
+        assert_eq!(picked(10), Some(2));
+        assert_eq!(picked(24), Some(2));
+        assert_eq!(picked(127), Some(3));
+        assert_eq!(for_temperature(&[], 20), None);
+    }
+}
@@ -42,6 +42,117 @@ impl Rect {
     }
 }
 
+/// Why a window can't be used by a partial update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowError {
+    /// The window doesn't start or end on a byte of the buffer: `x` or the width isn't a
+    /// multiple of 8
+    Misaligned,
+    /// The window has no pixel
+    Empty,
+}
+
+impl core::fmt::Display for WindowError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            WindowError::Misaligned => write!(f, "window not aligned to a byte"),
+            WindowError::Empty => write!(f, "empty window"),
+        }
+    }
+}
+
+/// Window of a partial update, starting and ending on byte boundaries
+///
+/// The drivers take the windows as `x`, `y`, `width` and `height`, and need `x` and `width`
+/// to be multiples of 8: [`new`](Self::new) checks it once, and
+/// [`expanded`](Self::expanded) grows a window to the bytes holding it.
+///
+/// ```rust
+/// use epd_waveshare::rect::{PartialWindow, WindowError};
+///
+/// assert_eq!(PartialWindow::new(3, 0, 8, 10), Err(WindowError::Misaligned));
+///
+/// let window = PartialWindow::expanded(3, 0, 8, 10)?;
+/// assert_eq!((window.x(), window.width()), (0, 16));
+/// assert_eq!(window.buffer_len(), 20);
+/// # Ok::<(), WindowError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartialWindow {
+    rect: Rect,
+}
+
+impl PartialWindow {
+    /// Checks that the window has pixels, and that `x` and `width` are multiples of 8
+    pub const fn new(x: u32, y: u32, width: u32, height: u32) -> Result<Self, WindowError> {
+        if width == 0 || height == 0 {
+            Err(WindowError::Empty)
+        } else if x % 8 != 0 || width % 8 != 0 {
+            Err(WindowError::Misaligned)
+        } else {
+            Ok(PartialWindow {
+                rect: Rect::new(x, y, width, height),
+            })
+        }
+    }
+
+    /// Smallest window starting and ending on byte boundaries which holds the given one
+    ///
+    /// The buffer sent must then hold the whole expanded window, not only the pixels drawn.
+    pub const fn expanded(x: u32, y: u32, width: u32, height: u32) -> Result<Self, WindowError> {
+        if width == 0 || height == 0 {
+            return Err(WindowError::Empty);
+        }
+        let start = x / 8 * 8;
+        let end = (x + width + 7) / 8 * 8;
+        Self::new(start, y, end - start, height)
+    }
+
+    /// Column of the left edge, a multiple of 8
+    pub const fn x(&self) -> u32 {
+        self.rect.x
+    }
+
+    /// Row of the top edge
+    pub const fn y(&self) -> u32 {
+        self.rect.y
+    }
+
+    /// Width in pixels, a multiple of 8
+    pub const fn width(&self) -> u32 {
+        self.rect.w
+    }
+
+    /// Height in pixels
+    pub const fn height(&self) -> u32 {
+        self.rect.h
+    }
+
+    /// The window as a rectangle
+    pub const fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    /// Length of a buffer with 1 bit per pixel holding the window
+    pub const fn buffer_len(&self) -> usize {
+        crate::buffer_len(self.rect.w as usize, self.rect.h as usize)
+    }
+}
+
+impl TryFrom<Rect> for PartialWindow {
+    type Error = WindowError;
+
+    fn try_from(rect: Rect) -> Result<Self, WindowError> {
+        Self::new(rect.x, rect.y, rect.w, rect.h)
+    }
+}
+
+impl From<PartialWindow> for Rect {
+    fn from(window: PartialWindow) -> Rect {
+        window.rect
+    }
+}
+
 #[test]
 fn test_intersect() {
     let r1 = Rect::new(0, 0, 10, 10);
@@ -85,3 +196,25 @@ fn sub_offset() {
         }
     ));
 }
+
+#[test]
+fn partial_window() {
+    assert_eq!(PartialWindow::new(8, 3, 0, 4), Err(WindowError::Empty));
+    assert_eq!(
+        PartialWindow::new(8, 3, 12, 4),
+        Err(WindowError::Misaligned)
+    );
+    assert_eq!(
+        PartialWindow::new(8, 3, 16, 4).map(Rect::from),
+        Ok(Rect::new(8, 3, 16, 4))
+    );
+
+    let window = PartialWindow::expanded(10, 3, 7, 4).unwrap();
+    assert_eq!(window.rect(), Rect::new(8, 3, 16, 4));
+    assert_eq!(window.buffer_len(), 8);
+    assert_eq!(PartialWindow::expanded(16, 3, 8, 4).unwrap().width(), 8);
+    assert_eq!(
+        PartialWindow::try_from(Rect::new(4, 0, 8, 1)),
+        Err(WindowError::Misaligned)
+    );
+}
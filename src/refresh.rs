@@ -0,0 +1,240 @@
+//! Choice between partial and full refreshes
+//!
+//! Partial refreshes are fast and don't flash, but leave ghosts of the previous images which
+//! build up over time. Waveshare recommends a full refresh every few partial ones, or every
+//! few minutes: [`RefreshPolicy`] counts the partial refreshes and tells when the next one
+//! must be full.
+//!
+//! The policy drives the displays implementing [`PartialRefresh`], which are only the
+//! Epd1in54 (V1/V2), the Epd2in13 (V2/V3), the Epd2in9 (V1/V2), the Epd2in9d, the Epd4in2,
+//! the Epd4in26, the Epd2in13b (V4) and the Epd2in9b (V4) for now.
+//!
+//! ```rust
+//!# use core::convert::Infallible;
+//!# use embedded_hal::{digital, spi};
+//!# use embedded_hal_mock::eh1::delay::NoopDelay;
+//!# struct Spi;
+//!# impl spi::ErrorType for Spi { type Error = Infallible; }
+//!# impl spi::SpiDevice for Spi {
+//!#     fn transaction(&mut self, _: &mut [spi::Operation<'_, u8>]) -> Result<(), Infallible> {
+//!#         Ok(())
+//!#     }
+//!# }
+//!# struct Pin;
+//!# impl digital::ErrorType for Pin { type Error = Infallible; }
+//!# impl digital::InputPin for Pin {
+//!#     fn is_high(&mut self) -> Result<bool, Infallible> { Ok(false) }
+//!#     fn is_low(&mut self) -> Result<bool, Infallible> { Ok(true) }
+//!# }
+//!# impl digital::OutputPin for Pin {
+//!#     fn set_low(&mut self) -> Result<(), Infallible> { Ok(()) }
+//!#     fn set_high(&mut self) -> Result<(), Infallible> { Ok(()) }
+//!# }
+//!# fn main() -> Result<(), epd_waveshare::error::Error<Infallible>> {
+//!use epd_waveshare::{epd2in13b_v4::*, prelude::*, refresh::{RefreshKind, RefreshPolicy}};
+//!#
+//!# let (mut spi, busy_in, dc, rst, mut delay) = (Spi, Pin, Pin, Pin, NoopDelay::new());
+//!
+//!let mut epd = Epd2in13b::new(&mut spi, busy_in, dc, rst, &mut delay, None)?;
+//!// a full refresh every 10 refreshes, and at least every hour
+//!let mut policy = RefreshPolicy::new().every(10).max_age_s(3600);
+//!let display = Display2in13b::default();
+//!for minute in 0..3 {
+//!    // draw on the display, the partial refreshes only showing the black and white plane...
+//!    let buffer = display.bw_buffer();
+//!    let kind = policy.update_and_display(&mut epd, &mut spi, &mut delay, buffer, minute * 60)?;
+//!    assert_eq!(kind == RefreshKind::Full, minute == 0);
+//!}
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{InputPin, OutputPin},
+    spi::SpiDevice,
+};
+
+use crate::error::Error;
+use crate::traits::{PartialRefresh, RefreshLut};
+
+/// Kind of a refresh chosen by a [`RefreshPolicy`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshKind {
+    /// Full refresh, clearing the ghosts
+    Full,
+    /// Partial refresh
+    Partial,
+}
+
+/// Counts the partial refreshes, to do a full one every `N` refreshes or `T` seconds
+///
+/// There is no clock in `no_std`, so the time is given by the caller, in seconds from any
+/// start (e.g. an RTC or a monotonic timer). Only differences matter, and they may wrap.
+/// The first refresh is always full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RefreshPolicy {
+    every: Option<u32>,
+    max_age_s: Option<u32>,
+    /// partial refreshes since the last full one
+    partial_count: u32,
+    /// time of the last full refresh, `None` before the first one
+    last_full_s: Option<u32>,
+    force_full: bool,
+}
+
+impl RefreshPolicy {
+    /// A policy doing only partial refreshes after the first full one, until `every` or
+    /// `max_age_s` is set
+    pub const fn new() -> Self {
+        RefreshPolicy {
+            every: None,
+            max_age_s: None,
+            partial_count: 0,
+            last_full_s: None,
+            force_full: false,
+        }
+    }
+
+    /// Does a full refresh every `n` refreshes, i.e. after `n - 1` partial ones
+    pub const fn every(mut self, n: u32) -> Self {
+        self.every = Some(n);
+        self
+    }
+
+    /// Does a full refresh when the last one is `seconds` old or more
+    pub const fn max_age_s(mut self, seconds: u32) -> Self {
+        self.max_age_s = Some(seconds);
+        self
+    }
+
+    /// Makes the next refresh a full one, e.g. after drawing a completely different screen
+    pub fn force_full(&mut self) {
+        self.force_full = true;
+    }
+
+    /// Number of partial refreshes since the last full one
+    pub fn partial_count(&self) -> u32 {
+        self.partial_count
+    }
+
+    /// Chooses the kind of a refresh done at `now_s`, and counts it
+    pub fn next(&mut self, now_s: u32) -> RefreshKind {
+        let full = match self.last_full_s {
+            None => true,
+            Some(last_full_s) => {
+                self.force_full
+                    || self.every.map_or(false, |n| self.partial_count + 1 >= n)
+                    || self
+                        .max_age_s
+                        .map_or(false, |age| now_s.wrapping_sub(last_full_s) >= age)
+            }
+        };
+
+        if full {
+            self.partial_count = 0;
+            self.last_full_s = Some(now_s);
+            self.force_full = false;
+            RefreshKind::Full
+        } else {
+            self.partial_count += 1;
+            RefreshKind::Partial
+        }
+    }
+
+    /// Shows the full frame `buffer` with the kind of refresh chosen by [`next`](Self::next)
+    ///
+    /// The frame is then set as the base image of the next partial refresh, and the display
+    /// is left in partial refresh mode.
+    pub fn update_and_display<SPI, BUSY, DC, RST, DELAY, EPD>(
+        &mut self,
+        epd: &mut EPD,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        now_s: u32,
+    ) -> Result<RefreshKind, Error<SPI::Error>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+        EPD: PartialRefresh<SPI, BUSY, DC, RST, DELAY>,
+    {
+        let kind = self.next(now_s);
+        if kind == RefreshKind::Full {
+            epd.set_update_mode(spi, delay, RefreshLut::Full)?;
+            epd.update_and_display_frame(spi, buffer, delay)?;
+            epd.set_update_mode(spi, delay, RefreshLut::Quick)?;
+        } else {
+            epd.update_and_display_frame(spi, buffer, delay)?;
+        }
+        epd.set_base_image(spi, delay, buffer)?;
+        Ok(kind)
+    }
+}
+
+/// Refreshes done by a display, to estimate the wear of the panel or the energy used
+///
+/// Times are counted like the busy timeouts, by adding up the delays between two polls of the
+/// busy pin, so they are only as precise as these delays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RefreshStats {
+    /// Number of full refreshes, including the fast ones
+    pub full_refreshes: u32,
+    /// Number of partial (quick) refreshes
+    pub partial_refreshes: u32,
+    /// Duration of the last refresh
+    pub last_busy_us: u32,
+    /// Total duration of the refreshes, during which the panel is driven
+    pub total_busy_us: u64,
+}
+
+impl RefreshStats {
+    /// Counts a refresh of the given kind, which lasted `busy_us`
+    #[cfg(feature = "epd2in13b_v4")]
+    pub(crate) fn count(&mut self, kind: RefreshKind, busy_us: u32) {
+        match kind {
+            RefreshKind::Full => self.full_refreshes = self.full_refreshes.saturating_add(1),
+            RefreshKind::Partial => {
+                self.partial_refreshes = self.partial_refreshes.saturating_add(1)
+            }
+        }
+        self.last_busy_us = busy_us;
+        self.total_busy_us = self.total_busy_us.saturating_add(busy_us as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_n_refreshes() {
+        use RefreshKind::*;
+        let mut policy = RefreshPolicy::new().every(3);
+        let kinds = [(); 7].map(|_| policy.next(0));
+        assert_eq!(
+            kinds,
+            [Full, Partial, Partial, Full, Partial, Partial, Full]
+        );
+
+        policy.next(0);
+        assert_eq!(policy.partial_count(), 1);
+        policy.force_full();
+        assert_eq!(policy.next(0), Full);
+        assert_eq!(policy.partial_count(), 0);
+    }
+
+    #[test]
+    fn max_age() {
+        let mut policy = RefreshPolicy::new().max_age_s(60);
+        assert_eq!(policy.next(u32::MAX - 10), RefreshKind::Full);
+        assert_eq!(policy.next(u32::MAX), RefreshKind::Partial);
+        // 60s later, the clock having wrapped
+        assert_eq!(policy.next(48), RefreshKind::Partial);
+        assert_eq!(policy.next(49), RefreshKind::Full);
+        assert_eq!(policy.next(100), RefreshKind::Partial);
+    }
+}
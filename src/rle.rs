@@ -0,0 +1,234 @@
+//! Run-length encoded frames, e.g. full screen images stored in flash
+//!
+//! Frames are mostly long runs of white or black bytes, which this encoding shrinks 5 to 20
+//! times. The encoding is PackBits, as in TIFF and MacPaint files: each block starts with a
+//! header byte `n`
+//! - `0..=127`: the `n + 1` following bytes are copied as is
+//! - `129..=255`: the following byte is repeated `257 - n` times, 2 to 128 times
+//! - `128`: nothing, the block is skipped
+//!
+//! [`encode`] compresses a frame, e.g. in a build script, and drivers with an
+//! `update_frame_rle` method decode it while sending it to the display:
+//!
+//! ```rust, no_run
+//!# use embedded_hal_mock::eh1::*;
+//!# fn main() -> Result<(), epd_waveshare::error::Error<embedded_hal::spi::ErrorKind>> {
+//!use epd_waveshare::{epd2in13b_v4::*, prelude::*, rle};
+//!#
+//!# let mut spi = spi::Mock::new(&[]);
+//!# let (busy_in, dc, rst) = (digital::Mock::new(&[]), digital::Mock::new(&[]), digital::Mock::new(&[]));
+//!# let mut delay = delay::NoopDelay::new();
+//!
+//!let mut frame = [0xFF; 4000];
+//!frame[2000..2016].fill(0x00);
+//!let mut encoded = [0; 4000];
+//!let len = rle::encode(&frame, &mut encoded).unwrap();
+//!assert_eq!(len, 66);
+//!
+//!let mut epd = Epd2in13b::new(&mut spi, busy_in, dc, rst, &mut delay, None)?;
+//!epd.update_frame_rle(&mut spi, &mut delay, &encoded[..len])?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+/// Longest literal block
+const MAX_LITERAL: usize = 128;
+/// Longest run
+const MAX_RUN: usize = 128;
+
+/// Error of the run-length encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RleError {
+    /// The encoded data ends in the middle of a block
+    Truncated,
+    /// The output buffer is too small for the encoded data, see [`encoded_len`]
+    OutputTooSmall,
+}
+
+impl core::fmt::Display for RleError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RleError::Truncated => write!(f, "Encoded data ends in the middle of a block"),
+            RleError::OutputTooSmall => write!(f, "Output buffer too small"),
+        }
+    }
+}
+
+/// Bytes of `encoded`, decoded one at a time
+///
+/// The decoding stops at the end of `encoded`, or in the middle of a truncated block: use
+/// [`decoded_len`] first to check the encoded data.
+#[derive(Debug, Clone)]
+pub struct Decoder<'a> {
+    encoded: &'a [u8],
+    /// bytes left in the current block
+    left: usize,
+    /// whether the current block is a run of `encoded[0]`
+    run: bool,
+}
+
+impl<'a> Decoder<'a> {
+    /// Decodes `encoded`
+    pub const fn new(encoded: &'a [u8]) -> Self {
+        Decoder {
+            encoded,
+            left: 0,
+            run: false,
+        }
+    }
+}
+
+impl Iterator for Decoder<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        while self.left == 0 {
+            let (&header, rest) = self.encoded.split_first()?;
+            self.encoded = rest;
+            match header {
+                0..=127 => (self.left, self.run) = (header as usize + 1, false),
+                128 => {}
+                _ => (self.left, self.run) = (257 - header as usize, true),
+            }
+        }
+        let (&byte, rest) = self.encoded.split_first()?;
+        self.left -= 1;
+        if !self.run || self.left == 0 {
+            self.encoded = rest;
+        }
+        Some(byte)
+    }
+}
+
+/// Length of `encoded` once decoded
+pub fn decoded_len(encoded: &[u8]) -> Result<usize, RleError> {
+    let (mut len, mut i) = (0, 0);
+    while let Some(&header) = encoded.get(i) {
+        let (decoded, block) = match header {
+            0..=127 => (header as usize + 1, header as usize + 2),
+            128 => (0, 1),
+            _ => (257 - header as usize, 2),
+        };
+        if i + block > encoded.len() {
+            return Err(RleError::Truncated);
+        }
+        len += decoded;
+        i += block;
+    }
+    Ok(len)
+}
+
+/// Encodes `data` into `out`, and returns the length of the encoded data
+pub fn encode(data: &[u8], out: &mut [u8]) -> Result<usize, RleError> {
+    let mut len = 0;
+    encode_with(data, |bytes| {
+        let end = len + bytes.len();
+        out.get_mut(len..end)
+            .ok_or(RleError::OutputTooSmall)?
+            .copy_from_slice(bytes);
+        len = end;
+        Ok(())
+    })?;
+    Ok(len)
+}
+
+/// Length of `data` once encoded by [`encode`]
+///
+/// It is at most one byte more than `data` for every 128 bytes, when nothing repeats.
+pub fn encoded_len(data: &[u8]) -> usize {
+    let mut len = 0;
+    let _ = encode_with(data, |bytes| {
+        len += bytes.len();
+        Ok(())
+    });
+    len
+}
+
+/// Encodes `data`, handing the blocks to `emit`
+///
+/// Runs of 3 bytes or more are encoded as runs, shorter ones are part of literal blocks:
+/// a run of 2 bytes in a literal block costs as much as a run block.
+fn encode_with(
+    data: &[u8],
+    mut emit: impl FnMut(&[u8]) -> Result<(), RleError>,
+) -> Result<(), RleError> {
+    // start of the pending literal block
+    let mut literal = 0;
+    let mut i = 0;
+    while i < data.len() {
+        let run = data[i..]
+            .iter()
+            .take(MAX_RUN)
+            .take_while(|&&byte| byte == data[i])
+            .count();
+        if run >= 3 {
+            emit_literal(&data[literal..i], &mut emit)?;
+            emit(&[(257 - run) as u8, data[i]])?;
+            i += run;
+            literal = i;
+        } else {
+            i += run;
+        }
+    }
+    emit_literal(&data[literal..], &mut emit)
+}
+
+/// Emits `bytes` as literal blocks
+fn emit_literal(
+    bytes: &[u8],
+    emit: &mut impl FnMut(&[u8]) -> Result<(), RleError>,
+) -> Result<(), RleError> {
+    for block in bytes.chunks(MAX_LITERAL) {
+        emit(&[block.len() as u8 - 1])?;
+        emit(block)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+    use std::vec::Vec;
+
+    fn round_trip(data: &[u8]) -> Vec<u8> {
+        let mut encoded = std::vec![0; encoded_len(data)];
+        assert_eq!(encode(data, &mut encoded), Ok(encoded.len()));
+        assert_eq!(decoded_len(&encoded), Ok(data.len()));
+        assert_eq!(Decoder::new(&encoded).collect::<Vec<_>>(), data);
+        encoded
+    }
+
+    #[test]
+    fn encoding() {
+        assert_eq!(round_trip(&[]), []);
+        assert_eq!(round_trip(&[1, 2, 2]), [2, 1, 2, 2]);
+        assert_eq!(round_trip(&[1, 2, 2, 2, 2, 3]), [0, 1, 253, 2, 0, 3]);
+        // runs longer than 128 bytes are split
+        assert_eq!(round_trip(&[0xFF; 200]), [129, 0xFF, 185, 0xFF]);
+
+        let data: Vec<u8> = (0..=255).chain([0; 1000]).collect();
+        let encoded = round_trip(&data);
+        assert_eq!(encoded.len(), 2 * 129 + 8 * 2);
+
+        assert_eq!(
+            encode(&[0xFF; 200], &mut [0; 3]),
+            Err(RleError::OutputTooSmall)
+        );
+    }
+
+    #[test]
+    fn decoding() {
+        // the no-op header is skipped
+        let encoded = [128, 1, 7, 8, 128, 254, 9];
+        assert_eq!(decoded_len(&encoded), Ok(5));
+        assert_eq!(Decoder::new(&encoded).collect::<Vec<_>>(), [7, 8, 9, 9, 9]);
+
+        for truncated in [&[2, 1, 2][..], &[254]] {
+            assert_eq!(decoded_len(truncated), Err(RleError::Truncated));
+        }
+        assert_eq!(Decoder::new(&[2, 1, 2]).collect::<Vec<_>>(), [1, 2]);
+    }
+}
@@ -0,0 +1,313 @@
+//! Host-side simulator of the displays
+//!
+//! [`SimDisplay`] is a [`DrawTarget`] with the geometry of a panel and the same colors
+//! ([`Color`](crate::color::Color), [`TriColor`](crate::color::TriColor), ...) as its
+//! [`Display`](crate::graphics::Display), which can be saved as a PNG or BMP image. The same
+//! drawing code can be tried on a computer, without flashing a board:
+//!
+//! ```rust, no_run
+//! use embedded_graphics::{
+//!     prelude::*,
+//!     primitives::{Circle, PrimitiveStyle},
+//! };
+//! use epd_waveshare::{epd2in13b_v4::*, prelude::*, simulator::SimDisplay};
+//!
+//! let mut display = SimDisplay::<WIDTH, HEIGHT, TriColor>::default();
+//! display.set_rotation(DisplayRotation::Rotate90);
+//! let _ = Circle::new(Point::new(10, 10), 50)
+//!     .into_styled(PrimitiveStyle::with_fill(TriColor::Chromatic))
+//!     .draw(&mut display);
+//! display.save("layout.png").unwrap();
+//! ```
+//!
+//! Needs the `simulator` feature, which depends on `std`.
+
+use std::{fs::File, io, io::Write, path::Path, vec, vec::Vec};
+
+use embedded_graphics_core::{pixelcolor::Rgb888, prelude::*, primitives::Rectangle};
+
+use crate::crc::Crc32;
+use crate::graphics::{buffer_rect, DisplayRotation};
+
+/// Simulated display, of `WIDTH` x `HEIGHT` pixels when it is not rotated
+///
+/// Rotations behave like the ones of [`Display`](crate::graphics::Display): they only concern
+/// the pixels drawn afterwards. Images are saved as the display is seen with the current
+/// rotation.
+#[derive(Clone)]
+pub struct SimDisplay<const WIDTH: u32, const HEIGHT: u32, COLOR> {
+    /// pixels in the coordinates of the panel, row by row
+    pixels: Vec<COLOR>,
+    rotation: DisplayRotation,
+}
+
+impl<const WIDTH: u32, const HEIGHT: u32, COLOR: PixelColor + Default> Default
+    for SimDisplay<WIDTH, HEIGHT, COLOR>
+{
+    /// A display filled with the default color of `COLOR`, white for the ones of this crate
+    fn default() -> Self {
+        Self::new(COLOR::default())
+    }
+}
+
+impl<const WIDTH: u32, const HEIGHT: u32, COLOR: PixelColor> SimDisplay<WIDTH, HEIGHT, COLOR> {
+    /// A display filled with `background`
+    pub fn new(background: COLOR) -> Self {
+        SimDisplay {
+            pixels: vec![background; WIDTH as usize * HEIGHT as usize],
+            rotation: DisplayRotation::default(),
+        }
+    }
+
+    /// Set the display rotation.
+    ///
+    /// This only concerns future drawing made to it, and how it is saved.
+    pub fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+    }
+
+    /// Get current rotation
+    pub fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+
+    /// Color of the pixel at `point`, with the current rotation, `None` outside of the display
+    pub fn pixel(&self, point: Point) -> Option<COLOR> {
+        self.index(point).map(|index| self.pixels[index])
+    }
+
+    /// Index in `pixels` of `point` of the rotated display
+    fn index(&self, point: Point) -> Option<usize> {
+        if !self.bounding_box().contains(point) {
+            return None;
+        }
+        let point = buffer_rect(
+            &Rectangle::new(point, Size::new(1, 1)),
+            self.rotation,
+            WIDTH,
+            HEIGHT,
+        )
+        .top_left;
+        Some(point.y as usize * WIDTH as usize + point.x as usize)
+    }
+}
+
+impl<const WIDTH: u32, const HEIGHT: u32, COLOR: PixelColor> DrawTarget
+    for SimDisplay<WIDTH, HEIGHT, COLOR>
+{
+    type Color = COLOR;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if let Some(index) = self.index(point) {
+                self.pixels[index] = color;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<const WIDTH: u32, const HEIGHT: u32, COLOR: PixelColor> OriginDimensions
+    for SimDisplay<WIDTH, HEIGHT, COLOR>
+{
+    fn size(&self) -> Size {
+        match self.rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => Size::new(WIDTH, HEIGHT),
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => Size::new(HEIGHT, WIDTH),
+        }
+    }
+}
+
+impl<const WIDTH: u32, const HEIGHT: u32, COLOR> SimDisplay<WIDTH, HEIGHT, COLOR>
+where
+    COLOR: PixelColor + Into<Rgb888>,
+{
+    /// Saves the display to `path`, as a BMP image if its extension is `bmp`, as a PNG image
+    /// otherwise
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut file = io::BufWriter::new(File::create(path)?);
+        match path.extension() {
+            Some(extension) if extension.eq_ignore_ascii_case("bmp") => self.write_bmp(&mut file),
+            _ => self.write_png(&mut file),
+        }?;
+        file.flush()
+    }
+
+    /// Writes the display as a 24 bits RGB PNG image
+    ///
+    /// The image data isn't compressed, as it is only meant to be looked at.
+    pub fn write_png(&self, mut w: impl Write) -> io::Result<()> {
+        let size = self.size();
+        // each row starts with its filter type, 0 for none
+        let mut data = Vec::with_capacity((size.width as usize * 3 + 1) * size.height as usize);
+        for y in 0..size.height as i32 {
+            data.push(0);
+            data.extend(self.row(y).flat_map(|c| [c.r(), c.g(), c.b()]));
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&size.width.to_be_bytes());
+        header.extend_from_slice(&size.height.to_be_bytes());
+        // 8 bits per component, RGB, deflate, no filter, no interlace
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        w.write_all(b"\x89PNG\r\n\x1a\n")?;
+        write_png_chunk(&mut w, b"IHDR", &header)?;
+        write_png_chunk(&mut w, b"IDAT", &zlib_stored(&data))?;
+        write_png_chunk(&mut w, b"IEND", &[])
+    }
+
+    /// Writes the display as a 24 bits BMP image
+    pub fn write_bmp(&self, mut w: impl Write) -> io::Result<()> {
+        let size = self.size();
+        // rows are padded to 4 bytes
+        let row_len = (size.width as usize * 3 + 3) / 4 * 4;
+        let data_len = (row_len * size.height as usize) as u32;
+
+        let mut header = Vec::with_capacity(54);
+        header.extend_from_slice(b"BM");
+        header.extend_from_slice(&(54 + data_len).to_le_bytes());
+        header.extend_from_slice(&[0; 4]);
+        header.extend_from_slice(&54u32.to_le_bytes());
+        header.extend_from_slice(&40u32.to_le_bytes());
+        header.extend_from_slice(&size.width.to_le_bytes());
+        header.extend_from_slice(&size.height.to_le_bytes());
+        // one plane, 24 bits per pixel, not compressed
+        header.extend_from_slice(&1u16.to_le_bytes());
+        header.extend_from_slice(&24u16.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(&data_len.to_le_bytes());
+        // 72 DPI, no palette
+        header.extend_from_slice(&2835u32.to_le_bytes());
+        header.extend_from_slice(&2835u32.to_le_bytes());
+        header.extend_from_slice(&[0; 8]);
+        w.write_all(&header)?;
+
+        // from the bottom row to the top one
+        let mut row = Vec::with_capacity(row_len);
+        for y in (0..size.height as i32).rev() {
+            row.clear();
+            row.extend(self.row(y).flat_map(|c| [c.b(), c.g(), c.r()]));
+            row.resize(row_len, 0);
+            w.write_all(&row)?;
+        }
+        Ok(())
+    }
+
+    /// Colors of the row `y` of the rotated display
+    fn row(&self, y: i32) -> impl Iterator<Item = Rgb888> + '_ {
+        (0..self.size().width as i32).map(move |x| {
+            self.pixel(Point::new(x, y))
+                .map(Into::into)
+                .unwrap_or(Rgb888::BLACK)
+        })
+    }
+}
+
+/// Writes a PNG chunk of type `kind`, with its length and CRC
+fn write_png_chunk(w: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+    let mut crc = Crc32::new();
+    crc.update(kind);
+    crc.update(data);
+    w.write_all(&crc.finish().to_be_bytes())
+}
+
+/// zlib stream of `data` in stored (not compressed) deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const BLOCK: usize = 0xFFFF;
+    let mut out = Vec::with_capacity(data.len() + data.len() / BLOCK * 5 + 11);
+    // deflate with a 32K window, no dictionary
+    out.extend_from_slice(&[0x78, 0x01]);
+    let mut blocks = data.chunks(BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(blocks.peek().is_none() as u8);
+        out.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// Adler-32 checksum of `bytes`, as used by zlib
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in bytes {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::TriColor;
+    use crate::crc::crc32;
+
+    #[test]
+    fn sim_display_rotation() {
+        let mut display = SimDisplay::<4, 2, TriColor>::default();
+        display.set_rotation(DisplayRotation::Rotate90);
+        assert_eq!(display.size(), Size::new(2, 4));
+        display
+            .draw_iter([Pixel(Point::new(0, 0), TriColor::Chromatic)])
+            .unwrap();
+        assert_eq!(display.pixel(Point::new(0, 0)), Some(TriColor::Chromatic));
+        assert_eq!(display.pixel(Point::new(2, 0)), None);
+
+        // top right corner of the panel
+        display.set_rotation(DisplayRotation::Rotate0);
+        assert_eq!(display.pixel(Point::new(3, 0)), Some(TriColor::Chromatic));
+        assert_eq!(display.pixel(Point::new(0, 0)), Some(TriColor::White));
+    }
+
+    #[test]
+    fn sim_display_bmp() {
+        let mut display = SimDisplay::<2, 2, TriColor>::new(TriColor::Black);
+        display
+            .draw_iter([Pixel(Point::new(1, 1), TriColor::Chromatic)])
+            .unwrap();
+        let mut bmp = Vec::new();
+        display.write_bmp(&mut bmp).unwrap();
+
+        assert_eq!(bmp.len(), 54 + 2 * 8);
+        assert_eq!(bmp[..2], *b"BM");
+        // bottom row first, in BGR, padded to 8 bytes
+        assert_eq!(bmp[54..62], [0, 0, 0, 0, 0, 255, 0, 0]);
+        assert_eq!(bmp[62..70], [0; 8]);
+    }
+
+    #[test]
+    fn sim_display_png() {
+        let display = SimDisplay::<3, 1, TriColor>::new(TriColor::White);
+        let mut png = Vec::new();
+        display.write_png(&mut png).unwrap();
+
+        assert_eq!(png[..8], *b"\x89PNG\r\n\x1a\n");
+        assert_eq!(png[12..16], *b"IHDR");
+        assert_eq!(png[16..24], [0, 0, 0, 3, 0, 0, 0, 1]);
+        // the CRC of the IHDR chunk of a 3x1 RGB image
+        assert_eq!(png[29..33], crc32(&png[12..29]).to_be_bytes());
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+
+        // filter type then the pixels, in a single stored block
+        let data = [0, 255, 255, 255, 255, 255, 255, 255, 255, 255];
+        assert_eq!(png[37..41], *b"IDAT");
+        assert_eq!(png[41..48], [0x78, 0x01, 1, 10, 0, 0xF5, 0xFF]);
+        assert_eq!(png[48..58], data);
+        assert_eq!(png[58..62], adler32(&data).to_be_bytes());
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+}
@@ -0,0 +1,199 @@
+//! Frames stored in an external flash, shown one after the other, e.g. by a battery powered
+//! photo frame
+//!
+//! A [`Slideshow`] knows where the frames are in the flash, and which one is next. Each
+//! [`show_next`](Slideshow::show_next) wakes the display up, streams the frame from the flash
+//! to its RAM, refreshes it and puts it back to deep sleep, so that the MCU can sleep until
+//! the next frame.
+//!
+//! The flash is read with a function with the signature of the `read` method of
+//! `embedded_storage::ReadStorage`. Only the 2.13" (B) V4 display can stream its frames for
+//! now, see [`Epd2in13b::update_frame_from_source`].
+//!
+//! ```rust, no_run
+//!# use embedded_hal_mock::eh1::*;
+//!# fn main() -> Result<(), epd_waveshare::frame::StreamError<embedded_hal::spi::ErrorKind, ()>> {
+//!use epd_waveshare::{epd2in13b_v4::*, prelude::*, slideshow::Slideshow};
+//!#
+//!# let mut spi = spi::Mock::new(&[]);
+//!# let (busy_in, dc, rst) = (digital::Mock::new(&[]), digital::Mock::new(&[]), digital::Mock::new(&[]));
+//!# let mut delay = delay::NoopDelay::new();
+//!# let flash = [0xFF; 3 * 4000];
+//!# let read_flash = |offset: u32, buf: &mut [u8]| {
+//!#     buf.copy_from_slice(&flash[offset as usize..][..buf.len()]);
+//!#     Ok(())
+//!# };
+//!
+//!let mut epd = Epd2in13b::new(&mut spi, busy_in, dc, rst, &mut delay, None)?;
+//!// 3 frames, one after the other from the start of the flash
+//!let mut slideshow = Slideshow::<3>::consecutive(0);
+//!loop {
+//!    slideshow.show_next(&mut epd, &mut spi, &mut delay, read_flash)?;
+//!    // e.g. sleep for an hour
+//!#   break;
+//!}
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{InputPin, OutputPin},
+    spi::SpiDevice,
+};
+
+use crate::buffer_len;
+use crate::epd2in13b_v4::{Epd2in13b, HEIGHT, WIDTH};
+use crate::frame::{StorageStream, StreamError};
+use crate::traits::{Sleepable, WaveshareDisplay};
+
+/// Length of a frame in the flash
+pub const FRAME_LEN: usize = buffer_len(WIDTH as usize, HEIGHT as usize);
+
+/// `N` frames at fixed offsets of an external flash, shown in turn
+#[derive(Debug, Clone)]
+pub struct Slideshow<const N: usize> {
+    offsets: [u32; N],
+    next: usize,
+}
+
+impl<const N: usize> Slideshow<N> {
+    /// Creates a slideshow of the frames at `offsets` in the flash, starting with the first
+    /// one
+    pub const fn new(offsets: [u32; N]) -> Self {
+        Slideshow { offsets, next: 0 }
+    }
+
+    /// Creates a slideshow of frames following each other in the flash, the first one at
+    /// `start`
+    pub const fn consecutive(start: u32) -> Self {
+        let mut offsets = [0; N];
+        let mut i = 0;
+        while i < N {
+            offsets[i] = start + (i * FRAME_LEN) as u32;
+            i += 1;
+        }
+        Self::new(offsets)
+    }
+
+    /// Offsets of the frames in the flash
+    pub fn offsets(&self) -> &[u32; N] {
+        &self.offsets
+    }
+
+    /// Index of the frame shown by the next [`show_next`](Self::show_next)
+    pub fn next_index(&self) -> usize {
+        self.next
+    }
+
+    /// Sets the frame shown by the next [`show_next`](Self::show_next), e.g. after a reset
+    /// of the MCU which kept the index in a memory surviving deep sleep
+    ///
+    /// The index wraps around the number of frames.
+    pub fn set_next_index(&mut self, index: usize) {
+        self.next = if N == 0 { 0 } else { index % N };
+    }
+
+    /// Shows the next frame, read from the flash with `read`, and returns its index
+    ///
+    /// The display is woken up, so it can be asleep or awake, then put to deep sleep once
+    /// refreshed. The slideshow only moves to the following frame if the frame was shown, so
+    /// calling it again after an error retries the same frame. A slideshow without frames
+    /// does nothing.
+    pub fn show_next<SPI, BUSY, DC, RST, DELAY, F, E>(
+        &mut self,
+        epd: &mut Epd2in13b<SPI, BUSY, DC, RST, DELAY>,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        read: F,
+    ) -> Result<usize, StreamError<SPI::Error, E>>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+        F: FnMut(u32, &mut [u8]) -> Result<(), E>,
+    {
+        let index = self.next;
+        let offset = match self.offsets.get(index) {
+            Some(&offset) => offset,
+            None => return Ok(index),
+        };
+        let mut frame = StorageStream::new(read, offset, FRAME_LEN);
+        epd.wake_up(spi, delay)?;
+        epd.update_frame_from_source(spi, delay, &mut frame)?;
+        epd.display_frame(spi, delay)?;
+        epd.sleep(spi, delay)?;
+        self.next = (index + 1) % N;
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epd2in13b_v4::command::Command;
+    use crate::testing::{Entry, Recorder};
+    use embedded_hal::digital::PinState;
+
+    extern crate std;
+    use std::vec::Vec;
+
+    #[test]
+    fn offsets() {
+        assert_eq!(
+            Slideshow::<3>::consecutive(100).offsets(),
+            &[100, 4100, 8100]
+        );
+
+        let mut slideshow = Slideshow::new([0, 8000]);
+        assert_eq!(slideshow.next_index(), 0);
+        slideshow.set_next_index(3);
+        assert_eq!(slideshow.next_index(), 1);
+    }
+
+    #[test]
+    fn show_next() {
+        let flash: Vec<u8> = (0..2 * FRAME_LEN)
+            .map(|i| 0xA0 + (i / FRAME_LEN) as u8)
+            .collect();
+        let read = |offset: u32, buf: &mut [u8]| {
+            let offset = offset as usize;
+            buf.copy_from_slice(&flash[offset..offset + buf.len()]);
+            Ok::<_, ()>(())
+        };
+
+        let recorder = Recorder::new();
+        let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+        let busy = recorder.busy(PinState::Low);
+        let (dc, rst) = (recorder.dc(), recorder.rst());
+        let mut epd = Epd2in13b::new(&mut spi, busy, dc, rst, &mut delay, None).unwrap();
+        let mut slideshow = Slideshow::<2>::consecutive(0);
+
+        // the frame of each call, and back to the first one
+        for expected in [0, 1, 0] {
+            recorder.clear();
+            let shown = slideshow.show_next(&mut epd, &mut spi, &mut delay, read);
+            assert_eq!(shown, Ok(expected));
+
+            let transcript = recorder.transcript();
+            let entries = transcript.entries();
+            assert_eq!(entries[0], Entry::Reset(PinState::High));
+            let write = Entry::Command(Command::WriteRam as u8);
+            let frame = entries.iter().position(|entry| *entry == write).unwrap() + 1;
+            let data = std::vec![0xA0 + expected as u8; FRAME_LEN];
+            assert_eq!(entries[frame], Entry::Data(data));
+            let sleep = Entry::Command(Command::DeepSleepMode as u8);
+            assert_eq!(entries[entries.len() - 2], sleep);
+        }
+
+        // the same frame is retried after a failed read
+        let failing = |_: u32, _: &mut [u8]| Err("flash error");
+        assert_eq!(
+            slideshow.show_next(&mut epd, &mut spi, &mut delay, failing),
+            Err(StreamError::Source("flash error"))
+        );
+        assert_eq!(slideshow.next_index(), 1);
+    }
+}
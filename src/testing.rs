@@ -0,0 +1,371 @@
+//! Recording of the traffic of the drivers, to compare it with golden transcripts
+//!
+//! A [`Recorder`] hands out an SPI device, pins and a delay which log everything a driver
+//! does with them: the commands and their data (told apart with the DC pin), the levels of
+//! the reset pin and the delays. The busy pin is always idle. The resulting [`Transcript`]
+//! is printed in a text format meant to be checked in, and compared with
+//! [`Transcript::assert_golden`]:
+//!
+//! ```rust, no_run
+//! use embedded_hal::digital::PinState;
+//! use epd_waveshare::{epd2in9_v2::*, prelude::*, testing::Recorder};
+//!
+//! let recorder = Recorder::new();
+//! let (mut spi, mut delay) = (recorder.spi(), recorder.delay());
+//! let busy = recorder.busy(PinState::Low);
+//! let mut epd = Epd2in9::new(&mut spi, busy, recorder.dc(), recorder.rst(), &mut delay, None)?;
+//! epd.clear_frame(&mut spi, &mut delay)?;
+//! recorder.transcript().assert_golden("tests/golden/epd2in9_v2_clear.txt");
+//! # Ok::<(), epd_waveshare::error::Error<core::convert::Infallible>>(())
+//! ```
+//!
+//! Golden transcripts are (re)written instead of compared when the `EPD_UPDATE_GOLDEN`
+//! environment variable is set, so that a change of the traffic shows up in the diff of the
+//! transcript. Needs the `testing` feature, which depends on `std`.
+
+use core::{convert::Infallible, fmt};
+use std::{cell::RefCell, env, fs, path::Path, rc::Rc, vec::Vec};
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{ErrorType as PinErrorType, InputPin, OutputPin, PinState},
+    spi::{ErrorType as SpiErrorType, Operation, SpiDevice},
+};
+
+/// Something a driver did, as recorded by a [`Recorder`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entry {
+    /// A command byte, sent with the DC pin low
+    Command(u8),
+    /// Data bytes, sent with the DC pin high. Consecutive writes are merged, however the
+    /// driver splits them.
+    Data(Vec<u8>),
+    /// The reset pin was set
+    Reset(PinState),
+    /// Consecutive delays, in nanoseconds
+    Delay(u64),
+}
+
+/// Everything a driver did, in order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transcript {
+    entries: Vec<Entry>,
+}
+
+impl Transcript {
+    /// The recorded entries
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Panics if the transcript differs from the one stored in the file at `path`, showing the
+    /// first different line
+    ///
+    /// The file is written instead when the `EPD_UPDATE_GOLDEN` environment variable is set.
+    pub fn assert_golden(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let actual = std::format!("{}", self);
+        if env::var_os("EPD_UPDATE_GOLDEN").is_some() {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).unwrap();
+            }
+            fs::write(path, actual).unwrap();
+            return;
+        }
+
+        let expected = fs::read_to_string(path).unwrap_or_else(|e| {
+            panic!(
+                "can't read {} ({}), set EPD_UPDATE_GOLDEN to create it",
+                path.display(),
+                e
+            )
+        });
+        if actual == expected {
+            return;
+        }
+        let mut expected_lines = expected.lines();
+        for (number, line) in actual.lines().enumerate() {
+            let golden = expected_lines.next();
+            if golden != Some(line) {
+                panic!(
+                    "{}:{}: transcript differs\n  golden: {}\n  actual: {}",
+                    path.display(),
+                    number + 1,
+                    golden.unwrap_or("<end>"),
+                    line
+                );
+            }
+        }
+        panic!("{}: transcript ends early", path.display());
+    }
+
+    fn push(&mut self, entry: Entry) {
+        match (self.entries.last_mut(), entry) {
+            (Some(Entry::Data(data)), Entry::Data(more)) => data.extend(more),
+            (Some(Entry::Delay(ns)), Entry::Delay(more)) => *ns += more,
+            (_, entry) => self.entries.push(entry),
+        }
+    }
+}
+
+/// Number of data bytes, or repeated bytes, on a line of the text format
+const TOKENS_PER_LINE: usize = 16;
+
+/// One entry per line, `data` being split over several lines and runs of the same byte being
+/// written `0xff*4000`
+impl fmt::Display for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            match entry {
+                Entry::Command(command) => writeln!(f, "command {:#04x}", command)?,
+                Entry::Reset(PinState::Low) => writeln!(f, "reset low")?,
+                Entry::Reset(PinState::High) => writeln!(f, "reset high")?,
+                Entry::Delay(ns) if ns % 1000 == 0 => writeln!(f, "delay {}us", ns / 1000)?,
+                Entry::Delay(ns) => writeln!(f, "delay {}ns", ns)?,
+                Entry::Data(data) => {
+                    let mut tokens = Vec::new();
+                    let mut rest = &data[..];
+                    while let Some(&byte) = rest.first() {
+                        let run = rest.iter().take_while(|&&b| b == byte).count();
+                        // short runs are easier to read byte by byte
+                        if run >= 4 {
+                            tokens.push(std::format!("{:#04x}*{}", byte, run));
+                            rest = &rest[run..];
+                        } else {
+                            tokens.push(std::format!("{:#04x}", byte));
+                            rest = &rest[1..];
+                        }
+                    }
+                    for (i, line) in tokens.chunks(TOKENS_PER_LINE).enumerate() {
+                        let prefix = if i == 0 { "data" } else { "    " };
+                        writeln!(f, "{} {}", prefix, line.join(" "))?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct State {
+    transcript: Transcript,
+    /// level of the DC pin, high for data
+    data: bool,
+}
+
+/// Source of the SPI device, pins and delay recording the traffic of a driver
+#[derive(Clone, Default)]
+pub struct Recorder {
+    state: Rc<RefCell<State>>,
+}
+
+impl Recorder {
+    /// A recorder with an empty transcript
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// SPI device recording the written bytes as commands or data, depending on the DC pin
+    pub fn spi(&self) -> RecordingSpi {
+        RecordingSpi {
+            state: self.state.clone(),
+        }
+    }
+
+    /// Data/Command pin
+    pub fn dc(&self) -> RecordingPin {
+        RecordingPin {
+            state: self.state.clone(),
+            reset: false,
+        }
+    }
+
+    /// Reset pin, whose levels are recorded
+    pub fn rst(&self) -> RecordingPin {
+        RecordingPin {
+            state: self.state.clone(),
+            reset: true,
+        }
+    }
+
+    /// Busy pin, always reading `idle`: the level of an idle display for the driver
+    pub fn busy(&self, idle: PinState) -> IdleBusy {
+        IdleBusy { idle }
+    }
+
+    /// Delay recording the time waited, without waiting
+    pub fn delay(&self) -> RecordingDelay {
+        RecordingDelay {
+            state: self.state.clone(),
+        }
+    }
+
+    /// Everything recorded so far
+    pub fn transcript(&self) -> Transcript {
+        self.state.borrow().transcript.clone()
+    }
+
+    /// Forgets everything recorded so far, e.g. the initialisation of the display
+    pub fn clear(&self) {
+        self.state.borrow_mut().transcript = Transcript::default();
+    }
+}
+
+/// SPI device of a [`Recorder`]
+pub struct RecordingSpi {
+    state: Rc<RefCell<State>>,
+}
+
+impl SpiErrorType for RecordingSpi {
+    type Error = Infallible;
+}
+
+impl SpiDevice for RecordingSpi {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Infallible> {
+        let mut state = self.state.borrow_mut();
+        for operation in operations {
+            let written = match operation {
+                Operation::Write(bytes) => &bytes[..],
+                Operation::Transfer(_, bytes) => &bytes[..],
+                Operation::TransferInPlace(bytes) => &bytes[..],
+                Operation::DelayNs(ns) => {
+                    state.transcript.push(Entry::Delay(*ns as u64));
+                    continue;
+                }
+                Operation::Read(_) => continue,
+            };
+            if state.data {
+                state.transcript.push(Entry::Data(written.to_vec()));
+            } else {
+                for command in written {
+                    state.transcript.push(Entry::Command(*command));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// DC or reset pin of a [`Recorder`]
+pub struct RecordingPin {
+    state: Rc<RefCell<State>>,
+    reset: bool,
+}
+
+impl RecordingPin {
+    fn set(&mut self, level: PinState) {
+        if self.reset {
+            self.state.borrow_mut().transcript.push(Entry::Reset(level));
+        } else {
+            self.state.borrow_mut().data = level == PinState::High;
+        }
+    }
+}
+
+impl PinErrorType for RecordingPin {
+    type Error = Infallible;
+}
+
+impl OutputPin for RecordingPin {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.set(PinState::Low);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.set(PinState::High);
+        Ok(())
+    }
+}
+
+/// Busy pin of a [`Recorder`], never busy
+pub struct IdleBusy {
+    idle: PinState,
+}
+
+impl PinErrorType for IdleBusy {
+    type Error = Infallible;
+}
+
+impl InputPin for IdleBusy {
+    fn is_high(&mut self) -> Result<bool, Infallible> {
+        Ok(self.idle == PinState::High)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Infallible> {
+        Ok(self.idle == PinState::Low)
+    }
+}
+
+/// Delay of a [`Recorder`]
+pub struct RecordingDelay {
+    state: Rc<RefCell<State>>,
+}
+
+impl DelayNs for RecordingDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.state
+            .borrow_mut()
+            .transcript
+            .push(Entry::Delay(ns as u64));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::ToString;
+
+    #[test]
+    fn recorder() {
+        let recorder = Recorder::new();
+        let (mut spi, mut dc, mut rst) = (recorder.spi(), recorder.dc(), recorder.rst());
+        let mut delay = recorder.delay();
+
+        rst.set_low().unwrap();
+        delay.delay_ms(2);
+        delay.delay_us(500);
+        rst.set_high().unwrap();
+        dc.set_low().unwrap();
+        spi.write(&[0x12, 0x24]).unwrap();
+        dc.set_high().unwrap();
+        spi.write(&[1, 2]).unwrap();
+        spi.write(&[3]).unwrap();
+
+        assert_eq!(
+            recorder.transcript().entries(),
+            [
+                Entry::Reset(PinState::Low),
+                Entry::Delay(2_500_000),
+                Entry::Reset(PinState::High),
+                Entry::Command(0x12),
+                Entry::Command(0x24),
+                Entry::Data(std::vec![1, 2, 3]),
+            ]
+        );
+        recorder.clear();
+        assert!(recorder.transcript().entries().is_empty());
+
+        let mut busy = recorder.busy(PinState::Low);
+        assert_eq!((busy.is_low(), busy.is_high()), (Ok(true), Ok(false)));
+    }
+
+    #[test]
+    fn transcript_text() {
+        let mut transcript = Transcript::default();
+        transcript.push(Entry::Command(0x4E));
+        transcript.push(Entry::Data([[1, 2, 2, 2].as_slice(), &[0xFF; 40]].concat()));
+        transcript.push(Entry::Data((0..20).collect()));
+        transcript.push(Entry::Delay(1500));
+
+        let text = transcript.to_string();
+        assert_eq!(
+            text,
+            "command 0x4e\n\
+             data 0x01 0x02 0x02 0x02 0xff*40 0x00 0x01 0x02 0x03 0x04 0x05 0x06 0x07 0x08 0x09 \
+             0x0a\n     0x0b 0x0c 0x0d 0x0e 0x0f 0x10 0x11 0x12 0x13\n\
+             delay 1500ns\n"
+        );
+    }
+}
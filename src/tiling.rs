@@ -0,0 +1,222 @@
+//! Several displays drawn on as a single one
+//!
+//! A [`TiledDisplay`] places the buffers of several panels (e.g. two 7.5" panels side by side)
+//! in one large [`DrawTarget`]: drawings are split between the buffers, and
+//! [`flush`](TiledDisplay::flush) hands each modified buffer to its driver.
+//!
+//! ```rust, no_run
+//!# use embedded_hal_mock::eh1::*;
+//!# fn main() -> Result<(), epd_waveshare::error::Error<embedded_hal::spi::ErrorKind>> {
+//!use embedded_graphics::{prelude::*, primitives::{Line, PrimitiveStyle}};
+//!use epd_waveshare::{epd7in5_v2::*, prelude::*, tiling::TiledDisplay};
+//!#
+//!# let mut spis = [spi::Mock::new(&[]), spi::Mock::new(&[])];
+//!# let pin = || digital::Mock::new(&[]);
+//!# let mut delay = delay::NoopDelay::new();
+//!
+//!// each panel has its own chip select, and so its own SPI device
+//!let mut epds = [
+//!    Epd7in5::new(&mut spis[0], pin(), pin(), pin(), &mut delay, None)?,
+//!    Epd7in5::new(&mut spis[1], pin(), pin(), pin(), &mut delay, None)?,
+//!];
+//!let mut display = TiledDisplay::row([Display7in5::default(), Display7in5::default()]);
+//!
+//!// a line across both panels
+//!let _ = Line::new(Point::new(0, 240), Point::new(1599, 240))
+//!    .into_styled(PrimitiveStyle::with_stroke(Color::Black, 1))
+//!    .draw(&mut display);
+//!display.flush(|i, tile| epds[i].update_and_display_frame(&mut spis[i], tile.buffer(), &mut delay))?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_graphics_core::{prelude::*, primitives::Rectangle};
+
+/// Displays placed side by side, seen as one [`DrawTarget`]
+///
+/// The tiles shouldn't overlap: a pixel drawn in an overlap goes to all the tiles.
+pub struct TiledDisplay<D, const N: usize> {
+    tiles: [D; N],
+    origins: [Point; N],
+    dirty: [bool; N],
+}
+
+impl<D: OriginDimensions, const N: usize> TiledDisplay<D, N> {
+    /// Places the top left corner of `tiles[i]` at `origins[i]`
+    pub fn new(tiles: [D; N], origins: [Point; N]) -> Self {
+        TiledDisplay {
+            tiles,
+            origins,
+            dirty: [false; N],
+        }
+    }
+
+    /// Places the tiles in a row, from left to right
+    pub fn row(tiles: [D; N]) -> Self {
+        let mut origins = [Point::zero(); N];
+        let mut x = 0;
+        for (origin, tile) in origins.iter_mut().zip(&tiles) {
+            *origin = Point::new(x, 0);
+            x += tile.size().width as i32;
+        }
+        Self::new(tiles, origins)
+    }
+
+    /// Places the tiles in a column, from top to bottom
+    pub fn column(tiles: [D; N]) -> Self {
+        let mut origins = [Point::zero(); N];
+        let mut y = 0;
+        for (origin, tile) in origins.iter_mut().zip(&tiles) {
+            *origin = Point::new(0, y);
+            y += tile.size().height as i32;
+        }
+        Self::new(tiles, origins)
+    }
+
+    /// Area covered by the tile `i`
+    pub fn tile_area(&self, i: usize) -> Rectangle {
+        Rectangle::new(self.origins[i], self.tiles[i].size())
+    }
+
+    /// The tiles
+    pub fn tiles(&self) -> &[D; N] {
+        &self.tiles
+    }
+
+    /// The tiles, e.g. to rotate them
+    ///
+    /// The tiles aren't moved if their size changes.
+    pub fn tiles_mut(&mut self) -> &mut [D; N] {
+        &mut self.tiles
+    }
+
+    /// Whether something was drawn on the tile `i` since the last flush
+    pub fn is_dirty(&self, i: usize) -> bool {
+        self.dirty[i]
+    }
+
+    /// Calls `flush_tile` with the index and the buffer of every tile drawn on since the last
+    /// flush, e.g. to show it on the display with the same index
+    ///
+    /// A tile stays dirty if it couldn't be flushed, and the first error is returned.
+    pub fn flush<E>(
+        &mut self,
+        mut flush_tile: impl FnMut(usize, &D) -> Result<(), E>,
+    ) -> Result<(), E> {
+        for (i, tile) in self.tiles.iter().enumerate() {
+            if self.dirty[i] {
+                flush_tile(i, tile)?;
+                self.dirty[i] = false;
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks all the tiles as drawn on, so that the next flush shows them all
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty = [true; N];
+    }
+}
+
+impl<D: OriginDimensions, const N: usize> OriginDimensions for TiledDisplay<D, N> {
+    /// Size of the smallest rectangle holding all the tiles
+    fn size(&self) -> Size {
+        let (mut width, mut height) = (0, 0);
+        for i in 0..N {
+            let bottom_right = self.tile_area(i).top_left + self.tiles[i].size();
+            width = width.max(bottom_right.x.max(0) as u32);
+            height = height.max(bottom_right.y.max(0) as u32);
+        }
+        Size::new(width, height)
+    }
+}
+
+impl<D: DrawTarget + OriginDimensions, const N: usize> DrawTarget for TiledDisplay<D, N> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            for i in 0..N {
+                if self.tile_area(i).contains(point) {
+                    let pixel = Pixel(point - self.origins[i], color);
+                    self.tiles[i].draw_iter(core::iter::once(pixel))?;
+                    self.dirty[i] = true;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        for i in 0..N {
+            let part = area.intersection(&self.tile_area(i));
+            if !part.is_zero_sized() {
+                let part = Rectangle::new(part.top_left - self.origins[i], part.size);
+                self.tiles[i].fill_solid(&part, color)?;
+                self.dirty[i] = true;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::graphics::Display;
+    use embedded_graphics::{
+        prelude::*,
+        primitives::{Line, PrimitiveStyle},
+    };
+
+    type Tile = Display<16, 8, false, 16, Color>;
+
+    #[test]
+    fn row() {
+        let mut display = TiledDisplay::row([Tile::default(), Tile::default(), Tile::default()]);
+        assert_eq!(display.size(), Size::new(48, 8));
+        display.clear(Color::White).unwrap();
+        display.flush(|_, _| Ok::<_, ()>(())).unwrap();
+
+        // from the middle of the first tile to the middle of the second one
+        Line::new(Point::new(8, 3), Point::new(23, 3))
+            .into_styled(PrimitiveStyle::with_stroke(Color::Black, 1))
+            .draw(&mut display)
+            .unwrap();
+        assert!(display.is_dirty(0) && display.is_dirty(1) && !display.is_dirty(2));
+        assert_eq!(display.tiles()[0].buffer()[6..8], [0xFF, 0x00]);
+        assert_eq!(display.tiles()[1].buffer()[6..8], [0x00, 0xFF]);
+
+        let mut flushed = [false; 3];
+        display
+            .flush(|i, _| {
+                flushed[i] = true;
+                Ok::<_, ()>(())
+            })
+            .unwrap();
+        assert_eq!(flushed, [true, true, false]);
+        assert!(!display.is_dirty(0));
+    }
+
+    #[test]
+    fn fill_across_tiles() {
+        let mut display = TiledDisplay::column([Tile::default(), Tile::default()]);
+        assert_eq!(display.size(), Size::new(16, 16));
+        display
+            .fill_solid(
+                &Rectangle::new(Point::new(0, 6), Size::new(16, 4)),
+                Color::White,
+            )
+            .unwrap();
+        // the last two rows of the first tile, the first two of the second one
+        assert!(display.tiles()[0].buffer()[..12].iter().all(|&b| b == 0));
+        assert!(display.tiles()[0].buffer()[12..].iter().all(|&b| b == 0xFF));
+        assert!(display.tiles()[1].buffer()[..4].iter().all(|&b| b == 0xFF));
+        assert!(display.tiles()[1].buffer()[4..].iter().all(|&b| b == 0));
+    }
+}
@@ -1,14 +1,22 @@
+use crate::color::TriColor;
+use crate::error::Error;
+use crate::rect::PartialWindow;
 use core::marker::Sized;
 use embedded_hal::{delay::*, digital::*, spi::SpiDevice};
 
-/// All commands need to have this trait which gives the address of the command
-/// which needs to be send via SPI with activated CommandsPin (Data/Command Pin in CommandMode)
-pub(crate) trait Command: Copy {
-    fn address(self) -> u8;
+cfg_interface! {
+    /// All commands need to have this trait which gives the address of the command
+    /// which needs to be send via SPI with activated CommandsPin (Data/Command Pin in CommandMode)
+    pub(crate) trait Command: Copy {
+        fn address(self) -> u8;
+    }
 }
 
 /// Seperates the different LUT for the Display Refresh process
+///
+/// More waveforms may be added, so matches on it need a wildcard arm.
 #[derive(Debug, Clone, PartialEq, Eq, Copy, Default)]
+#[non_exhaustive]
 pub enum RefreshLut {
     /// The "normal" full Lookuptable for the Refresh-Sequence
     #[default]
@@ -16,27 +24,69 @@ pub enum RefreshLut {
     /// The quick LUT where not the full refresh sequence is followed.
     /// This might lead to some
     Quick,
+    /// A shorter full refresh sequence ("fast mode"), trading some contrast for speed.
+    /// Displays without a dedicated fast waveform use the full one instead.
+    Fast,
 }
 
-pub(crate) trait InternalWiAdditions<SPI, BUSY, DC, RST, DELAY>
-where
-    SPI: SpiDevice,
-    BUSY: InputPin,
-    DC: OutputPin,
-    RST: OutputPin,
-    DELAY: DelayNs,
-{
-    /// This initialises the EPD and powers it up
-    ///
-    /// This function is already called from
-    ///  - [new()](WaveshareDisplay::new())
-    ///  - [`wake_up`]
-    ///
-    ///
-    /// This function calls [reset](WaveshareDisplay::reset),
-    /// so you don't need to call reset your self when trying to wake your device up
-    /// after setting it to sleep.
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error>;
+/// Refresh modes of [`LutControl::set_refresh_mode`], named after the vendor documentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RefreshMode {
+    /// Full refresh with the default waveform, flashing but clean
+    #[default]
+    Full,
+    /// Shorter full refresh, ~1.5s, see [`RefreshLut::Fast`]
+    Fast,
+    /// Partial refresh of the pixels which changed, see [`RefreshLut::Quick`]
+    Partial,
+}
+
+impl From<RefreshMode> for RefreshLut {
+    fn from(mode: RefreshMode) -> Self {
+        match mode {
+            RefreshMode::Full => RefreshLut::Full,
+            RefreshMode::Fast => RefreshLut::Fast,
+            RefreshMode::Partial => RefreshLut::Quick,
+        }
+    }
+}
+
+/// Deep sleep modes of the displays, from the one keeping the most state to the one using
+/// the least power, see [`Sleepable::sleep_with_mode`]
+///
+/// Both deep sleep modes return to standby with a hardware reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeepSleepMode {
+    /// Normal mode: the display doesn't sleep, and stays awake
+    Normal = 0x00,
+    /// Sleeps without access to RAM/controller but keeps RAM content
+    #[default]
+    Mode1 = 0x01,
+    /// Same as [`Mode1`](Self::Mode1) but RAM content is not kept
+    Mode2 = 0x11,
+}
+
+cfg_interface! {
+    pub(crate) trait InternalWiAdditions<SPI, BUSY, DC, RST, DELAY>
+    where
+        SPI: SpiDevice,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        /// This initialises the EPD and powers it up
+        ///
+        /// This function is already called from
+        ///  - [new()](WaveshareDisplay::new())
+        ///  - [`wake_up`]
+        ///
+        ///
+        /// This function calls [reset](WaveshareDisplay::reset),
+        /// so you don't need to call reset your self when trying to wake your device up
+        /// after setting it to sleep.
+        fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
+    }
 }
 
 /// Functions to interact with three color panels
@@ -58,7 +108,7 @@ where
         delay: &mut DELAY,
         black: &[u8],
         chromatic: &[u8],
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Update only the black/white data of the display.
     ///
@@ -68,7 +118,7 @@ where
         spi: &mut SPI,
         delay: &mut DELAY,
         black: &[u8],
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Update only the chromatic data of the display.
     ///
@@ -79,7 +129,43 @@ where
         spi: &mut SPI,
         delay: &mut DELAY,
         chromatic: &[u8],
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
+}
+
+/// Displays which can enter a deep sleep to save power
+///
+/// All the displays can, as a part of [`WaveshareDisplay`]: code which only puts a display to
+/// sleep and wakes it up can be bound on this trait alone.
+pub trait Sleepable<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Let the device enter deep-sleep mode to save power.
+    ///
+    /// The deep sleep mode returns to standby with a hardware reset.
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
+
+    /// Same as [`sleep`](Self::sleep), entering the deep sleep `mode`
+    ///
+    /// Displays with a single deep sleep mode ignore `mode`.
+    fn sleep_with_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mode: DeepSleepMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        let _ = mode;
+        self.sleep(spi, delay)
+    }
+
+    /// Wakes the device up from sleep
+    ///
+    /// Also reintialises the device if necessary.
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
 }
 
 /// All the functions to interact with the EPDs
@@ -90,7 +176,7 @@ where
 ///
 ///```rust, no_run
 ///# use embedded_hal_mock::eh1::*;
-///# fn main() -> Result<(), embedded_hal::spi::ErrorKind> {
+///# fn main() -> Result<(), epd_waveshare::error::Error<embedded_hal::spi::ErrorKind>> {
 ///use embedded_graphics::{
 ///    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
 ///};
@@ -144,7 +230,8 @@ where
 ///     .into_styled(PrimitiveStyle::with_stroke(Color::Black, 1))
 ///     .draw(&mut *display);
 ///```
-pub trait WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
+pub trait WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>:
+    Sleepable<SPI, BUSY, DC, RST, DELAY>
 where
     SPI: SpiDevice,
     BUSY: InputPin,
@@ -168,20 +255,10 @@ where
         rst: RST,
         delay: &mut DELAY,
         delay_us: Option<u32>,
-    ) -> Result<Self, SPI::Error>
+    ) -> Result<Self, Error<SPI::Error>>
     where
         Self: Sized;
 
-    /// Let the device enter deep-sleep mode to save power.
-    ///
-    /// The deep sleep mode returns to standby with a hardware reset.
-    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error>;
-
-    /// Wakes the device up from sleep
-    ///
-    /// Also reintialises the device if necessary.
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error>;
-
     /// Sets the backgroundcolor for various commands like [clear_frame](WaveshareDisplay::clear_frame)
     fn set_background_color(&mut self, color: Self::DisplayColor);
 
@@ -200,29 +277,12 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error>;
-
-    /// Transmits partial data to the SRAM of the EPD
-    ///
-    /// (x,y) is the top left corner
-    ///
-    /// BUFFER needs to be of size: width / 8 * height !
-    #[allow(clippy::too_many_arguments)]
-    fn update_partial_frame(
-        &mut self,
-        spi: &mut SPI,
-        delay: &mut DELAY,
-        buffer: &[u8],
-        x: u32,
-        y: u32,
-        width: u32,
-        height: u32,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Displays the frame data from SRAM
     ///
     /// This function waits until the device isn`t busy anymore
-    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error>;
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
 
     /// Provide a combined update&display and save some time (skipping a busy check in between)
     fn update_and_display_frame(
@@ -230,32 +290,21 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Clears the frame buffer on the EPD with the declared background color
     ///
     /// The background color can be changed with [`WaveshareDisplay::set_background_color`]
-    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error>;
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
 
-    /// Trait for using various Waveforms from different LUTs
-    /// E.g. for partial refreshes
-    ///
-    /// A full refresh is needed after a certain amount of quick refreshes!
-    ///
-    /// WARNING: Quick Refresh might lead to ghosting-effects/problems with your display. Especially for the 4.2in Display!
+    /// Wait until the display has stopped processing data
     ///
-    /// If None is used the old value will be loaded on the LUTs once more
-    fn set_lut(
+    /// You can call this to make sure a frame is displayed before goin further
+    fn wait_until_idle(
         &mut self,
         spi: &mut SPI,
         delay: &mut DELAY,
-        refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error>;
-
-    /// Wait until the display has stopped processing data
-    ///
-    /// You can call this to make sure a frame is displayed before goin further
-    fn wait_until_idle(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 }
 
 /// Allows quick refresh support for displays that support it; lets you send both
@@ -269,7 +318,7 @@ where
 /// Example:
 ///```rust, no_run
 ///# use embedded_hal_mock::eh1::*;
-///# fn main() -> Result<(), embedded_hal::spi::ErrorKind> {
+///# fn main() -> Result<(), epd_waveshare::error::Error<embedded_hal::spi::ErrorKind>> {
 ///# use embedded_graphics::{
 ///#   pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
 ///# };
@@ -317,7 +366,7 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Updates the new frame.
     fn update_new_frame(
@@ -325,10 +374,14 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Displays the new frame
-    fn display_new_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error>;
+    fn display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Updates and displays the new frame.
     fn update_and_display_new_frame(
@@ -336,7 +389,7 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Updates the old frame for a portion of the display.
     #[allow(clippy::too_many_arguments)]
@@ -349,7 +402,28 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
+
+    /// Same as [`update_partial_old_frame`](Self::update_partial_old_frame) with a
+    /// checked window, and checks the length of the buffer first
+    fn update_partial_old_window(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        window: PartialWindow,
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, window.buffer_len())?;
+        self.update_partial_old_frame(
+            spi,
+            delay,
+            buffer,
+            window.x(),
+            window.y(),
+            window.width(),
+            window.height(),
+        )
+    }
 
     /// Updates the new frame for a portion of the display.
     #[allow(clippy::too_many_arguments)]
@@ -362,7 +436,28 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
+
+    /// Same as [`update_partial_new_frame`](Self::update_partial_new_frame) with a
+    /// checked window, and checks the length of the buffer first
+    fn update_partial_new_window(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        window: PartialWindow,
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, window.buffer_len())?;
+        self.update_partial_new_frame(
+            spi,
+            delay,
+            buffer,
+            window.x(),
+            window.y(),
+            window.width(),
+            window.height(),
+        )
+    }
 
     /// Clears the partial frame buffer on the EPD with the declared background color
     /// The background color can be changed with [`WaveshareDisplay::set_background_color`]
@@ -374,5 +469,266 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
+}
+
+/// Displays which can write a window of the frame, instead of the whole frame
+pub trait PartialUpdate<SPI, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Transmits partial data to the SRAM of the EPD
+    ///
+    /// (x,y) is the top left corner
+    ///
+    /// BUFFER needs to be of size: width / 8 * height !
+    #[allow(clippy::too_many_arguments)]
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>>;
+
+    /// Same as [`update_partial_frame`](Self::update_partial_frame) with a checked window, and
+    /// checks the length of the buffer first
+    ///
+    /// ```rust, no_run
+    ///# use embedded_hal_mock::eh1::*;
+    ///# fn main() -> Result<(), epd_waveshare::error::Error<embedded_hal::spi::ErrorKind>> {
+    ///use epd_waveshare::{epd2in9_v2::*, error::Error, prelude::*, rect::PartialWindow};
+    ///#
+    ///# let mut spi = spi::Mock::new(&[]);
+    ///# let (busy_in, dc, rst) = (digital::Mock::new(&[]), digital::Mock::new(&[]), digital::Mock::new(&[]));
+    ///# let mut delay = delay::NoopDelay::new();
+    ///
+    ///let mut epd = Epd2in9::new(&mut spi, busy_in, dc, rst, &mut delay, None)?;
+    ///// columns 3 to 12 are in the bytes of columns 0 to 15
+    ///let window = PartialWindow::expanded(3, 40, 10, 8).map_err(Error::Window)?;
+    ///epd.update_partial_window(&mut spi, &mut delay, &[0xFF; 16], window)?;
+    ///# Ok(())
+    ///# }
+    ///```
+    fn update_partial_window(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+        window: PartialWindow,
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(buffer, window.buffer_len())?;
+        self.update_partial_frame(
+            spi,
+            delay,
+            buffer,
+            window.x(),
+            window.y(),
+            window.width(),
+            window.height(),
+        )
+    }
+}
+
+/// Three color displays which can write a window of both frames
+pub trait PartialColorUpdate<SPI, BUSY, DC, RST, DELAY>:
+    WaveshareThreeColorDisplay<SPI, BUSY, DC, RST, DELAY> + PartialUpdate<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Transmit a window of both the black and the chromatic data to the SRAM of the EPD, e.g.
+    /// to change a single field of a badge without sending the whole frames
+    ///
+    /// (x,y) is the top left corner. `x` and `width` need to be multiples of 8, and both
+    /// buffers of size `width / 8 * height`.
+    #[allow(clippy::too_many_arguments)]
+    fn update_partial_color_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        black: &[u8],
+        chromatic: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>>;
+
+    /// Same as [`update_partial_color_frame`](Self::update_partial_color_frame) with a
+    /// checked window, and checks the length of both buffers first
+    fn update_partial_color_window(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        black: &[u8],
+        chromatic: &[u8],
+        window: PartialWindow,
+    ) -> Result<(), Error<SPI::Error>> {
+        Error::check_len(black, window.buffer_len())?;
+        Error::check_len(chromatic, window.buffer_len())?;
+        self.update_partial_color_frame(
+            spi,
+            delay,
+            black,
+            chromatic,
+            window.x(),
+            window.y(),
+            window.width(),
+            window.height(),
+        )
+    }
+}
+
+/// Displays whose waveform can be changed, e.g. for quick refreshes
+pub trait LutControl<SPI, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Sets the LUT of the next refreshes, e.g. for partial refreshes
+    ///
+    /// A full refresh is needed after a certain amount of quick refreshes!
+    ///
+    /// WARNING: Quick Refresh might lead to ghosting-effects/problems with your display. Especially for the 4.2in Display!
+    ///
+    /// If None is used the old value will be loaded on the LUTs once more
+    fn set_lut(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>>;
+
+    /// Sets the refresh mode of the next refreshes, applying whatever the display needs for
+    /// it, e.g. the waveform or the temperature register trick of the fast mode
+    ///
+    /// Same as [`set_lut`](Self::set_lut) with the [`RefreshLut`] of `mode`.
+    fn set_refresh_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mode: RefreshMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.set_lut(spi, delay, Some(mode.into()))
+    }
+}
+
+/// Displays whose analog power (booster, source and gate drivers) can be kept on between
+/// refreshes
+///
+/// The displays usually turn the power on before each refresh and off after it, which takes
+/// some time and power. Keeping it on across several back-to-back partial updates saves the
+/// power ramp of each of them.
+pub trait PowerControl<SPI, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Turns the power on, and keeps it on after the next refreshes until
+    /// [`power_off`](Self::power_off)
+    ///
+    /// Deep sleep turns the power off.
+    fn power_on(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
+
+    /// Turns the power off, the next refreshes turning it off again at their end
+    ///
+    /// Leaving the panel powered for a long time can damage it: turn it off once the updates
+    /// are done.
+    fn power_off(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
+}
+
+/// Displays which can refresh only the pixels that changed, compared to a base image.
+///
+/// This allows code which is generic over the display to use partial refresh. The windows
+/// themselves are written with [`PartialUpdate::update_partial_frame`].
+///
+/// ```rust
+/// use embedded_hal::{delay::DelayNs, digital::{InputPin, OutputPin}, spi::SpiDevice};
+/// use epd_waveshare::{error::Error, prelude::*};
+///
+/// /// Redraws a clock in the top left corner of a display showing `background`
+/// fn update_clock<SPI, BUSY, DC, RST, DELAY, EPD>(
+///     epd: &mut EPD,
+///     spi: &mut SPI,
+///     delay: &mut DELAY,
+///     background: &[u8],
+///     clock: &[u8],
+/// ) -> Result<(), Error<SPI::Error>>
+/// where
+///     SPI: SpiDevice,
+///     BUSY: InputPin,
+///     DC: OutputPin,
+///     RST: OutputPin,
+///     DELAY: DelayNs,
+///     EPD: PartialRefresh<SPI, BUSY, DC, RST, DELAY>,
+/// {
+///     epd.set_update_mode(spi, delay, RefreshLut::Quick)?;
+///     epd.set_base_image(spi, delay, background)?;
+///     epd.update_partial_frame(spi, delay, clock, 0, 0, 64, 16)?;
+///     epd.display_frame(spi, delay)
+/// }
+/// ```
+pub trait PartialRefresh<SPI, BUSY, DC, RST, DELAY>:
+    PartialUpdate<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Sets the full frame the partial updates are compared against, usually what is
+    /// currently shown.
+    fn set_base_image(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>>;
+
+    /// Switches between partial ([`RefreshLut::Quick`]) and full refreshes
+    fn set_update_mode(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mode: RefreshLut,
+    ) -> Result<(), Error<SPI::Error>>;
+}
+
+/// Displays whose border, the frame around the pixels, can be set to a color
+///
+/// The border is often driven like a white pixel, and so flashes dark during full refreshes.
+/// Black and white displays can't show [`TriColor::Chromatic`].
+pub trait BorderColor<SPI, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    /// Sets the color of the border, shown from the next refresh
+    fn set_border_color(&mut self, spi: &mut SPI, color: TriColor)
+        -> Result<(), Error<SPI::Error>>;
 }
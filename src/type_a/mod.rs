@@ -1,2 +1,4 @@
 pub(crate) mod command;
+// the V2 displays have their own LUTs
+#[cfg(any(feature = "epd1in54", feature = "epd1in54_v2", feature = "epd2in9"))]
 pub(crate) mod constants;